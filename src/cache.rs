@@ -0,0 +1,379 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Default on-disk cache directory.
+///
+/// Entries are JSON files grouped by namespace (`<dir>/<namespace>/<key>.json`),
+/// with cumulative hit/miss counters per namespace in `<dir>/stats.json`.
+pub const DEFAULT_CACHE_DIR: &str = ".firekeeper/cache";
+
+/// File recording cumulative hit/miss counters per namespace
+const STATS_FILE: &str = "stats.json";
+/// Lock file held while updating the stats file, as concurrent runs share the cache
+const STATS_LOCK_FILE: &str = "stats.json.lock";
+/// Age after which a stats lock is assumed left behind by a killed run
+const STALE_LOCK: Duration = Duration::from_secs(10);
+/// Attempts to take the stats lock before giving up on recording a lookup
+const LOCK_ATTEMPTS: usize = 200;
+
+/// Cumulative lookup counters for one namespace
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Counters {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+fn read_counters(path: &Path) -> BTreeMap<String, Counters> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Exclusive lock on the stats file, released when dropped
+struct StatsLock(PathBuf);
+
+impl StatsLock {
+    /// Take the lock, waiting for other runs and breaking a stale lock
+    fn acquire(dir: &Path) -> std::io::Result<Self> {
+        let path = dir.join(STATS_LOCK_FILE);
+        for _ in 0..LOCK_ATTEMPTS {
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(Self(path)),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|m| m.elapsed().is_ok_and(|age| age > STALE_LOCK));
+                    if stale {
+                        let _ = std::fs::remove_file(&path);
+                    } else {
+                        std::thread::sleep(Duration::from_millis(5));
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("{} is held by another run", path.display()),
+        ))
+    }
+}
+
+impl Drop for StatsLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Count a lookup in a namespace as a hit or a miss
+fn record_lookup(dir: &Path, namespace: &str, hit: bool) {
+    let path = dir.join(STATS_FILE);
+    let recorded = std::fs::create_dir_all(dir).and_then(|()| {
+        let _lock = StatsLock::acquire(dir)?;
+        let mut counters = read_counters(&path);
+        let ns = counters.entry(namespace.to_string()).or_default();
+        if hit {
+            ns.hits += 1;
+        } else {
+            ns.misses += 1;
+        }
+        // Renamed into place, so readers never see a partly written file
+        let temp = dir.join(format!("{}.{}.tmp", STATS_FILE, std::process::id()));
+        std::fs::write(&temp, serde_json::to_string(&counters).unwrap())?;
+        std::fs::rename(&temp, &path)
+    });
+    if let Err(e) = recorded {
+        tracing::debug!("Failed to record cache lookup in {}: {}", path.display(), e);
+    }
+}
+
+/// Read an entry, counting the lookup as a hit or a miss
+pub fn get<T: DeserializeOwned>(dir: &Path, namespace: &str, key: &str) -> Option<T> {
+    let value = std::fs::read_to_string(dir.join(namespace).join(format!("{}.json", key)))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    record_lookup(dir, namespace, value.is_some());
+    value
+}
+
+/// Write an entry, replacing any previous one
+pub fn put<T: Serialize>(dir: &Path, namespace: &str, key: &str, value: &T) -> Result<(), String> {
+    let ns_dir = dir.join(namespace);
+    std::fs::create_dir_all(&ns_dir)
+        .and_then(|()| {
+            std::fs::write(
+                ns_dir.join(format!("{}.json", key)),
+                serde_json::to_string(value).unwrap(),
+            )
+        })
+        .map_err(|e| format!("Failed to write cache entry {}/{}: {}", namespace, key, e))
+}
+
+/// Size and usage of one cache namespace
+#[derive(Debug, Default, PartialEq)]
+pub struct NamespaceStats {
+    pub entries: usize,
+    pub bytes: u64,
+    pub oldest: Option<SystemTime>,
+    pub counters: Counters,
+}
+
+/// Collect entry counts, sizes, and hit/miss counters per namespace
+pub fn stats(dir: &Path) -> BTreeMap<String, NamespaceStats> {
+    let mut stats: BTreeMap<String, NamespaceStats> = BTreeMap::new();
+    for (namespace, _, metadata) in entries(dir) {
+        let ns = stats.entry(namespace).or_default();
+        ns.entries += 1;
+        ns.bytes += metadata.len();
+        if let Ok(modified) = metadata.modified() {
+            ns.oldest = Some(ns.oldest.map_or(modified, |o| o.min(modified)));
+        }
+    }
+    for (namespace, counters) in read_counters(&dir.join(STATS_FILE)) {
+        stats.entry(namespace).or_default().counters = counters;
+    }
+    stats
+}
+
+/// List cache entries as (namespace, path, metadata)
+fn entries(dir: &Path) -> Vec<(String, PathBuf, std::fs::Metadata)> {
+    let Ok(namespaces) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    namespaces
+        .flatten()
+        .filter(|ns| ns.path().is_dir())
+        .flat_map(|ns| {
+            let namespace = ns.file_name().to_string_lossy().to_string();
+            std::fs::read_dir(ns.path())
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(move |entry| {
+                    let metadata = entry.metadata().ok()?;
+                    metadata
+                        .is_file()
+                        .then(|| (namespace.clone(), entry.path(), metadata))
+                })
+        })
+        .collect()
+}
+
+/// Remove the whole cache directory, returning the number of entries removed
+pub fn clear(dir: &Path) -> Result<usize, String> {
+    let count = entries(dir).len();
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => Ok(count),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(format!("Failed to remove {}: {}", dir.display(), e)),
+    }
+}
+
+/// Remove entries last written before `older_than` ago, returning (entries, bytes) removed
+pub fn gc(dir: &Path, older_than: Duration) -> Result<(usize, u64), String> {
+    let cutoff = SystemTime::now()
+        .checked_sub(older_than)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut removed = (0, 0);
+    for (_, path, metadata) in entries(dir) {
+        if metadata.modified().is_ok_and(|m| m < cutoff) {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            removed.0 += 1;
+            removed.1 += metadata.len();
+        }
+    }
+    Ok(removed)
+}
+
+/// Parse a duration such as `30m`, `12h`, `7d`, or `2w`
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("Missing unit in duration '{}' (use s, m, h, d, or w)", s))?;
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}'", s))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("Unknown unit '{}' in duration '{}'", unit, s)),
+    };
+    number
+        .checked_mul(secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration '{}' is too long", s))
+}
+
+/// Format a byte count for humans
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Format cache stats as one line per namespace plus a total
+pub fn format_stats(stats: &BTreeMap<String, NamespaceStats>) -> String {
+    if stats.is_empty() {
+        return "Cache is empty".to_string();
+    }
+    let mut output = String::new();
+    let (mut entries, mut bytes) = (0, 0);
+    for (namespace, ns) in stats {
+        entries += ns.entries;
+        bytes += ns.bytes;
+        let lookups = ns.counters.hits + ns.counters.misses;
+        let hit_rate = if lookups == 0 {
+            "no lookups".to_string()
+        } else {
+            format!(
+                "{:.0}% hit rate ({}/{} lookups)",
+                ns.counters.hits as f64 * 100.0 / lookups as f64,
+                ns.counters.hits,
+                lookups
+            )
+        };
+        let age = ns
+            .oldest
+            .and_then(|o| o.elapsed().ok())
+            .map(|d| format!(", oldest {}d", d.as_secs() / (24 * 60 * 60)))
+            .unwrap_or_default();
+        output.push_str(&format!(
+            "{}: {} entries, {}{}, {}\n",
+            namespace,
+            ns.entries,
+            format_bytes(ns.bytes),
+            age,
+            hit_rate
+        ));
+    }
+    output.push_str(&format!(
+        "Total: {} entries, {}",
+        entries,
+        format_bytes(bytes)
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> crate::util::temp::TempDir {
+        crate::util::temp::TempDir::new(&format!(
+            "firekeeper-cache-{}-{}",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn write_entry(dir: &Path, namespace: &str, key: &str) {
+        std::fs::create_dir_all(dir.join(namespace)).unwrap();
+        std::fs::write(dir.join(namespace).join(format!("{}.json", key)), "{}").unwrap();
+    }
+
+    #[test]
+    fn test_stats() {
+        let dir = temp_dir("stats");
+        write_entry(dir.path(), "results", "a");
+        write_entry(dir.path(), "results", "b");
+        std::fs::write(
+            dir.path().join(STATS_FILE),
+            r#"{"results":{"hits":3,"misses":1}}"#,
+        )
+        .unwrap();
+
+        let stats = stats(dir.path());
+        let results = &stats["results"];
+        assert_eq!(results.entries, 2);
+        assert_eq!(results.bytes, 4);
+        assert_eq!(results.counters, Counters { hits: 3, misses: 1 });
+        assert!(format_stats(&stats).contains("results: 2 entries, 4 B, oldest 0d, 75% hit rate"));
+    }
+
+    #[test]
+    fn test_get_put() {
+        let dir = temp_dir("get-put");
+        assert_eq!(get::<Vec<u32>>(dir.path(), "results", "a"), None);
+        put(dir.path(), "results", "a", &vec![1, 2]).unwrap();
+        assert_eq!(
+            get::<Vec<u32>>(dir.path(), "results", "a"),
+            Some(vec![1, 2])
+        );
+        let stats = stats(dir.path());
+        assert_eq!(stats["results"].counters, Counters { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn test_concurrent_lookups_are_all_counted() {
+        let dir = temp_dir("concurrent");
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        get::<Vec<u32>>(dir.path(), "results", "missing");
+                    }
+                });
+            }
+        });
+        let stats = stats(dir.path());
+        assert_eq!(
+            stats["results"].counters,
+            Counters {
+                hits: 0,
+                misses: 80
+            }
+        );
+    }
+
+    #[test]
+    fn test_gc() {
+        let dir = temp_dir("gc");
+        write_entry(dir.path(), "results", "a");
+        assert_eq!(gc(dir.path(), Duration::from_secs(3600)).unwrap(), (0, 0));
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(gc(dir.path(), Duration::ZERO).unwrap(), (1, 2));
+        clear(dir.path()).unwrap();
+        assert!(!dir.path().exists());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604800));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert_eq!(
+            parse_duration("99999999999999999w").unwrap_err(),
+            "Duration '99999999999999999w' is too long"
+        );
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+    }
+}
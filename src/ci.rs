@@ -0,0 +1,74 @@
+use crate::cli::{CiArgs, ReviewArgs};
+use crate::github::{self, PullRequest};
+use crate::publish::PublishConfig;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Artifacts written under `--out-dir`
+const RESULTS_JSON: &str = "results.json";
+const RESULTS_MARKDOWN: &str = "results.md";
+const RESULTS_SARIF: &str = "results.sarif";
+
+fn in_github_actions() -> bool {
+    std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true")
+}
+
+/// Review arguments for `firekeeper ci`: JSON, Markdown and SARIF results under the output
+/// directory unless given explicitly, and annotations when running in GitHub Actions
+pub fn review_args(args: &CiArgs) -> Result<ReviewArgs, String> {
+    let mut review = args.review.clone();
+    let artifact = |name: &str| {
+        Path::new(&args.out_dir)
+            .join(name)
+            .to_string_lossy()
+            .to_string()
+    };
+    if review.output.is_empty() && review.retry_from.is_none() {
+        review.output = vec![artifact(RESULTS_JSON), artifact(RESULTS_MARKDOWN)];
+    }
+    if review.sarif.is_none() {
+        review.sarif = Some(artifact(RESULTS_SARIF));
+    }
+    review.annotations |= in_github_actions();
+    if !review.dry_run {
+        std::fs::create_dir_all(&args.out_dir)
+            .map_err(|e| format!("Failed to create {}: {}", args.out_dir, e))?;
+    }
+    Ok(review)
+}
+
+/// Pull request for the summary comment: `--pr`, or the pull request of the GitHub Actions run,
+/// with a client for its host.
+///
+/// Skipped with `--no-comment`, and outside pull requests or without a token.
+pub fn comment_target(
+    args: &CiArgs,
+    pull_request: Option<&PullRequest>,
+    publish: &PublishConfig,
+) -> Option<(github::Api, PullRequest)> {
+    if args.no_comment {
+        return None;
+    }
+    let pr = pull_request
+        .cloned()
+        .or_else(|| in_github_actions().then(PullRequest::from_env).flatten());
+    let Some(pr) = pr else {
+        info!("Not running for a pull request, skipping the summary comment");
+        return None;
+    };
+    let api = match github::Api::new(publish, &pr.host) {
+        Ok(api) => api,
+        Err(e) => {
+            warn!("{}, skipping the summary comment on {}", e, pr);
+            return None;
+        }
+    };
+    if !api.has_token() {
+        warn!(
+            "No token for {} (GITHUB_TOKEN, GH_TOKEN, or [publish] token_command), skipping the summary comment",
+            pr
+        );
+        return None;
+    }
+    Some((api, pr))
+}
@@ -23,6 +23,13 @@ pub struct Cli {
     )]
     pub log_level: String,
 
+    /// Run as if started in this directory, e.g. to review another repository: git
+    /// operations, file tools, resources, and relative paths resolve from there.
+    /// Repeat with `review` to review several repositories in one run, with a combined
+    /// report namespaced by repository directory name
+    #[arg(short = 'C', long, global = true, value_name = "PATH")]
+    pub repo: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -33,11 +40,52 @@ pub enum Commands {
     /// Initialize a default firekeeper.toml config file
     Init(InitArgs),
     /// Review code changes against rules
-    Review(ReviewArgs),
+    Review(Box<ReviewArgs>),
+    /// Review in CI with artifacts, annotations, a pull request comment, and exit codes
+    /// (1: blocking violations, 2: incomplete review)
+    Ci(Box<CiArgs>),
     /// Render JSON trace/output to Markdown
     Render(RenderArgs),
+    /// Check results/trace JSON files against the current schema version
+    ValidateResults(ValidateResultsArgs),
+    /// Replay fixtures from `render --extract-fixture` and check they report the
+    /// expected violations
+    Test(TestArgs),
+    /// Ask follow-up questions about a reported violation
+    Explain(ExplainArgs),
+    /// Step through violations and mark each as accepted, dismissed, or suppressed
+    Triage(TriageArgs),
+    /// Suggest rule instruction refinements from triaged review results, or new rules
+    /// from incident postmortems and review checklists
+    Suggest(SuggestArgs),
+    /// Evaluate rules against a labeled corpus and report precision/recall per model
+    Bench(BenchArgs),
+    /// Serve reviews of files and editor buffers over stdio as JSON-RPC, streaming
+    /// diagnostics back for editor integrations
+    Serve(ServeArgs),
+    /// Review uncommitted changes again whenever they change, until interrupted
+    Watch(WatchArgs),
+    /// Show trends across recorded review runs
+    Stats(StatsArgs),
+    /// Count the tokens of a file, stdin, or the diff to review, with the tokenizer of
+    /// the configured model
+    Tokens(TokensArgs),
+    /// Upload review results to a central service
+    Publish(PublishArgs),
+    /// Inspect and prune the on-disk cache
+    Cache(CacheArgs),
+    /// Install git hooks that run `firekeeper review`
+    InstallHooks(InstallHooksArgs),
+    /// Remove git hooks installed by `firekeeper install-hooks`
+    UninstallHooks(UninstallHooksArgs),
     /// Config file operations
     Config(ConfigArgs),
+    /// Rule catalog operations
+    Rules(RulesArgs),
+    /// Print shell completion script
+    Completions(CompletionsArgs),
+    /// Generate man pages
+    Man(ManArgs),
 }
 
 /// Template type for init command
@@ -49,6 +97,15 @@ pub enum Template {
     Full,
 }
 
+/// Git hook type
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Hook {
+    /// Review uncommitted (or staged) changes before each commit
+    PreCommit,
+    /// Review commits not yet on the upstream branch before each push
+    PrePush,
+}
+
 /// Arguments for the init command
 #[derive(Parser)]
 pub struct InitArgs {
@@ -63,15 +120,21 @@ pub struct InitArgs {
     /// Template to use
     #[arg(long, default_value = "fast")]
     pub template: Template,
+
+    /// Ask about languages, rules, LLM provider and CI platform instead of using a template,
+    /// and also write a CI workflow
+    #[arg(short, long, conflicts_with = "template")]
+    pub interactive: bool,
 }
 
 /// Arguments for the review command
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 pub struct ReviewArgs {
     /// Base commit to compare against.
     /// Examples: HEAD^ or ^, HEAD~1 or ~1, commit hash, @{1.day.ago}.
     /// HEAD for uncommitted changes, ROOT for all files
-    /// [default: HEAD if uncommitted changes exist, otherwise ^]
+    /// [default: merge/pull request base in GitHub Actions or GitLab CI,
+    /// otherwise HEAD if uncommitted changes exist, otherwise ^]
     #[arg(
         long,
         default_value = "",
@@ -80,41 +143,501 @@ pub struct ReviewArgs {
     )]
     pub base: String,
 
+    /// Audit the changes from --base to this commit instead of the working tree,
+    /// e.g. `--base v1.2.0 --head v1.3.0` before a release. Tasks are batched per
+    /// directory, results are cached per task so overlapping ranges are not reviewed
+    /// twice, and Markdown output is a release-notes style report
+    #[arg(
+        long,
+        requires = "base",
+        conflicts_with_all = ["staged", "files", "files_from"],
+        verbatim_doc_comment
+    )]
+    pub head: Option<String>,
+
+    /// Review a GitHub pull request by URL in a temporary clone
+    /// (e.g. https://github.com/org/repo/pull/123; self-hosted instances are set up in `[publish]`)
+    #[arg(
+        long,
+        conflicts_with_all = ["base", "head", "staged", "files", "files_from"],
+        verbatim_doc_comment
+    )]
+    pub pr: Option<String>,
+
+    /// Load the config from the pull request's head when there is none locally or on its
+    /// base branch. Its author controls that config, including shell commands it runs
+    #[arg(long, requires = "pr")]
+    pub trust_pr_config: bool,
+
+    /// Publish results as a comment on the pull request
+    /// (requires GITHUB_TOKEN, GH_TOKEN, or `[publish] token_command`)
+    #[arg(long, requires = "pr")]
+    pub pr_comment: bool,
+
+    /// Review only changes staged for commit (e.g. in a pre-commit hook)
+    #[arg(long, conflicts_with = "base")]
+    pub staged: bool,
+
+    /// Review these files instead of detecting changes with git (repeatable)
+    #[arg(long = "file", conflicts_with_all = ["base", "staged"])]
+    pub files: Vec<String>,
+
+    /// Read files to review from a list, one per line (`-` for stdin), instead of detecting changes with git
+    #[arg(long, conflicts_with_all = ["base", "staged"])]
+    pub files_from: Option<String>,
+
+    /// Only run rules with any of these tags (comma-separated, e.g. fast)
+    #[arg(long, value_delimiter = ',')]
+    pub rules_tags: Vec<String>,
+
+    /// Run this rule (name or id) without blocking, labeling its violations as quarantined (repeatable)
+    #[arg(long, value_name = "RULE")]
+    pub quarantine: Vec<String>,
+
+    /// Only review changed files matching these globs, after rule scopes are applied (repeatable)
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// Skip changed files matching these globs, after rule scopes are applied (repeatable)
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// Review against a one-off instruction instead of the configured rules
+    #[arg(long, conflicts_with = "rules_tags")]
+    pub inline_rule: Option<String>,
+
+    /// Glob patterns to scope the inline rule to (repeatable, defaults to all files)
+    #[arg(long, requires = "inline_rule")]
+    pub inline_scope: Vec<String>,
+
     /// Path to config file (initialize with `firekeeper init`)
     #[arg(long, default_value = "firekeeper.toml")]
     pub config: String,
 
+    /// Apply a `[profiles.<name>]` section of the config (e.g. ci, local)
+    #[arg(long, env = "FIREKEEPER_PROFILE")]
+    pub profile: Option<String>,
+
     /// Override config values using dot notation (e.g. llm.model=gpt-4)
     #[arg(long = "config-override")]
     pub config_overrides: Vec<String>,
 
     /// LLM API key
     #[arg(long, env = "FIREKEEPER_LLM_API_KEY", display_order = API_KEY_DISPLAY_ORDER)]
-    pub api_key: String,
+    pub api_key: Option<String>,
+
+    /// Zero-config mode when the config file is missing: run the built-in rules for the
+    /// repository's languages, non-blocking, with the API key from --api-key,
+    /// OPENROUTER_API_KEY, or OPENAI_API_KEY
+    #[arg(long, verbatim_doc_comment)]
+    pub auto: bool,
 
     /// Dry run: only show tasks without executing workers
     #[arg(long)]
     pub dry_run: bool,
 
-    /// Output file path (.md or .json)
-    #[arg(long)]
-    pub output: Option<String>,
+    /// Output file path (.md or .json), repeatable to write both.
+    /// With --dry-run, .json outputs receive the task plan for --plan
+    #[arg(long, verbatim_doc_comment)]
+    pub output: Vec<String>,
 
     /// Trace file path to record agent responses and tool use (.md or .json)
     #[arg(long)]
     pub trace: Option<String>,
+
+    /// Bundle file path (.fkbundle): one zip of the results, trace, config snapshot,
+    /// skipped files, and run manifest, read by `render`, `render --diff`, `triage`,
+    /// and `explain` in place of the JSON files
+    #[arg(long, conflicts_with = "dry_run", verbatim_doc_comment)]
+    pub bundle: Option<String>,
+
+    /// Shields.io endpoint badge file path (.json) summarizing the run
+    #[arg(long)]
+    pub badge: Option<String>,
+
+    /// Suppressions file path, as written by `firekeeper triage`
+    #[arg(long, default_value = crate::review::triage::DEFAULT_SUPPRESSIONS_PATH)]
+    pub suppressions: String,
+
+    /// SARIF file path (.sarif) for code scanning dashboards
+    #[arg(long)]
+    pub sarif: Option<String>,
+
+    /// Print GitHub Actions annotations on the lines of each violation
+    #[arg(long)]
+    pub annotations: bool,
+
+    /// Group Markdown and terminal output by file owner (CODEOWNERS or `[owners]`)
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+
+    /// Re-run only the tasks that failed, timed out, or were interrupted in a previous
+    /// `--output results.json`, merging their results into it (or into --output if given).
+    /// Use the same base as the previous run
+    #[arg(long, conflicts_with = "dry_run")]
+    pub retry_from: Option<String>,
+
+    /// Run the tasks of a plan written by `--dry-run --output plan.json`, e.g. edited
+    /// to a subset. Use the same base as the dry run
+    #[arg(long, conflicts_with = "retry_from")]
+    pub plan: Option<String>,
+
+    /// Process the review as a queue persisted in .firekeeper/queue.json, recording each
+    /// completed task, so very large reviews (e.g. `--base ROOT`) can be stopped and resumed
+    /// by running the same command again. The queue is removed once all tasks complete
+    #[arg(
+        long = "continue",
+        conflicts_with_all = ["dry_run", "retry_from", "plan"],
+        verbatim_doc_comment
+    )]
+    pub continue_queue: bool,
+
+    /// Review every task again instead of reusing the results of tasks with the same
+    /// rule, model, and diffs cached by earlier runs in .firekeeper/cache
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+/// Arguments for the ci command
+#[derive(Parser, Debug, Clone)]
+pub struct CiArgs {
+    #[command(flatten)]
+    pub review: ReviewArgs,
+
+    /// Directory for the results.json, results.md and results.sarif artifacts
+    /// (ignored for results given with --output or --sarif)
+    #[arg(long, default_value = "firekeeper-report", verbatim_doc_comment)]
+    pub out_dir: String,
+
+    /// Do not post or update the summary comment on the pull request
+    #[arg(long)]
+    pub no_comment: bool,
 }
 
 /// Arguments for the render command
 #[derive(Parser, Debug)]
 pub struct RenderArgs {
-    /// Input JSON file path (trace or output)
+    /// Input JSON file path (trace or output), or a `review --bundle` to render its results
+    #[arg(long, required_unless_present = "diff")]
+    pub input: Option<String>,
+
+    /// Compare two JSON trace files or bundles of the same review, e.g. before and after
+    /// editing a rule:
+    /// workers are aligned by rule and files, showing changes in the instruction,
+    /// reported violations, and tool calls
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"], conflicts_with_all = ["input", "group_by"])]
+    pub diff: Vec<String>,
+
+    /// Output file path: Markdown, or JSON with --extract-fixture (prints to stdout if omitted)
+    #[arg(long)]
+    pub output: Option<String>,
+
+    /// Group results by the owners recorded on each violation
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+
+    /// Extract a worker of a JSON trace as a replayable JSON fixture (prompt, model
+    /// responses, tool results, and reported violations) for regression tests
+    #[arg(
+        long,
+        value_name = "WORKER_ID",
+        requires = "input",
+        conflicts_with = "group_by",
+        verbatim_doc_comment
+    )]
+    pub extract_fixture: Option<String>,
+}
+
+/// Arguments for the validate-results command
+#[derive(Parser, Debug)]
+pub struct ValidateResultsArgs {
+    /// Results or trace JSON files from `firekeeper review --output` or `--trace`
+    #[arg(required = true)]
+    pub files: Vec<String>,
+
+    /// Upgrade files written by an older version in place
+    #[arg(long)]
+    pub migrate: bool,
+}
+
+/// Arguments for the test command
+#[derive(Parser, Debug)]
+pub struct TestArgs {
+    /// Fixture JSON files from `firekeeper render --extract-fixture`
+    #[arg(required = true)]
+    pub fixtures: Vec<String>,
+}
+
+/// Alternative grouping of violation reports
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum GroupBy {
+    /// Group by file owner, then file
+    Owner,
+}
+
+/// Arguments for the explain command
+#[derive(Parser, Debug)]
+pub struct ExplainArgs {
+    /// Results JSON file from `firekeeper review --output`, or a `--bundle`
+    pub results: String,
+
+    /// Violation to explain: 1-based index or fingerprint prefix.
+    /// Lists all violations if omitted
+    #[arg(verbatim_doc_comment)]
+    pub violation: Option<String>,
+
+    /// Trace JSON file from the same run, to resume the reporting worker's conversation
+    /// (defaults to the trace of a bundle)
+    #[arg(long)]
+    pub trace: Option<String>,
+
+    /// Path to config file
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    /// LLM API key
+    #[arg(long, env = "FIREKEEPER_LLM_API_KEY", display_order = API_KEY_DISPLAY_ORDER)]
+    pub api_key: Option<String>,
+}
+
+/// Arguments for the triage command
+#[derive(Parser, Debug)]
+pub struct TriageArgs {
+    /// Results JSON file from `firekeeper review --output`, or a `--bundle`
+    pub results: String,
+
+    /// Triage decisions file path
+    #[arg(long, default_value = crate::review::triage::DEFAULT_DECISIONS_PATH)]
+    pub decisions: String,
+
+    /// Suppressions file path, applied by `firekeeper review`
+    #[arg(long, default_value = crate::review::triage::DEFAULT_SUPPRESSIONS_PATH)]
+    pub suppressions: String,
+
+    /// Revisit violations that already have a decision
     #[arg(long)]
-    pub input: String,
+    pub all: bool,
+}
+
+/// Arguments for the suggest command
+#[derive(Parser, Debug)]
+pub struct SuggestArgs {
+    /// Results JSON file from `firekeeper review --output`
+    #[arg(required_unless_present_any = ["from_file", "from_lint"])]
+    pub results: Option<String>,
+
+    /// Derive candidate rules from incident postmortems or review checklists instead
+    /// (file path or http(s):// URL, repeatable)
+    #[arg(long, conflicts_with = "results")]
+    pub from_file: Vec<String>,
 
-    /// Output Markdown file path (prints to stdout if omitted)
+    /// Derive candidate rules for the intent behind linter configs instead, covering
+    /// policies the linters can't express (e.g. `.eslintrc`, `clippy.toml`, repeatable)
+    #[arg(long, conflicts_with_all = ["results", "from_file"])]
+    pub from_lint: Vec<String>,
+
+    /// Triage decisions file from `firekeeper triage`
+    #[arg(long, default_value = crate::review::triage::DEFAULT_DECISIONS_PATH)]
+    pub decisions: String,
+
+    /// Path to config file
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    /// Output TOML file path (prints to stdout if omitted)
     #[arg(long)]
     pub output: Option<String>,
+
+    /// LLM API key
+    #[arg(long, env = "FIREKEEPER_LLM_API_KEY", display_order = API_KEY_DISPLAY_ORDER)]
+    pub api_key: Option<String>,
+}
+
+/// Arguments for the bench command
+#[derive(Parser, Debug)]
+pub struct BenchArgs {
+    /// Corpus directory with one subdirectory per case, each containing
+    /// `diff.patch` and `expected.json`
+    #[arg(verbatim_doc_comment)]
+    pub corpus: String,
+
+    /// Models to compare (comma-separated, defaults to the configured model)
+    #[arg(long, value_delimiter = ',')]
+    pub models: Vec<String>,
+
+    /// Path to config file
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    /// Apply a `[profiles.<name>]` section of the config
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Override config values using dot notation (e.g. llm.base_url=...)
+    #[arg(long = "config-override")]
+    pub config_overrides: Vec<String>,
+
+    /// LLM API key
+    #[arg(long, env = "FIREKEEPER_LLM_API_KEY", display_order = API_KEY_DISPLAY_ORDER)]
+    pub api_key: Option<String>,
+}
+
+/// Arguments for the watch command
+#[derive(Parser, Debug)]
+pub struct WatchArgs {
+    /// Path to config file
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    /// Apply a `[profiles.<name>]` section of the config
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Override config values using dot notation (e.g. llm.base_url=...)
+    #[arg(long = "config-override")]
+    pub config_overrides: Vec<String>,
+
+    /// Only run rules with any of these tags (comma-separated, e.g. fast)
+    #[arg(long, value_delimiter = ',')]
+    pub rules_tags: Vec<String>,
+
+    /// Seconds between checks of the working tree for changes
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+
+    /// Serve Prometheus metrics at `/metrics` on this address (e.g. 127.0.0.1:9464)
+    #[arg(long, value_name = "ADDR")]
+    pub metrics: Option<String>,
+
+    /// LLM API key
+    #[arg(long, env = "FIREKEEPER_LLM_API_KEY", display_order = API_KEY_DISPLAY_ORDER)]
+    pub api_key: Option<String>,
+}
+
+/// Arguments for the serve command
+#[derive(Parser, Debug)]
+pub struct ServeArgs {
+    /// Path to config file
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    /// Apply a `[profiles.<name>]` section of the config
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Override config values using dot notation (e.g. llm.base_url=...)
+    #[arg(long = "config-override")]
+    pub config_overrides: Vec<String>,
+
+    /// Serve Prometheus metrics at `/metrics` on this address (e.g. 127.0.0.1:9464)
+    #[arg(long, value_name = "ADDR")]
+    pub metrics: Option<String>,
+
+    /// LLM API key
+    #[arg(long, env = "FIREKEEPER_LLM_API_KEY", display_order = API_KEY_DISPLAY_ORDER)]
+    pub api_key: Option<String>,
+}
+
+/// Arguments for the stats command
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    /// Run history file written by `firekeeper review`
+    #[arg(long, default_value = crate::review::history::DEFAULT_HISTORY_PATH)]
+    pub history: String,
+
+    /// Only include the most recent N runs
+    #[arg(long)]
+    pub last: Option<usize>,
+
+    /// Report rules whose findings changed between runs on identical diffs
+    #[arg(long)]
+    pub flaky: bool,
+}
+
+/// Arguments for the tokens command
+#[derive(Parser, Debug)]
+pub struct TokensArgs {
+    /// File to count (`-` for stdin)
+    #[arg(required_unless_present = "diff")]
+    pub input: Option<String>,
+
+    /// Count the diff of each changed file instead, as sent to reviewers
+    #[arg(long, conflicts_with = "input")]
+    pub diff: bool,
+
+    /// Base commit of the diff, as for `firekeeper review --base`
+    #[arg(long, default_value = "", hide_default_value = true, requires = "diff")]
+    pub base: String,
+
+    /// Model whose tokenizer to use [default: llm.model of the config]
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// Path to config file
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+}
+
+/// Arguments for the cache command
+#[derive(Parser, Debug)]
+pub struct CacheArgs {
+    /// Cache directory
+    #[arg(long, global = true, default_value = crate::cache::DEFAULT_CACHE_DIR)]
+    pub dir: String,
+
+    #[command(subcommand)]
+    pub command: CacheCommands,
+}
+
+/// Cache subcommands
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Show cache size and hit rates
+    Stats,
+    /// Remove all cache entries
+    Clear,
+    /// Remove old cache entries
+    Gc {
+        /// Remove entries older than this (e.g. 12h, 7d, 2w)
+        #[arg(long, default_value = "7d", value_parser = crate::cache::parse_duration)]
+        older_than: std::time::Duration,
+    },
+}
+
+/// Arguments for the install-hooks command
+#[derive(Parser, Debug)]
+pub struct InstallHooksArgs {
+    /// Hooks to install (comma-separated)
+    #[arg(long, value_delimiter = ',', default_value = "pre-commit")]
+    pub hooks: Vec<Hook>,
+
+    /// Review only staged changes in the pre-commit hook
+    #[arg(long)]
+    pub staged: bool,
+
+    /// Only run rules with any of these tags (comma-separated, e.g. fast)
+    #[arg(long, value_delimiter = ',')]
+    pub rules_tags: Vec<String>,
+
+    /// Path to config file used by the hooks
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    /// Config profile used by the hooks (e.g. local)
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Overwrite existing hooks not installed by firekeeper
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Arguments for the uninstall-hooks command
+#[derive(Parser, Debug)]
+pub struct UninstallHooksArgs {
+    /// Hooks to remove (comma-separated)
+    #[arg(long, value_delimiter = ',', default_value = "pre-commit,pre-push")]
+    pub hooks: Vec<Hook>,
 }
 
 /// Arguments for the config command
@@ -136,3 +659,143 @@ pub enum ConfigCommands {
     /// Validate config file
     Validate,
 }
+
+/// Arguments for the rules command
+#[derive(Parser, Debug)]
+pub struct RulesArgs {
+    /// Config file path
+    #[arg(long, global = true, default_value = "firekeeper.toml")]
+    pub config: String,
+
+    #[command(subcommand)]
+    pub command: RulesCommands,
+}
+
+/// Rules subcommands
+#[derive(Subcommand, Debug)]
+pub enum RulesCommands {
+    /// Export the configured rules as a machine-readable catalog
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "json")]
+        format: RulesFormat,
+        /// Output file path (prints to stdout if omitted)
+        #[arg(long)]
+        output: Option<String>,
+    },
+}
+
+/// Rule catalog format
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum RulesFormat {
+    /// JSON catalog with ids, scopes, and tags
+    Json,
+}
+
+/// Arguments for the publish command
+#[derive(Parser, Debug)]
+pub struct PublishArgs {
+    #[command(subcommand)]
+    pub command: PublishCommands,
+}
+
+/// Publish targets
+#[derive(Subcommand, Debug)]
+pub enum PublishCommands {
+    /// POST the results JSON (and optionally the trace) to an HTTP endpoint
+    Http {
+        /// Endpoint URL, e.g. https://reviews.example.com/api/results
+        #[arg(long)]
+        url: String,
+        /// Results JSON file from `firekeeper review --output`
+        #[arg(long)]
+        results: String,
+        /// Trace JSON file from `firekeeper review --trace` to include
+        #[arg(long)]
+        trace: Option<String>,
+        /// Request header as `Name: value` (repeatable).
+        /// FIREKEEPER_PUBLISH_TOKEN is sent as a bearer token unless an Authorization header is given
+        #[arg(long = "header", verbatim_doc_comment)]
+        headers: Vec<String>,
+    },
+    /// Post a summary of the results to a Slack incoming webhook
+    Slack(NotifyArgs),
+    /// Post a summary of the results to a Microsoft Teams workflow webhook
+    Teams(NotifyArgs),
+    /// POST a summary of the results as JSON to a webhook
+    Webhook(NotifyArgs),
+}
+
+/// Arguments for notification targets of the publish command
+#[derive(Parser, Debug)]
+pub struct NotifyArgs {
+    /// Webhook URL (defaults to the target's `url` under `[publish]`)
+    #[arg(long, env = "FIREKEEPER_NOTIFY_URL", hide_env_values = true)]
+    pub url: Option<String>,
+
+    /// Results JSON file from `firekeeper review --output`
+    #[arg(long)]
+    pub results: String,
+
+    /// Link to the full report, e.g. the CI job or artifact
+    #[arg(long)]
+    pub report_url: Option<String>,
+
+    /// Message template with placeholders such as {status}, {blocking}, {blocking_rules},
+    /// and {report_url} (defaults to the target's `template` under `[publish]`)
+    #[arg(long, verbatim_doc_comment)]
+    pub template: Option<String>,
+
+    /// Path to config file, for the webhook settings and which rules block
+    #[arg(long, default_value = "firekeeper.toml")]
+    pub config: String,
+}
+
+/// Arguments for the completions command
+#[derive(Parser, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    pub shell: clap_complete::Shell,
+}
+
+/// Arguments for the man command
+#[derive(Parser, Debug)]
+pub struct ManArgs {
+    /// Directory to write man pages for all subcommands (prints the main page to stdout if omitted)
+    #[arg(long)]
+    pub out_dir: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_review_suppressions_path() {
+        let suppressions = |args: &[&str]| match Cli::parse_from(args).command {
+            Commands::Review(args) => args.suppressions,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            suppressions(&["firekeeper", "review", "--api-key", "key"]),
+            crate::review::triage::DEFAULT_SUPPRESSIONS_PATH
+        );
+        assert_eq!(
+            suppressions(&[
+                "firekeeper",
+                "review",
+                "--api-key",
+                "key",
+                "--suppressions",
+                "ci/suppressions.json"
+            ]),
+            "ci/suppressions.json"
+        );
+    }
+}
@@ -1,11 +1,24 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::path::Path;
 use toml_scaffold::TomlScaffold;
+use tracing::info;
 
+use crate::llm::{Capabilities, ProviderKind, RetryConfig, ToolProtocol};
+use crate::mcp::McpServerConfig;
+use crate::publish::PublishConfig;
+use crate::review::audit::AuditConfig;
+use crate::review::filters::FiltersConfig;
+use crate::review::usage::ModelPrice;
 use crate::rule::body::RuleBody;
+use crate::rule::resource::Resource;
+use crate::rule::tools::ToolsConfig;
+use crate::telemetry::TelemetryConfig;
+use crate::tool::sh::Shell;
+use crate::util::DiffStyle;
 
 /// Configuration for Firekeeper.
 ///
@@ -20,6 +33,45 @@ pub struct Config {
     pub review: ReviewConfig,
     /// Code review rules
     pub rules: Vec<crate::rule::body::RuleBody>,
+    /// Anonymous usage metrics (opt-in)
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// Named setting overrides selected with `--profile` (optional), e.g. `[profiles.ci]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Owners by path pattern in CODEOWNERS syntax (optional), e.g. `"/src/api/" = ["@org/api"]`.
+    /// Added to CODEOWNERS, with the longest matching pattern here taking precedence.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, Vec<String>>,
+    /// Drop or downgrade collected violations by rule and path before output and gating (optional),
+    /// e.g. `drop = [{ rule = "No Magic Numbers", path = "benches/**" }]`
+    #[serde(default, skip_serializing_if = "FiltersConfig::is_empty")]
+    pub filters: FiltersConfig,
+    /// Self-hosted GitHub settings for `--pr` and pull request comments, and webhooks for
+    /// `publish slack|teams|webhook` (optional), e.g. `api_base = "https://github.example.com/api/v3"`
+    #[serde(default, skip_serializing_if = "PublishConfig::is_default")]
+    pub publish: PublishConfig,
+    /// Append-only log of each review's gating decision (optional),
+    /// e.g. `path = ".firekeeper/audit.jsonl"`
+    #[serde(default, skip_serializing_if = "AuditConfig::is_default")]
+    pub audit: AuditConfig,
+}
+
+/// Settings applied on top of the config when selected with `--profile`
+#[derive(Deserialize, Serialize, Debug, Clone, Default, JsonSchema, TomlScaffold)]
+pub struct Profile {
+    /// LLM settings to override, e.g. `{ model = "..." }` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm: Option<Value>,
+    /// Review settings to override, e.g. `{ max_parallel_workers = 4 }` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<Value>,
+    /// Only run rules with any of these tags (optional, defaults to all rules)
+    #[serde(default)]
+    pub rules_tags: Vec<String>,
+    /// Override whether violations of every rule block the pipeline (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocking: Option<bool>,
 }
 
 impl Config {
@@ -32,6 +84,12 @@ impl Config {
                 RuleBody::no_magic_numbers(),
                 RuleBody::no_hardcoded_credentials(),
             ],
+            telemetry: TelemetryConfig::default(),
+            profiles: BTreeMap::new(),
+            owners: BTreeMap::new(),
+            filters: FiltersConfig::default(),
+            publish: PublishConfig::default(),
+            audit: AuditConfig::default(),
         }
     }
 
@@ -45,6 +103,12 @@ impl Config {
                 RuleBody::no_hardcoded_credentials(),
                 RuleBody::no_code_duplication(),
             ],
+            telemetry: TelemetryConfig::default(),
+            profiles: BTreeMap::new(),
+            owners: BTreeMap::new(),
+            filters: FiltersConfig::default(),
+            publish: PublishConfig::default(),
+            audit: AuditConfig::default(),
         }
     }
 }
@@ -53,8 +117,20 @@ impl Config {
 #[derive(Deserialize, Serialize, Debug, JsonSchema, TomlScaffold)]
 #[serde(default)]
 pub struct LlmConfig {
-    /// OpenAI compatible API base URL
-    pub base_url: String,
+    /// API of the provider: `openai` for OpenAI-compatible APIs (default),
+    /// `anthropic` for the Anthropic Messages API, or `azure` for Azure OpenAI
+    pub provider: ProviderKind,
+    /// API base URL (optional, defaults to `https://openrouter.ai/api/v1` for `provider =
+    /// "openai"` and `https://api.anthropic.com/v1` for `provider = "anthropic"`). Required
+    /// for `provider = "azure"`: the resource endpoint `https://<resource>.openai.azure.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Azure OpenAI deployment (optional, defaults to the model name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    /// Azure OpenAI API version (optional, defaults to a GA version)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
     /// LLM model name
     pub model: String,
     /// Custom HTTP headers (optional)
@@ -62,12 +138,43 @@ pub struct LlmConfig {
     /// Custom request body fields (optional)
     #[format = "*dotted"]
     pub body: Value,
+    /// How tools are offered to the model: `native` tool calls (default),
+    /// `react` text blocks for models without tool calling, or `auto` to switch
+    /// to text blocks once the endpoint rejects tools or the model garbles a tool call
+    /// (e.g. local models via Ollama)
+    pub tool_protocol: ToolProtocol,
+    /// Stream replies from OpenAI-compatible and Azure providers (defaults to false), logging progress
+    /// as text and tool calls arrive (`RUST_LOG=firekeeper=debug`) and keeping the partial
+    /// reply of a cancelled worker in the trace
+    pub stream: bool,
+    /// What the model supports (optional, probed from the provider where it publishes
+    /// them), e.g. `{ parallel_tool_calls = false, context_tokens = 32768 }`.
+    /// Unsupported features are left out of requests.
+    #[serde(skip_serializing_if = "Capabilities::is_default")]
+    pub capabilities: Capabilities,
+    /// Retries of requests failed by rate limits, server or network errors (optional),
+    /// e.g. `{ max_retries = 5, base_delay_ms = 2000 }`
+    #[serde(skip_serializing_if = "RetryConfig::is_default")]
+    pub retry: RetryConfig,
+    /// Model prices in USD per million tokens (optional), e.g.
+    /// `{ "google/gemini-3-flash-preview" = { prompt = 0.5, completion = 3.0 } }`,
+    /// to report the cost of each task, rule, and run
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub prices: HashMap<String, ModelPrice>,
+    /// Prompt and completion tokens all workers of a run may use (optional, defaults to
+    /// no limit). Once spent, tasks not yet started are skipped and listed as incomplete,
+    /// to re-run with `--retry-from`. Running tasks finish, so a run may exceed it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_total_tokens: Option<usize>,
 }
 
 impl Default for LlmConfig {
     fn default() -> Self {
         Self {
-            base_url: "https://openrouter.ai/api/v1".into(),
+            provider: ProviderKind::OpenAI,
+            base_url: Some(ProviderKind::OpenAI.default_base_url().into()),
+            deployment: None,
+            api_version: None,
             model: "google/gemini-3-flash-preview".into(),
             headers: HashMap::from([
                 (
@@ -82,6 +189,91 @@ impl Default for LlmConfig {
                     "effort": "medium",
                 },
             }),
+            tool_protocol: ToolProtocol::Native,
+            stream: false,
+            capabilities: Capabilities::default(),
+            retry: RetryConfig::default(),
+            prices: HashMap::new(),
+            max_total_tokens: None,
+        }
+    }
+}
+
+impl LlmConfig {
+    /// API base URL: `base_url`, or the default of the provider
+    pub fn base_url(&self) -> &str {
+        self.base_url
+            .as_deref()
+            .unwrap_or(self.provider.default_base_url())
+    }
+
+    /// URL requests are sent to: `base_url`, or for Azure the deployment endpoint
+    /// with the API version
+    pub fn endpoint(&self) -> String {
+        match self.provider {
+            ProviderKind::Azure => crate::llm::azure::endpoint(
+                self.base_url(),
+                self.deployment.as_deref().unwrap_or(&self.model),
+                self.api_version
+                    .as_deref()
+                    .unwrap_or(crate::llm::azure::DEFAULT_API_VERSION),
+            ),
+            _ => self.base_url().to_string(),
+        }
+    }
+
+    /// Whether the provider runs on this machine, e.g. Ollama, so no API key is needed
+    pub fn is_local(&self) -> bool {
+        reqwest::Url::parse(self.base_url())
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .is_some_and(|host| {
+                matches!(
+                    host.as_str(),
+                    "localhost" | "127.0.0.1" | "[::1]" | "0.0.0.0"
+                )
+            })
+    }
+}
+
+/// Gate features on a model's capabilities: offer tools as text without tool calling,
+/// drop body fields the model does not support, and fit the conversation budget to
+/// its context window
+pub fn gate_features(
+    capabilities: &Capabilities,
+    tool_protocol: &mut ToolProtocol,
+    body: &mut Value,
+    max_history_tokens: &mut usize,
+) {
+    if capabilities.tool_calls == Some(false) && *tool_protocol != ToolProtocol::React {
+        info!("Model has no tool calling, offering tools as text (tool_protocol = \"react\")");
+        *tool_protocol = ToolProtocol::React;
+    }
+    let mut unsupported = Vec::new();
+    if capabilities.parallel_tool_calls == Some(false) || capabilities.tool_calls == Some(false) {
+        unsupported.push("parallel_tool_calls");
+    }
+    if capabilities.json_mode == Some(false) {
+        unsupported.push("response_format");
+    }
+    if let Some(body) = body.as_object_mut() {
+        for field in unsupported {
+            if body.remove(field).is_some() {
+                info!(
+                    "Model does not support `{}`, leaving it out of requests",
+                    field
+                );
+            }
+        }
+    }
+    if let Some(context) = capabilities.context_tokens {
+        let budget = context * ReviewConfig::HISTORY_CONTEXT_PERCENT / 100;
+        if *max_history_tokens > budget {
+            info!(
+                "Capping max_history_tokens to {} for the model's {}-token context",
+                budget, context
+            );
+            *max_history_tokens = budget;
         }
     }
 }
@@ -92,8 +284,12 @@ impl Default for LlmConfig {
 pub struct ReviewConfig {
     /// Default maximum number of files to review per task
     pub max_files_per_task: usize,
-    /// Maximum number of parallel workers (optional, defaults to unlimited)
+    /// Maximum number of parallel workers (optional).
+    /// Derived from CPU count and requests-per-minute limits when unset.
     pub max_parallel_workers: Option<usize>,
+    /// LLM requests per minute allowed by the provider (optional).
+    /// Caps derived parallelism together with any limit the provider advertises.
+    pub requests_per_minute: Option<u32>,
     /// Worker timeout in seconds (defaults to 300)
     pub timeout: u64,
     /// Global resources to include in review context.
@@ -101,12 +297,150 @@ pub struct ReviewConfig {
     /// Supported formats:
     /// - `file://glob` - Include matched files' full text, e.g. `file://**/README.md`
     /// - `sh://command` - Include command output, e.g. `sh://git ls-files`
-    /// - `skill://glob` - Include matched skills, e.g. `skill://~/skills/**/SKILL.md`
-    pub resources: Vec<String>,
+    /// - `skill://glob` - Include matched skills' instructions, e.g. `skill://.skills/review/SKILL.md`
+    /// - `skill://dir` - List skills under a directory for the agent to load on demand, e.g. `skill://~/skills`
+    /// - `https://url` - Include a web page converted to Markdown, e.g. `https://example.com/style-guide`
+    /// - `mcp://server/uri` - Include a resource read from a configured MCP server, e.g. `mcp://docs/docs://style-guide`
+    /// - `results://previous` - Include the previous run's violations in the task's files, to check whether they were fixed
+    /// - `results://path` - Include violations in the task's files from a results JSON file, e.g. `results://main-results.json`
+    /// - `cargo://metadata` - Include the Rust workspace's crates, features, and dependencies from `cargo metadata`
+    ///
+    /// URIs may reference run variables: `{base}` (the revision compared against),
+    /// `{files}` (the task's files), `{rule.name}`, and `{rule.id}`, e.g. `sh://git log {base}..HEAD --stat`.
+    ///
+    /// Resources that fail to load are skipped with a warning.
+    /// Use `{ uri = "...", required = true }` to fail the task instead.
+    /// Use `{ uri = "...", inline = false }` to list a resource for the agent to read on demand
+    /// with the `resource` tool instead of including it in the prompt, e.g. for large style guides.
+    pub resources: Vec<Resource>,
+    /// Fail the task if any resource fails to load, as if all resources were required
+    pub resources_strict: bool,
+    /// Follow symlinks in `file://` and `skill://` resource globs that resolve outside the
+    /// repository (or the glob's absolute or `~` root) (defaults to false). Symlinks inside
+    /// it are always followed, and each directory is walked once.
+    pub follow_symlinks: bool,
+    /// Run `sh://` resources with any command (defaults to false).
+    /// Only enable for trusted configs: rules from remote rule packs could run arbitrary commands.
+    pub allow_sh_resources: bool,
+    /// Commands `sh://` resources may run besides `allowed_shell_commands`, checked with the
+    /// same validator as the agent's `sh` tool (defaults to ["git"])
+    pub sh_resource_commands: Vec<String>,
+    /// MCP servers providing `mcp://<server>/<resource uri>` resources, keyed by server name (optional).
+    ///
+    /// e.g. `docs = { command = "npx", args = ["-y", "@acme/docs-mcp"] }`
+    pub mcp_servers: HashMap<String, McpServerConfig>,
+    /// Maximum characters per loaded resource, truncated with a hint beyond this. The files of
+    /// a `file://` glob share the limit; files beyond it are listed for the agent to read.
+    /// Resources are loaded once per run and shared by all workers.
+    pub max_resource_chars: usize,
     /// Allowed shell commands during review (read-only operations only).
-    /// Commands are executed via sh on Unix/Linux, PowerShell on Windows.
+    /// Commands are executed via `shell`.
     /// Add tools like `rg` (ripgrep), `sg` (ast-grep), `fd`, `jq` to enhance search capabilities.
     pub allowed_shell_commands: Vec<String>,
+    /// Shell running the `sh` tool and `sh://` resources: sh, bash, zsh, pwsh, powershell or cmd
+    /// (optional, defaults to sh on Unix and powershell on Windows). Commands are validated
+    /// with the shell's syntax, so `allowed_shell_commands` should be commands of that shell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Shell>,
+    /// Ask the agent to call `think` before its first report of violations (defaults to true).
+    /// Reasoning first filters out borderline findings.
+    pub require_think: bool,
+    /// Built-in tools to disable for all rules, e.g. { fetch = false }.
+    /// Rules may override each tool with their own `tools` table.
+    pub tools: ToolsConfig,
+    /// Prompt templates for review workers
+    pub prompts: PromptsConfig,
+    /// Estimated tokens of a worker's conversation before old tool outputs are evicted
+    /// (0 disables). The system prompt, rule, reasoning, and recent turns are kept.
+    pub max_history_tokens: usize,
+    /// Sampling of enormous changesets, e.g. vendored imports
+    pub sampling: SamplingConfig,
+    /// Glob patterns of vendored or third-party code skipped by the review, e.g.
+    /// ["third_party/**", "vendor/**"]. Files marked `linguist-vendored` in `.gitattributes`
+    /// are skipped too. Rules with `vendored = true` still review them.
+    pub vendored: Vec<String>,
+    /// Include a repository overview (top-level layout, languages, manifest excerpts)
+    /// in each worker's prompt, generated once per run (defaults to true)
+    pub repo_overview: bool,
+    /// Language for violation details and report text, e.g. `ja` (optional, defaults to English)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Violations reported per rule before the rest are summarized as "and N more similar
+    /// issues" (optional, defaults to no limit). Rules may override it with `max_violations`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_violations: Option<usize>,
+    /// Fetch the base from `origin` when a shallow clone lacks it, e.g. in CI (defaults to true).
+    /// When disabled, or the fetch fails, the review fails with a remediation message.
+    pub fetch_missing_base: bool,
+    /// Format of diffs shown to workers: `unified` (`git diff` output) or `compact`, which drops
+    /// file headers and long runs of context for smaller prompts (defaults to unified)
+    pub diff_style: DiffStyle,
+    /// Number the lines of compact diffs with their line in the new file, so reported line
+    /// ranges match the file (defaults to false)
+    pub diff_line_numbers: bool,
+}
+
+/// Sampling of changesets too large to review fully.
+///
+/// Blocking rules still review every file in their scope, while non-blocking rules review
+/// a weighted sample of the changed files, favoring larger changes. Skipped files are listed
+/// in the results.
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct SamplingConfig {
+    /// Sample when more files than this are reviewed (optional, defaults to never sampling)
+    pub threshold: Option<usize>,
+    /// Number of files non-blocking rules review when sampling
+    pub sample_files: usize,
+}
+
+impl SamplingConfig {
+    /// Default sample size, enough to spot patterns across a large import
+    /// at a fraction of the cost of reviewing it fully.
+    const DEFAULT_SAMPLE_FILES: usize = 200;
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            sample_files: Self::DEFAULT_SAMPLE_FILES,
+        }
+    }
+}
+
+/// Prompt templates for review workers, e.g. to add organization context or localize
+#[derive(Deserialize, Serialize, Debug, Clone, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct PromptsConfig {
+    /// System prompt
+    pub system: String,
+    /// User message layout. Placeholders expand to whole sections (empty when not applicable):
+    /// `{overview}`, `{commit_messages}`, `{files}`, `{rule}`, `{diffs}`, `{resources}`
+    pub user: String,
+}
+
+impl PromptsConfig {
+    const DEFAULT_SYSTEM: &str = r"You are a code reviewer. Your task is to review code changes against a specific rule.
+Focus only on the files provided and only check for violations of the given rule.
+You can read related files if needed, but only report issues related to the provided files and rule.
+
+Workflow:
+1. Review the provided diffs to understand what changed
+2. Read other related diffs or files if needed for context
+3. Use the 'think' tool to reason about whether the changes violate the rule
+4. Use the 'report' tool to report all violations found, then exit without summary";
+
+    const DEFAULT_USER: &str = "{overview}{commit_messages}{files}{rule}{diffs}{resources}";
+}
+
+impl Default for PromptsConfig {
+    fn default() -> Self {
+        Self {
+            system: Self::DEFAULT_SYSTEM.to_string(),
+            user: Self::DEFAULT_USER.to_string(),
+        }
+    }
 }
 
 impl ReviewConfig {
@@ -114,6 +448,19 @@ impl ReviewConfig {
     /// 5 is a balanced value for most rules,
     /// allowing each worker to review multiple files without overwhelming the context.
     const DEFAULT_MAX_FILES_PER_TASK: usize = 5;
+
+    /// Default maximum characters per resource.
+    /// Large enough for typical docs and file listings, small enough that
+    /// a broad glob or verbose command does not crowd out the diffs.
+    const DEFAULT_MAX_RESOURCE_CHARS: usize = 50_000;
+
+    /// Default conversation budget before compaction.
+    /// Leaves room for the reply within the 128k context most models offer.
+    const DEFAULT_MAX_HISTORY_TOKENS: usize = 100_000;
+
+    /// Share of a model's context window the conversation may fill, the same as the default
+    /// budget leaves of a 128k context
+    const HISTORY_CONTEXT_PERCENT: usize = 78;
 }
 
 impl Default for ReviewConfig {
@@ -121,8 +468,15 @@ impl Default for ReviewConfig {
         Self {
             max_files_per_task: Self::DEFAULT_MAX_FILES_PER_TASK,
             max_parallel_workers: None,
+            requests_per_minute: None,
             timeout: 300,
             resources: vec![],
+            resources_strict: false,
+            follow_symlinks: false,
+            allow_sh_resources: false,
+            sh_resource_commands: vec!["git".to_string()],
+            mcp_servers: HashMap::new(),
+            max_resource_chars: Self::DEFAULT_MAX_RESOURCE_CHARS,
             allowed_shell_commands: if cfg!(windows) {
                 vec![
                     "Get-ChildItem".to_string(),
@@ -144,6 +498,19 @@ impl Default for ReviewConfig {
                     "wc".to_string(),
                 ]
             },
+            shell: None,
+            require_think: true,
+            tools: ToolsConfig::default(),
+            prompts: PromptsConfig::default(),
+            max_history_tokens: Self::DEFAULT_MAX_HISTORY_TOKENS,
+            sampling: SamplingConfig::default(),
+            vendored: vec![],
+            repo_overview: true,
+            language: None,
+            max_violations: None,
+            fetch_missing_base: true,
+            diff_style: DiffStyle::default(),
+            diff_line_numbers: false,
         }
     }
 }
@@ -151,10 +518,123 @@ impl Default for ReviewConfig {
 impl Config {
     pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let content = fs::read_to_string(path)?;
-        let config = toml::from_str(&content)?;
+        let mut config: Self = toml::from_str(&content)?;
+        config.load_instruction_files(path)?;
+        config.check_rule_ids()?;
         Ok(config)
     }
 
+    /// Config file as committed at `rev`, with its rules' `instruction_file`s from the same
+    /// revision; `None` if the revision has no such file
+    pub fn load_at_revision(
+        rev: &str,
+        path: &str,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Some(content) = crate::util::show_file(rev, path) else {
+            return Ok(None);
+        };
+        let mut config: Self = toml::from_str(&content)?;
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        for rule in &mut config.rules {
+            rule.load_instruction_file_with(dir, |file| {
+                crate::util::show_file(rev, &file.to_string_lossy())
+                    .ok_or_else(|| format!("not found at {}", rev))
+            })?;
+        }
+        config.check_rule_ids()?;
+        Ok(Some(config))
+    }
+
+    /// Read the rules' `instruction_file`s, relative to the directory of the config file
+    pub fn load_instruction_files(&mut self, config_path: &str) -> Result<(), String> {
+        let dir = Path::new(config_path).parent().unwrap_or(Path::new(""));
+        for rule in &mut self.rules {
+            rule.load_instruction_file(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Ensure rule ids (configured or derived from names) are unique
+    pub fn check_rule_ids(&self) -> Result<(), String> {
+        let mut seen = std::collections::HashSet::new();
+        for rule in &self.rules {
+            let id = rule.id();
+            if !seen.insert(id.clone()) {
+                return Err(format!(
+                    "Duplicate rule id '{}' (rule '{}'); set a unique `id`",
+                    id, rule.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep only rules that have at least one of the given tags
+    pub fn retain_rules_with_tags(&mut self, tags: &[String]) {
+        self.rules
+            .retain(|rule| rule.tags.iter().any(|tag| tags.contains(tag)));
+    }
+
+    /// Gate features on the model's capabilities, see [`gate_features`]
+    pub fn apply_capabilities(&mut self, capabilities: &Capabilities) {
+        gate_features(
+            capabilities,
+            &mut self.llm.tool_protocol,
+            &mut self.llm.body,
+            &mut self.review.max_history_tokens,
+        );
+    }
+
+    /// Quarantine rules by name or id, failing on names that match no rule
+    pub fn quarantine_rules(&mut self, names: &[String]) -> Result<(), String> {
+        for name in names {
+            let rule = self
+                .rules
+                .iter_mut()
+                .find(|rule| rule.name == *name || rule.id() == *name)
+                .ok_or_else(|| format!("No rule '{}' to quarantine", name))?;
+            rule.quarantined = true;
+        }
+        Ok(())
+    }
+
+    /// Apply the profile selected with `--profile`, if any: merge its LLM and review
+    /// settings, select rules by its tags, and override rule blocking
+    pub fn apply_profile(&mut self, name: Option<&str>) -> Result<(), String> {
+        let Some(name) = name else {
+            return Ok(());
+        };
+        let profile = self.profiles.get(name).cloned().ok_or_else(|| {
+            format!(
+                "Unknown profile '{}' (available: {})",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+
+        let mut json_value = serde_json::to_value(&self)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        if let Some(llm) = &profile.llm {
+            merge_json(&mut json_value["llm"], llm);
+        }
+        if let Some(review) = &profile.review {
+            merge_json(&mut json_value["review"], review);
+        }
+        *self = serde_json::from_value(json_value)
+            .map_err(|e| format!("Invalid profile '{}': {}", name, e))?;
+
+        if !profile.rules_tags.is_empty() {
+            self.retain_rules_with_tags(&profile.rules_tags);
+        }
+        if let Some(blocking) = profile.blocking {
+            for rule in &mut self.rules {
+                rule.blocking = Some(blocking);
+            }
+        }
+        info!("Applied profile '{}'", name);
+        Ok(())
+    }
+
     /// Apply config overrides using dot notation (e.g. "llm.model=gpt-4")
     ///
     /// Converts config to JSON, navigates to the field using dot-separated path,
@@ -210,3 +690,134 @@ impl Config {
         Ok(())
     }
 }
+
+/// Recursively merge `patch` into `target`, replacing non-table values
+fn merge_json(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_endpoint() {
+        let mut llm: LlmConfig = toml::from_str(
+            r#"
+            provider = "azure"
+            base_url = "https://res.openai.azure.com"
+            model = "gpt-4o"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            llm.endpoint(),
+            "https://res.openai.azure.com/openai/deployments/gpt-4o?api-version=2024-10-21"
+        );
+        llm.deployment = Some("review".into());
+        llm.api_version = Some("2025-01-01-preview".into());
+        assert!(
+            llm.endpoint()
+                .ends_with("/deployments/review?api-version=2025-01-01-preview")
+        );
+        llm.provider = ProviderKind::OpenAI;
+        assert_eq!(llm.endpoint(), "https://res.openai.azure.com");
+        assert!(!llm.is_local());
+        llm.base_url = Some("http://localhost:11434/v1".into());
+        assert!(llm.is_local());
+
+        // Without `base_url`, each provider has its own default
+        let llm: LlmConfig = toml::from_str(r#"provider = "anthropic""#).unwrap();
+        assert_eq!(llm.endpoint(), "https://api.anthropic.com/v1");
+        let llm: LlmConfig = toml::from_str(r#"model = "gpt-4o""#).unwrap();
+        assert_eq!(llm.base_url(), "https://openrouter.ai/api/v1");
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut config = Config::template_full();
+        config.profiles.insert(
+            "local".into(),
+            toml::from_str(
+                r#"
+                rules_tags = ["fast"]
+                blocking = false
+                llm = { model = "small-model", body = { reasoning = { effort = "low" } } }
+                review = { max_parallel_workers = 2 }
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let rules = config.rules.len();
+        config.apply_profile(None).unwrap();
+        assert_eq!(config.rules.len(), rules);
+        config.apply_profile(Some("local")).unwrap();
+        assert_eq!(config.llm.model, "small-model");
+        assert_eq!(config.llm.body["reasoning"]["effort"], "low");
+        assert_eq!(config.llm.body["parallel_tool_calls"], true);
+        assert_eq!(config.review.max_parallel_workers, Some(2));
+        assert_eq!(config.rules.len(), 3);
+        assert!(config.rules.iter().all(|r| !r.is_blocking()));
+        assert!(
+            config
+                .apply_profile(Some("ci"))
+                .unwrap_err()
+                .contains("available: local")
+        );
+    }
+
+    #[test]
+    fn test_apply_capabilities() {
+        let mut config = Config::template_fast();
+        config.llm.body["response_format"] = json!({ "type": "json_object" });
+        config.apply_capabilities(&Capabilities {
+            tool_calls: Some(false),
+            context_tokens: Some(32_000),
+            ..Default::default()
+        });
+        assert_eq!(config.llm.tool_protocol, ToolProtocol::React);
+        assert!(config.llm.body.get("parallel_tool_calls").is_none());
+        assert!(config.llm.body.get("response_format").is_some());
+        assert_eq!(config.review.max_history_tokens, 24_960);
+
+        let mut config = Config::template_fast();
+        config.llm.tool_protocol = ToolProtocol::Auto;
+        config.apply_capabilities(&Capabilities {
+            tool_calls: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(config.llm.tool_protocol, ToolProtocol::React);
+
+        let mut config = Config::template_fast();
+        config.apply_capabilities(&Capabilities {
+            tool_calls: Some(true),
+            json_mode: Some(false),
+            context_tokens: Some(1_000_000),
+            ..Default::default()
+        });
+        assert_eq!(config.llm.tool_protocol, ToolProtocol::Native);
+        assert_eq!(config.llm.body["parallel_tool_calls"], true);
+        assert_eq!(config.review.max_history_tokens, 100_000);
+    }
+
+    #[test]
+    fn test_quarantine_rules() {
+        let mut config = Config::template_full();
+        let name = config.rules[0].name.clone();
+        config.quarantine_rules(&[name]).unwrap();
+        assert!(config.rules[0].quarantined && !config.rules[0].blocks());
+        assert!(!config.rules[1].quarantined);
+        assert_eq!(
+            config.quarantine_rules(&["missing".into()]).unwrap_err(),
+            "No rule 'missing' to quarantine"
+        );
+    }
+}
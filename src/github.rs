@@ -0,0 +1,440 @@
+use crate::publish::PublishConfig;
+use crate::util::temp::TempDir;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// Host of github.com pull request URLs, whose API is served from a separate host
+const GITHUB_HOST: &str = "github.com";
+const GITHUB_API_BASE: &str = "https://api.github.com";
+
+/// Hidden marker identifying firekeeper's comment, so later runs update it instead of adding another
+const COMMENT_MARKER: &str = "<!-- firekeeper-review -->";
+
+/// Comments fetched per page when looking for a previous review comment (the API maximum)
+const COMMENTS_PER_PAGE: usize = 100;
+
+/// A GitHub pull request identified by host, repository and number
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullRequest {
+    /// Web host, e.g. `github.com` or a GitHub Enterprise Server host
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+}
+
+impl PullRequest {
+    /// Parse a pull request URL such as `https://github.com/org/repo/pull/123`,
+    /// on github.com or a self-hosted instance
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid pull request URL: {}", url);
+        let path = url
+            .trim()
+            .trim_end_matches('/')
+            .strip_prefix("https://")
+            .ok_or_else(invalid)?;
+        let parts: Vec<&str> = path.split('/').collect();
+        match parts.as_slice() {
+            [host, owner, repo, "pull", number, ..] => Ok(Self {
+                host: host.to_string(),
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                number: number.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Pull request of a GitHub Actions run, from `GITHUB_SERVER_URL`, `GITHUB_REPOSITORY`
+    /// and the `pull_request` object of the event payload at `GITHUB_EVENT_PATH`
+    pub fn from_env() -> Option<Self> {
+        let server = std::env::var("GITHUB_SERVER_URL").unwrap_or_default();
+        let repository = std::env::var("GITHUB_REPOSITORY").ok()?;
+        let event = std::fs::read_to_string(std::env::var("GITHUB_EVENT_PATH").ok()?).ok()?;
+        Self::from_event(&server, &repository, &event)
+    }
+
+    fn from_event(server: &str, repository: &str, event: &str) -> Option<Self> {
+        let (owner, repo) = repository.split_once('/')?;
+        let event: serde_json::Value = serde_json::from_str(event).ok()?;
+        let host = server.trim_start_matches("https://").trim_end_matches('/');
+        Some(Self {
+            host: if host.is_empty() { GITHUB_HOST } else { host }.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            number: event["pull_request"]["number"].as_u64()?,
+        })
+    }
+}
+
+impl std::fmt::Display for PullRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}#{}", self.owner, self.repo, self.number)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PullRequestInfo {
+    base: Branch,
+    head: Branch,
+}
+
+#[derive(Deserialize, Debug)]
+struct Branch {
+    sha: String,
+    repo: Option<Repo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Repo {
+    clone_url: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Comment {
+    id: u64,
+    #[serde(default)]
+    body: String,
+}
+
+/// API base URL of a host: `[publish] api_base` if set, otherwise the standard location
+fn api_base(config: &PublishConfig, host: &str) -> String {
+    match &config.api_base {
+        Some(base) => base.trim_end_matches('/').to_string(),
+        None if host == GITHUB_HOST => GITHUB_API_BASE.to_string(),
+        None => format!("https://{}/api/v3", host),
+    }
+}
+
+/// Whether `GITHUB_TOKEN`/`GH_TOKEN` may be sent to a host: github.com, or the host of the
+/// configured `api_base`. Other hosts found in pull request URLs need `token_command`, so a
+/// github.com token is not leaked to an arbitrary server.
+fn env_token_allowed(config: &PublishConfig, host: &str) -> bool {
+    host == GITHUB_HOST || config.api_base.is_some()
+}
+
+/// GitHub token from `GITHUB_TOKEN` or `GH_TOKEN` for hosts they may be sent to, otherwise
+/// printed by `token_command`
+fn token(config: &PublishConfig, host: &str) -> Result<Option<String>, String> {
+    let env_token = std::env::var("GITHUB_TOKEN")
+        .or_else(|_| std::env::var("GH_TOKEN"))
+        .ok()
+        .filter(|t| !t.is_empty());
+    if let Some(token) = env_token {
+        if env_token_allowed(config, host) {
+            return Ok(Some(token));
+        }
+        if config.token_command.is_none() {
+            warn!(
+                "Not sending GITHUB_TOKEN/GH_TOKEN to {}; set [publish] api_base or token_command",
+                host
+            );
+        }
+    }
+    let Some(command) = &config.token_command else {
+        return Ok(None);
+    };
+    let shell = crate::tool::sh::Shell::default();
+    let output = Command::new(shell.program())
+        .args([shell.command_flag(), command])
+        .output()
+        .map_err(|e| format!("Failed to run token_command: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "token_command failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(Some(token).filter(|t| !t.is_empty()))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    debug!("git {}", args.join(" "));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Client of the API of one GitHub host, configured by `[publish]`
+pub struct Api {
+    base: String,
+    client: reqwest::Client,
+    token: Option<String>,
+    /// CA certificates also trusted by git when cloning
+    ca_cert: Option<String>,
+}
+
+impl Api {
+    /// Client for the host of a pull request
+    pub fn new(config: &PublishConfig, host: &str) -> Result<Self, String> {
+        let mut client = reqwest::Client::builder();
+        if let Some(path) = &config.ca_cert {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("Failed to read CA certificate {}: {}", path, e))?;
+            let certs = reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+            client = client.tls_certs_merge(certs);
+        }
+        Ok(Self {
+            base: api_base(config, host),
+            client: client
+                .build()
+                .map_err(|e| format!("Failed to create HTTP client: {}", e))?,
+            token: token(config, host)?,
+            ca_cert: config.ca_cert.clone(),
+        })
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base, path))
+            .header("Accept", "application/vnd.github+json")
+            .header(
+                "User-Agent",
+                concat!("firekeeper/", env!("CARGO_PKG_VERSION")),
+            );
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        request
+    }
+
+    async fn fetch_info(&self, pr: &PullRequest) -> Result<PullRequestInfo, String> {
+        let path = format!("/repos/{}/{}/pulls/{}", pr.owner, pr.repo, pr.number);
+        let response = self
+            .request(reqwest::Method::GET, &path)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch {}: {}", pr, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Failed to fetch {}: HTTP {}", pr, status));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid response for {}: {}", pr, e))
+    }
+
+    /// Clone a pull request into a temporary directory with its head checked out.
+    ///
+    /// Returns the clone directory, removed once dropped, and the merge base of the pull
+    /// request, to review against.
+    pub async fn checkout(&self, pr: &PullRequest) -> Result<(TempDir, String), String> {
+        let info = self.fetch_info(pr).await?;
+        let clone_url = info
+            .base
+            .repo
+            .map(|r| r.clone_url)
+            .unwrap_or_else(|| format!("https://{}/{}/{}.git", pr.host, pr.owner, pr.repo));
+
+        let temp = TempDir::new(&format!(
+            "firekeeper-pr-{}-{}-{}-{}",
+            pr.owner,
+            pr.repo,
+            pr.number,
+            std::process::id()
+        ));
+        let dir = temp.path();
+        info!("Cloning {} into {}", pr, dir.display());
+
+        // Blobless clone keeps history for merge-base while fetching file contents lazily
+        let mut clone = Command::new("git");
+        if let Some(path) = &self.ca_cert {
+            clone.arg("-c").arg(format!("http.sslCAInfo={}", path));
+        }
+        let status = clone
+            .args(["clone", "--quiet", "--filter=blob:none", "--no-checkout"])
+            .arg(&clone_url)
+            .arg(dir)
+            .status()
+            .map_err(|e| format!("Failed to execute git: {}", e))?;
+        if !status.success() {
+            return Err(format!("Failed to clone {}", clone_url));
+        }
+        if let Some(path) = &self.ca_cert {
+            git(dir, &["config", "http.sslCAInfo", path])?;
+        }
+
+        let pull_ref = format!("pull/{}/head", pr.number);
+        git(dir, &["fetch", "--quiet", "--no-tags", "origin", &pull_ref])?;
+        git(dir, &["checkout", "--quiet", "--detach", &info.head.sha])?;
+        let merge_base = git(dir, &["merge-base", &info.base.sha, "HEAD"]).unwrap_or_else(|e| {
+            warn!("{}, reviewing against base branch tip", e);
+            info.base.sha.clone()
+        });
+        Ok((temp, merge_base))
+    }
+
+    /// Find the id of a comment posted by a previous run
+    async fn find_comment(&self, pr: &PullRequest) -> Result<Option<u64>, String> {
+        for page in 1.. {
+            let path = format!(
+                "/repos/{}/{}/issues/{}/comments?per_page={}&page={}",
+                pr.owner, pr.repo, pr.number, COMMENTS_PER_PAGE, page
+            );
+            let response = self
+                .request(reqwest::Method::GET, &path)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to list comments on {}: {}", pr, e))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(format!(
+                    "Failed to list comments on {}: HTTP {}",
+                    pr, status
+                ));
+            }
+            let comments: Vec<Comment> = response
+                .json()
+                .await
+                .map_err(|e| format!("Invalid comments response for {}: {}", pr, e))?;
+            if let Some(comment) = comments.iter().find(|c| c.body.contains(COMMENT_MARKER)) {
+                return Ok(Some(comment.id));
+            }
+            if comments.len() < COMMENTS_PER_PAGE {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Publish review results as a pull request comment (requires a token).
+    ///
+    /// A comment from a previous run is updated in place, so re-runs do not flood the pull request.
+    pub async fn post_comment(&self, pr: &PullRequest, body: &str) -> Result<(), String> {
+        if !self.has_token() {
+            return Err(
+            "GITHUB_TOKEN, GH_TOKEN, or [publish] token_command is required to comment on pull requests"
+                .into(),
+        );
+        }
+        let existing = self.find_comment(pr).await?;
+        let (method, path) = match existing {
+            Some(id) => (
+                reqwest::Method::PATCH,
+                format!("/repos/{}/{}/issues/comments/{}", pr.owner, pr.repo, id),
+            ),
+            None => (
+                reqwest::Method::POST,
+                format!(
+                    "/repos/{}/{}/issues/{}/comments",
+                    pr.owner, pr.repo, pr.number
+                ),
+            ),
+        };
+        let response = self
+            .request(method, &path)
+            .json(&serde_json::json!({ "body": format!("{}\n{}", COMMENT_MARKER, body) }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to comment on {}: {}", pr, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Failed to comment on {}: HTTP {}", pr, status));
+        }
+        if existing.is_some() {
+            info!("Updated comment on {}", pr);
+        } else {
+            info!("Commented on {}", pr);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pull_request_url() {
+        let pr = PullRequest::parse("https://github.com/org/repo/pull/123/files").unwrap();
+        assert_eq!(
+            pr,
+            PullRequest {
+                host: "github.com".into(),
+                owner: "org".into(),
+                repo: "repo".into(),
+                number: 123,
+            }
+        );
+        assert_eq!(pr.to_string(), "org/repo#123");
+        assert!(PullRequest::parse("https://github.com/org/repo/issues/1").is_err());
+        assert!(PullRequest::parse("http://github.com/org/repo/pull/1").is_err());
+        let pr = PullRequest::parse("https://github.example.com/org/repo/pull/7").unwrap();
+        assert_eq!(pr.host, "github.example.com");
+    }
+
+    #[test]
+    fn test_pull_request_from_event() {
+        let event = r#"{"action": "synchronize", "pull_request": {"number": 42}}"#;
+        assert_eq!(
+            PullRequest::from_event("", "org/repo", event),
+            Some(PullRequest {
+                host: "github.com".into(),
+                owner: "org".into(),
+                repo: "repo".into(),
+                number: 42,
+            })
+        );
+        assert_eq!(
+            PullRequest::from_event("", "org/repo", r#"{"ref": "main"}"#),
+            None
+        );
+        assert_eq!(
+            PullRequest::from_event("https://github.example.com", "org/repo", event)
+                .unwrap()
+                .host,
+            "github.example.com"
+        );
+    }
+
+    #[test]
+    fn test_api_base() {
+        let config = PublishConfig::default();
+        assert_eq!(api_base(&config, "github.com"), "https://api.github.com");
+        assert_eq!(
+            api_base(&config, "github.example.com"),
+            "https://github.example.com/api/v3"
+        );
+        let config = PublishConfig {
+            api_base: Some("https://git.internal/api/v3/".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            api_base(&config, "github.com"),
+            "https://git.internal/api/v3"
+        );
+    }
+
+    #[test]
+    fn test_env_token_allowed() {
+        let config = PublishConfig::default();
+        assert!(env_token_allowed(&config, "github.com"));
+        assert!(!env_token_allowed(&config, "github.example.com"));
+        let config = PublishConfig {
+            api_base: Some("https://github.example.com/api/v3".into()),
+            ..Default::default()
+        };
+        assert!(env_token_allowed(&config, "github.example.com"));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::cli::Hook;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// Marker identifying hooks written by firekeeper, so they can be updated and removed safely
+const HOOK_MARKER: &str = "# Installed by `firekeeper install-hooks`";
+
+impl Hook {
+    fn file_name(&self) -> &'static str {
+        match self {
+            Hook::PreCommit => "pre-commit",
+            Hook::PrePush => "pre-push",
+        }
+    }
+}
+
+/// Resolve the hooks directory, respecting `core.hooksPath` and worktrees
+fn hooks_dir() -> Result<PathBuf, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+    if !output.status.success() {
+        return Err("Not a git repository".to_string());
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+/// Quote a value for POSIX sh
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Build the hook script invoking `firekeeper review`
+///
+/// pre-commit reviews staged (or all uncommitted) changes;
+/// pre-push reviews commits not yet on the upstream branch.
+pub fn hook_script(
+    hook: Hook,
+    staged: bool,
+    rules_tags: &[String],
+    config: &str,
+    profile: Option<&str>,
+) -> String {
+    let mut args = vec!["review".to_string()];
+    let mut script = format!("#!/bin/sh\n{}\n", HOOK_MARKER);
+    match hook {
+        Hook::PreCommit if staged => args.push("--staged".into()),
+        Hook::PreCommit => args.push("--base HEAD".into()),
+        Hook::PrePush => {
+            script.push_str("base=$(git rev-parse --verify --quiet '@{upstream}') || base='^'\n");
+            args.push("--base \"$base\"".into());
+        }
+    }
+    if !rules_tags.is_empty() {
+        args.push(format!(
+            "--rules-tags {}",
+            shell_quote(&rules_tags.join(","))
+        ));
+    }
+    args.push(format!("--config {}", shell_quote(config)));
+    if let Some(profile) = profile {
+        args.push(format!("--profile {}", shell_quote(profile)));
+    }
+    script.push_str(&format!("exec firekeeper {}\n", args.join(" ")));
+    script
+}
+
+fn is_firekeeper_hook(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .map(|content| content.contains(HOOK_MARKER))
+        .unwrap_or(false)
+}
+
+/// Write git hooks invoking firekeeper
+///
+/// Existing hooks not installed by firekeeper are kept unless `force` is set.
+pub fn install_hooks(
+    hooks: &[Hook],
+    staged: bool,
+    rules_tags: &[String],
+    config: &str,
+    profile: Option<&str>,
+    force: bool,
+) -> Result<(), String> {
+    let dir = hooks_dir()?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    for hook in hooks {
+        let path = dir.join(hook.file_name());
+        if path.exists() && !force && !is_firekeeper_hook(&path) {
+            return Err(format!(
+                "{} already exists and was not installed by firekeeper (use --force to overwrite)",
+                path.display()
+            ));
+        }
+        std::fs::write(
+            &path,
+            hook_script(*hook, staged, rules_tags, config, profile),
+        )
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+        }
+        info!("Installed {}", path.display());
+    }
+    Ok(())
+}
+
+/// Remove git hooks installed by firekeeper, leaving other hooks untouched
+pub fn uninstall_hooks(hooks: &[Hook]) -> Result<(), String> {
+    let dir = hooks_dir()?;
+    for hook in hooks {
+        let path = dir.join(hook.file_name());
+        if !path.exists() {
+            continue;
+        }
+        if !is_firekeeper_hook(&path) {
+            warn!("Skipping {}: not installed by firekeeper", path.display());
+            continue;
+        }
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        info!("Removed {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pre_commit_script() {
+        let script = hook_script(
+            Hook::PreCommit,
+            true,
+            &["fast".to_string()],
+            "firekeeper.toml",
+            Some("local"),
+        );
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(HOOK_MARKER));
+        assert!(script.ends_with(
+            "exec firekeeper review --staged --rules-tags 'fast' --config 'firekeeper.toml' --profile 'local'\n"
+        ));
+    }
+
+    #[test]
+    fn test_pre_push_script() {
+        let script = hook_script(Hook::PrePush, true, &[], "it's.toml", None);
+        assert!(script.contains("@{upstream}"));
+        assert!(
+            script.ends_with("exec firekeeper review --base \"$base\" --config 'it'\\''s.toml'\n")
+        );
+    }
+}
@@ -0,0 +1,580 @@
+use crate::config::{Config, LlmConfig};
+use crate::llm::ToolProtocol;
+use crate::rule::body::{RuleBody, default_scope};
+use globset::{Glob, GlobSetBuilder};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use toml_scaffold::TomlScaffold;
+use tracing::{info, warn};
+
+/// Languages offered by the wizard, with the globs their rules are scoped to
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("Rust", &["**/*.rs"]),
+    ("Python", &["**/*.py"]),
+    (
+        "JavaScript/TypeScript",
+        &[
+            "**/*.js", "**/*.jsx", "**/*.mjs", "**/*.cjs", "**/*.ts", "**/*.tsx",
+        ],
+    ),
+    ("Go", &["**/*.go"]),
+    ("Java/Kotlin", &["**/*.java", "**/*.kt", "**/*.kts"]),
+    (
+        "C/C++",
+        &["**/*.c", "**/*.h", "**/*.cc", "**/*.cpp", "**/*.hpp"],
+    ),
+    ("C#", &["**/*.cs"]),
+    ("Ruby", &["**/*.rb"]),
+    ("PHP", &["**/*.php"]),
+];
+
+/// Constructor of a built-in rule
+type Preset = fn() -> RuleBody;
+
+/// Built-in rule presets, and whether each is enabled by default (the `fast` template)
+const PRESETS: &[(Preset, bool)] = &[
+    (RuleBody::config_file_comments, true),
+    (RuleBody::no_magic_numbers, true),
+    (RuleBody::no_hardcoded_credentials, true),
+    (RuleBody::no_code_duplication, false),
+];
+
+/// LLM providers offered by the wizard: (name, base URL, default model).
+/// An empty base URL asks for one.
+const PROVIDERS: &[(&str, &str, &str)] = &[
+    (
+        "OpenRouter",
+        "https://openrouter.ai/api/v1",
+        "google/gemini-3-flash-preview",
+    ),
+    ("OpenAI", "https://api.openai.com/v1", "gpt-5-mini"),
+    ("Ollama (local)", "http://localhost:11434/v1", "qwen3-coder"),
+    ("Other OpenAI-compatible API", "", ""),
+];
+
+/// Standard environment variables holding an API key for `review --auto`, with the
+/// provider (index into PROVIDERS) each belongs to
+const AUTO_API_KEY_VARS: &[(&str, usize)] = &[("OPENROUTER_API_KEY", 0), ("OPENAI_API_KEY", 1)];
+
+/// Workers running at once in zero-config mode, to stay within provider rate limits
+const AUTO_MAX_PARALLEL_WORKERS: usize = 2;
+
+/// CI platforms a workflow can be generated for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CiPlatform {
+    GitHubActions,
+    GitLabCi,
+}
+
+const CI_PLATFORMS: &[(&str, Option<CiPlatform>)] = &[
+    ("GitHub Actions", Some(CiPlatform::GitHubActions)),
+    ("GitLab CI", Some(CiPlatform::GitLabCi)),
+    ("None", None),
+];
+
+/// Installer used by the generated CI jobs
+const INSTALLER: &str = "curl --proto '=https' --tlsv1.2 -LsSf https://github.com/firekeeper-ai/firekeeper/releases/latest/download/firekeeper-installer.sh | sh";
+
+/// Report directory of `firekeeper ci`, uploaded as an artifact by the generated CI jobs
+const CI_OUTPUT: &str = "firekeeper-report";
+
+/// Generated CI workflow
+#[derive(Debug)]
+pub struct Workflow {
+    /// Path to write the workflow to, relative to the repository root
+    pub path: &'static str,
+    pub content: String,
+    /// How to enable the workflow once written
+    pub hint: &'static str,
+}
+
+/// Result of the wizard: the tailored config and an optional CI workflow
+pub struct Wizard {
+    pub config: Config,
+    pub workflow: Option<Workflow>,
+}
+
+/// Line-based prompts over any reader/writer, so the wizard can be driven from tests
+struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    fn ask(&mut self, prompt: &str) -> Result<String, String> {
+        write!(self.output, "{}", prompt).map_err(|e| e.to_string())?;
+        self.output.flush().map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Err("Input ended before the wizard finished".to_string());
+        }
+        Ok(line.trim().to_string())
+    }
+
+    fn say(&mut self, text: &str) -> Result<(), String> {
+        writeln!(self.output, "{}", text).map_err(|e| e.to_string())
+    }
+
+    fn list(&mut self, question: &str, options: &[&str]) -> Result<(), String> {
+        self.say(&format!("\n{}", question))?;
+        for (i, option) in options.iter().enumerate() {
+            self.say(&format!("  {}) {}", i + 1, option))?;
+        }
+        Ok(())
+    }
+
+    /// Pick one option by number, `default` on empty input
+    fn choose(
+        &mut self,
+        question: &str,
+        options: &[&str],
+        default: usize,
+    ) -> Result<usize, String> {
+        self.list(question, options)?;
+        loop {
+            let answer = self.ask(&format!("Choose one [{}]: ", default + 1))?;
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            match parse_selection(&answer, options.len()) {
+                Some(selection) if selection.len() == 1 => return Ok(selection[0]),
+                _ => self.say(&format!("Enter a number between 1 and {}", options.len()))?,
+            }
+        }
+    }
+
+    /// Pick any options by comma or space separated numbers, `defaults` on empty input
+    fn choose_many(
+        &mut self,
+        question: &str,
+        options: &[&str],
+        defaults: &[usize],
+    ) -> Result<Vec<usize>, String> {
+        self.list(question, options)?;
+        let default_label = if defaults.is_empty() {
+            "none".to_string()
+        } else {
+            defaults
+                .iter()
+                .map(|i| (i + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        loop {
+            let answer = self.ask(&format!("Choose any, e.g. 1,3 [{}]: ", default_label))?;
+            if answer.is_empty() {
+                return Ok(defaults.to_vec());
+            }
+            match parse_selection(&answer, options.len()) {
+                Some(selection) => return Ok(selection),
+                None => self.say(&format!(
+                    "Enter numbers between 1 and {}, separated by commas",
+                    options.len()
+                ))?,
+            }
+        }
+    }
+
+    /// Free text, `default` on empty input; an empty default requires an answer
+    fn text(&mut self, question: &str, default: &str) -> Result<String, String> {
+        loop {
+            let answer = if default.is_empty() {
+                self.ask(&format!("{}: ", question))?
+            } else {
+                self.ask(&format!("{} [{}]: ", question, default))?
+            };
+            if !answer.is_empty() {
+                return Ok(answer);
+            }
+            if !default.is_empty() {
+                return Ok(default.to_string());
+            }
+        }
+    }
+}
+
+/// Parse 1-indexed numbers separated by commas or spaces into sorted, deduplicated indices
+fn parse_selection(answer: &str, len: usize) -> Option<Vec<usize>> {
+    let mut selection = answer
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| match s.parse::<usize>() {
+            Ok(n) if (1..=len).contains(&n) => Some(n - 1),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    selection.sort_unstable();
+    selection.dedup();
+    Some(selection)
+}
+
+/// Ask about the repository and build a tailored config and CI workflow.
+///
+/// `config_path` is passed to the generated CI job when it is not the default.
+pub fn run(input: impl BufRead, output: impl Write, config_path: &str) -> Result<Wizard, String> {
+    let mut prompter = Prompter { input, output };
+    prompter.say("Answer a few questions to tailor firekeeper.toml (press Enter for defaults).")?;
+
+    let language_names: Vec<&str> = LANGUAGES.iter().map(|(name, _)| *name).collect();
+    let languages = prompter.choose_many(
+        "Which languages does the repository use? (none reviews all files)",
+        &language_names,
+        &[],
+    )?;
+
+    let preset_rules: Vec<RuleBody> = PRESETS.iter().map(|(rule, _)| rule()).collect();
+    let preset_labels: Vec<String> = preset_rules
+        .iter()
+        .map(|rule| format!("{} - {}", rule.name, rule.description))
+        .collect();
+    let preset_labels: Vec<&str> = preset_labels.iter().map(String::as_str).collect();
+    let default_presets: Vec<usize> = PRESETS
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, enabled))| *enabled)
+        .map(|(i, _)| i)
+        .collect();
+    let presets = prompter.choose_many(
+        "Which built-in rules should be enabled?",
+        &preset_labels,
+        &default_presets,
+    )?;
+
+    let provider_names: Vec<&str> = PROVIDERS.iter().map(|(name, _, _)| *name).collect();
+    let provider = prompter.choose("Which LLM provider?", &provider_names, 0)?;
+    let (_, base_url, default_model) = PROVIDERS[provider];
+    let base_url = if base_url.is_empty() {
+        prompter.text("API base URL", "")?
+    } else {
+        base_url.to_string()
+    };
+    let model = prompter.text("Model", default_model)?;
+
+    let ci_names: Vec<&str> = CI_PLATFORMS.iter().map(|(name, _)| *name).collect();
+    let ci = CI_PLATFORMS[prompter.choose("Which CI platform?", &ci_names, 0)?].1;
+
+    let scope: Vec<String> = languages
+        .iter()
+        .flat_map(|&i| LANGUAGES[i].1.iter().map(|glob| glob.to_string()))
+        .collect();
+    let rules = presets
+        .into_iter()
+        .map(|i| narrow_scope(preset_rules[i].clone(), &scope))
+        .collect();
+
+    Ok(Wizard {
+        config: Config {
+            llm: llm_config(provider == 0, base_url, model),
+            rules,
+            ..Config::template_fast()
+        },
+        workflow: ci.map(|platform| workflow(platform, config_path)),
+    })
+}
+
+/// Narrow a rule reviewing all files to the globs of the chosen languages
+fn narrow_scope(mut rule: RuleBody, scope: &[String]) -> RuleBody {
+    if !scope.is_empty() && rule.scope == default_scope() {
+        rule.scope = scope.to_vec();
+    }
+    rule
+}
+
+/// Languages (indices into LANGUAGES) with any of the given files
+fn detect_languages(files: &[String]) -> Vec<usize> {
+    LANGUAGES
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, globs))| {
+            let mut builder = GlobSetBuilder::new();
+            for glob in globs.iter() {
+                if let Ok(glob) = Glob::new(glob) {
+                    builder.add(glob);
+                }
+            }
+            builder
+                .build()
+                .is_ok_and(|set| files.iter().any(|file| set.is_match(file)))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Config for `review --auto` without a config file, and the API key found in the
+/// environment: the default presets scoped to the languages of the tracked `files`, all
+/// non-blocking, with few parallel workers. `api_key` (`--api-key` or
+/// `FIREKEEPER_LLM_API_KEY`) selects the default provider; without it, the first of
+/// AUTO_API_KEY_VARS set in `env` selects its provider.
+pub fn auto_config(
+    files: &[String],
+    api_key: Option<String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> (Config, Option<String>) {
+    let languages = detect_languages(files);
+    let names: Vec<&str> = languages.iter().map(|&i| LANGUAGES[i].0).collect();
+    info!("Detected languages: {:?}", names);
+    let scope: Vec<String> = languages
+        .iter()
+        .flat_map(|&i| LANGUAGES[i].1.iter().map(|glob| glob.to_string()))
+        .collect();
+    // Without a config file there is no config to check for comments
+    let config_rule = RuleBody::config_file_comments().name;
+    let rules = PRESETS
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(rule, _)| rule())
+        .filter(|rule| rule.name != config_rule)
+        .map(|rule| RuleBody {
+            blocking: Some(false),
+            ..narrow_scope(rule, &scope)
+        })
+        .collect();
+
+    let found = AUTO_API_KEY_VARS.iter().find_map(|(var, provider)| {
+        env(var)
+            .filter(|key| !key.is_empty())
+            .map(|key| (key, *provider))
+    });
+    let (llm, api_key) = match (api_key, found) {
+        (Some(key), _) => (LlmConfig::default(), Some(key)),
+        (None, Some((key, provider))) => {
+            let (_, base_url, model) = PROVIDERS[provider];
+            (
+                llm_config(provider == 0, base_url.into(), model.into()),
+                Some(key),
+            )
+        }
+        (None, None) => (LlmConfig::default(), None),
+    };
+
+    let mut config = Config {
+        llm,
+        rules,
+        ..Config::template_fast()
+    };
+    config.review.max_parallel_workers = Some(AUTO_MAX_PARALLEL_WORKERS);
+    (config, api_key)
+}
+
+/// Write the config, and the CI workflow unless one exists at its path (then it is printed instead)
+pub fn write(config_path: &str, wizard: &Wizard, r#override: bool) -> Result<(), String> {
+    let content = wizard
+        .config
+        .to_scaffold()
+        .map_err(|e| format!("Error rendering config: {}", e))?;
+    std::fs::write(config_path, content).map_err(|e| format!("Error writing config: {}", e))?;
+    info!("Created {}", config_path);
+
+    let Some(workflow) = &wizard.workflow else {
+        return Ok(());
+    };
+    let path = Path::new(workflow.path);
+    if path.exists() && !r#override {
+        warn!(
+            "{} already exists, add this workflow manually:\n{}",
+            workflow.path, workflow.content
+        );
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Error creating {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, &workflow.content)
+        .map_err(|e| format!("Error writing {}: {}", workflow.path, e))?;
+    info!("Created {}. {}", workflow.path, workflow.hint);
+    Ok(())
+}
+
+/// LLM config for the chosen provider; OpenRouter keeps its attribution headers and reasoning
+/// options, and local servers offer tools as text once a model rejects tool calls
+fn llm_config(openrouter: bool, base_url: String, model: String) -> LlmConfig {
+    let defaults = LlmConfig::default();
+    if openrouter {
+        return LlmConfig {
+            base_url: Some(base_url),
+            model,
+            ..defaults
+        };
+    }
+    let llm = LlmConfig {
+        base_url: Some(base_url),
+        model,
+        headers: HashMap::new(),
+        body: json!({ "parallel_tool_calls": true }),
+        ..defaults
+    };
+    if llm.is_local() {
+        return LlmConfig {
+            tool_protocol: ToolProtocol::Auto,
+            ..llm
+        };
+    }
+    llm
+}
+
+/// CI job reviewing merge/pull requests with `firekeeper ci` and keeping the report as an artifact
+pub fn workflow(platform: CiPlatform, config_path: &str) -> Workflow {
+    let mut review = "firekeeper ci".to_string();
+    if config_path != "firekeeper.toml" {
+        review.push_str(&format!(" --config {}", config_path));
+    }
+    match platform {
+        CiPlatform::GitHubActions => Workflow {
+            path: ".github/workflows/firekeeper.yml",
+            content: format!(
+                r#"name: Firekeeper
+
+on:
+  pull_request:
+
+permissions:
+  contents: read
+  # Post the summary comment
+  pull-requests: write
+
+jobs:
+  review:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+        with:
+          fetch-depth: 0
+      - name: Install firekeeper
+        run: {installer}
+      - name: Review
+        env:
+          FIREKEEPER_LLM_API_KEY: ${{{{ secrets.FIREKEEPER_LLM_API_KEY }}}}
+          GITHUB_TOKEN: ${{{{ github.token }}}}
+        run: {review}
+      - uses: actions/upload-artifact@v4
+        if: always()
+        with:
+          name: firekeeper-report
+          path: {output}
+"#,
+                installer = INSTALLER,
+                review = review,
+                output = CI_OUTPUT
+            ),
+            hint: "Add the FIREKEEPER_LLM_API_KEY repository secret",
+        },
+        CiPlatform::GitLabCi => Workflow {
+            path: ".gitlab/firekeeper.yml",
+            content: format!(
+                r#"# Requires the FIREKEEPER_LLM_API_KEY CI/CD variable
+firekeeper:
+  rules:
+    - if: $CI_PIPELINE_SOURCE == "merge_request_event"
+  script:
+    - {installer}
+    - export PATH="$HOME/.cargo/bin:$PATH"
+    - {review}
+  artifacts:
+    when: always
+    paths:
+      - {output}
+"#,
+                installer = INSTALLER,
+                review = review,
+                output = CI_OUTPUT
+            ),
+            hint: "Include it from .gitlab-ci.yml with `include: [{ local: .gitlab/firekeeper.yml }]` \
+                   and add the FIREKEEPER_LLM_API_KEY CI/CD variable",
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_config() {
+        let (_, base_url, model) = PROVIDERS[2];
+        let ollama = llm_config(false, base_url.into(), model.into());
+        assert_eq!(ollama.tool_protocol, ToolProtocol::Auto);
+        assert!(ollama.headers.is_empty());
+        let (_, base_url, model) = PROVIDERS[1];
+        let openai = llm_config(false, base_url.into(), model.into());
+        assert_eq!(openai.tool_protocol, ToolProtocol::Native);
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("3, 1 3", 4), Some(vec![0, 2]));
+        assert_eq!(parse_selection("5", 4), None);
+        assert_eq!(parse_selection("x", 4), None);
+    }
+
+    #[test]
+    fn test_run() {
+        // Rust and Go, credentials only, OpenAI with a custom model, GitHub Actions;
+        // an invalid answer is asked again
+        let input = "1,4\n9\n3\n2\ngpt-5\n1\n";
+        let mut output = Vec::new();
+        let wizard = run(input.as_bytes(), &mut output, "firekeeper.toml").unwrap();
+
+        let config = wizard.config;
+        assert_eq!(config.llm.base_url(), "https://api.openai.com/v1");
+        assert_eq!(config.llm.model, "gpt-5");
+        assert!(config.llm.headers.is_empty());
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].name, "No Hardcoded Credentials");
+        assert_eq!(config.rules[0].scope, ["**/*.rs", "**/*.go"]);
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Enter numbers between")
+        );
+
+        let workflow = wizard.workflow.unwrap();
+        assert_eq!(workflow.path, ".github/workflows/firekeeper.yml");
+        assert!(
+            workflow
+                .content
+                .contains("${{ secrets.FIREKEEPER_LLM_API_KEY }}")
+        );
+        assert!(workflow.content.contains("run: firekeeper ci\n"));
+    }
+
+    #[test]
+    fn test_run_defaults() {
+        let wizard = run("\n\n\n\n\n".as_bytes(), Vec::new(), "ci.toml").unwrap();
+        let config = wizard.config;
+        assert_eq!(config.llm.model, LlmConfig::default().model);
+        assert_eq!(config.rules.len(), 3);
+        // Without languages, rules keep their default scope
+        assert_eq!(config.rules[1].scope, default_scope());
+        assert!(
+            wizard
+                .workflow
+                .unwrap()
+                .content
+                .contains("--config ci.toml")
+        );
+        assert!(run("\n".as_bytes(), Vec::new(), "firekeeper.toml").is_err());
+    }
+
+    #[test]
+    fn test_auto_config() {
+        let files = ["src/main.rs".to_string(), "web/app.tsx".to_string()];
+        let env = |var: &str| (var == "OPENAI_API_KEY").then(|| "sk-test".to_string());
+        let (config, api_key) = auto_config(&files, None, env);
+        assert_eq!(api_key.as_deref(), Some("sk-test"));
+        assert_eq!(config.llm.base_url(), "https://api.openai.com/v1");
+        assert_eq!(config.review.max_parallel_workers, Some(2));
+        let names: Vec<&str> = config.rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["No Magic Numbers", "No Hardcoded Credentials"]);
+        assert!(config.rules.iter().all(|rule| !rule.is_blocking()));
+        assert_eq!(config.rules[0].scope[0], "**/*.rs");
+        assert!(config.rules[0].scope.contains(&"**/*.tsx".to_string()));
+
+        let (config, api_key) = auto_config(&files, Some("fk".into()), env);
+        assert_eq!(api_key.as_deref(), Some("fk"));
+        assert_eq!(config.llm.base_url(), "https://openrouter.ai/api/v1");
+        assert_eq!(auto_config(&files, None, |_| None).1, None);
+    }
+}
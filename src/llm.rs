@@ -1,49 +1,839 @@
-use tiny_loop::{Agent, llm::OpenAIProvider};
+mod anthropic;
+pub mod azure;
+pub mod error;
+mod openai;
+mod react;
+pub mod replay;
+pub mod stream;
 
-/// Create an LLM provider with the specified configuration
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tiny_loop::Agent;
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{FinishReason, LLMResponse, Message, ToolDefinition};
+use tokio_util::sync::CancellationToken;
+use toml_scaffold::TomlScaffold;
+use tracing::warn;
+
+use anthropic::AnthropicProvider;
+use azure::AzureProvider;
+use error::{ErrorClass, ProviderError};
+use openai::OpenAIProvider;
+use react::ReactProvider;
+use stream::{StreamProgress, StreamingProvider};
+
+/// How tools are offered to the model
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolProtocol {
+    /// OpenAI tool calls
+    #[default]
+    Native,
+    /// `ACTION:`/`ARGS:` text blocks, for models without tool calling
+    React,
+    /// Native tool calls, switching to text blocks once the endpoint rejects tools or the
+    /// model garbles a tool call, e.g. Ollama models without reliable tool support
+    Auto,
+}
+
+impl TomlScaffold for ToolProtocol {}
+
+/// API the LLM provider speaks
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// OpenAI-compatible chat completions
+    #[default]
+    OpenAI,
+    /// Anthropic Messages API
+    Anthropic,
+    /// Azure OpenAI deployments
+    Azure,
+}
+
+impl TomlScaffold for ProviderKind {}
+
+impl ProviderKind {
+    /// API base URL used when `llm.base_url` is not set; Azure has no default, as the
+    /// endpoint names the resource
+    pub fn default_base_url(self) -> &'static str {
+        match self {
+            Self::OpenAI => "https://openrouter.ai/api/v1",
+            Self::Anthropic => "https://api.anthropic.com/v1",
+            Self::Azure => "",
+        }
+    }
+}
+
+/// What the model supports. Unset capabilities are probed from the provider's model list
+/// where it publishes them (e.g. OpenRouter), and features are used as configured otherwise.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, JsonSchema, TomlScaffold,
+)]
+#[serde(default)]
+pub struct Capabilities {
+    /// Native tool calling (optional); without it tools are offered with `tool_protocol = "react"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<bool>,
+    /// Several tool calls per reply (optional); without it the `parallel_tool_calls` body
+    /// field is not sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    /// JSON mode and structured outputs (optional); without it the `response_format`
+    /// body field is not sent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_mode: Option<bool>,
+    /// Context window in tokens (optional); `review.max_history_tokens` is capped to fit it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_tokens: Option<usize>,
+}
+
+impl Capabilities {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether every capability is configured, so there is nothing to probe
+    pub fn is_complete(&self) -> bool {
+        self.tool_calls.is_some()
+            && self.parallel_tool_calls.is_some()
+            && self.json_mode.is_some()
+            && self.context_tokens.is_some()
+    }
+
+    /// These capabilities, with unset ones taken from `other`
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            tool_calls: self.tool_calls.or(other.tool_calls),
+            parallel_tool_calls: self.parallel_tool_calls.or(other.parallel_tool_calls),
+            json_mode: self.json_mode.or(other.json_mode),
+            context_tokens: self.context_tokens.or(other.context_tokens),
+        }
+    }
+}
+
+/// Retries of a request after a transient failure: a rate limit, server or network error
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries of a failed request before the task fails (defaults to 3, 0 disables)
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds (defaults to 1000), doubled per retry.
+    /// Rate limits back off from 5 times this delay.
+    pub base_delay_ms: u64,
+    /// Fraction of each delay randomly added or removed (defaults to 0.2), so parallel
+    /// workers limited at once do not retry in lockstep
+    pub jitter: f64,
+    /// HTTP status codes retried (defaults to 408, 429, 500, 502, 503, 504, 529).
+    /// Authentication, quota, and content filter errors are never retried; connection
+    /// failures and responses without a completion always are.
+    pub retry_status: Vec<u16>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 1000,
+            jitter: 0.2,
+            retry_status: vec![408, 429, 500, 502, 503, 504, 529],
+        }
+    }
+}
+
+impl RetryConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a failed request is retried, regardless of the retries left
+    fn is_retryable(&self, error: &ProviderError) -> bool {
+        match error.status {
+            Some(status) => self.retry_status.contains(&status) && !error.class.is_permanent(),
+            None => error.class.is_retryable(),
+        }
+    }
+
+    /// Delay before the given retry (1-based), with `random` in [0, 1) picking the jitter
+    fn delay(&self, class: ErrorClass, retry: u32, random: f64) -> Duration {
+        let delay = class.retry_delay(retry, self.base_delay_ms);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 + jitter * (2.0 * random - 1.0))
+    }
+}
+
+/// Random number in [0, 1), from the randomly keyed std hasher
+fn random_fraction() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Token counts a provider reported with a response
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReportedUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+/// Usage reported with the last response, taken by whoever meters the requests
+pub type UsageSlot = Arc<Mutex<Option<ReportedUsage>>>;
+
+/// Client of the configured provider API
+pub enum Client {
+    OpenAI(OpenAIProvider),
+    Anthropic(AnthropicProvider),
+    Azure(AzureProvider),
+    Streaming(StreamingProvider),
+}
+
+#[async_trait]
+impl LLMProvider for Client {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        match self {
+            Self::OpenAI(provider) => provider.call(messages, tools).await,
+            Self::Anthropic(provider) => provider.call(messages, tools).await,
+            Self::Azure(provider) => provider.call(messages, tools).await,
+            Self::Streaming(provider) => provider.call(messages, tools).await,
+        }
+    }
+}
+
+impl Client {
+    /// Where the client reports the token counts of each response
+    fn reported_usage(&self) -> UsageSlot {
+        match self {
+            Self::OpenAI(provider) => provider.reported_usage(),
+            Self::Anthropic(provider) => provider.reported_usage(),
+            Self::Azure(provider) => provider.reported_usage(),
+            Self::Streaming(provider) => provider.reported_usage(),
+        }
+    }
+}
+
+/// Client offering tools with the configured tool protocol
+enum Protocol {
+    Native(Client),
+    React(ReactProvider<Client>),
+    /// Native tool calls until the endpoint rejects them or the model garbles them, then
+    /// text blocks
+    Auto {
+        provider: ReactProvider<Client>,
+        fallen_back: bool,
+        /// Endpoint and model, shared by the providers of all workers
+        model_key: String,
+    },
+}
+
+/// Endpoints and models that `tool_protocol = "auto"` switched to text blocks, so other
+/// workers skip the native attempt and its warning
+static REACT_FALLBACKS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// LLM provider using the configured tool protocol and retry policy
+pub struct Provider {
+    protocol: Protocol,
+    retry: RetryConfig,
+    reported: UsageSlot,
+}
+
+impl Provider {
+    fn new(client: Client, tool_protocol: ToolProtocol, base_url: &str, model: &str) -> Self {
+        let reported = client.reported_usage();
+        let protocol = match tool_protocol {
+            ToolProtocol::Native => Protocol::Native(client),
+            ToolProtocol::React => Protocol::React(ReactProvider::new(client)),
+            ToolProtocol::Auto => Protocol::Auto {
+                provider: ReactProvider::new(client),
+                fallen_back: false,
+                model_key: format!("{} {}", base_url, model),
+            },
+        };
+        Self {
+            protocol,
+            retry: RetryConfig::default(),
+            reported,
+        }
+    }
+
+    /// Token counts reported with each response, empty when the server omits them
+    pub fn reported_usage(&self) -> UsageSlot {
+        self.reported.clone()
+    }
+
+    /// Retry failed requests with `retry` instead of the default policy
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    async fn call_once(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> Result<LLMResponse, ProviderError> {
+        // Another worker may have fallen back since this one started
+        if let Protocol::Auto {
+            fallen_back,
+            model_key,
+            ..
+        } = &mut self.protocol
+            && !*fallen_back
+        {
+            *fallen_back = REACT_FALLBACKS.lock().unwrap().contains(model_key);
+        }
+        let response = match &mut self.protocol {
+            Protocol::Native(provider) => provider.call(messages, tools).await,
+            Protocol::React(provider) => provider.call(messages, tools).await,
+            Protocol::Auto {
+                provider,
+                fallen_back: false,
+                ..
+            } => provider.inner_mut().call(messages, tools).await,
+            Protocol::Auto { provider, .. } => provider.call(messages, tools).await,
+        }
+        .map_err(|e| error::classify(&e))?;
+        let message = &response.message;
+        if matches!(response.finish_reason, FinishReason::ContentFilter)
+            && message.content.is_empty()
+            && message.tool_calls.as_ref().is_none_or(Vec::is_empty)
+        {
+            return Err(ProviderError {
+                class: ErrorClass::ContentFilter,
+                status: None,
+                message: "the completion was withheld".to_string(),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Switch `tool_protocol = "auto"` to text blocks for every worker of the model, as
+    /// native tool calls failed for `reason`, returning whether the request should be sent
+    /// again
+    fn fall_back_to_react(&mut self, reason: &str, tools: &[ToolDefinition]) -> bool {
+        let Protocol::Auto {
+            fallen_back,
+            model_key,
+            ..
+        } = &mut self.protocol
+        else {
+            return false;
+        };
+        if *fallen_back || tools.is_empty() {
+            return false;
+        }
+        *fallen_back = true;
+        if REACT_FALLBACKS.lock().unwrap().insert(model_key.clone()) {
+            warn!(
+                "{}; offering tools as text blocks instead (tool_protocol = \"react\")",
+                reason
+            );
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl LLMProvider for Provider {
+    /// Call the provider, retrying transient failures with backoff.
+    /// Other failures are returned at once with an actionable message.
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let mut retry = 0;
+        loop {
+            match self.call_once(messages, tools).await {
+                Ok(response) => match malformed_tool_call(&response, tools) {
+                    Some(reason) if self.fall_back_to_react(&reason, tools) => {}
+                    _ => return Ok(response),
+                },
+                Err(e) if e.rejects_tools() && self.fall_back_to_react(&e.message, tools) => {}
+                Err(e) if self.retry.is_retryable(&e) && retry < self.retry.max_retries => {
+                    retry += 1;
+                    let delay = self.retry.delay(e.class, retry, random_fraction());
+                    warn!(
+                        "{}; retrying in {:.1}s ({}/{})",
+                        e,
+                        delay.as_secs_f64(),
+                        retry,
+                        self.retry.max_retries
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(tiny_loop::Error::Custom(e.to_string())),
+            }
+        }
+    }
+}
+
+/// Create an LLM provider with the specified configuration; `base_url` is the
+/// deployment endpoint for Azure
 pub fn create_provider(
     api_key: &str,
     base_url: &str,
     model: &str,
     headers: &std::collections::HashMap<String, String>,
     body: &serde_json::Value,
-) -> anyhow::Result<OpenAIProvider> {
-    let mut provider = OpenAIProvider::new()
-        .api_key(api_key)
-        .base_url(base_url)
-        .model(model);
+    tool_protocol: ToolProtocol,
+    kind: ProviderKind,
+) -> anyhow::Result<Provider> {
+    let client = match kind {
+        // The ReAct wrapper sends no tools, so tool options are dropped from the request
+        ProviderKind::OpenAI => Client::OpenAI(OpenAIProvider::new(
+            api_key, base_url, model, headers, body,
+        )?),
+        // The ReAct wrapper sends no tools, so the request needs no changes
+        ProviderKind::Anthropic => Client::Anthropic(AnthropicProvider::new(
+            api_key, base_url, model, headers, body,
+        )?),
+        // `base_url` is the deployment endpoint resolved by `LlmConfig::endpoint`
+        ProviderKind::Azure => {
+            Client::Azure(AzureProvider::new(api_key, base_url, model, headers, body)?)
+        }
+    };
+    Ok(Provider::new(client, tool_protocol, base_url, model))
+}
+
+/// Why the native tool calls of `response` are unusable, as sent by models with flaky tool
+/// calling: a call to a tool not offered, arguments that are not a JSON object, or a call
+/// written as JSON text instead
+fn malformed_tool_call(response: &LLMResponse, tools: &[ToolDefinition]) -> Option<String> {
+    let offered = |name: &str| tools.iter().any(|tool| tool.function.name == name);
+    let Some(calls) = response
+        .message
+        .tool_calls
+        .as_ref()
+        .filter(|c| !c.is_empty())
+    else {
+        let text: serde_json::Value = serde_json::from_str(response.message.content.trim()).ok()?;
+        let name = text["name"].as_str().filter(|name| offered(name))?;
+        return Some(format!("the model wrote a call to '{}' as text", name));
+    };
+    calls.iter().find_map(|call| {
+        let name = &call.function.name;
+        if !offered(name) {
+            return Some(format!("the model called the unknown tool '{}'", name));
+        }
+        let arguments = call.function.arguments.trim();
+        if arguments.is_empty() {
+            return None;
+        }
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(arguments)
+            .err()
+            .map(|e| format!("the model sent invalid arguments to '{}': {}", name, e))
+    })
+}
+
+/// Create an OpenAI-compatible or Azure LLM provider that streams replies, reporting to
+/// `progress`
+#[allow(clippy::too_many_arguments)]
+pub fn create_streaming_provider(
+    api_key: &str,
+    base_url: &str,
+    model: &str,
+    headers: &std::collections::HashMap<String, String>,
+    body: &serde_json::Value,
+    tool_protocol: ToolProtocol,
+    provider: ProviderKind,
+    progress: StreamProgress,
+) -> anyhow::Result<Provider> {
+    let streaming = StreamingProvider::new(api_key, base_url, model, headers, body, progress)?;
+    let client = Client::Streaming(match provider {
+        ProviderKind::Azure => streaming.for_azure(),
+        _ => streaming,
+    });
+    Ok(Provider::new(client, tool_protocol, base_url, model))
+}
+
+/// Timeout for probing provider rate limits and model capabilities (seconds)
+const PROBE_TIMEOUT_SECS: u64 = 5;
+
+/// Model list and rate limit of a provider, from a single `GET {base_url}/models`
+#[derive(Debug, Default)]
+pub struct ProviderProbe {
+    /// Models response, if the provider answered with one
+    models: Option<serde_json::Value>,
+    /// Requests-per-minute limit advertised in the response headers
+    pub requests_per_minute: Option<u32>,
+}
+
+impl ProviderProbe {
+    /// Request the provider's model list once, reading the advertised requests-per-minute
+    /// limit from `x-ratelimit-limit-requests` (`anthropic-ratelimit-requests-limit` for
+    /// Anthropic) and keeping the models to read capabilities from
+    pub async fn fetch(
+        api_key: &str,
+        base_url: &str,
+        headers: &std::collections::HashMap<String, String>,
+        kind: ProviderKind,
+    ) -> Self {
+        let Ok(client) = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(PROBE_TIMEOUT_SECS))
+            .build()
+        else {
+            return Self::default();
+        };
+        let mut request = models_request(&client, api_key, base_url, kind);
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+        let Ok(response) = request.send().await else {
+            return Self::default();
+        };
+        let requests_per_minute = parse_requests_limit(response.headers(), kind);
+        let models = if response.status().is_success() {
+            response.json::<serde_json::Value>().await.ok()
+        } else {
+            None
+        };
+        Self {
+            models,
+            requests_per_minute,
+        }
+    }
+
+    /// Capabilities of `model` in the model list.
+    ///
+    /// Reads the context window (`context_length`, `context_window`, or `max_input_tokens`)
+    /// and, where the provider lists `supported_parameters` like OpenRouter, tool calling and
+    /// JSON mode. Capabilities the provider does not publish are left unset.
+    pub fn capabilities(&self, model: &str) -> Capabilities {
+        self.models
+            .as_ref()
+            .map(|models| parse_capabilities(models, model))
+            .unwrap_or_default()
+    }
+}
 
-    for (key, value) in headers {
-        provider = provider.header(key, value)?;
+fn parse_capabilities(models: &serde_json::Value, model: &str) -> Capabilities {
+    let Some(entry) = models["data"]
+        .as_array()
+        .and_then(|data| data.iter().find(|entry| entry["id"] == model))
+    else {
+        return Capabilities::default();
+    };
+    let context_tokens = ["context_length", "context_window", "max_input_tokens"]
+        .iter()
+        .find_map(|key| entry[key].as_u64())
+        .map(|tokens| tokens as usize);
+    let Some(parameters) = entry["supported_parameters"].as_array() else {
+        return Capabilities {
+            context_tokens,
+            ..Default::default()
+        };
+    };
+    let supports = |name: &str| parameters.iter().any(|p| p == name);
+    let tool_calls = supports("tools");
+    Capabilities {
+        tool_calls: Some(tool_calls),
+        // Only an absent tools parameter rules out parallel calls
+        parallel_tool_calls: (!tool_calls).then_some(false),
+        json_mode: Some(supports("response_format") || supports("structured_outputs")),
+        context_tokens,
     }
+}
 
-    if !body.is_null() {
-        provider = provider.body(body.clone())?;
+/// `GET {base_url}/models` with the provider's authentication
+fn models_request(
+    client: &reqwest::Client,
+    api_key: &str,
+    base_url: &str,
+    kind: ProviderKind,
+) -> reqwest::RequestBuilder {
+    let url = match kind {
+        ProviderKind::Azure => azure::models_url(base_url),
+        _ => format!("{}/models", base_url.trim_end_matches('/')),
+    };
+    let request = client.get(url);
+    match kind {
+        ProviderKind::OpenAI => request.bearer_auth(api_key),
+        ProviderKind::Anthropic => request
+            .header("x-api-key", api_key)
+            .header("anthropic-version", anthropic::ANTHROPIC_VERSION),
+        ProviderKind::Azure => request.header("api-key", api_key),
     }
+}
 
-    Ok(provider)
+fn parse_requests_limit(headers: &reqwest::header::HeaderMap, kind: ProviderKind) -> Option<u32> {
+    let name = match kind {
+        ProviderKind::OpenAI | ProviderKind::Azure => "x-ratelimit-limit-requests",
+        ProviderKind::Anthropic => "anthropic-ratelimit-requests-limit",
+    };
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
 }
 
-/// Register common tools (sh, fetch, think) to an agent
-pub fn register_common_tools(agent: Agent, allowed_shell_commands: &[String]) -> Agent {
-    let defs = vec![crate::tool::sh::sh_tool_def(allowed_shell_commands)];
+/// Register common tools (think, fetch, sh) to an agent, skipping disabled ones.
+/// Shell commands run in `dir`, or the current directory if `None`.
+pub fn register_common_tools(
+    mut agent: Agent,
+    allowed_shell_commands: &[String],
+    shell: crate::tool::sh::Shell,
+    dir: Option<&std::path::Path>,
+    think: crate::tool::think::Think,
+    tools: &crate::rule::tools::ToolsConfig,
+    cancel: &CancellationToken,
+) -> Agent {
+    if tools.think() {
+        agent = agent.bind(think, crate::tool::think::Think::think);
+    }
+    if tools.fetch() {
+        agent = agent.bind(
+            crate::tool::fetch::Fetch::new(cancel.clone()),
+            crate::tool::fetch::Fetch::fetch,
+        );
+    }
+    if !tools.sh() {
+        return agent;
+    }
+
+    let defs = vec![crate::tool::sh::sh_tool_def(allowed_shell_commands, shell)];
 
     let allowed_cmds = allowed_shell_commands.to_vec();
+    let dir = dir.map(std::path::Path::to_path_buf);
+    let cancel = cancel.clone();
     let exec = move |name: String, args: String| {
         let allowed_cmds = allowed_cmds.clone();
+        let dir = dir.clone();
+        let cancel = cancel.clone();
         async move {
             match name.as_str() {
                 crate::tool::sh::ShArgs::TOOL_NAME => {
                     let args: crate::tool::sh::ShArgs = serde_json::from_str(&args).unwrap();
-                    crate::tool::sh::execute_sh_args(args, &allowed_cmds).await
+                    crate::tool::sh::execute_sh_args(
+                        args,
+                        &allowed_cmds,
+                        shell,
+                        dir.as_deref(),
+                        &cancel,
+                    )
+                    .await
                 }
                 _ => format!("Unknown tool: {}", name),
             }
         }
     };
 
-    agent
-        .tool(crate::tool::fetch::fetch)
-        .tool(crate::tool::think::think)
-        .external(defs, exec)
+    agent.external(defs, exec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    #[test]
+    fn test_auto_tool_protocol_falls_back_once() {
+        let provider = |tool_protocol| {
+            create_provider(
+                "",
+                "http://localhost:11434/v1",
+                "gemma3:4b",
+                &Default::default(),
+                &serde_json::Value::Null,
+                tool_protocol,
+                ProviderKind::OpenAI,
+            )
+            .unwrap()
+        };
+        let rejected = error::classify_api_error(
+            400,
+            r#"{"error":{"message":"registry.ollama.ai/library/gemma3:4b does not support tools"}}"#,
+        );
+        let tools: [ToolDefinition; 1] = [serde_json::from_value(serde_json::json!({
+            "type": "function",
+            "function": { "name": "read", "description": "Read a file", "parameters": {} },
+        }))
+        .unwrap()];
+
+        assert!(rejected.rejects_tools());
+
+        let mut auto = provider(ToolProtocol::Auto);
+        assert!(!auto.fall_back_to_react(&rejected.message, &[]));
+        assert!(auto.fall_back_to_react(&rejected.message, &tools));
+        assert!(matches!(
+            auto.protocol,
+            Protocol::Auto {
+                fallen_back: true,
+                ..
+            }
+        ));
+        assert!(!auto.fall_back_to_react(&rejected.message, &tools));
+        assert!(!provider(ToolProtocol::Native).fall_back_to_react(&rejected.message, &tools));
+        // Workers started later skip native tool calls for the same model
+        assert!(
+            REACT_FALLBACKS
+                .lock()
+                .unwrap()
+                .contains("http://localhost:11434/v1 gemma3:4b")
+        );
+    }
+
+    #[test]
+    fn test_malformed_tool_call() {
+        let tools: [ToolDefinition; 1] = [serde_json::from_value(serde_json::json!({
+            "type": "function",
+            "function": { "name": "read", "description": "Read a file", "parameters": {} },
+        }))
+        .unwrap()];
+        let response = |content: &str, calls: serde_json::Value| LLMResponse {
+            message: tiny_loop::types::AssistantMessage {
+                content: content.to_string(),
+                tool_calls: serde_json::from_value(calls).unwrap(),
+            },
+            finish_reason: FinishReason::ToolCalls,
+        };
+        let call = |name: &str, arguments: &str| {
+            serde_json::json!([{
+                "id": "call_1",
+                "type": "function",
+                "function": { "name": name, "arguments": arguments },
+            }])
+        };
+
+        let valid = response("", call("read", r#"{"path":"a.rs"}"#));
+        assert_eq!(malformed_tool_call(&valid, &tools), None);
+        assert_eq!(
+            malformed_tool_call(&response("", call("read", "")), &tools),
+            None
+        );
+        let unknown = response("", call("open", "{}"));
+        assert!(
+            malformed_tool_call(&unknown, &tools)
+                .unwrap()
+                .contains("unknown tool")
+        );
+        let invalid = response("", call("read", r#"{"path":"a.rs""#));
+        assert!(
+            malformed_tool_call(&invalid, &tools)
+                .unwrap()
+                .contains("invalid arguments")
+        );
+        let text = response(
+            r#"{"name":"read","arguments":{"path":"a.rs"}}"#,
+            serde_json::Value::Null,
+        );
+        assert!(
+            malformed_tool_call(&text, &tools)
+                .unwrap()
+                .contains("as text")
+        );
+        let answer = response("No violations.", serde_json::Value::Null);
+        assert_eq!(malformed_tool_call(&answer, &tools), None);
+    }
+
+    #[test]
+    fn test_parse_requests_limit() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_requests_limit(&headers, ProviderKind::OpenAI), None);
+        headers.insert(
+            "x-ratelimit-limit-requests",
+            HeaderValue::from_static("500"),
+        );
+        assert_eq!(
+            parse_requests_limit(&headers, ProviderKind::OpenAI),
+            Some(500)
+        );
+        assert_eq!(
+            parse_requests_limit(&headers, ProviderKind::Anthropic),
+            None
+        );
+        headers.insert(
+            "anthropic-ratelimit-requests-limit",
+            HeaderValue::from_static("50"),
+        );
+        assert_eq!(
+            parse_requests_limit(&headers, ProviderKind::Anthropic),
+            Some(50)
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let retry = RetryConfig::default();
+        let error = |status| error::classify_api_error(status, "");
+        assert!(retry.is_retryable(&error(429)));
+        assert!(retry.is_retryable(&error(529)));
+        assert!(!retry.is_retryable(&error(501)));
+        assert!(!retry.is_retryable(&error::classify_api_error(
+            429,
+            r#"{"error": {"message": "Out of credits", "type": "insufficient_quota"}}"#
+        )));
+        let network = ProviderError {
+            class: ErrorClass::Network,
+            status: None,
+            message: "connection reset".into(),
+        };
+        assert!(retry.is_retryable(&network));
+
+        let retry = RetryConfig {
+            retry_status: vec![400],
+            ..retry
+        };
+        assert!(retry.is_retryable(&error(400)));
+        assert!(!retry.is_retryable(&error(429)));
+        assert_eq!(
+            retry.delay(ErrorClass::Server, 2, 0.0),
+            Duration::from_millis(1600)
+        );
+        assert_eq!(
+            retry.delay(ErrorClass::RateLimit, 1, 0.5),
+            Duration::from_secs(5)
+        );
+        assert!((0.0..1.0).contains(&random_fraction()));
+    }
+
+    #[test]
+    fn test_parse_capabilities() {
+        let models = serde_json::json!({ "data": [
+            {
+                "id": "vendor/chat",
+                "context_length": 32768,
+                "supported_parameters": ["temperature", "response_format"],
+            },
+            { "id": "vendor/tools", "supported_parameters": ["tools", "tool_choice"] },
+            { "id": "claude", "max_input_tokens": 200000 },
+        ]});
+        assert_eq!(
+            parse_capabilities(&models, "vendor/chat"),
+            Capabilities {
+                tool_calls: Some(false),
+                parallel_tool_calls: Some(false),
+                json_mode: Some(true),
+                context_tokens: Some(32768),
+            }
+        );
+        let tools = parse_capabilities(&models, "vendor/tools");
+        assert_eq!(tools.tool_calls, Some(true));
+        assert_eq!(tools.parallel_tool_calls, None);
+        assert_eq!(
+            parse_capabilities(&models, "claude"),
+            Capabilities {
+                context_tokens: Some(200000),
+                ..Default::default()
+            }
+        );
+        assert!(parse_capabilities(&models, "missing").is_default());
+
+        let configured = Capabilities {
+            tool_calls: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            configured
+                .or(parse_capabilities(&models, "vendor/chat"))
+                .tool_calls,
+            Some(true)
+        );
+    }
 }
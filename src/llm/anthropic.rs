@@ -0,0 +1,323 @@
+use super::{ReportedUsage, UsageSlot};
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use serde_json::{Map, Value, json};
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{
+    AssistantMessage, FinishReason, FunctionCall, LLMResponse, Message, ToolCall, ToolDefinition,
+};
+
+/// Version of the Messages API the requests are written against
+pub(super) const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Output token limit of a response, which the Messages API requires;
+/// override with `max_tokens` in the `[llm] body`
+const DEFAULT_MAX_TOKENS: u64 = 8192;
+
+/// Body fields of OpenAI-compatible APIs that the Messages API rejects, dropped so the
+/// default `[llm] body` works with either provider
+const OPENAI_ONLY_FIELDS: [&str; 2] = ["parallel_tool_calls", "reasoning"];
+
+/// Anthropic Messages API provider.
+///
+/// Converts the OpenAI-style conversation of the agent loop: system messages become the
+/// top-level `system` prompt, tool calls become `tool_use` blocks, and tool results are
+/// sent as `tool_result` blocks in user turns.
+pub struct AnthropicProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+    headers: HeaderMap,
+    body: Map<String, Value>,
+    reported: UsageSlot,
+}
+
+impl AnthropicProvider {
+    pub fn new(
+        api_key: &str,
+        base_url: &str,
+        model: &str,
+        headers: &std::collections::HashMap<String, String>,
+        body: &Value,
+    ) -> tiny_loop::Result<Self> {
+        let (header_map, mut body) = super::openai::request_options(headers, body)?;
+        for field in OPENAI_ONLY_FIELDS {
+            body.remove(field);
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            headers: header_map,
+            body,
+            reported: UsageSlot::default(),
+        })
+    }
+
+    pub fn reported_usage(&self) -> UsageSlot {
+        self.reported.clone()
+    }
+
+    /// Request body of a Messages API call
+    fn request(&self, messages: &[Message], tools: &[ToolDefinition]) -> Value {
+        let (system, messages) = to_anthropic_messages(messages);
+        let mut request = Map::new();
+        request.insert("model".into(), self.model.clone().into());
+        request.insert("max_tokens".into(), DEFAULT_MAX_TOKENS.into());
+        if !system.is_empty() {
+            request.insert("system".into(), system.into());
+        }
+        request.insert("messages".into(), messages.into());
+        if !tools.is_empty() {
+            let tools: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.function.name,
+                        "description": tool.function.description,
+                        "input_schema": tool.function.parameters,
+                    })
+                })
+                .collect();
+            request.insert("tools".into(), tools.into());
+        }
+        request.extend(self.body.clone());
+        Value::Object(request)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for AnthropicProvider {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let response = self
+            .client
+            .post(format!("{}/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .headers(self.headers.clone())
+            .json(&self.request(messages, tools))
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(tiny_loop::Error::ApiError {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let value: Value = serde_json::from_str(&body).map_err(|e| {
+            tiny_loop::Error::Custom(format!("Failed to parse response: {}. Body: {}", e, body))
+        })?;
+        *self.reported.lock().unwrap() = reported_usage(&value);
+        Ok(from_anthropic_response(&value))
+    }
+}
+
+/// Append content blocks to the conversation, merging consecutive turns of a role
+/// as the Messages API expects alternating user and assistant turns
+fn push_blocks(messages: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut()
+        && last["role"] == role
+        && let Some(content) = last["content"].as_array_mut()
+    {
+        content.extend(blocks);
+        return;
+    }
+    messages.push(json!({ "role": role, "content": blocks }));
+}
+
+fn text_block(text: &str) -> Value {
+    json!({ "type": "text", "text": text })
+}
+
+/// Split an OpenAI-style conversation into the system prompt and Messages API turns
+fn to_anthropic_messages(messages: &[Message]) -> (String, Vec<Value>) {
+    let mut system = Vec::new();
+    let mut turns = Vec::new();
+    for message in messages {
+        match message {
+            Message::System(msg) => system.push(msg.content.as_str()),
+            Message::User(msg) if !msg.content.is_empty() => {
+                push_blocks(&mut turns, "user", vec![text_block(&msg.content)]);
+            }
+            Message::User(_) => {}
+            Message::Assistant(msg) => {
+                let mut blocks = Vec::new();
+                if !msg.content.trim().is_empty() {
+                    blocks.push(text_block(&msg.content));
+                }
+                for call in msg.tool_calls.iter().flatten() {
+                    let input = serde_json::from_str::<Value>(&call.function.arguments)
+                        .ok()
+                        .filter(Value::is_object)
+                        .unwrap_or_else(|| json!({}));
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.function.name,
+                        "input": input,
+                    }));
+                }
+                push_blocks(&mut turns, "assistant", blocks);
+            }
+            Message::Tool(msg) => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": msg.tool_call_id,
+                    "content": msg.content,
+                });
+                push_blocks(&mut turns, "user", vec![block]);
+            }
+            Message::Custom(msg) => {
+                if let Some(content) = msg.body["content"].as_str() {
+                    let role = if msg.role == "assistant" {
+                        "assistant"
+                    } else {
+                        "user"
+                    };
+                    push_blocks(&mut turns, role, vec![text_block(content)]);
+                }
+            }
+        }
+    }
+    (system.join("\n\n"), turns)
+}
+
+/// Token counts of a Messages API response; cached prompt tokens are counted separately
+fn reported_usage(value: &Value) -> Option<ReportedUsage> {
+    let usage = value.get("usage")?;
+    let tokens = |key: &str| usage[key].as_u64().unwrap_or_default() as usize;
+    Some(ReportedUsage {
+        prompt_tokens: tokens("input_tokens")
+            + tokens("cache_creation_input_tokens")
+            + tokens("cache_read_input_tokens"),
+        completion_tokens: tokens("output_tokens"),
+    })
+}
+
+/// Assistant message of a Messages API response, with `tool_use` blocks as tool calls
+fn from_anthropic_response(value: &Value) -> LLMResponse {
+    let mut content = Vec::new();
+    let mut tool_calls = Vec::new();
+    for block in value["content"].as_array().into_iter().flatten() {
+        match block["type"].as_str() {
+            Some("text") => content.extend(block["text"].as_str()),
+            Some("tool_use") => tool_calls.push(ToolCall {
+                id: block["id"].as_str().unwrap_or_default().to_string(),
+                call_type: "function".into(),
+                function: FunctionCall {
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: block["input"].to_string(),
+                },
+            }),
+            _ => {}
+        }
+    }
+    let finish_reason = match value["stop_reason"].as_str() {
+        Some("end_turn" | "stop_sequence") | None => FinishReason::Stop,
+        Some("max_tokens") => FinishReason::Length,
+        Some("tool_use") => FinishReason::ToolCalls,
+        Some("refusal") => FinishReason::ContentFilter,
+        Some(other) => FinishReason::Custom(other.to_string()),
+    };
+    LLMResponse {
+        message: AssistantMessage {
+            content: content.join(""),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        },
+        finish_reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_loop::types::{SystemMessage, ToolMessage, UserMessage};
+
+    #[test]
+    fn test_to_anthropic_messages() {
+        let call = |id: &str| ToolCall {
+            id: id.into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: "read".into(),
+                arguments: r#"{"path":"src/main.rs"}"#.into(),
+            },
+        };
+        let messages = [
+            Message::System(SystemMessage {
+                content: "Review".into(),
+            }),
+            Message::User(UserMessage {
+                content: "Diff".into(),
+            }),
+            Message::Assistant(AssistantMessage {
+                content: String::new(),
+                tool_calls: Some(vec![call("a"), call("b")]),
+            }),
+            Message::Tool(ToolMessage {
+                content: "fn main() {}".into(),
+                tool_call_id: "a".into(),
+            }),
+            Message::Tool(ToolMessage {
+                content: "mod x;".into(),
+                tool_call_id: "b".into(),
+            }),
+        ];
+        let (system, turns) = to_anthropic_messages(&messages);
+        assert_eq!(system, "Review");
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[1]["content"][0]["type"], "tool_use");
+        assert_eq!(turns[1]["content"][1]["input"]["path"], "src/main.rs");
+        assert_eq!(turns[2]["role"], "user");
+        assert_eq!(turns[2]["content"][1]["tool_use_id"], "b");
+    }
+
+    #[test]
+    fn test_request_and_response() {
+        let provider = AnthropicProvider::new(
+            "key",
+            "https://api.anthropic.com/v1/",
+            "claude-sonnet-4-5",
+            &Default::default(),
+            &json!({ "parallel_tool_calls": true, "max_tokens": 1024 }),
+        )
+        .unwrap();
+        assert_eq!(provider.base_url, "https://api.anthropic.com/v1");
+        let request = provider.request(&[], &[]);
+        assert_eq!(request["max_tokens"], 1024);
+        assert!(request.get("parallel_tool_calls").is_none());
+        assert!(request.get("tools").is_none());
+
+        let value = json!({
+            "content": [
+                { "type": "text", "text": "Reading" },
+                { "type": "tool_use", "id": "t1", "name": "read", "input": { "path": "a" } },
+            ],
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": 20, "cache_read_input_tokens": 1000, "output_tokens": 15 },
+        });
+        assert_eq!(
+            reported_usage(&value),
+            Some(ReportedUsage {
+                prompt_tokens: 1020,
+                completion_tokens: 15,
+            })
+        );
+        let response = from_anthropic_response(&value);
+        assert_eq!(response.message.content, "Reading");
+        let calls = response.message.tool_calls.unwrap();
+        assert_eq!(calls[0].function.arguments, r#"{"path":"a"}"#);
+        assert!(matches!(response.finish_reason, FinishReason::ToolCalls));
+    }
+}
@@ -0,0 +1,207 @@
+use super::UsageSlot;
+use super::openai::{from_response, reported_usage};
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use serde_json::{Map, Value, json};
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{LLMResponse, Message, ToolDefinition};
+
+/// OpenRouter fields of the default `[llm] body` that Azure rejects
+pub(super) const UNSUPPORTED_FIELDS: [&str; 1] = ["reasoning"];
+
+/// GA version of the Azure OpenAI data plane API, used without `llm.api_version`
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// Endpoint of an Azure OpenAI deployment, with the API version as query:
+/// `{base_url}/openai/deployments/{deployment}?api-version={api_version}`
+pub fn endpoint(base_url: &str, deployment: &str, api_version: &str) -> String {
+    format!(
+        "{}/openai/deployments/{}?api-version={}",
+        base_url.trim_end_matches('/'),
+        deployment,
+        api_version
+    )
+}
+
+/// URL of `path` under a deployment endpoint, keeping its `api-version` query
+pub(super) fn url(endpoint: &str, path: &str) -> String {
+    match endpoint.split_once('?') {
+        Some((base, query)) => format!("{}/{}?{}", base.trim_end_matches('/'), path, query),
+        None => format!("{}/{}", endpoint.trim_end_matches('/'), path),
+    }
+}
+
+/// URL of the model list of the resource of a deployment endpoint:
+/// `{base_url}/openai/models?api-version={api_version}`
+pub(super) fn models_url(endpoint: &str) -> String {
+    let Some((base, deployment)) = endpoint.split_once("/openai/deployments/") else {
+        return url(endpoint, "models");
+    };
+    match deployment.split_once('?') {
+        Some((_, query)) => format!("{}/openai/models?{}", base, query),
+        None => format!("{}/openai/models", base),
+    }
+}
+
+/// Azure OpenAI provider.
+///
+/// Speaks OpenAI chat completions, but addresses a deployment rather than a model,
+/// passes the API version as a query parameter, and authenticates with an `api-key`
+/// header instead of a bearer token.
+pub struct AzureProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    model: String,
+    headers: HeaderMap,
+    body: Map<String, Value>,
+    reported: UsageSlot,
+}
+
+impl AzureProvider {
+    /// Provider of the deployment at `endpoint`, as built by [`endpoint`]
+    pub fn new(
+        api_key: &str,
+        endpoint: &str,
+        model: &str,
+        headers: &std::collections::HashMap<String, String>,
+        body: &Value,
+    ) -> tiny_loop::Result<Self> {
+        let (header_map, mut body) = super::openai::request_options(headers, body)?;
+        for field in UNSUPPORTED_FIELDS {
+            body.remove(field);
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            headers: header_map,
+            body,
+            reported: UsageSlot::default(),
+        })
+    }
+
+    pub fn reported_usage(&self) -> UsageSlot {
+        self.reported.clone()
+    }
+
+    /// Request body of a chat completion
+    fn request(&self, messages: &[Message], tools: &[ToolDefinition]) -> Value {
+        let mut request = Map::new();
+        // The deployment selects the model; Azure accepts and ignores the name
+        request.insert("model".into(), self.model.clone().into());
+        request.insert("messages".into(), json!(messages));
+        if !tools.is_empty() {
+            request.insert("tools".into(), json!(tools));
+        }
+        request.extend(self.body.clone());
+        // Azure rejects tool options in requests without tools
+        if tools.is_empty() {
+            request.remove("parallel_tool_calls");
+            if request.get("tools").is_some_and(Value::is_null) {
+                request.remove("tools");
+            }
+        }
+        Value::Object(request)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for AzureProvider {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let response = self
+            .client
+            .post(url(&self.endpoint, "chat/completions"))
+            .header("api-key", &self.api_key)
+            .headers(self.headers.clone())
+            .json(&self.request(messages, tools))
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(tiny_loop::Error::ApiError {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let value: Value = serde_json::from_str(&body).map_err(|e| {
+            tiny_loop::Error::Custom(format!("Failed to parse response: {}. Body: {}", e, body))
+        })?;
+        *self.reported.lock().unwrap() = reported_usage(&value);
+        from_response(&value).ok_or_else(|| {
+            tiny_loop::Error::Custom(format!("Response has no completion. Body: {}", body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_loop::types::FinishReason;
+
+    #[test]
+    fn test_endpoint_urls() {
+        let endpoint = endpoint("https://res.openai.azure.com/", "gpt-4o", "2024-10-21");
+        assert_eq!(
+            endpoint,
+            "https://res.openai.azure.com/openai/deployments/gpt-4o?api-version=2024-10-21"
+        );
+        assert_eq!(
+            url(&endpoint, "chat/completions"),
+            "https://res.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(url("http://proxy/v1/", "models"), "http://proxy/v1/models");
+        assert_eq!(
+            models_url(&endpoint),
+            "https://res.openai.azure.com/openai/models?api-version=2024-10-21"
+        );
+        assert_eq!(models_url("http://proxy/v1/"), "http://proxy/v1/models");
+    }
+
+    #[test]
+    fn test_request_and_response() {
+        let provider = AzureProvider::new(
+            "key",
+            "https://res.openai.azure.com/openai/deployments/gpt-4o?api-version=2024-10-21",
+            "gpt-4o",
+            &Default::default(),
+            &json!({ "parallel_tool_calls": true, "reasoning": {}, "temperature": 0 }),
+        )
+        .unwrap();
+        let request = provider.request(&[], &[]);
+        assert_eq!(request["temperature"], 0);
+        assert!(request.get("parallel_tool_calls").is_none());
+        assert!(request.get("reasoning").is_none());
+
+        let value = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "read", "arguments": "{\"path\":\"a.rs\"}" },
+                    }],
+                },
+                "finish_reason": "tool_calls",
+            }],
+            "usage": { "prompt_tokens": 50, "completion_tokens": 12, "total_tokens": 62 },
+        });
+        assert_eq!(reported_usage(&value).unwrap().prompt_tokens, 50);
+        let response = from_response(&value).unwrap();
+        assert_eq!(response.message.content, "");
+        assert_eq!(
+            response.message.tool_calls.unwrap()[0].function.name,
+            "read"
+        );
+        assert!(matches!(response.finish_reason, FinishReason::ToolCalls));
+        assert!(from_response(&json!({ "choices": [] })).is_none());
+    }
+}
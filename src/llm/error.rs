@@ -0,0 +1,315 @@
+use serde_json::Value;
+use std::time::Duration;
+
+/// Multiple of the base delay before the first retry after a rate limit
+const RATE_LIMIT_DELAY_FACTOR: u64 = 5;
+
+/// Prefix of tiny-loop's error for unparseable response bodies
+const PARSE_ERROR_BODY: &str = ". Body: ";
+
+/// Kind of provider failure, deciding whether a request is retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorClass {
+    /// Missing, invalid, or unauthorized API key
+    Auth,
+    /// Out of credits or over a spending limit
+    Quota,
+    /// Too many requests
+    RateLimit,
+    /// Request or response blocked by the provider's content filter
+    ContentFilter,
+    /// Request rejected as invalid, e.g. unknown model or context too long
+    BadRequest,
+    /// Provider-side failure (5xx)
+    Server,
+    /// Connection failure or timeout
+    Network,
+    /// Success status without a usable completion, e.g. empty `choices`
+    InvalidResponse,
+}
+
+impl ErrorClass {
+    /// Whether retrying the same request may succeed
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::RateLimit | Self::Server | Self::Network | Self::InvalidResponse
+        )
+    }
+
+    /// Whether the failure is ours or the account's, so no status code makes a retry succeed
+    pub fn is_permanent(self) -> bool {
+        matches!(self, Self::Auth | Self::Quota | Self::ContentFilter)
+    }
+
+    /// Delay before the given retry (1-based), backing off exponentially from `base_delay_ms`;
+    /// rate limits back off from a longer delay
+    pub fn retry_delay(self, retry: u32, base_delay_ms: u64) -> Duration {
+        let base = if self == Self::RateLimit {
+            base_delay_ms.saturating_mul(RATE_LIMIT_DELAY_FACTOR)
+        } else {
+            base_delay_ms
+        };
+        Duration::from_millis(base.saturating_mul(1 << retry.saturating_sub(1).min(6)))
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Auth => "authentication failed",
+            Self::Quota => "quota exceeded",
+            Self::RateLimit => "rate limited",
+            Self::ContentFilter => "blocked by content filter",
+            Self::BadRequest => "bad request",
+            Self::Server => "provider error",
+            Self::Network => "connection failed",
+            Self::InvalidResponse => "invalid response",
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            Self::Auth => "Check the API key (--api-key or FIREKEEPER_LLM_API_KEY) and llm.headers",
+            Self::Quota => "Add credits or raise the spending limit of the provider account",
+            Self::RateLimit => "Lower review.max_parallel_workers or request a higher rate limit",
+            Self::ContentFilter => {
+                "Exclude the flagged files from the rule's scope or use a model without the filter"
+            }
+            Self::BadRequest => {
+                "Check llm.model, llm.base_url and llm.body; lower max_files_per_task if the context is too long"
+            }
+            Self::Server => "The provider may be down; try again later",
+            Self::Network => "Check the network connection and llm.base_url",
+            Self::InvalidResponse => {
+                "The provider returned no completion; check llm.base_url points to an OpenAI-compatible API"
+            }
+        }
+    }
+}
+
+/// Error messages of servers rejecting requests with tools, e.g. Ollama's
+/// "<model> does not support tools" or vLLM's "--enable-auto-tool-choice" hint
+const TOOLS_UNSUPPORTED_HINTS: [&str; 4] = [
+    "does not support tools",
+    "tools are not supported",
+    "tool calling is not supported",
+    "enable-auto-tool-choice",
+];
+
+/// Classified provider failure with an actionable message
+#[derive(Debug)]
+pub struct ProviderError {
+    pub class: ErrorClass,
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl ProviderError {
+    /// Whether the endpoint rejected the request for offering tools
+    pub fn rejects_tools(&self) -> bool {
+        let message = self.message.to_lowercase();
+        self.class == ErrorClass::BadRequest
+            && TOOLS_UNSUPPORTED_HINTS
+                .iter()
+                .any(|hint| message.contains(hint))
+    }
+}
+
+impl std::fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LLM {}", self.class.description())?;
+        if let Some(status) = self.status {
+            write!(f, " ({})", status)?;
+        }
+        write!(f, ": {}. {}", self.message, self.class.hint())
+    }
+}
+
+/// Error message and code fields of an OpenAI-style `{"error": {...}}` body
+fn error_fields(body: &str) -> Option<(String, String)> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let error = value.get("error").unwrap_or(&value);
+    let message = match error {
+        Value::String(message) => message.clone(),
+        _ => error.get("message")?.as_str()?.to_string(),
+    };
+    let code = ["code", "type", "status"]
+        .iter()
+        .filter_map(|key| error.get(key))
+        .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some((message, code))
+}
+
+/// Classify a non-success response by status and error body
+pub fn classify_api_error(status: u16, body: &str) -> ProviderError {
+    let (message, code) =
+        error_fields(body).unwrap_or_else(|| (body.trim().to_string(), String::new()));
+    let text = format!("{} {}", code, message).to_lowercase();
+    let mentions = |hints: &[&str]| hints.iter().any(|hint| text.contains(hint));
+
+    let class = if mentions(&["content_filter", "content_policy", "moderation", "flagged"]) {
+        ErrorClass::ContentFilter
+    } else if status == 402 || mentions(&["insufficient_quota", "quota", "credits", "billing"]) {
+        ErrorClass::Quota
+    } else if matches!(status, 401 | 403) || mentions(&["invalid_api_key", "authentication"]) {
+        ErrorClass::Auth
+    } else if status == 429 || mentions(&["rate_limit", "rate limit"]) {
+        ErrorClass::RateLimit
+    } else if status >= 500 || status == 408 {
+        ErrorClass::Server
+    } else if status >= 400 {
+        ErrorClass::BadRequest
+    } else {
+        ErrorClass::InvalidResponse
+    };
+    ProviderError {
+        class,
+        status: (status != 200).then_some(status),
+        message: if message.is_empty() {
+            "empty response body".to_string()
+        } else {
+            message
+        },
+    }
+}
+
+/// Classify an error returned by the provider client
+pub fn classify(error: &tiny_loop::Error) -> ProviderError {
+    let other = |class, message: String| ProviderError {
+        class,
+        status: None,
+        message,
+    };
+    match error {
+        tiny_loop::Error::ApiError { status, body } => classify_api_error(*status, body),
+        tiny_loop::Error::Http(e) => other(ErrorClass::Network, e.to_string()),
+        tiny_loop::Error::InvalidHeader(_) | tiny_loop::Error::InvalidBody => {
+            other(ErrorClass::BadRequest, error.to_string())
+        }
+        // Some providers report errors in a success response, e.g. `{"error": {"code": 402}}`
+        tiny_loop::Error::Custom(message) => match message.split_once(PARSE_ERROR_BODY) {
+            Some((_, body)) if error_fields(body).is_some() => {
+                let status = serde_json::from_str::<Value>(body)
+                    .ok()
+                    .and_then(|v| v["error"]["code"].as_u64())
+                    .and_then(|code| u16::try_from(code).ok())
+                    .unwrap_or(200);
+                classify_api_error(status, body)
+            }
+            _ => other(ErrorClass::InvalidResponse, message.clone()),
+        },
+        tiny_loop::Error::Json(_) | tiny_loop::Error::UnexpectedMessage(_) => {
+            other(ErrorClass::InvalidResponse, error.to_string())
+        }
+    }
+}
+
+/// Whether a failed review task failed on the LLM provider rather than e.g. loading its
+/// resources: provider failures reach the worker's caller as a [`tiny_loop::Error`]
+pub fn is_provider_failure(error: &(dyn std::error::Error + 'static)) -> bool {
+    error.downcast_ref::<tiny_loop::Error>().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_api_error() {
+        let class = |status, body| classify_api_error(status, body).class;
+        assert_eq!(
+            class(
+                401,
+                r#"{"error": {"message": "Incorrect API key", "code": "invalid_api_key"}}"#
+            ),
+            ErrorClass::Auth
+        );
+        assert_eq!(
+            class(
+                429,
+                r#"{"error": {"message": "You exceeded your current quota", "type": "insufficient_quota"}}"#
+            ),
+            ErrorClass::Quota
+        );
+        assert_eq!(class(429, "Too Many Requests"), ErrorClass::RateLimit);
+        assert_eq!(
+            class(
+                400,
+                r#"{"error": {"message": "The response was filtered", "code": "content_filter"}}"#
+            ),
+            ErrorClass::ContentFilter
+        );
+        assert_eq!(
+            class(
+                400,
+                r#"{"error": {"message": "maximum context length exceeded"}}"#
+            ),
+            ErrorClass::BadRequest
+        );
+        assert_eq!(class(503, ""), ErrorClass::Server);
+    }
+
+    #[test]
+    fn test_rejects_tools() {
+        let ollama = r#"{"error":{"message":"registry.ollama.ai/library/gemma3:4b does not support tools","type":"api_error"}}"#;
+        assert!(classify_api_error(400, ollama).rejects_tools());
+        let vllm = r#"{"object":"error","message":"\"auto\" tool choice requires --enable-auto-tool-choice and --tool-call-parser to be set"}"#;
+        assert!(classify_api_error(400, vllm).rejects_tools());
+        assert!(!classify_api_error(500, ollama).rejects_tools());
+        assert!(
+            !classify_api_error(
+                400,
+                r#"{"error":{"message":"maximum context length exceeded"}}"#
+            )
+            .rejects_tools()
+        );
+    }
+
+    #[test]
+    fn test_classify_error_in_success_body() {
+        let error = tiny_loop::Error::Custom(
+            r#"Failed to parse response: missing field `choices`. Body: {"error": {"message": "Insufficient credits", "code": 402}}"#.into(),
+        );
+        let error = classify(&error);
+        assert_eq!(error.class, ErrorClass::Quota);
+        assert_eq!(error.status, Some(402));
+        assert!(!error.class.is_retryable());
+        assert!(
+            error
+                .to_string()
+                .starts_with("LLM quota exceeded (402): Insufficient credits. Add credits")
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert!(ErrorClass::RateLimit.is_retryable());
+        assert!(!ErrorClass::Auth.is_retryable());
+        assert!(ErrorClass::Quota.is_permanent());
+        assert_eq!(
+            ErrorClass::Server.retry_delay(1, 1000),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            ErrorClass::Server.retry_delay(3, 1000),
+            Duration::from_secs(4)
+        );
+        assert_eq!(
+            ErrorClass::RateLimit.retry_delay(2, 1000),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn test_is_provider_failure() {
+        let provider: Box<dyn std::error::Error> =
+            Box::new(tiny_loop::Error::Custom("LLM server error".into()));
+        assert!(is_provider_failure(provider.as_ref()));
+        let agent: Box<dyn std::error::Error> =
+            anyhow::anyhow!("Duplicated tool call detected").into();
+        assert!(!is_provider_failure(agent.as_ref()));
+        let resource: Box<dyn std::error::Error> = "Failed to load resource".into();
+        assert!(!is_provider_failure(resource.as_ref()));
+    }
+}
@@ -0,0 +1,193 @@
+use super::{ReportedUsage, UsageSlot};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::{Map, Value, json};
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{
+    AssistantMessage, FinishReason, LLMResponse, Message, ToolCall, ToolDefinition,
+};
+
+/// OpenAI-compatible chat completions provider.
+///
+/// Unlike tiny-loop's provider, a response without choices is returned as an error
+/// instead of panicking, so it is classified and retried like other invalid responses.
+pub struct OpenAIProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+    headers: HeaderMap,
+    body: Map<String, Value>,
+    reported: UsageSlot,
+}
+
+impl OpenAIProvider {
+    pub fn new(
+        api_key: &str,
+        base_url: &str,
+        model: &str,
+        headers: &std::collections::HashMap<String, String>,
+        body: &Value,
+    ) -> tiny_loop::Result<Self> {
+        let (header_map, body) = super::openai::request_options(headers, body)?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            headers: header_map,
+            body,
+            reported: UsageSlot::default(),
+        })
+    }
+
+    pub fn reported_usage(&self) -> UsageSlot {
+        self.reported.clone()
+    }
+
+    /// Request body of a chat completion
+    fn request(&self, messages: &[Message], tools: &[ToolDefinition]) -> Value {
+        let mut request = Map::new();
+        request.insert("model".into(), self.model.clone().into());
+        request.insert("messages".into(), json!(messages));
+        if !tools.is_empty() {
+            request.insert("tools".into(), json!(tools));
+        }
+        request.extend(self.body.clone());
+        // Servers without tool support may reject tool options in requests without tools
+        if tools.is_empty() {
+            request.remove("parallel_tool_calls");
+            if request.get("tools").is_some_and(Value::is_null) {
+                request.remove("tools");
+            }
+        }
+        Value::Object(request)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for OpenAIProvider {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let response = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .headers(self.headers.clone())
+            .json(&self.request(messages, tools))
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(tiny_loop::Error::ApiError {
+                status: status.as_u16(),
+                body,
+            });
+        }
+        let value: Value = serde_json::from_str(&body).map_err(|e| {
+            tiny_loop::Error::Custom(format!("Failed to parse response: {}. Body: {}", e, body))
+        })?;
+        *self.reported.lock().unwrap() = reported_usage(&value);
+        from_response(&value).ok_or_else(|| {
+            tiny_loop::Error::Custom(format!("Response has no completion. Body: {}", body))
+        })
+    }
+}
+
+/// Custom headers and body fields of the requests to a provider, from `[llm] headers` and
+/// `[llm] body`
+pub(super) fn request_options(
+    headers: &std::collections::HashMap<String, String>,
+    body: &Value,
+) -> tiny_loop::Result<(HeaderMap, Map<String, Value>)> {
+    let mut header_map = HeaderMap::new();
+    for (key, value) in headers {
+        header_map.insert(
+            HeaderName::try_from(key.as_str())
+                .map_err(|e| tiny_loop::Error::InvalidHeader(e.to_string()))?,
+            HeaderValue::try_from(value.as_str())
+                .map_err(|e| tiny_loop::Error::InvalidHeader(e.to_string()))?,
+        );
+    }
+    let body = match body {
+        Value::Null => Map::new(),
+        Value::Object(fields) => fields.clone(),
+        _ => return Err(tiny_loop::Error::InvalidBody),
+    };
+    Ok((header_map, body))
+}
+
+/// Assistant message of a chat completion, if it has a choice. Some servers (e.g. Azure)
+/// send `null` content with tool calls.
+pub(super) fn from_response(value: &Value) -> Option<LLMResponse> {
+    let choice = value["choices"].get(0)?;
+    let message = &choice["message"];
+    let tool_calls: Vec<ToolCall> =
+        serde_json::from_value(message["tool_calls"].clone()).unwrap_or_default();
+    Some(LLMResponse {
+        message: AssistantMessage {
+            content: message["content"].as_str().unwrap_or_default().to_string(),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        },
+        finish_reason: serde_json::from_value(choice["finish_reason"].clone())
+            .unwrap_or(FinishReason::Stop),
+    })
+}
+
+/// Token counts of a chat completion, or of the last event of a stream
+pub(super) fn reported_usage(value: &Value) -> Option<ReportedUsage> {
+    let usage = value.get("usage").filter(|u| u.is_object())?;
+    let tokens = |key: &str| usage[key].as_u64().unwrap_or_default() as usize;
+    Some(ReportedUsage {
+        prompt_tokens: tokens("prompt_tokens"),
+        completion_tokens: tokens("completion_tokens"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_response() {
+        let provider = OpenAIProvider::new(
+            "key",
+            "http://localhost:11434/v1/",
+            "qwen3-coder",
+            &Default::default(),
+            &json!({ "parallel_tool_calls": true, "tools": null, "temperature": 0 }),
+        )
+        .unwrap();
+        assert_eq!(provider.base_url, "http://localhost:11434/v1");
+        let request = provider.request(&[], &[]);
+        assert_eq!(request["model"], "qwen3-coder");
+        assert_eq!(request["temperature"], 0);
+        assert!(request.get("parallel_tool_calls").is_none());
+        assert!(request.get("tools").is_none());
+
+        let value = json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": "No violations." },
+                "finish_reason": "stop",
+            }],
+            "usage": { "prompt_tokens": 50, "completion_tokens": 12 },
+        });
+        assert_eq!(
+            reported_usage(&value),
+            Some(ReportedUsage {
+                prompt_tokens: 50,
+                completion_tokens: 12,
+            })
+        );
+        assert_eq!(reported_usage(&json!({ "usage": null })), None);
+        let response = from_response(&value).unwrap();
+        assert_eq!(response.message.content, "No violations.");
+        assert!(response.message.tool_calls.is_none());
+        assert!(from_response(&json!({ "choices": [] })).is_none());
+        assert!(from_response(&json!({ "error": { "code": 402 } })).is_none());
+    }
+}
@@ -0,0 +1,279 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{
+    AssistantMessage, FinishReason, FunctionCall, LLMResponse, Message, SystemMessage, ToolCall,
+    ToolDefinition, UserMessage,
+};
+
+const ACTION_PREFIX: &str = "ACTION:";
+const ARGS_PREFIX: &str = "ARGS:";
+const OBSERVATION_PREFIX: &str = "OBSERVATION";
+
+/// Text protocol appended to the system prompt, followed by the tool list
+const PROTOCOL_PROMPT: &str =
+    "You can call tools. To call a tool, write a block in exactly this format:
+
+ACTION: <tool name>
+ARGS: <JSON object with the tool arguments>
+
+You may write several blocks to call several tools. Stop after your ACTION blocks and wait: \
+each result is returned in a message starting with OBSERVATION. Never write OBSERVATION yourself. \
+When you need no more tools, reply without any ACTION block.
+
+Available tools:";
+
+/// Provider wrapper that exposes tools through `ACTION:`/`ARGS:` text blocks,
+/// for models without native tool calling.
+///
+/// Tool calls parsed from the reply are returned as regular tool calls, so the agent
+/// loop and tool implementations are unchanged.
+pub struct ReactProvider<P> {
+    inner: P,
+    next_id: usize,
+}
+
+impl<P> ReactProvider<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner, next_id: 0 }
+    }
+
+    /// Wrapped provider, to call it with native tool calls
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for ReactProvider<P> {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let messages = to_text_messages(messages, tools);
+        let mut response = self.inner.call(&messages, &[]).await?;
+        let (content, actions) = parse_actions(&response.message.content);
+        if !actions.is_empty() {
+            let tool_calls = actions
+                .into_iter()
+                .map(|(name, arguments)| {
+                    self.next_id += 1;
+                    ToolCall {
+                        id: format!("react_{}", self.next_id),
+                        call_type: "function".into(),
+                        function: FunctionCall { name, arguments },
+                    }
+                })
+                .collect();
+            response.message = AssistantMessage {
+                content,
+                tool_calls: Some(tool_calls),
+            };
+            response.finish_reason = FinishReason::ToolCalls;
+        }
+        Ok(response)
+    }
+}
+
+/// Describe tools for the system prompt
+fn format_tools(tools: &[ToolDefinition]) -> String {
+    let mut output = String::from(PROTOCOL_PROMPT);
+    for tool in tools {
+        output.push_str(&format!(
+            "\n\n## {}\n{}\nARGS schema: {}",
+            tool.function.name,
+            tool.function.description.trim(),
+            serde_json::to_string(&tool.function.parameters).unwrap_or_default()
+        ));
+    }
+    output
+}
+
+/// Rewrite a conversation without tool roles: tool calls become ACTION blocks
+/// and tool results become OBSERVATION user messages
+fn to_text_messages(messages: &[Message], tools: &[ToolDefinition]) -> Vec<Message> {
+    let mut output = Vec::new();
+    let mut tool_names: HashMap<&str, &str> = HashMap::new();
+    if !tools.is_empty() && !matches!(messages.first(), Some(Message::System(_))) {
+        output.push(Message::System(SystemMessage {
+            content: format_tools(tools),
+        }));
+    }
+
+    for (i, message) in messages.iter().enumerate() {
+        match message {
+            Message::System(sm) if i == 0 && !tools.is_empty() => {
+                output.push(Message::System(SystemMessage {
+                    content: format!("{}\n\n{}", sm.content, format_tools(tools)),
+                }));
+            }
+            Message::Assistant(am) if am.tool_calls.is_some() => {
+                let mut content = am.content.trim().to_string();
+                for tc in am.tool_calls.iter().flatten() {
+                    tool_names.insert(&tc.id, &tc.function.name);
+                    content.push_str(&format!(
+                        "\n{} {}\n{} {}",
+                        ACTION_PREFIX, tc.function.name, ARGS_PREFIX, tc.function.arguments
+                    ));
+                }
+                output.push(Message::Assistant(AssistantMessage {
+                    content: content.trim_start().to_string(),
+                    tool_calls: None,
+                }));
+            }
+            Message::Tool(tm) => {
+                let observation = format!(
+                    "{} ({}):\n{}",
+                    OBSERVATION_PREFIX,
+                    tool_names
+                        .get(tm.tool_call_id.as_str())
+                        .copied()
+                        .unwrap_or("tool"),
+                    tm.content
+                );
+                // Results of one turn's calls share a single user message
+                if let Some(Message::User(um)) = output.last_mut()
+                    && um.content.starts_with(OBSERVATION_PREFIX)
+                {
+                    um.content.push_str("\n\n");
+                    um.content.push_str(&observation);
+                } else {
+                    output.push(Message::User(UserMessage {
+                        content: observation,
+                    }));
+                }
+            }
+            other => output.push(other.clone()),
+        }
+    }
+    output
+}
+
+/// Split a reply into the text before its first ACTION block and the (tool name, JSON arguments)
+/// of each block. Anything from an OBSERVATION line on is ignored, as the model made it up.
+fn parse_actions(text: &str) -> (String, Vec<(String, String)>) {
+    let mut content = Vec::new();
+    let mut actions: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(OBSERVATION_PREFIX) {
+            break;
+        }
+        if let Some(name) = trimmed.strip_prefix(ACTION_PREFIX) {
+            actions.push((name.trim().trim_matches('`').to_string(), Vec::new()));
+        } else if let Some((_, args)) = actions.last_mut() {
+            args.push(trimmed.strip_prefix(ARGS_PREFIX).unwrap_or(line));
+        } else {
+            content.push(line);
+        }
+    }
+
+    let actions = actions
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, args)| (name, normalize_args(&args.join("\n"))))
+        .collect();
+    (content.join("\n").trim().to_string(), actions)
+}
+
+/// First JSON value in the arguments, ignoring code fences and trailing text
+fn normalize_args(args: &str) -> String {
+    let args = args.trim();
+    let start = args.find(['{', '[']).unwrap_or(0);
+    match serde_json::Deserializer::from_str(&args[start..])
+        .into_iter::<serde_json::Value>()
+        .next()
+    {
+        Some(Ok(value)) => value.to_string(),
+        _ if args.is_empty() => "{}".to_string(),
+        _ => args.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_loop::types::{Parameters, ToolFunction, ToolMessage};
+
+    #[test]
+    fn test_parse_actions() {
+        let reply = "Let me check.\nACTION: think\nARGS: {\"reasoning\": \"looks fine\"}\n\
+                     ACTION: `diff`\nARGS:\n```json\n{\"files\": [\"a.rs\"]}\n```\n\
+                     OBSERVATION (diff):\nmade up";
+        let (content, actions) = parse_actions(reply);
+        assert_eq!(content, "Let me check.");
+        assert_eq!(
+            actions,
+            vec![
+                ("think".into(), r#"{"reasoning":"looks fine"}"#.into()),
+                ("diff".into(), r#"{"files":["a.rs"]}"#.into()),
+            ]
+        );
+        assert_eq!(parse_actions("No violations.").1, vec![]);
+    }
+
+    #[test]
+    fn test_to_text_messages() {
+        let tools = vec![ToolDefinition {
+            tool_type: "function".into(),
+            function: ToolFunction {
+                name: "think".into(),
+                description: "Think".into(),
+                parameters: Parameters::from_schema(
+                    serde_json::from_value(serde_json::json!({})).unwrap(),
+                ),
+            },
+        }];
+        let call = |id: &str| ToolCall {
+            id: id.into(),
+            call_type: "function".into(),
+            function: FunctionCall {
+                name: "think".into(),
+                arguments: "{}".into(),
+            },
+        };
+        let result = |id: &str| {
+            Message::Tool(ToolMessage {
+                content: "OK".into(),
+                tool_call_id: id.into(),
+            })
+        };
+        let messages = vec![
+            Message::System(SystemMessage {
+                content: "Review".into(),
+            }),
+            Message::Assistant(AssistantMessage {
+                content: String::new(),
+                tool_calls: Some(vec![call("1"), call("2")]),
+            }),
+            result("1"),
+            result("2"),
+        ];
+
+        let converted = to_text_messages(&messages, &tools);
+        assert_eq!(converted.len(), 3);
+        let Message::System(sm) = &converted[0] else {
+            panic!("expected system message");
+        };
+        assert!(
+            sm.content.starts_with("Review\n\nYou can call tools.")
+                && sm.content.contains("## think")
+        );
+        let Message::Assistant(am) = &converted[1] else {
+            panic!("expected assistant message");
+        };
+        assert_eq!(
+            am.content,
+            "ACTION: think\nARGS: {}\nACTION: think\nARGS: {}"
+        );
+        assert!(am.tool_calls.is_none());
+        let Message::User(um) = &converted[2] else {
+            panic!("expected user message");
+        };
+        assert_eq!(
+            um.content,
+            "OBSERVATION (think):\nOK\n\nOBSERVATION (think):\nOK"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{AssistantMessage, FinishReason, LLMResponse, Message, ToolDefinition};
+
+/// Provider answering with recorded responses in order, e.g. from a fixture, without
+/// calling a model
+pub struct ReplayProvider {
+    responses: VecDeque<AssistantMessage>,
+}
+
+impl ReplayProvider {
+    pub fn new(responses: impl IntoIterator<Item = AssistantMessage>) -> Self {
+        Self {
+            responses: responses.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl LLMProvider for ReplayProvider {
+    async fn call(
+        &mut self,
+        _messages: &[Message],
+        _tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let message = self.responses.pop_front().ok_or_else(|| {
+            tiny_loop::Error::Custom("No recorded responses left to replay".into())
+        })?;
+        let finish_reason = if message.tool_calls.is_some() {
+            FinishReason::ToolCalls
+        } else {
+            FinishReason::Stop
+        };
+        Ok(LLMResponse {
+            message,
+            finish_reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replay_in_order() {
+        let mut provider = ReplayProvider::new([AssistantMessage {
+            content: "No violations.".into(),
+            tool_calls: None,
+        }]);
+        let response = provider.call(&[], &[]).await.unwrap();
+        assert_eq!(response.message.content, "No violations.");
+        assert!(matches!(response.finish_reason, FinishReason::Stop));
+        assert!(provider.call(&[], &[]).await.is_err());
+    }
+}
@@ -0,0 +1,379 @@
+use super::{ReportedUsage, UsageSlot};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use reqwest::header::HeaderMap;
+use serde_json::{Map, Value, json};
+use std::sync::{Arc, Mutex};
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{
+    AssistantMessage, FinishReason, FunctionCall, LLMResponse, Message, ToolCall, ToolDefinition,
+};
+use tracing::{debug, trace};
+
+/// Characters of streamed text between progress logs
+const PROGRESS_LOG_CHARS: usize = 2000;
+
+/// Reply being streamed, kept until the response completes
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Partial {
+    pub content: String,
+    /// Names of the tool calls started so far
+    pub tool_calls: Vec<String>,
+}
+
+impl Partial {
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty() && self.tool_calls.is_empty()
+    }
+}
+
+/// Progress of a streaming provider: logs text and tool calls as they arrive, and keeps
+/// the reply in flight so a cancelled worker can still record it
+#[derive(Clone, Default)]
+pub struct StreamProgress {
+    label: String,
+    partial: Arc<Mutex<Partial>>,
+}
+
+impl StreamProgress {
+    /// Progress logged with `label`, e.g. `[Worker 3]`
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            partial: Arc::default(),
+        }
+    }
+
+    /// Reply of the request in flight, empty between requests
+    pub fn partial(&self) -> Partial {
+        self.partial.lock().unwrap().clone()
+    }
+
+    fn reset(&self) {
+        *self.partial.lock().unwrap() = Partial::default();
+    }
+
+    fn text(&self, delta: &str) {
+        let mut partial = self.partial.lock().unwrap();
+        let before = partial.content.len() / PROGRESS_LOG_CHARS;
+        partial.content.push_str(delta);
+        if partial.content.len() / PROGRESS_LOG_CHARS > before {
+            debug!(
+                "{} Streamed {} characters",
+                self.label,
+                partial.content.len()
+            );
+        }
+        trace!("{} Delta: {:?}", self.label, delta);
+    }
+
+    fn tool_call(&self, name: &str) {
+        debug!("{} Streaming tool call '{}'", self.label, name);
+        self.partial
+            .lock()
+            .unwrap()
+            .tool_calls
+            .push(name.to_string());
+    }
+}
+
+/// Tool call assembled from stream deltas
+#[derive(Default)]
+struct ToolCallBuilder {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// OpenAI-compatible provider receiving replies as server-sent events.
+///
+/// Tool calls arrive in pieces keyed by `index` and are assembled before the reply is
+/// returned, so the agent loop sees the same response as without streaming.
+pub struct StreamingProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+    headers: HeaderMap,
+    body: Map<String, Value>,
+    progress: StreamProgress,
+    reported: UsageSlot,
+    /// Address an Azure OpenAI deployment endpoint with an `api-key` header
+    azure: bool,
+}
+
+impl StreamingProvider {
+    pub fn new(
+        api_key: &str,
+        base_url: &str,
+        model: &str,
+        headers: &std::collections::HashMap<String, String>,
+        body: &Value,
+        progress: StreamProgress,
+    ) -> tiny_loop::Result<Self> {
+        let (header_map, body) = super::openai::request_options(headers, body)?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            headers: header_map,
+            body,
+            progress,
+            reported: UsageSlot::default(),
+            azure: false,
+        })
+    }
+
+    /// Stream from the Azure OpenAI deployment at `base_url`, as built by
+    /// [`super::azure::endpoint`], instead
+    pub fn for_azure(mut self) -> Self {
+        for field in super::azure::UNSUPPORTED_FIELDS {
+            self.body.remove(field);
+        }
+        self.azure = true;
+        self
+    }
+
+    pub fn reported_usage(&self) -> UsageSlot {
+        self.reported.clone()
+    }
+
+    /// Request body of a streamed chat completion
+    fn request(&self, messages: &[Message], tools: &[ToolDefinition]) -> Value {
+        let mut request = Map::new();
+        request.insert("model".into(), self.model.clone().into());
+        request.insert("messages".into(), json!(messages));
+        request.insert("stream".into(), true.into());
+        // Token counts arrive in a final event without choices
+        request.insert("stream_options".into(), json!({ "include_usage": true }));
+        if !tools.is_empty() {
+            request.insert("tools".into(), json!(tools));
+        }
+        request.extend(self.body.clone());
+        // Servers without tool support may reject tool options in requests without tools
+        if tools.is_empty() {
+            request.remove("parallel_tool_calls");
+            if request.get("tools").is_some_and(Value::is_null) {
+                request.remove("tools");
+            }
+        }
+        Value::Object(request)
+    }
+}
+
+#[async_trait]
+impl LLMProvider for StreamingProvider {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        self.progress.reset();
+        let request = if self.azure {
+            self.client
+                .post(super::azure::url(&self.base_url, "chat/completions"))
+                .header("api-key", &self.api_key)
+        } else {
+            self.client
+                .post(format!("{}/chat/completions", self.base_url))
+                .bearer_auth(&self.api_key)
+        };
+        let response = request
+            .headers(self.headers.clone())
+            .json(&self.request(messages, tools))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(tiny_loop::Error::ApiError {
+                status: status.as_u16(),
+                body: response.text().await?,
+            });
+        }
+
+        let mut stream = response.bytes_stream();
+        let mut buffer = Vec::new();
+        let mut reply = Reply::default();
+        'stream: while let Some(bytes) = stream.try_next().await? {
+            buffer.extend_from_slice(&bytes);
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                // Stop at `[DONE]` rather than wait for the server to close the connection
+                if reply.line(line.trim(), &self.progress)? {
+                    break 'stream;
+                }
+            }
+        }
+        *self.reported.lock().unwrap() = reply.usage;
+        let response = reply.finish();
+        self.progress.reset();
+        Ok(response)
+    }
+}
+
+/// Reply assembled from stream events
+#[derive(Default)]
+struct Reply {
+    content: String,
+    tool_calls: Vec<ToolCallBuilder>,
+    finish_reason: Option<FinishReason>,
+    usage: Option<ReportedUsage>,
+}
+
+impl Reply {
+    /// Apply one line of the event stream, returning whether the stream is done
+    fn line(&mut self, line: &str, progress: &StreamProgress) -> tiny_loop::Result<bool> {
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            return Ok(false);
+        };
+        if data == "[DONE]" {
+            return Ok(true);
+        }
+        let Ok(event) = serde_json::from_str::<Value>(data) else {
+            return Ok(false);
+        };
+        // Providers report failures mid-stream as an error event
+        if event.get("error").is_some() {
+            return Err(tiny_loop::Error::Custom(format!(
+                "Error in response stream. Body: {}",
+                data
+            )));
+        }
+        if let Some(usage) = super::openai::reported_usage(&event) {
+            self.usage = Some(usage);
+        }
+        let choice = &event["choices"][0];
+        let delta = &choice["delta"];
+        if let Some(text) = delta["content"].as_str()
+            && !text.is_empty()
+        {
+            self.content.push_str(text);
+            progress.text(text);
+        }
+        for call in delta["tool_calls"].as_array().into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or_default() as usize;
+            if self.tool_calls.len() <= index {
+                self.tool_calls.resize_with(index + 1, Default::default);
+            }
+            let builder = &mut self.tool_calls[index];
+            if let Some(id) = call["id"].as_str() {
+                builder.id.push_str(id);
+            }
+            if let Some(name) = call["function"]["name"].as_str() {
+                builder.name.push_str(name);
+                progress.tool_call(name);
+            }
+            if let Some(arguments) = call["function"]["arguments"].as_str() {
+                builder.arguments.push_str(arguments);
+            }
+        }
+        if let Some(reason) = choice.get("finish_reason").filter(|r| !r.is_null()) {
+            self.finish_reason = serde_json::from_value(reason.clone()).ok();
+        }
+        Ok(false)
+    }
+
+    fn finish(self) -> LLMResponse {
+        let tool_calls: Vec<ToolCall> = self
+            .tool_calls
+            .into_iter()
+            .filter(|call| !call.name.is_empty())
+            .enumerate()
+            .map(|(i, call)| ToolCall {
+                id: if call.id.is_empty() {
+                    format!("call_{}", i)
+                } else {
+                    call.id
+                },
+                call_type: "function".into(),
+                function: FunctionCall {
+                    name: call.name,
+                    arguments: call.arguments,
+                },
+            })
+            .collect();
+        LLMResponse {
+            message: AssistantMessage {
+                content: self.content,
+                tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            },
+            finish_reason: self.finish_reason.unwrap_or(FinishReason::Stop),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_reply() {
+        let progress = StreamProgress::new("[Worker 0]");
+        let mut reply = Reply::default();
+        let lines = [
+            r#"data: {"choices":[{"delta":{"role":"assistant","content":"Check"}}]}"#,
+            "",
+            r#"data: {"choices":[{"delta":{"content":"ing"}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"c1","type":"function","function":{"name":"read","arguments":""}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"path\":"}}]}}]}"#,
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"a.rs\"}"}}]}}]}"#,
+        ];
+        for line in lines {
+            assert!(!reply.line(line, &progress).unwrap());
+        }
+        assert_eq!(
+            progress.partial(),
+            Partial {
+                content: "Checking".into(),
+                tool_calls: vec!["read".into()],
+            }
+        );
+        let done = r#"data: {"choices":[{"delta":{},"finish_reason":"tool_calls"}]}"#;
+        assert!(!reply.line(done, &progress).unwrap());
+        let usage = r#"data: {"choices":[],"usage":{"prompt_tokens":120,"completion_tokens":30}}"#;
+        assert!(!reply.line(usage, &progress).unwrap());
+        assert!(reply.line("data: [DONE]", &progress).unwrap());
+        assert_eq!(
+            reply.usage,
+            Some(ReportedUsage {
+                prompt_tokens: 120,
+                completion_tokens: 30,
+            })
+        );
+
+        let response = reply.finish();
+        assert_eq!(response.message.content, "Checking");
+        let calls = response.message.tool_calls.unwrap();
+        assert_eq!(calls[0].id, "c1");
+        assert_eq!(calls[0].function.arguments, r#"{"path":"a.rs"}"#);
+        assert!(matches!(response.finish_reason, FinishReason::ToolCalls));
+
+        let error = r#"data: {"error":{"message":"Rate limited","code":429}}"#;
+        assert!(Reply::default().line(error, &progress).is_err());
+    }
+
+    #[test]
+    fn test_stream_request_without_tools() {
+        let provider = StreamingProvider::new(
+            "key",
+            "http://localhost:11434/v1",
+            "qwen3-coder",
+            &Default::default(),
+            &json!({ "parallel_tool_calls": true, "tools": null, "reasoning": {}, "temperature": 0 }),
+            StreamProgress::default(),
+        )
+        .unwrap();
+        let request = provider.request(&[], &[]);
+        assert_eq!(request["stream"], true);
+        assert_eq!(request["temperature"], 0);
+        assert!(request.get("parallel_tool_calls").is_none());
+        assert!(request.get("tools").is_none());
+        assert!(request.get("reasoning").is_some());
+
+        let request = provider.for_azure().request(&[], &[]);
+        assert_eq!(request["stream"], true);
+        assert!(request.get("reasoning").is_none());
+    }
+}
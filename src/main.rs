@@ -1,28 +1,56 @@
+mod cache;
+mod ci;
 mod cli;
 mod config;
+mod github;
+mod hooks;
+mod init;
 mod llm;
+mod mcp;
+mod publish;
 mod review;
 mod rule;
+mod telemetry;
+mod tokens;
 mod tool;
 mod types;
 mod util;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use cli::{Cli, Commands};
 use config::Config;
+use rule::body::RuleBody;
+use rule::catalog::RuleCatalog;
 use toml_scaffold::TomlScaffold;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
-    // Initialize tracing subscriber with log level from CLI/env
-    tracing_subscriber::fmt()
+    // Initialize tracing subscriber with log level from CLI/env. `serve` speaks its
+    // protocol on stdout, so it logs to stderr.
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::new(&cli.log_level))
         .without_time()
-        .with_target(false)
-        .init();
+        .with_target(false);
+    if matches!(cli.command, Commands::Serve(_)) {
+        subscriber.with_writer(std::io::stderr).init();
+    } else {
+        subscriber.init();
+    }
+
+    match cli.repo.as_slice() {
+        [] => {}
+        [repo] => enter_checkout(std::path::Path::new(repo)),
+        repos => {
+            let Commands::Review(args) = &cli.command else {
+                error!("Several --repo paths are only supported by `review`");
+                std::process::exit(1);
+            };
+            std::process::exit(review::workspace::review_repos(repos, args, &cli.log_level).await);
+        }
+    }
 
     match &cli.command {
         Commands::Init(args) => {
@@ -31,6 +59,19 @@ async fn main() {
                 std::process::exit(1);
             }
 
+            if args.interactive {
+                let wizard = init::run(std::io::stdin().lock(), std::io::stdout(), &args.config)
+                    .unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    });
+                if let Err(e) = init::write(&args.config, &wizard, args.r#override) {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+
             let template = match args.template {
                 cli::Template::Fast => config::Config::template_fast(),
                 cli::Template::Full => config::Config::template_full(),
@@ -43,82 +84,433 @@ async fn main() {
 
             info!("Created {}", args.config);
         }
-        Commands::Review(args) => {
+        Commands::Review(args) => review((**args).clone(), None).await,
+        Commands::Ci(args) => {
+            let review_args = ci::review_args(args).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            });
+            review(review_args, Some(args)).await;
+        }
+        Commands::Render(args) => {
+            let rendered = if let (Some(worker_id), Some(input)) =
+                (&args.extract_fixture, &args.input)
+            {
+                let trace_file = review::render::TraceFile::load(input).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1);
+                });
+                let fixture = review::fixture::extract(&trace_file.entries, worker_id)
+                    .unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    });
+                serde_json::to_string_pretty(&fixture).unwrap()
+            } else if let [old, new] = args.diff.as_slice() {
+                let load = |path: &str| {
+                    review::render::TraceFile::load(path).unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    })
+                };
+                review::trace_diff::format_trace_diff(&load(old).entries, &load(new).entries)
+            } else {
+                // Required by clap unless --diff is given
+                let input = args.input.as_deref().unwrap_or_default();
+                let content = review::bundle::read_input(input).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1);
+                });
+
+                let (file, changes) =
+                    review::validate::ReviewFile::parse(&content).unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    });
+                if !changes.is_empty() {
+                    warn!(
+                        "{} was written by an older version, rendering it upgraded in memory (run `firekeeper validate-results --migrate {}` to upgrade the file)",
+                        input, input
+                    );
+                }
+                match file {
+                    review::validate::ReviewFile::Trace(trace_file) => {
+                        review::render::format_trace_markdown(&trace_file.entries)
+                    }
+                    review::validate::ReviewFile::Results(violation_file) => {
+                        let strings = review::locale::strings(violation_file.language.as_deref());
+                        let mut markdown = if args.group_by == Some(cli::GroupBy::Owner) {
+                            review::render::format_by_owner(
+                                &violation_file.violations,
+                                &violation_file.tips,
+                                strings,
+                            )
+                        } else {
+                            review::render::format_violations(
+                                &violation_file.violations,
+                                &violation_file.tips,
+                                strings,
+                            )
+                        };
+                        markdown.extend(review::render::format_quarantined(
+                            &violation_file.quarantined,
+                            &violation_file.violations,
+                            strings,
+                        ));
+                        markdown.extend(review::render::format_skipped(
+                            &violation_file.skipped,
+                            strings,
+                        ));
+                        markdown
+                    }
+                }
+            };
+
+            if let Some(output_path) = &args.output {
+                std::fs::write(output_path, rendered).unwrap_or_else(|e| {
+                    error!("Failed to write output file: {}", e);
+                    std::process::exit(1);
+                });
+                info!("Rendered to {}", output_path);
+            } else {
+                println!("{}", rendered);
+            }
+        }
+        Commands::ValidateResults(args) => {
+            let mut invalid = 0;
+            for path in &args.files {
+                if let Err(e) = review::validate::validate_file(path, args.migrate) {
+                    error!("{}: {}", path, e);
+                    invalid += 1;
+                }
+            }
+            if invalid > 0 {
+                error!("{} of {} files are invalid", invalid, args.files.len());
+                std::process::exit(1);
+            }
+        }
+        Commands::Test(args) => {
+            let mut failed = 0;
+            for path in &args.fixtures {
+                let result = match review::fixture::Fixture::load(path) {
+                    Ok(fixture) => fixture.check().await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(()) => info!("{}: ok", path),
+                    Err(e) => {
+                        error!("{}: {}", path, e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                error!("{} of {} fixtures failed", failed, args.fixtures.len());
+                std::process::exit(1);
+            }
+        }
+        Commands::Explain(args) => {
+            let violation_file =
+                review::render::ViolationFile::load(&args.results).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1);
+                });
+
+            let Some(selector) = &args.violation else {
+                println!(
+                    "{}",
+                    review::explain::format_violation_list(&violation_file)
+                );
+                return;
+            };
+
             let mut config = Config::load(&args.config).unwrap_or_else(|e| {
                 error!("Failed to load config: {}", e);
                 std::process::exit(1);
             });
+            let api_key = &require_api_key(args.api_key.as_deref(), &config);
+            review::orchestrator::probe_provider(&mut config, api_key).await;
 
-            if let Err(e) = config.apply_overrides(&args.config_overrides) {
-                error!("Failed to apply config overrides: {}", e);
+            // A bundle carries the trace of its run
+            let trace_path = args.trace.clone().or_else(|| {
+                review::bundle::contains(&args.results, review::bundle::TRACE)
+                    .then(|| args.results.clone())
+            });
+            let trace_file = trace_path.map(|path| {
+                review::render::TraceFile::load(&path).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1);
+                })
+            });
+
+            if let Err(e) = review::explain::explain(
+                &config,
+                api_key,
+                &violation_file,
+                trace_file.as_ref(),
+                selector,
+            )
+            .await
+            {
+                error!("{}", e);
                 std::process::exit(1);
             }
+        }
+        Commands::Triage(args) => {
+            let violation_file =
+                review::render::ViolationFile::load(&args.results).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    std::process::exit(1);
+                });
 
-            trace!("args: {:#?}", args);
-            trace!("config: {:#?}", config);
-
-            review::orchestrator::orchestrate_and_run(
-                &config.rules,
-                &args.base,
-                config.review.max_files_per_task,
-                config.review.max_parallel_workers,
-                config.review.timeout,
-                &config.llm.base_url,
-                &args.api_key,
-                &config.llm.model,
-                &config.llm.headers,
-                &config.llm.body,
-                args.dry_run,
-                args.output.as_deref(),
-                args.trace.as_deref(),
-                &args.config,
-                &config.review.resources,
-                &config.review.allowed_shell_commands,
-            )
-            .await;
+            if let Err(e) = review::triage::triage(
+                &violation_file,
+                &args.decisions,
+                &args.suppressions,
+                args.all,
+            ) {
+                error!("{}", e);
+                std::process::exit(1);
+            }
         }
-        Commands::Render(args) => {
-            let content = std::fs::read_to_string(&args.input).unwrap_or_else(|e| {
-                error!("Failed to read input file: {}", e);
+        Commands::Suggest(args) => {
+            let mut config = Config::load(&args.config).unwrap_or_else(|e| {
+                error!("Failed to load config: {}", e);
                 std::process::exit(1);
             });
+            let api_key = &require_api_key(args.api_key.as_deref(), &config);
+            review::orchestrator::probe_provider(&mut config, api_key).await;
 
-            let markdown = if let Ok(trace_file) =
-                serde_json::from_str::<review::render::TraceFile>(&content)
-            {
-                review::render::format_trace_markdown(&trace_file.entries)
-            } else if let Ok(violation_file) =
-                serde_json::from_str::<review::render::ViolationFile>(&content)
-            {
-                review::render::format_violations(&violation_file.violations, &violation_file.tips)
+            let patch = if let Some(results) = &args.results {
+                let violation_file =
+                    review::render::ViolationFile::load(results).unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    });
+                let decisions: review::triage::TriageFile =
+                    review::triage::load_or_default(&args.decisions).unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    });
+                review::suggest::suggest(&config, api_key, &violation_file, &decisions).await
             } else {
-                // Check version compatibility
-                if let Ok(value) = serde_json::from_str::<serde_json::Value>(&content) {
-                    if let Some(file_version) = value.get("version").and_then(|v| v.as_str()) {
-                        let current_version = env!("CARGO_PKG_VERSION");
-                        let file_minor = file_version.split('.').nth(1);
-                        let current_minor = current_version.split('.').nth(1);
-                        if file_minor != current_minor {
-                            error!(
-                                "Incompatible file version: {} (current: {})",
-                                file_version, current_version
-                            );
+                let mut sources = Vec::new();
+                for source in args.from_file.iter().chain(&args.from_lint) {
+                    let content = review::suggest::load_source(source)
+                        .await
+                        .unwrap_or_else(|e| {
+                            error!("{}", e);
                             std::process::exit(1);
-                        }
-                    }
+                        });
+                    sources.push((source.clone(), content));
                 }
-                error!("Invalid JSON format");
+                if args.from_lint.is_empty() {
+                    review::suggest::suggest_from_sources(&config, api_key, &sources).await
+                } else {
+                    review::suggest::suggest_from_lint(&config, api_key, &sources).await
+                }
+            }
+            .unwrap_or_else(|e| {
+                error!("{}", e);
                 std::process::exit(1);
-            };
-
+            });
+            if patch.is_empty() {
+                return;
+            }
             if let Some(output_path) = &args.output {
-                std::fs::write(output_path, markdown).unwrap_or_else(|e| {
+                std::fs::write(output_path, patch).unwrap_or_else(|e| {
                     error!("Failed to write output file: {}", e);
                     std::process::exit(1);
                 });
-                info!("Rendered to {}", output_path);
+                info!("Suggestions written to {}", output_path);
             } else {
-                println!("{}", markdown);
+                println!("{}", patch);
+            }
+        }
+        Commands::Bench(args) => {
+            let mut config = Config::load(&args.config).unwrap_or_else(|e| {
+                error!("Failed to load config: {}", e);
+                std::process::exit(1);
+            });
+            if let Err(e) = config.apply_profile(args.profile.as_deref()) {
+                error!("Failed to apply profile: {}", e);
+                std::process::exit(1);
+            }
+            if let Err(e) = config.apply_overrides(&args.config_overrides) {
+                error!("Failed to apply config overrides: {}", e);
+                std::process::exit(1);
+            }
+            let cases = review::bench::load_corpus(&args.corpus).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            });
+            let models = if args.models.is_empty() {
+                vec![config.llm.model.clone()]
+            } else {
+                args.models.clone()
+            };
+
+            let api_key = require_api_key(args.api_key.as_deref(), &config);
+            let report = review::bench::bench(&config, &api_key, &cases, &models).await;
+            println!("{}", review::bench::format_report(&report));
+        }
+        Commands::Serve(args) => {
+            let mut config = Config::load(&args.config).unwrap_or_else(|e| {
+                error!("Failed to load config: {}", e);
+                std::process::exit(1);
+            });
+            if let Err(e) = config.apply_profile(args.profile.as_deref()) {
+                error!("Failed to apply profile: {}", e);
+                std::process::exit(1);
+            }
+            if let Err(e) = config.apply_overrides(&args.config_overrides) {
+                error!("Failed to apply config overrides: {}", e);
+                std::process::exit(1);
+            }
+            let api_key = require_api_key(args.api_key.as_deref(), &config);
+            if let Err(e) =
+                review::serve::serve(&mut config, &api_key, args.metrics.as_deref()).await
+            {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Watch(args) => {
+            let mut config = Config::load(&args.config).unwrap_or_else(|e| {
+                error!("Failed to load config: {}", e);
+                std::process::exit(1);
+            });
+            if let Err(e) = config.apply_profile(args.profile.as_deref()) {
+                error!("Failed to apply profile: {}", e);
+                std::process::exit(1);
+            }
+            if let Err(e) = config.apply_overrides(&args.config_overrides) {
+                error!("Failed to apply config overrides: {}", e);
+                std::process::exit(1);
+            }
+            let api_key = require_api_key(args.api_key.as_deref(), &config);
+            let options = review::watch::WatchOptions {
+                interval_secs: args.interval.max(1),
+                rules_tags: &args.rules_tags,
+                metrics_addr: args.metrics.as_deref(),
+            };
+            if let Err(e) = review::watch::watch(&mut config, &api_key, options).await {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Stats(args) => {
+            let mut entries = review::history::load(&args.history).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            });
+            if let Some(last) = args.last {
+                entries = entries.split_off(entries.len().saturating_sub(last));
+            }
+            if args.flaky {
+                println!("{}", review::history::format_flaky(&entries));
+            } else {
+                println!("{}", review::history::format_stats(&entries));
+            }
+        }
+        Commands::Tokens(args) => {
+            let model = args.model.clone().unwrap_or_else(|| {
+                Config::load(&args.config)
+                    .map(|config| config.llm.model)
+                    .unwrap_or_else(|e| {
+                        error!("Failed to load config (or pass --model): {}", e);
+                        std::process::exit(1);
+                    })
+            });
+            let tokenizer = tokens::Tokenizer::for_model(&model);
+            let counts: Vec<(String, usize)> = if args.diff {
+                let base = util::Base::parse(&args.base);
+                let files = util::get_changed_files(&base);
+                let diffs = util::get_diffs(&base, &files);
+                files
+                    .into_iter()
+                    .map(|file| {
+                        let count = diffs.get(&file).map_or(0, |diff| tokenizer.count(diff));
+                        (file, count)
+                    })
+                    .collect()
+            } else {
+                // Required by clap unless --diff is given
+                let input = args.input.as_deref().unwrap_or_default();
+                let content = if input == "-" {
+                    std::io::read_to_string(std::io::stdin())
+                } else {
+                    std::fs::read_to_string(input)
+                }
+                .unwrap_or_else(|e| {
+                    error!("Failed to read {}: {}", input, e);
+                    std::process::exit(1);
+                });
+                vec![(input.to_string(), tokenizer.count(&content))]
+            };
+            println!("{}", tokens::format_counts(&tokenizer, &counts));
+        }
+        Commands::Publish(args) => match &args.command {
+            cli::PublishCommands::Http {
+                url,
+                results,
+                trace,
+                headers,
+            } => {
+                if let Err(e) = publish::http(url, results, trace.as_deref(), headers).await {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            cli::PublishCommands::Slack(args) => notify(publish::NotifyTarget::Slack, args).await,
+            cli::PublishCommands::Teams(args) => notify(publish::NotifyTarget::Teams, args).await,
+            cli::PublishCommands::Webhook(args) => {
+                notify(publish::NotifyTarget::Webhook, args).await
+            }
+        },
+        Commands::Cache(args) => {
+            let dir = std::path::Path::new(&args.dir);
+            match &args.command {
+                cli::CacheCommands::Stats => {
+                    println!("{}", cache::format_stats(&cache::stats(dir)));
+                }
+                cli::CacheCommands::Clear => match cache::clear(dir) {
+                    Ok(count) => info!("Removed {} cache entries", count),
+                    Err(e) => {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    }
+                },
+                cli::CacheCommands::Gc { older_than } => match cache::gc(dir, *older_than) {
+                    Ok((count, bytes)) => info!(
+                        "Removed {} cache entries ({})",
+                        count,
+                        cache::format_bytes(bytes)
+                    ),
+                    Err(e) => {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    }
+                },
+            }
+        }
+        Commands::InstallHooks(args) => {
+            if let Err(e) = hooks::install_hooks(
+                &args.hooks,
+                args.staged,
+                &args.rules_tags,
+                &args.config,
+                args.profile.as_deref(),
+                args.force,
+            ) {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::UninstallHooks(args) => {
+            if let Err(e) = hooks::uninstall_hooks(&args.hooks) {
+                error!("{}", e);
+                std::process::exit(1);
             }
         }
         Commands::Config(args) => match &args.command {
@@ -151,8 +543,13 @@ async fn main() {
                     std::process::exit(1);
                 });
 
-                match toml::from_str::<config::Config>(&content) {
-                    Ok(_) => {
+                match toml::from_str::<config::Config>(&content)
+                    .map_err(|e| e.to_string())
+                    .and_then(|mut c| {
+                        c.load_instruction_files(&args.config)?;
+                        c.check_rule_ids()
+                    }) {
+                    Ok(()) => {
                         info!("Config is valid: {}", args.config);
                     }
                     Err(e) => {
@@ -162,5 +559,384 @@ async fn main() {
                 }
             }
         },
+        Commands::Rules(args) => match &args.command {
+            cli::RulesCommands::Export { format, output } => {
+                let config = config::Config::load(&args.config).unwrap_or_else(|e| {
+                    error!("Failed to load config: {}", e);
+                    std::process::exit(1);
+                });
+                let catalog = RuleCatalog::new(&config.rules);
+                let content = match format {
+                    cli::RulesFormat::Json => serde_json::to_string_pretty(&catalog)
+                        .expect("rule catalog serializes to JSON"),
+                };
+                match output {
+                    Some(path) => {
+                        std::fs::write(path, content + "\n").unwrap_or_else(|e| {
+                            error!("Failed to write {}: {}", path, e);
+                            std::process::exit(1);
+                        });
+                        info!("Exported {} rules to {}", catalog.rules.len(), path);
+                    }
+                    None => println!("{}", content),
+                }
+            }
+        },
+        Commands::Completions(args) => {
+            clap_complete::generate(
+                args.shell,
+                &mut Cli::command(),
+                "firekeeper",
+                &mut std::io::stdout(),
+            );
+        }
+        Commands::Man(args) => {
+            let result = if let Some(out_dir) = &args.out_dir {
+                std::fs::create_dir_all(out_dir)
+                    .and_then(|_| clap_mangen::generate_to(Cli::command(), out_dir))
+                    .map(|_| info!("Generated man pages in {}", out_dir))
+            } else {
+                clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())
+            };
+            if let Err(e) = result {
+                error!("Failed to generate man pages: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Resolve local paths before switching to a checkout;
+/// a config missing locally is read from the checkout instead (for a pull request, from its
+/// base branch unless `--trust-pr-config` is given)
+fn absolutize_paths(args: &mut cli::ReviewArgs) {
+    let absolute = |path: &str| {
+        std::path::absolute(path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| path.to_string())
+    };
+    for path in [
+        &mut args.trace,
+        &mut args.bundle,
+        &mut args.badge,
+        &mut args.sarif,
+        &mut args.retry_from,
+        &mut args.plan,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(&mut args.output)
+    {
+        *path = absolute(path);
+    }
+    if std::path::Path::new(&args.config).exists() {
+        args.config = absolute(&args.config);
+    }
+}
+
+/// The LLM API key, which local servers such as Ollama do without
+fn require_api_key(api_key: Option<&str>, config: &Config) -> String {
+    match api_key {
+        Some(api_key) => api_key.to_string(),
+        None if config.llm.is_local() => String::new(),
+        None => {
+            error!("LLM API key is required (--api-key or FIREKEEPER_LLM_API_KEY)");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Change the working directory, which git operations, tools, and resources resolve from
+fn enter_checkout(dir: &std::path::Path) {
+    std::env::set_current_dir(dir).unwrap_or_else(|e| {
+        error!("Failed to enter {}: {}", dir.display(), e);
+        util::temp::exit(1);
+    });
+}
+
+/// Run `firekeeper publish slack|teams|webhook`. Without a config file, every rule blocks
+async fn notify(target: publish::NotifyTarget, args: &cli::NotifyArgs) {
+    let config = if std::path::Path::new(&args.config).exists() {
+        Some(Config::load(&args.config).unwrap_or_else(|e| {
+            error!("Failed to load config: {}", e);
+            std::process::exit(1);
+        }))
+    } else {
+        None
+    };
+    let publish = config
+        .as_ref()
+        .map(|config| config.publish.clone())
+        .unwrap_or_default();
+    let non_blocking_rules = config
+        .iter()
+        .flat_map(|config| &config.rules)
+        .filter(|rule| !rule.blocks())
+        .map(|rule| rule.name.clone())
+        .collect();
+    if let Err(e) = publish::notify(
+        target,
+        target.config(&publish),
+        args.url.as_deref(),
+        args.template.as_deref(),
+        &args.results,
+        args.report_url.as_deref(),
+        &non_blocking_rules,
+    )
+    .await
+    {
+        error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Run `firekeeper review`, or `firekeeper ci` with its arguments when given
+async fn review(mut args: cli::ReviewArgs, ci: Option<&cli::CiArgs>) {
+    // Host settings are read from the local config, as a pull request's config can only
+    // be read once it is cloned
+    let publish = Config::load(&args.config)
+        .map(|config| config.publish)
+        .unwrap_or_default();
+    let pull_request = args.pr.as_deref().map(|url| {
+        github::PullRequest::parse(url).unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(1);
+        })
+    });
+    let github_api = pull_request.as_ref().map(|pr| {
+        github::Api::new(&publish, &pr.host).unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(1);
+        })
+    });
+    // Resolved before entering a checkout, so runs on any revision share the cache
+    let cache_dir = std::path::absolute(cache::DEFAULT_CACHE_DIR)
+        .unwrap_or_else(|_| cache::DEFAULT_CACHE_DIR.into());
+    let mut checkout_base = None;
+    // Removes the clone of a pull request or release head when the review ends
+    let mut _checkout = None;
+    // The config of a pull request's head is written by its author, so without a local
+    // config the base branch's is used
+    let mut base_config = None;
+    let untrusted_config = pull_request.is_some()
+        && !args.trust_pr_config
+        && !std::path::Path::new(&args.config).exists();
+    if let (Some(pr), Some(api)) = (&pull_request, &github_api) {
+        absolutize_paths(&mut args);
+        let (dir, base) = api.checkout(pr).await.unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(1);
+        });
+        enter_checkout(dir.path());
+        _checkout = Some(dir);
+        if untrusted_config {
+            base_config = Config::load_at_revision(&base, &args.config).unwrap_or_else(|e| {
+                error!("Failed to load config from the base branch: {}", e);
+                util::temp::exit(1);
+            });
+            if base_config.is_none() && !args.auto {
+                error!(
+                    "No {} locally or on the base branch of {}; pass --trust-pr-config to use \
+                     the pull request's, which can run commands through resources and tools",
+                    args.config, pr
+                );
+                util::temp::exit(1);
+            }
+        }
+        checkout_base = Some(base);
+    }
+    let mut release = None;
+    if let Some(head) = args.head.clone() {
+        let base = util::rev_parse(&args.base).unwrap_or_else(|| {
+            error!("Unknown revision '{}'", args.base);
+            util::temp::exit(1);
+        });
+        absolutize_paths(&mut args);
+        let dir = util::checkout_revision(&head).unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(1);
+        });
+        enter_checkout(dir.path());
+        _checkout = Some(dir);
+        info!("Auditing {}..{}", args.base, head);
+        checkout_base = Some(base);
+        release = Some(review::release::ReleaseAudit {
+            base: args.base.clone(),
+            head,
+            cache_dir: cache_dir.clone(),
+        });
+    }
+    let task_cache = match &release {
+        _ if args.no_cache => None,
+        Some(release) => Some(release.cache()),
+        None => Some(review::task_cache::TaskCache::new(
+            cache_dir,
+            review::task_cache::RESULTS_NAMESPACE,
+        )),
+    };
+
+    let (mut config, api_key) = if let Some(config) = base_config {
+        info!("Using {} of the pull request's base branch", args.config);
+        (config, args.api_key.clone())
+    } else if args.auto && (untrusted_config || !std::path::Path::new(&args.config).exists()) {
+        info!("No {} found, reviewing with built-in rules", args.config);
+        init::auto_config(&util::list_files("."), args.api_key.clone(), |var| {
+            std::env::var(var).ok()
+        })
+    } else {
+        let config = Config::load(&args.config).unwrap_or_else(|e| {
+            error!("Failed to load config: {}", e);
+            util::temp::exit(1);
+        });
+        (config, args.api_key.clone())
+    };
+    if let Err(e) = config.apply_profile(args.profile.as_deref()) {
+        error!("Failed to apply profile: {}", e);
+        util::temp::exit(1);
+    }
+    if let Err(e) = config.apply_overrides(&args.config_overrides) {
+        error!("Failed to apply config overrides: {}", e);
+        util::temp::exit(1);
+    }
+    // No workers run in a dry run, and local servers such as Ollama take any key
+    let api_key = match api_key {
+        Some(api_key) => api_key,
+        None if args.dry_run || config.llm.is_local() => String::new(),
+        None => {
+            error!("LLM API key is required (--api-key or FIREKEEPER_LLM_API_KEY)");
+            util::temp::exit(1);
+        }
+    };
+
+    if let Some(instruction) = &args.inline_rule {
+        config.rules = vec![RuleBody::inline(instruction, &args.inline_scope)];
     }
+
+    if let Err(e) = config.quarantine_rules(&args.quarantine) {
+        error!("{}", e);
+        util::temp::exit(1);
+    }
+
+    if !args.rules_tags.is_empty() {
+        config.retain_rules_with_tags(&args.rules_tags);
+        info!(
+            "Selected {} rules with tags {:?}",
+            config.rules.len(),
+            args.rules_tags
+        );
+    }
+
+    trace!("args: {:#?}", args);
+    trace!("config: {:#?}", config);
+
+    let mut files = args.files.clone();
+    if let Some(path) = &args.files_from {
+        files.extend(util::read_file_list(path).unwrap_or_else(|e| {
+            error!("Failed to read file list {}: {}", path, e);
+            util::temp::exit(1);
+        }));
+    }
+    let base = if let Some(base) = checkout_base {
+        util::Base::Commit(base)
+    } else if args.files.is_empty() && args.files_from.is_none() {
+        if args.staged {
+            util::Base::Staged
+        } else {
+            util::Base::parse(&args.base)
+        }
+    } else {
+        util::Base::Files(files)
+    };
+    if let Err(e) = util::ensure_base(&base, config.review.fetch_missing_base) {
+        error!("{}", e);
+        util::temp::exit(1);
+    }
+
+    let previous = args.retry_from.as_deref().map(|path| {
+        let previous = review::render::ViolationFile::load(path).unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(1);
+        });
+        if previous.incomplete.is_empty() {
+            info!("No incomplete tasks to retry in {}", path);
+            util::temp::exit(0);
+        }
+        info!(
+            "Retrying {} incomplete task(s) from {}",
+            previous.incomplete.len(),
+            path
+        );
+        previous
+    });
+    let task_plan = args.plan.as_deref().map(|path| {
+        let task_plan = review::plan::TaskPlan::load(path).unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(1);
+        });
+        info!("Running {} task(s) from {}", task_plan.tasks.len(), path);
+        task_plan
+    });
+    let outputs = if args.output.is_empty() {
+        args.retry_from.iter().cloned().collect()
+    } else {
+        args.output.clone()
+    };
+    let pr_comment = match ci {
+        Some(ci) => ci::comment_target(ci, pull_request.as_ref(), &publish),
+        None => github_api.zip(pull_request).filter(|_| args.pr_comment),
+    };
+
+    // No workers run in a dry run, so skip probing the provider
+    let max_parallel_workers = if args.dry_run {
+        config.review.max_parallel_workers
+    } else {
+        let probe = review::orchestrator::probe_provider(&mut config, &api_key).await;
+        Some(review::orchestrator::resolve_max_parallel_workers(
+            &config.review,
+            &probe,
+        ))
+    };
+
+    review::orchestrator::orchestrate_and_run(
+        &config.rules,
+        base,
+        &args.include,
+        &args.exclude,
+        config.review.max_files_per_task,
+        max_parallel_workers,
+        &review::worker::WorkerSettings::from_config(&config, &api_key),
+        &config.llm.prices,
+        config.llm.max_total_tokens,
+        args.dry_run,
+        &outputs,
+        args.trace.as_deref(),
+        args.bundle.as_deref(),
+        args.badge.as_deref(),
+        args.sarif.as_deref(),
+        args.annotations,
+        pr_comment.as_ref().map(|(api, pr)| (api, pr)),
+        &args.config,
+        &args.suppressions,
+        &serde_json::to_value(&config).unwrap_or_default(),
+        &review::resource::ResourceLoader::from_config(&config.review),
+        config.review.max_violations,
+        &config.telemetry,
+        &config.review.sampling,
+        &config.review.vendored,
+        config.review.repo_overview,
+        config.review.diff_style,
+        config.review.diff_line_numbers,
+        previous.as_ref(),
+        task_plan.as_ref(),
+        args.continue_queue
+            .then_some(review::queue::DEFAULT_QUEUE_PATH),
+        release.as_ref(),
+        task_cache.as_ref(),
+        &review::owners::Owners::load(&config.owners),
+        &config.filters,
+        args.group_by == Some(cli::GroupBy::Owner),
+        ci.is_some(),
+        &config.audit,
+    )
+    .await;
 }
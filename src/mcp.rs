@@ -0,0 +1,176 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{ChildStdout, Command};
+use toml_scaffold::TomlScaffold;
+use tracing::debug;
+
+/// MCP protocol version sent during initialization
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Timeout for starting a server and reading one resource (seconds)
+const TIMEOUT_SECS: u64 = 30;
+
+/// MCP server launched over stdio
+#[derive(Deserialize, Serialize, Debug, Clone, Default, JsonSchema, TomlScaffold)]
+pub struct McpServerConfig {
+    /// Command to start the server, e.g. `npx`
+    pub command: String,
+    /// Command arguments (optional)
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables for the server process (optional)
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Split `server/resource-uri` from an `mcp://` resource
+pub fn parse_resource(rest: &str) -> Result<(&str, &str), String> {
+    match rest.split_once('/') {
+        Some((server, uri)) if !server.is_empty() && !uri.is_empty() => Ok((server, uri)),
+        _ => Err("expected mcp://<server>/<resource uri>".to_string()),
+    }
+}
+
+/// Start the server, read one resource, and return its text contents
+pub async fn read_resource(server: &McpServerConfig, uri: &str) -> Result<String, String> {
+    tokio::time::timeout(
+        std::time::Duration::from_secs(TIMEOUT_SECS),
+        read_resource_inner(server, uri),
+    )
+    .await
+    .map_err(|_| format!("timed out after {}s", TIMEOUT_SECS))?
+}
+
+async fn read_resource_inner(server: &McpServerConfig, uri: &str) -> Result<String, String> {
+    let mut child = Command::new(&server.command)
+        .args(&server.args)
+        .envs(&server.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start '{}': {}", server.command, e))?;
+    let mut stdin = child.stdin.take().unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+
+    let mut send = async |message: Value| {
+        let line = format!("{}\n", message);
+        stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("failed to write to server: {}", e))
+    };
+
+    send(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "firekeeper", "version": env!("CARGO_PKG_VERSION")},
+        },
+    }))
+    .await?;
+    receive(&mut lines, 1).await?;
+    send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"})).await?;
+    send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "resources/read",
+        "params": {"uri": uri},
+    }))
+    .await?;
+    let result = receive(&mut lines, 2).await?;
+    resource_text(&result)
+}
+
+/// Wait for the response to request `id`, skipping notifications and server requests
+async fn receive(lines: &mut Lines<BufReader<ChildStdout>>, id: u64) -> Result<Value, String> {
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("failed to read from server: {}", e))?
+    {
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            debug!("Ignoring non-JSON MCP output: {}", line);
+            continue;
+        };
+        if message.get("id").and_then(Value::as_u64) != Some(id) || message.get("method").is_some()
+        {
+            continue;
+        }
+        if let Some(error) = message.get("error") {
+            return Err(format!(
+                "server error: {}",
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+            ));
+        }
+        return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+    }
+    Err("server exited without responding".to_string())
+}
+
+/// Join the text contents of a `resources/read` result
+fn resource_text(result: &Value) -> Result<String, String> {
+    let contents = result
+        .get("contents")
+        .and_then(Value::as_array)
+        .ok_or("invalid resources/read result")?;
+    let texts: Vec<&str> = contents
+        .iter()
+        .filter_map(|c| c.get("text").and_then(Value::as_str))
+        .collect();
+    if texts.is_empty() {
+        return Err("resource has no text contents".to_string());
+    }
+    Ok(texts.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resource() {
+        assert_eq!(
+            parse_resource("docs/docs://style-guide").unwrap(),
+            ("docs", "docs://style-guide")
+        );
+        assert!(parse_resource("docs").is_err());
+        assert!(parse_resource("/style").is_err());
+    }
+
+    #[test]
+    fn test_resource_text() {
+        let result = json!({"contents": [{"uri": "a", "text": "one"}, {"uri": "b", "blob": "AA=="}, {"uri": "c", "text": "two"}]});
+        assert_eq!(resource_text(&result).unwrap(), "one\n\ntwo");
+        assert!(resource_text(&json!({"contents": []})).is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_read_resource() {
+        // Minimal server: answers initialize, skips the initialized notification, answers the read
+        let script = r#"read l; echo '{"jsonrpc":"2.0","id":1,"result":{}}'
+read l; read l; echo '{"jsonrpc":"2.0","method":"notifications/message"}'
+echo '{"jsonrpc":"2.0","id":2,"result":{"contents":[{"uri":"docs://style","text":"Use tabs"}]}}'"#;
+        let server = McpServerConfig {
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            env: HashMap::new(),
+        };
+        assert_eq!(
+            read_resource(&server, "docs://style").await.unwrap(),
+            "Use tabs"
+        );
+    }
+}
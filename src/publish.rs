@@ -0,0 +1,401 @@
+use crate::review::render::{self, ViolationFile};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use toml_scaffold::TomlScaffold;
+use tracing::info;
+
+/// Timeout for uploading results (seconds)
+const TIMEOUT_SECS: u64 = 60;
+
+/// Environment variable with a bearer token, kept out of the command line
+const TOKEN_ENV: &str = "FIREKEEPER_PUBLISH_TOKEN";
+
+/// Settings for self-hosted GitHub instances such as GitHub Enterprise Server, used by
+/// `review --pr` and pull request comments, and webhooks notified by `publish`
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct PublishConfig {
+    /// GitHub API base URL (optional, defaults to https://api.github.com for github.com
+    /// and `https://<host>/api/v3` for other hosts)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    /// PEM file with additional CA certificates to trust, e.g. a corporate root (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// Command printing the API token, used when GITHUB_TOKEN and GH_TOKEN are not set, and
+    /// required for hosts other than github.com without `api_base` (optional), e.g.
+    /// `gh auth token --hostname github.example.com`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_command: Option<String>,
+    /// Slack incoming webhook for `publish slack` (optional)
+    #[serde(skip_serializing_if = "NotifyConfig::is_default")]
+    pub slack: NotifyConfig,
+    /// Microsoft Teams incoming webhook for `publish teams` (optional)
+    #[serde(skip_serializing_if = "NotifyConfig::is_default")]
+    pub teams: NotifyConfig,
+    /// Generic JSON webhook for `publish webhook` (optional)
+    #[serde(skip_serializing_if = "NotifyConfig::is_default")]
+    pub webhook: NotifyConfig,
+}
+
+impl PublishConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Webhook notified with a summary of a review, e.g.
+/// `slack = { template = "Review {status}: {blocking} blocking ({blocking_rules})" }`
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Webhook URL (optional, prefer --url or FIREKEEPER_NOTIFY_URL to keep it out of the repository)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Message template (optional) with the placeholders `{status}`, `{violations}`,
+    /// `{blocking}`, `{non_blocking}`, `{blocking_rules}`, `{incomplete}`, and `{report_url}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+impl NotifyConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Service notified by `publish slack|teams|webhook`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifyTarget {
+    Slack,
+    Teams,
+    Webhook,
+}
+
+impl NotifyTarget {
+    pub fn config(self, publish: &PublishConfig) -> &NotifyConfig {
+        match self {
+            Self::Slack => &publish.slack,
+            Self::Teams => &publish.teams,
+            Self::Webhook => &publish.webhook,
+        }
+    }
+}
+
+/// Outcome of a review, as sent in notifications
+#[derive(Serialize, Debug, PartialEq)]
+struct Summary {
+    /// `failed` with blocking violations or incomplete tasks, `passed` otherwise
+    status: &'static str,
+    violations: usize,
+    blocking: usize,
+    non_blocking: usize,
+    /// Violation count per blocking rule with violations
+    blocking_rules: BTreeMap<String, usize>,
+    incomplete: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report_url: Option<String>,
+}
+
+impl Summary {
+    /// Summarize results; rules not in `non_blocking_rules` block unless quarantined
+    fn new(
+        results: &ViolationFile,
+        non_blocking_rules: &BTreeSet<String>,
+        report_url: Option<&str>,
+    ) -> Self {
+        let mut blocking_rules: BTreeMap<String, usize> = BTreeMap::new();
+        let mut non_blocking = 0;
+        for (rule, _) in render::flatten_violations(&results.violations) {
+            if non_blocking_rules.contains(rule) || results.quarantined.contains(rule) {
+                non_blocking += 1;
+            } else {
+                *blocking_rules.entry(rule.to_string()).or_default() += 1;
+            }
+        }
+        let blocking = blocking_rules.values().sum();
+        Self {
+            status: if blocking > 0 || !results.incomplete.is_empty() {
+                "failed"
+            } else {
+                "passed"
+            },
+            violations: blocking + non_blocking,
+            blocking,
+            non_blocking,
+            blocking_rules,
+            incomplete: results.incomplete.len(),
+            report_url: report_url.map(str::to_string),
+        }
+    }
+
+    /// Message text, from `template` when given
+    fn message(&self, template: Option<&str>) -> String {
+        let blocking_rules = self
+            .blocking_rules
+            .iter()
+            .map(|(rule, count)| format!("{} ({})", rule, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Some(template) = template {
+            let report_url = self.report_url.as_deref().unwrap_or_default();
+            return crate::util::render_template(
+                template,
+                &[
+                    ("status", self.status),
+                    ("violations", &self.violations.to_string()),
+                    ("blocking", &self.blocking.to_string()),
+                    ("non_blocking", &self.non_blocking.to_string()),
+                    ("blocking_rules", &blocking_rules),
+                    ("incomplete", &self.incomplete.to_string()),
+                    ("report_url", report_url),
+                ],
+            );
+        }
+        let mut lines = vec![format!(
+            "Firekeeper review {}: {} blocking, {} non-blocking violation(s)",
+            self.status, self.blocking, self.non_blocking
+        )];
+        if !blocking_rules.is_empty() {
+            lines.push(format!("Blocking rules: {}", blocking_rules));
+        }
+        if self.incomplete > 0 {
+            lines.push(format!("Incomplete tasks: {}", self.incomplete));
+        }
+        if let Some(url) = &self.report_url {
+            lines.push(format!("Report: {}", url));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Request body for a target: Slack and generic webhooks take `text`, Teams workflows an
+/// Adaptive Card; generic webhooks also get the summary fields
+fn notification_body(target: NotifyTarget, summary: &Summary, message: &str) -> Value {
+    match target {
+        NotifyTarget::Slack => json!({ "text": message }),
+        NotifyTarget::Teams => json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": [{ "type": "TextBlock", "text": message, "wrap": true }],
+                },
+            }],
+        }),
+        NotifyTarget::Webhook => json!({
+            "firekeeper_version": env!("CARGO_PKG_VERSION"),
+            "text": message,
+            "summary": summary,
+        }),
+    }
+}
+
+/// Send a compact summary of the results to a Slack, Teams, or generic webhook.
+///
+/// The URL and template given on the command line take precedence over the target's
+/// `[publish]` config; `non_blocking_rules` are the configured rules that do not block.
+pub async fn notify(
+    target: NotifyTarget,
+    config: &NotifyConfig,
+    url: Option<&str>,
+    template: Option<&str>,
+    results_path: &str,
+    report_url: Option<&str>,
+    non_blocking_rules: &BTreeSet<String>,
+) -> Result<(), String> {
+    let url = url.or(config.url.as_deref()).ok_or_else(|| {
+        "No webhook URL: pass --url, set FIREKEEPER_NOTIFY_URL, or configure it under [publish]"
+            .to_string()
+    })?;
+    let results = ViolationFile::load(results_path)?;
+    let summary = Summary::new(&results, non_blocking_rules, report_url);
+    let message = summary.message(template.or(config.template.as_deref()));
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+        .header(
+            "User-Agent",
+            concat!("firekeeper/", env!("CARGO_PKG_VERSION")),
+        )
+        .json(&notification_body(target, &summary, &message))
+        .send()
+        .await
+        // The URL is left out as webhook URLs embed their secret
+        .map_err(|e| format!("Failed to send notification: {}", e.without_url()))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to send notification: HTTP {} {}",
+            status,
+            body.trim()
+        ));
+    }
+    info!("Sent review summary to {:?} webhook", target);
+    Ok(())
+}
+
+/// Parse a `Name: value` header
+fn parse_header(header: &str) -> Result<(String, String), String> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!(
+            "Invalid header '{}': expected 'Name: value'",
+            header
+        )),
+    }
+}
+
+/// Read a JSON file written by `review --output` or `--trace`, checking its top-level key
+fn read_json(path: &str, key: &str) -> Result<Value, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let value: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid JSON in {}: {}", path, e))?;
+    if value.get(key).is_none() {
+        return Err(format!("{} has no '{}' field", path, key));
+    }
+    Ok(value)
+}
+
+/// Request body: the results file, and the trace file when given
+fn payload(results: Value, trace: Option<Value>) -> Value {
+    let mut payload = json!({
+        "firekeeper_version": env!("CARGO_PKG_VERSION"),
+        "results": results,
+    });
+    if let Some(trace) = trace {
+        payload["trace"] = trace;
+    }
+    payload
+}
+
+/// POST results (and optionally the trace) as JSON to `url`.
+///
+/// Headers are `Name: value` strings. A token in `FIREKEEPER_PUBLISH_TOKEN` is sent as a bearer
+/// token unless an `Authorization` header is given.
+pub async fn http(
+    url: &str,
+    results_path: &str,
+    trace_path: Option<&str>,
+    headers: &[String],
+) -> Result<(), String> {
+    let headers = headers
+        .iter()
+        .map(|h| parse_header(h))
+        .collect::<Result<Vec<_>, _>>()?;
+    let results = read_json(results_path, "violations")?;
+    let trace = trace_path
+        .map(|path| read_json(path, "entries"))
+        .transpose()?;
+
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+        .header(
+            "User-Agent",
+            concat!("firekeeper/", env!("CARGO_PKG_VERSION")),
+        )
+        .json(&payload(results, trace));
+    let has_authorization = headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("authorization"));
+    if !has_authorization
+        && let Ok(token) = std::env::var(TOKEN_ENV)
+        && !token.is_empty()
+    {
+        request = request.bearer_auth(token);
+    }
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to publish to {}: {}", url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to publish to {}: HTTP {} {}",
+            url,
+            status,
+            body.trim()
+        ));
+    }
+    info!("Published {} to {}", results_path, url);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Authorization: Bearer abc:def").unwrap(),
+            ("Authorization".to_string(), "Bearer abc:def".to_string())
+        );
+        assert!(parse_header("Authorization").is_err());
+        assert!(parse_header(": value").is_err());
+    }
+
+    #[test]
+    fn test_payload() {
+        let results = json!({"version": "0.5.0", "violations": {}});
+        assert_eq!(
+            payload(results.clone(), None),
+            json!({"firekeeper_version": env!("CARGO_PKG_VERSION"), "results": results})
+        );
+        let trace = json!({"version": "0.5.0", "entries": []});
+        assert_eq!(payload(results, Some(trace.clone()))["trace"], trace);
+    }
+
+    #[test]
+    fn test_notification_summary() {
+        let results: ViolationFile = serde_json::from_value(json!({
+            "version": "0.5.0",
+            "violations": {
+                "src/main.rs": {
+                    "Secrets": [{ "file": "src/main.rs", "detail": "Key", "start_line": 1, "end_line": 1 }],
+                    "Style": [{ "file": "src/main.rs", "detail": "Name", "start_line": 2, "end_line": 2 }],
+                },
+            },
+            "tips": {},
+        }))
+        .unwrap();
+        let non_blocking = BTreeSet::from(["Style".to_string()]);
+        let summary = Summary::new(&results, &non_blocking, Some("https://ci.example.com/1"));
+        assert_eq!(summary.status, "failed");
+        assert_eq!((summary.blocking, summary.non_blocking), (1, 1));
+        assert_eq!(
+            summary.message(None),
+            "Firekeeper review failed: 1 blocking, 1 non-blocking violation(s)\n\
+             Blocking rules: Secrets (1)\nReport: https://ci.example.com/1"
+        );
+        assert_eq!(
+            summary.message(Some("{status}: {blocking_rules} <{report_url}>")),
+            "failed: Secrets (1) <https://ci.example.com/1>"
+        );
+
+        let message = summary.message(None);
+        let teams = notification_body(NotifyTarget::Teams, &summary, &message);
+        assert_eq!(
+            teams["attachments"][0]["content"]["body"][0]["text"],
+            message
+        );
+        let webhook = notification_body(NotifyTarget::Webhook, &summary, &message);
+        assert_eq!(webhook["summary"]["blocking_rules"]["Secrets"], 1);
+    }
+}
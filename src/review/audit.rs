@@ -0,0 +1,179 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use toml_scaffold::TomlScaffold;
+use tracing::{error, info};
+
+/// Timeout for sending an audit entry (seconds)
+const TIMEOUT_SECS: u64 = 30;
+
+/// Environment variable with a bearer token for the audit endpoint
+const TOKEN_ENV: &str = "FIREKEEPER_AUDIT_TOKEN";
+
+/// Environment variables naming who triggered a CI run, in order of preference
+const ACTOR_ENVS: [&str; 4] = [
+    "GITHUB_ACTOR",
+    "GITLAB_USER_LOGIN",
+    "BUILDKITE_BUILD_CREATOR_EMAIL",
+    "BUILD_REQUESTEDFOREMAIL",
+];
+
+/// Append-only record of the gating decision of each review, e.g. to prove which checks ran
+/// for a release
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// File each decision is appended to as one JSON line (optional), e.g. `.firekeeper/audit.jsonl`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// URL receiving each decision as a JSON POST (optional). A token in
+    /// `FIREKEEPER_AUDIT_TOKEN` is sent as a bearer token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+impl AuditConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Gating decision of one review
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub version: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Who ran the review: the CI user, else the git user, else the OS user
+    pub actor: String,
+    /// Base as given, e.g. `main`, and the commit it resolved to
+    pub base: String,
+    pub base_commit: Option<String>,
+    /// Commit reviewed
+    pub head: Option<String>,
+    /// Hash of the resolved config, so a decision can be matched to the config that made it
+    pub config_hash: String,
+    /// Rules run
+    pub rules: Vec<String>,
+    /// Blocking rules with violations
+    pub blocking_rules: Vec<String>,
+    pub failed_tasks: usize,
+    pub exit_code: i32,
+}
+
+/// Who ran the review
+pub fn actor() -> String {
+    ACTOR_ENVS
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .chain(crate::util::user_email())
+        .chain(
+            ["USER", "USERNAME"]
+                .iter()
+                .filter_map(|n| std::env::var(n).ok()),
+        )
+        .find(|actor| !actor.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Hash of a resolved config
+pub fn config_hash(config: &serde_json::Value) -> String {
+    crate::util::hash_parts(&[&config.to_string()])
+}
+
+/// Append an entry to the audit file, creating parent directories as needed
+fn append(path: &str, entry: &AuditEntry) -> Result<(), String> {
+    if let Some(parent) = std::path::Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    writeln!(file, "{}", serde_json::to_string(entry).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// POST an entry to the audit endpoint
+async fn send(endpoint: &str, entry: &AuditEntry) -> Result<(), String> {
+    let mut request = reqwest::Client::new()
+        .post(endpoint)
+        .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+        .header(
+            "User-Agent",
+            concat!("firekeeper/", env!("CARGO_PKG_VERSION")),
+        )
+        .json(entry);
+    if let Ok(token) = std::env::var(TOKEN_ENV)
+        && !token.is_empty()
+    {
+        request = request.bearer_auth(token);
+    }
+    request
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to send audit entry to {}: {}", endpoint, e))?;
+    Ok(())
+}
+
+/// Record a gating decision to the configured file and endpoint. Failures are logged as
+/// errors without changing the decision.
+pub async fn record(config: &AuditConfig, entry: &AuditEntry) {
+    if let Some(path) = config.path.as_deref().filter(|p| !p.is_empty()) {
+        match append(path, entry) {
+            Ok(()) => info!("Audit entry appended to {}", path),
+            Err(e) => error!("{}", e),
+        }
+    }
+    if let Some(endpoint) = config.endpoint.as_deref().filter(|e| !e.is_empty()) {
+        match send(endpoint, entry).await {
+            Ok(()) => info!("Audit entry sent to {}", endpoint),
+            Err(e) => error!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_appends() {
+        let dir =
+            crate::util::temp::TempDir::new(&format!("firekeeper-audit-{}", std::process::id()));
+        let path = dir.path().join("audit.jsonl");
+        let config = AuditConfig {
+            path: Some(path.to_string_lossy().to_string()),
+            endpoint: None,
+        };
+        let entry = |exit_code| AuditEntry {
+            version: "0.5.0".into(),
+            timestamp: chrono::Utc::now(),
+            actor: "ci-bot".into(),
+            base: "main".into(),
+            base_commit: Some("abc123".into()),
+            head: Some("def456".into()),
+            config_hash: config_hash(&serde_json::json!({"rules": []})),
+            rules: vec!["No Magic Numbers".into()],
+            blocking_rules: vec![],
+            failed_tasks: 0,
+            exit_code,
+        };
+        record(&config, &entry(1)).await;
+        record(&config, &entry(0)).await;
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<AuditEntry> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].exit_code, 1);
+        assert_eq!(entries[1].exit_code, 0);
+        assert_eq!(entries[0].config_hash, entries[1].config_hash);
+    }
+}
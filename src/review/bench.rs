@@ -0,0 +1,502 @@
+use super::resource::ResourceLoader;
+use super::usage;
+use super::worker;
+use crate::config::Config;
+use crate::llm::{Capabilities, ProviderProbe};
+use crate::rule::body::RuleBody;
+use crate::tool::read::Snapshot;
+use crate::types::Violation;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+/// Unified diff of a corpus case
+const DIFF_FILE: &str = "diff.patch";
+/// Expected violations of a corpus case
+const EXPECTED_FILE: &str = "expected.json";
+/// Optional directory of a corpus case holding its changed files, served to the `read`
+/// and `sh` tools
+const FILES_DIR: &str = "files";
+
+/// Labeled violation expected in a corpus case
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExpectedViolation {
+    pub rule: String,
+    pub file: String,
+    /// Expected line range; any line in the file matches if omitted
+    #[serde(default)]
+    pub start_line: Option<u32>,
+    #[serde(default)]
+    pub end_line: Option<u32>,
+}
+
+/// Expected results of a corpus case
+#[derive(Deserialize, Debug, Default)]
+pub struct Expected {
+    /// Rules to evaluate (defaults to all configured rules in scope)
+    #[serde(default)]
+    pub rules: Vec<String>,
+    pub violations: Vec<ExpectedViolation>,
+}
+
+/// A labeled corpus case: a diff and the violations it should produce
+#[derive(Debug)]
+pub struct Case {
+    pub name: String,
+    pub diffs: HashMap<String, String>,
+    pub expected: Expected,
+    /// Changed files of the case, if it has a `files` directory
+    pub files_dir: Option<PathBuf>,
+}
+
+/// Precision/recall counters for one (model, rule) pair
+#[derive(Serialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct Score {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub failed_tasks: usize,
+    pub elapsed_secs: f64,
+    /// Estimated LLM cost in USD, if the model is priced in `[llm.prices]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl Score {
+    pub fn precision(&self) -> Option<f64> {
+        let reported = self.true_positives + self.false_positives;
+        (reported > 0).then(|| self.true_positives as f64 / reported as f64)
+    }
+
+    pub fn recall(&self) -> Option<f64> {
+        let expected = self.true_positives + self.false_negatives;
+        (expected > 0).then(|| self.true_positives as f64 / expected as f64)
+    }
+}
+
+/// Scores keyed by model, then rule
+pub type BenchReport = BTreeMap<String, BTreeMap<String, Score>>;
+
+/// Split a unified diff into per-file diffs keyed by the new path (old path for deletions)
+pub fn split_patch(patch: &str) -> HashMap<String, String> {
+    let mut diffs = HashMap::new();
+    let mut chunks: Vec<String> = Vec::new();
+    for line in patch.lines() {
+        if line.starts_with("diff --git ") || chunks.is_empty() {
+            chunks.push(String::new());
+        }
+        let chunk = chunks.last_mut().unwrap();
+        chunk.push_str(line);
+        chunk.push('\n');
+    }
+
+    for chunk in chunks {
+        let path_of = |prefix: &str| {
+            chunk
+                .lines()
+                .find_map(|l| l.strip_prefix(prefix))
+                .map(|p| p.split('\t').next().unwrap_or(p).trim())
+                .filter(|p| *p != "/dev/null")
+                .map(|p| p.strip_prefix("b/").or(p.strip_prefix("a/")).unwrap_or(p))
+                .map(str::to_string)
+        };
+        if let Some(path) = path_of("+++ ").or_else(|| path_of("--- ")) {
+            diffs.insert(path, chunk);
+        }
+    }
+    diffs
+}
+
+/// Load corpus cases from subdirectories containing `diff.patch` and `expected.json`
+pub fn load_corpus(dir: &str) -> Result<Vec<Case>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read corpus {}: {}", dir, e))?;
+    let mut cases = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let diff_path = path.join(DIFF_FILE);
+        if !path.is_dir() || !diff_path.exists() {
+            continue;
+        }
+        let patch = std::fs::read_to_string(&diff_path)
+            .map_err(|e| format!("Failed to read {}: {}", diff_path.display(), e))?;
+        let expected = read_expected(&path.join(EXPECTED_FILE))?;
+        let files_dir = path.join(FILES_DIR);
+        cases.push(Case {
+            name: entry.file_name().to_string_lossy().to_string(),
+            diffs: split_patch(&patch),
+            expected,
+            files_dir: files_dir.is_dir().then_some(files_dir),
+        });
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+fn read_expected(path: &Path) -> Result<Expected, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+fn matches(expected: &ExpectedViolation, violation: &Violation) -> bool {
+    if expected.file != violation.file {
+        return false;
+    }
+    let start = expected.start_line.unwrap_or(u32::MIN);
+    let end = expected
+        .end_line
+        .or(expected.start_line)
+        .unwrap_or(u32::MAX);
+    violation.start_line <= end && violation.end_line >= start
+}
+
+/// Score reported violations against expected ones, matching each expected violation at most once
+pub fn score(expected: &[&ExpectedViolation], reported: &[Violation]) -> Score {
+    let mut unmatched: Vec<&ExpectedViolation> = expected.to_vec();
+    let mut score = Score::default();
+    for violation in reported {
+        if let Some(i) = unmatched.iter().position(|e| matches(e, violation)) {
+            unmatched.remove(i);
+            score.true_positives += 1;
+        } else {
+            score.false_positives += 1;
+        }
+    }
+    score.false_negatives = unmatched.len();
+    score
+}
+
+fn accumulate(total: &mut Score, score: Score) {
+    total.true_positives += score.true_positives;
+    total.false_positives += score.false_positives;
+    total.false_negatives += score.false_negatives;
+    total.failed_tasks += score.failed_tasks;
+    total.elapsed_secs += score.elapsed_secs;
+    total.cost = match (total.cost, score.cost) {
+        (Some(cost), Some(other)) => Some(cost + other),
+        (cost, other) => cost.or(other),
+    };
+}
+
+/// Rules evaluated for a case: the listed ones, or all configured rules in scope of its files
+fn case_rules<'a>(rules: &'a [RuleBody], case: &Case) -> Vec<&'a RuleBody> {
+    let files: Vec<String> = case.diffs.keys().cloned().collect();
+    rules
+        .iter()
+        .filter(|r| {
+            if case.expected.rules.is_empty() {
+                !super::scope::filter_files_by_scope(r, &files).is_empty()
+            } else {
+                case.expected.rules.contains(&r.name)
+            }
+        })
+        .collect()
+}
+
+/// Run every case against each model and score the results per rule
+pub async fn bench(
+    config: &Config,
+    api_key: &str,
+    cases: &[Case],
+    models: &[String],
+) -> BenchReport {
+    let mut tasks = Vec::new();
+    for model in models {
+        for case in cases {
+            for rule in case_rules(&config.rules, case) {
+                tasks.push((model.as_str(), case, rule));
+            }
+        }
+    }
+    info!(
+        "Running {} tasks ({} cases x {} models)",
+        tasks.len(),
+        cases.len(),
+        models.len()
+    );
+
+    let shutdown = Arc::new(Mutex::new(false));
+    let resources = ResourceLoader::from_config(&config.review);
+    // One model list serves every model's capabilities; configured ones describe `llm.model`
+    let llm = &config.llm;
+    let probe = ProviderProbe::fetch(api_key, &llm.endpoint(), &llm.headers, llm.provider).await;
+    let base_settings = worker::WorkerSettings::from_config(config, api_key);
+    let settings: HashMap<&str, worker::WorkerSettings> = models
+        .iter()
+        .map(|model| {
+            let configured = if *model == llm.model {
+                llm.capabilities
+            } else {
+                Capabilities::default()
+            };
+            let capabilities = configured.or(probe.capabilities(model));
+            let settings = base_settings
+                .with_model(model)
+                .with_capabilities(&capabilities);
+            (model.as_str(), settings)
+        })
+        .collect();
+    let settings = &settings;
+    // Cases are unrelated to the working tree, so without their own files the tools are
+    // served an empty directory rather than the repository's
+    let empty_dir =
+        crate::util::temp::TempDir::new(&format!("firekeeper-bench-{}", std::process::id()));
+    if let Err(e) = std::fs::create_dir_all(empty_dir.path()) {
+        warn!("Failed to create {}: {}", empty_dir.path().display(), e);
+    }
+    let parallelism = super::orchestrator::resolve_max_parallel_workers(&config.review, &probe);
+    let results: Vec<_> = stream::iter(tasks.iter().enumerate().map(|(i, (model, case, rule))| {
+        let files: Vec<String> = {
+            let mut files: Vec<_> = case.diffs.keys().cloned().collect();
+            files.sort();
+            files
+        };
+        let shutdown = shutdown.clone();
+        let resources = resources.clone();
+        let snapshot = Snapshot::Directory(Arc::from(
+            case.files_dir.as_deref().unwrap_or(empty_dir.path()),
+        ));
+        async move {
+            let usage = Arc::<std::sync::Mutex<usage::TokenUsage>>::default();
+            let task = worker::Task {
+                worker_id: i.to_string(),
+                rule,
+                files: files.clone(),
+                chunk: None,
+                all_changed_files: files,
+                commit_messages: String::new(),
+                overview: Arc::from(""),
+                diffs: case.diffs.clone(),
+                is_root_base: false,
+                snapshot,
+                resources: None,
+            };
+            let result = worker::worker(
+                task,
+                &settings[model],
+                resources,
+                false,
+                shutdown,
+                usage.clone(),
+            )
+            .await;
+            let usage = *usage.lock().unwrap();
+            (i, result, usage)
+        }
+    }))
+    .buffer_unordered(parallelism)
+    .collect()
+    .await;
+
+    let mut report = BenchReport::new();
+    for (i, result, usage) in results {
+        let (model, case, rule) = tasks[i];
+        // Failed tasks are priced too, for the tokens they used before failing
+        let cost = usage.priced(config.llm.prices.get(model)).cost;
+        let expected: Vec<_> = case
+            .expected
+            .violations
+            .iter()
+            .filter(|v| v.rule == rule.name)
+            .collect();
+        let task_score = match result {
+            Ok(result) => {
+                debug!(
+                    "Case '{}' rule '{}' model '{}': {} violations",
+                    case.name,
+                    rule.name,
+                    model,
+                    result.violations.len()
+                );
+                Score {
+                    elapsed_secs: result.elapsed_secs,
+                    cost,
+                    ..score(&expected, &result.violations)
+                }
+            }
+            Err(e) => {
+                error!(
+                    "Case '{}' rule '{}' model '{}' failed: {}",
+                    case.name, rule.name, model, e
+                );
+                Score {
+                    failed_tasks: 1,
+                    false_negatives: expected.len(),
+                    cost,
+                    ..Score::default()
+                }
+            }
+        };
+        accumulate(
+            report
+                .entry(model.to_string())
+                .or_default()
+                .entry(rule.name.clone())
+                .or_default(),
+            task_score,
+        );
+    }
+
+    for case in cases {
+        for expected in &case.expected.violations {
+            if !config.rules.iter().any(|r| r.name == expected.rule) {
+                warn!(
+                    "Case '{}' expects unknown rule '{}'",
+                    case.name, expected.rule
+                );
+            }
+        }
+    }
+    report
+}
+
+/// Format the report as a Markdown table
+pub fn format_report(report: &BenchReport) -> String {
+    let percent =
+        |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{:.0}%", v * 100.0));
+    let mut output = String::from(
+        "| Model | Rule | Precision | Recall | TP | FP | FN | Failed | Time (s) | Cost |\n\
+         |---|---|---|---|---|---|---|---|---|---|\n",
+    );
+    for (model, rules) in report {
+        for (rule, score) in rules {
+            output.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {:.1} | {} |\n",
+                model,
+                rule,
+                percent(score.precision()),
+                percent(score.recall()),
+                score.true_positives,
+                score.false_positives,
+                score.false_negatives,
+                score.failed_tasks,
+                score.elapsed_secs,
+                score.cost.map_or("-".to_string(), usage::format_cost)
+            ));
+        }
+    }
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violation(file: &str, start: u32, end: u32) -> Violation {
+        Violation {
+            file: file.into(),
+            detail: String::new(),
+            start_line: start,
+            end_line: end,
+            rule_id: None,
+            owners: vec![],
+        }
+    }
+
+    fn expected(file: &str, lines: Option<(u32, u32)>) -> ExpectedViolation {
+        ExpectedViolation {
+            rule: "Rule".into(),
+            file: file.into(),
+            start_line: lines.map(|l| l.0),
+            end_line: lines.map(|l| l.1),
+        }
+    }
+
+    #[test]
+    fn test_split_patch() {
+        let patch = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1 +1 @@\n-x\n+y\n\
+                     diff --git a/old.rs b/old.rs\n--- a/old.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-z\n";
+        let diffs = split_patch(patch);
+        assert_eq!(diffs.len(), 2);
+        assert!(diffs["src/a.rs"].ends_with("+y\n"));
+        assert!(diffs["old.rs"].contains("-z"));
+    }
+
+    #[test]
+    fn test_load_corpus_files_dir() {
+        let corpus =
+            crate::util::temp::TempDir::new(&format!("firekeeper-corpus-{}", std::process::id()));
+        for (case, files) in [("with-files", true), ("without-files", false)] {
+            let dir = corpus.path().join(case);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(DIFF_FILE), "").unwrap();
+            std::fs::write(dir.join(EXPECTED_FILE), r#"{"violations":[]}"#).unwrap();
+            if files {
+                std::fs::create_dir(dir.join(FILES_DIR)).unwrap();
+            }
+        }
+        let cases = load_corpus(corpus.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            cases[0].files_dir,
+            Some(corpus.path().join("with-files").join(FILES_DIR))
+        );
+        assert_eq!(cases[1].files_dir, None);
+    }
+
+    #[test]
+    fn test_score() {
+        let a = expected("a.rs", Some((10, 12)));
+        let b = expected("b.rs", None);
+        let c = expected("c.rs", Some((1, 1)));
+        let reported = vec![
+            violation("a.rs", 11, 11),
+            violation("a.rs", 12, 14),
+            violation("b.rs", 99, 99),
+        ];
+        let score = score(&[&a, &b, &c], &reported);
+        assert_eq!(score.true_positives, 2);
+        assert_eq!(score.false_positives, 1);
+        assert_eq!(score.false_negatives, 1);
+        assert_eq!(score.precision(), Some(2.0 / 3.0));
+        assert_eq!(score.recall(), Some(2.0 / 3.0));
+    }
+
+    #[test]
+    fn test_format_report() {
+        let report = BenchReport::from([(
+            "model".to_string(),
+            BTreeMap::from([(
+                "Rule".to_string(),
+                Score {
+                    true_positives: 1,
+                    false_positives: 1,
+                    ..Score::default()
+                },
+            )]),
+        )]);
+        assert!(
+            format_report(&report)
+                .ends_with("| model | Rule | 50% | 100% | 1 | 1 | 0 | 0 | 0.0 | - |")
+        );
+    }
+
+    #[test]
+    fn test_report_cost() {
+        let mut total = Score::default();
+        accumulate(
+            &mut total,
+            Score {
+                cost: Some(0.01),
+                ..Score::default()
+            },
+        );
+        accumulate(&mut total, Score::default());
+        accumulate(
+            &mut total,
+            Score {
+                cost: Some(0.0025),
+                ..Score::default()
+            },
+        );
+        let report = BenchReport::from([(
+            "model".to_string(),
+            BTreeMap::from([("Rule".to_string(), total)]),
+        )]);
+        assert!(format_report(&report).ends_with("| 0.0 | $0.0125 |"));
+    }
+}
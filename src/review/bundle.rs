@@ -0,0 +1,179 @@
+use super::manifest::RunManifest;
+use super::render::{TraceEntry, TraceFile, ViolationFile};
+use tracing::info;
+
+/// Extension of bundle files
+pub const EXTENSION: &str = ".fkbundle";
+
+/// Bundle members
+pub const MANIFEST: &str = "manifest.json";
+pub const RESULTS: &str = "results.json";
+pub const TRACE: &str = "trace.json";
+pub const CONFIG: &str = "config.json";
+pub const SKIPPED: &str = "skipped.json";
+
+/// Leading bytes of a zip archive, the signature of its first member's local header
+const ZIP_SIGNATURE: &[u8; 4] = b"PK\x03\x04";
+
+/// Write a bundle of a run: its manifest and config snapshot (when recorded), results,
+/// skipped files, and trace, as a zip of JSON files
+pub fn write(
+    path: &str,
+    results: &ViolationFile,
+    manifest: Option<&RunManifest>,
+    traces: &[TraceEntry],
+) -> Result<(), String> {
+    if !path.ends_with(EXTENSION) {
+        return Err(format!("Bundle file must end with {}: {}", EXTENSION, path));
+    }
+    let mut members = Vec::new();
+    if let Some(manifest) = manifest {
+        members.push((MANIFEST, json(manifest)));
+        members.push((CONFIG, json(&manifest.config)));
+    }
+    members.push((RESULTS, json(results)));
+    members.push((SKIPPED, json(&results.skipped)));
+    if !traces.is_empty() {
+        members.push((TRACE, json(&TraceFile::new(traces.to_vec()))));
+    }
+    write_zip(path, &members).map_err(|e| format!("Failed to write bundle {}: {}", path, e))?;
+    info!("Bundle written to {}", path);
+    Ok(())
+}
+
+fn json<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap()
+}
+
+/// Whether `path` is a bundle rather than a JSON file, by its leading bytes
+pub fn is_bundle(path: &str) -> bool {
+    use std::io::Read;
+    let mut magic = [0; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|()| &magic == ZIP_SIGNATURE)
+}
+
+/// Whether `path` is a bundle with the member `name`
+pub fn contains(path: &str, name: &str) -> bool {
+    is_bundle(path) && read_member(path, name).is_ok_and(|member| member.is_some())
+}
+
+/// Content of the member `name` of a bundle, `None` if the bundle lacks it
+pub fn read_member(path: &str, name: &str) -> Result<Option<String>, String> {
+    use std::io::Read;
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to read bundle {}: {}", path, e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Invalid bundle {}: {}", path, e))?;
+    let mut member = match archive.by_name(name) {
+        Ok(member) => member,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Invalid bundle {}: {}", path, e)),
+    };
+    let mut content = String::new();
+    member
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Invalid bundle {}: {}: {}", path, name, e))?;
+    Ok(Some(content))
+}
+
+/// Content of a results or trace JSON file, or the results of a bundle
+pub fn read_input(path: &str) -> Result<String, String> {
+    if is_bundle(path) {
+        return read_member(path, RESULTS)?
+            .ok_or_else(|| format!("Bundle {} has no {}", path, RESULTS));
+    }
+    std::fs::read_to_string(path).map_err(|e| format!("Failed to read input file: {}", e))
+}
+
+/// Zip archive of deflated members. Timestamps are left at the zip epoch, so bundles of
+/// the same run are identical
+fn write_zip(path: &str, members: &[(&str, String)]) -> zip::result::ZipResult<()> {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path)?);
+    for (name, content) in members {
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(content.len() as u64 >= u64::from(u32::MAX));
+        zip.start_file(*name, options)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::render::{SkipReason, SkippedFile};
+    use crate::util::temp::TempDir;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_zip_round_trip() {
+        let dir = TempDir::new(&format!("firekeeper-zip-{}", std::process::id()));
+        std::fs::create_dir_all(dir.path()).unwrap();
+        let path = dir
+            .path()
+            .join("run.fkbundle")
+            .to_string_lossy()
+            .to_string();
+        let members = [
+            (RESULTS, "{}".to_string()),
+            ("ドキュメント.md", "# 見出し".into()),
+        ];
+        write_zip(&path, &members).unwrap();
+        assert!(is_bundle(&path));
+        assert_eq!(read_member(&path, RESULTS).unwrap().unwrap(), "{}");
+        assert_eq!(
+            read_member(&path, "ドキュメント.md").unwrap().unwrap(),
+            "# 見出し"
+        );
+        assert_eq!(read_member(&path, TRACE).unwrap(), None);
+
+        let json = dir
+            .path()
+            .join("results.json")
+            .to_string_lossy()
+            .to_string();
+        std::fs::write(&json, "{}").unwrap();
+        assert!(!is_bundle(&json));
+        assert!(read_member(&json, RESULTS).is_err());
+    }
+
+    #[test]
+    fn test_write_and_read_bundle() {
+        let temp = TempDir::new(&format!("firekeeper-bundle-{}", std::process::id()));
+        let dir = temp.path();
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("run.fkbundle").to_string_lossy().to_string();
+        let results = ViolationFile {
+            version: "0".into(),
+            violations: BTreeMap::new(),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: vec![SkippedFile {
+                file: "vendor/a.js".into(),
+                reason: SkipReason::Vendored,
+            }],
+            incomplete: vec![],
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        assert!(write(&dir.join("run.zip").to_string_lossy(), &results, None, &[]).is_err());
+        write(&path, &results, None, &[]).unwrap();
+
+        assert!(is_bundle(&path));
+        assert!(contains(&path, SKIPPED));
+        assert!(!contains(&path, TRACE));
+        let loaded = ViolationFile::load(&path).unwrap();
+        assert_eq!(loaded.skipped, results.skipped);
+        assert!(read_input(&path).unwrap().contains("vendor/a.js"));
+        let Err(e) = TraceFile::load(&path) else {
+            panic!("bundle without a trace loaded one");
+        };
+        assert!(e.contains("has no trace.json"));
+    }
+}
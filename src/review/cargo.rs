@@ -0,0 +1,176 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Subset of `cargo metadata --format-version 1 --no-deps` output
+#[derive(Deserialize, Debug)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_root: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Package {
+    name: String,
+    version: String,
+    manifest_path: String,
+    #[serde(default)]
+    dependencies: Vec<Dependency>,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Dependency {
+    name: String,
+    req: String,
+    /// `dev` or `build`, `None` for normal dependencies
+    kind: Option<String>,
+    rename: Option<String>,
+    #[serde(default)]
+    optional: bool,
+    #[serde(default = "default_true")]
+    uses_default_features: bool,
+    #[serde(default)]
+    features: Vec<String>,
+    target: Option<String>,
+    /// Local path of path dependencies
+    path: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Path relative to the workspace root, as the agent sees files
+fn relative<'a>(path: &'a str, root: &str) -> &'a str {
+    Path::new(path)
+        .strip_prefix(root)
+        .ok()
+        .and_then(Path::to_str)
+        .unwrap_or(path)
+}
+
+fn format_dependency(dep: &Dependency, root: &str) -> String {
+    let mut line = match &dep.rename {
+        Some(rename) => format!("{} (package {})", rename, dep.name),
+        None => dep.name.clone(),
+    };
+    match &dep.path {
+        Some(path) => line.push_str(&format!(" path={}", relative(path, root))),
+        None => line.push_str(&format!(" {}", dep.req)),
+    }
+    let mut notes = Vec::new();
+    if let Some(kind) = &dep.kind {
+        notes.push(kind.clone());
+    }
+    if dep.optional {
+        notes.push("optional".to_string());
+    }
+    if !dep.uses_default_features {
+        notes.push("no default features".to_string());
+    }
+    if !dep.features.is_empty() {
+        notes.push(format!("features: {}", dep.features.join(", ")));
+    }
+    if let Some(target) = &dep.target {
+        notes.push(format!("target: {}", target));
+    }
+    if !notes.is_empty() {
+        line.push_str(&format!(" ({})", notes.join("; ")));
+    }
+    line
+}
+
+/// Condense `cargo metadata` JSON to the workspace crates with their features and
+/// declared dependencies
+pub fn format_workspace(json: &str) -> Result<String, String> {
+    let metadata: Metadata =
+        serde_json::from_str(json).map_err(|e| format!("Invalid cargo metadata: {}", e))?;
+    let root = metadata.workspace_root.as_str();
+    let mut output = format!(
+        "Workspace with {} crate(s). Paths are relative to the workspace root.\n",
+        metadata.packages.len()
+    );
+    for package in &metadata.packages {
+        output.push_str(&format!(
+            "\n#### {} {} (`{}`)\n\n",
+            package.name,
+            package.version,
+            relative(&package.manifest_path, root)
+        ));
+        if !package.features.is_empty() {
+            let features: Vec<String> = package
+                .features
+                .iter()
+                .map(|(name, enables)| format!("{} = [{}]", name, enables.join(", ")))
+                .collect();
+            output.push_str(&format!("Features: {}\n\n", features.join("; ")));
+        }
+        if package.dependencies.is_empty() {
+            output.push_str("No dependencies\n");
+        }
+        for dep in &package.dependencies {
+            output.push_str(&format!("- {}\n", format_dependency(dep, root)));
+        }
+    }
+    Ok(output)
+}
+
+/// Run `cargo metadata` for the workspace of `dir`, without resolving the dependency
+/// graph so it works offline
+pub async fn workspace_metadata(dir: &Path) -> Result<String, String> {
+    let output = tokio::process::Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run cargo metadata: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "cargo metadata exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("Invalid cargo metadata: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_workspace() {
+        let json = r#"{
+            "workspace_root": "/repo",
+            "packages": [{
+                "name": "app",
+                "version": "0.1.0",
+                "manifest_path": "/repo/crates/app/Cargo.toml",
+                "features": {"default": ["tls"], "tls": ["dep:rustls"]},
+                "dependencies": [
+                    {"name": "core", "req": "*", "kind": null, "rename": null, "optional": false,
+                     "uses_default_features": true, "features": [], "target": null,
+                     "path": "/repo/crates/core"},
+                    {"name": "rustls", "req": "^0.23", "kind": null, "rename": null,
+                     "optional": true, "uses_default_features": false, "features": ["ring"],
+                     "target": null},
+                    {"name": "tempfile", "req": "^3", "kind": "dev", "rename": null,
+                     "optional": false, "uses_default_features": true, "features": [],
+                     "target": "cfg(unix)"}
+                ]
+            }]
+        }"#;
+        assert_eq!(
+            format_workspace(json).unwrap(),
+            "Workspace with 1 crate(s). Paths are relative to the workspace root.\n\n\
+             #### app 0.1.0 (`crates/app/Cargo.toml`)\n\n\
+             Features: default = [tls]; tls = [dep:rustls]\n\n\
+             - core path=crates/core\n\
+             - rustls ^0.23 (optional; no default features; features: ring)\n\
+             - tempfile ^3 (dev; target: cfg(unix))\n"
+        );
+        assert!(format_workspace("{}").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::tokens::Tokenizer;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tiny_loop::history::History;
+use tiny_loop::types::{Message, TimedMessage};
+/// Most recent messages never compacted, so the current turn keeps its tool results
+const KEEP_RECENT_MESSAGES: usize = 6;
+/// Tool results shorter than this (in bytes) are kept, as evicting them saves little
+const MIN_EVICTED_BYTES: usize = 500;
+
+/// One compaction of the conversation, recorded in the trace
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Compaction {
+    /// Number of messages in the history when compacted
+    pub messages: usize,
+    /// Estimated tokens before compacting
+    pub tokens_before: usize,
+    /// Estimated tokens after compacting
+    pub tokens_after: usize,
+    /// Number of tool results removed
+    pub evicted: usize,
+}
+
+/// History that evicts old tool results once the conversation exceeds a token budget.
+///
+/// The system prompt, user message with the rule, assistant messages, and the most recent
+/// messages are kept, so the agent retains its reasoning and report calls.
+pub struct CompactingHistory {
+    messages: Vec<TimedMessage>,
+    /// Tokens of each message, counted once as messages are added or compacted
+    tokens: Vec<usize>,
+    max_tokens: usize,
+    tokenizer: Tokenizer,
+    compactions: Arc<Mutex<Vec<Compaction>>>,
+}
+
+impl CompactingHistory {
+    /// Create a history limited to `max_tokens` tokens (0 disables compaction)
+    pub fn new(max_tokens: usize, tokenizer: Tokenizer) -> Self {
+        Self {
+            messages: Vec::new(),
+            tokens: Vec::new(),
+            max_tokens,
+            tokenizer,
+            compactions: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Shared log of compactions, readable after the history is moved into an agent
+    pub fn compactions(&self) -> Arc<Mutex<Vec<Compaction>>> {
+        self.compactions.clone()
+    }
+
+    fn total_tokens(&self) -> usize {
+        self.tokens.iter().sum()
+    }
+
+    /// Evict the oldest large tool results until the history is under 3/4 of the budget,
+    /// leaving headroom so compaction does not run on every message
+    fn compact(&mut self) {
+        let tokens_before = self.total_tokens();
+        let target = self.max_tokens / 4 * 3;
+        let mut tokens = tokens_before;
+        let mut evicted = 0;
+        let end = self.messages.len().saturating_sub(KEEP_RECENT_MESSAGES);
+        for (tm, message_tokens) in self.messages[..end].iter_mut().zip(&mut self.tokens) {
+            if tokens <= target {
+                break;
+            }
+            if let Message::Tool(tool) = &mut tm.message
+                && tool.content.len() >= MIN_EVICTED_BYTES
+            {
+                tool.content = format!(
+                    "[Earlier tool output removed to save context ({} bytes)]",
+                    tool.content.len()
+                );
+                let remaining = self.tokenizer.count(&tool.content);
+                tokens = tokens.saturating_sub(message_tokens.saturating_sub(remaining));
+                *message_tokens = remaining;
+                evicted += 1;
+            }
+        }
+        if evicted > 0 {
+            self.compactions.lock().unwrap().push(Compaction {
+                messages: self.messages.len(),
+                tokens_before,
+                tokens_after: self.total_tokens(),
+                evicted,
+            });
+        }
+    }
+}
+
+impl History for CompactingHistory {
+    fn add(&mut self, message: TimedMessage) {
+        if self.max_tokens > 0 {
+            self.tokens
+                .push(message_tokens(&self.tokenizer, &message.message));
+        }
+        self.messages.push(message);
+        if self.max_tokens > 0 && self.total_tokens() > self.max_tokens {
+            self.compact();
+        }
+    }
+
+    fn get_all(&self) -> &[TimedMessage] {
+        &self.messages
+    }
+}
+
+/// Tokens of a message's content and tool calls
+pub(super) fn message_tokens(tokenizer: &Tokenizer, message: &Message) -> usize {
+    match message {
+        Message::System(m) => tokenizer.count(&m.content),
+        Message::User(m) => tokenizer.count(&m.content),
+        Message::Assistant(m) => {
+            tokenizer.count(&m.content)
+                + m.tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|tc| {
+                        tokenizer.count(&tc.function.name) + tokenizer.count(&tc.function.arguments)
+                    })
+                    .sum::<usize>()
+        }
+        Message::Tool(m) => tokenizer.count(&m.content),
+        Message::Custom(m) => tokenizer.count(&m.body.to_string()),
+    }
+}
+
+pub(super) fn message_chars(message: &Message) -> usize {
+    match message {
+        Message::System(m) => m.content.len(),
+        Message::User(m) => m.content.len(),
+        Message::Assistant(m) => {
+            m.content.len()
+                + m.tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|tc| tc.function.name.len() + tc.function.arguments.len())
+                    .sum::<usize>()
+        }
+        Message::Tool(m) => m.content.len(),
+        Message::Custom(m) => m.body.to_string().len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_loop::types::{ToolMessage, UserMessage};
+
+    fn timed(message: Message) -> TimedMessage {
+        TimedMessage {
+            message,
+            timestamp: std::time::SystemTime::now(),
+            elapsed: std::time::Duration::ZERO,
+        }
+    }
+
+    fn tool(content: String) -> TimedMessage {
+        timed(Message::Tool(ToolMessage {
+            content,
+            tool_call_id: "1".into(),
+        }))
+    }
+
+    #[test]
+    fn test_compacts_old_tool_results() {
+        let mut history = CompactingHistory::new(3_000, Tokenizer::for_model("unknown"));
+        let compactions = history.compactions();
+        history.add(timed(Message::User(UserMessage {
+            content: "rule".repeat(500),
+        })));
+        for _ in 0..10 {
+            history.add(tool("x".repeat(2_000)));
+        }
+
+        let messages = history.get_all();
+        let Message::User(user) = &messages[0].message else {
+            panic!("expected user message");
+        };
+        assert_eq!(user.content.len(), 2_000);
+        let Message::Tool(first) = &messages[1].message else {
+            panic!("expected tool message");
+        };
+        assert!(first.content.starts_with("[Earlier tool output removed"));
+        let Message::Tool(last) = &messages[10].message else {
+            panic!("expected tool message");
+        };
+        assert_eq!(last.content.len(), 2_000);
+
+        let compactions = compactions.lock().unwrap();
+        assert!(!compactions.is_empty());
+        assert!(compactions.iter().all(|c| c.tokens_after < c.tokens_before));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut history = CompactingHistory::new(0, Tokenizer::for_model("unknown"));
+        for _ in 0..10 {
+            history.add(tool("x".repeat(2_000)));
+        }
+        assert!(history.compactions().lock().unwrap().is_empty());
+    }
+}
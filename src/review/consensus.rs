@@ -0,0 +1,153 @@
+use super::worker::WorkerResult;
+use crate::types::Violation;
+use std::collections::BTreeSet;
+use tracing::info;
+
+type AgentResult = Result<WorkerResult, Box<dyn std::error::Error>>;
+
+/// Violations of different agents at overlapping lines of a file, counted as one finding
+struct Finding {
+    violation: Violation,
+    agents: BTreeSet<usize>,
+    details: Vec<String>,
+}
+
+/// Agents that must report a finding for it to be kept: a strict majority
+pub fn majority(agents: usize) -> usize {
+    agents / 2 + 1
+}
+
+/// Keep the violations reported by a majority of `agents`, matched by file and overlapping
+/// lines. Kept violations span the lines of all matching reports, with their distinct details.
+fn agree(violations_by_agent: &[Vec<Violation>], agents: usize) -> Vec<Violation> {
+    let mut findings: Vec<Finding> = Vec::new();
+    for (agent, violations) in violations_by_agent.iter().enumerate() {
+        for v in violations {
+            let existing = findings.iter_mut().find(|f| {
+                !f.agents.contains(&agent)
+                    && f.violation.file == v.file
+                    && f.violation.start_line <= v.end_line
+                    && v.start_line <= f.violation.end_line
+            });
+            match existing {
+                Some(finding) => {
+                    finding.agents.insert(agent);
+                    finding.violation.start_line = finding.violation.start_line.min(v.start_line);
+                    finding.violation.end_line = finding.violation.end_line.max(v.end_line);
+                    if !finding.details.contains(&v.detail) {
+                        finding.details.push(v.detail.clone());
+                    }
+                }
+                None => findings.push(Finding {
+                    violation: v.clone(),
+                    agents: BTreeSet::from([agent]),
+                    details: vec![v.detail.clone()],
+                }),
+            }
+        }
+    }
+    findings
+        .into_iter()
+        .filter(|f| f.agents.len() >= majority(agents))
+        .map(|f| Violation {
+            detail: f.details.join("\n\n"),
+            ..f.violation
+        })
+        .collect()
+}
+
+/// Merge the results of the agents that reviewed the same task into one result.
+///
+/// Fails only if every agent failed; otherwise failed agents mark the result incomplete,
+/// since their votes are missing. Traces, compactions and usage of all agents are kept.
+pub fn merge(worker_id: String, results: Vec<AgentResult>) -> AgentResult {
+    let agents = results.len();
+    let mut succeeded = Vec::new();
+    let mut first_error = None;
+    for result in results {
+        match result {
+            Ok(result) => succeeded.push(result),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    let failed = agents - succeeded.len();
+    let mut results = succeeded.into_iter();
+    let Some(mut merged) = results.next() else {
+        return Err(first_error.unwrap_or_else(|| "no consensus agents ran".into()));
+    };
+
+    let mut violations_by_agent = vec![std::mem::take(&mut merged.violations)];
+    for result in results {
+        violations_by_agent.push(result.violations);
+        merged.elapsed_secs += result.elapsed_secs;
+        merged.compactions.extend(result.compactions);
+        merged.usage.add(&result.usage);
+        merged.incomplete = merged.incomplete.or(result.incomplete);
+        if let (Some(messages), Some(more)) = (&mut merged.messages, result.messages) {
+            messages.extend(more);
+        }
+    }
+    let reported: usize = violations_by_agent.iter().map(Vec::len).sum();
+    merged.violations = agree(&violations_by_agent, agents);
+    info!(
+        "[Worker {}] {} of {} agents agreed on {} violation(s) ({} reported in total)",
+        worker_id,
+        majority(agents),
+        agents,
+        merged.violations.len(),
+        reported
+    );
+    if failed > 0 {
+        merged.incomplete = Some(format!(
+            "{} of {} consensus agents failed: {}",
+            failed,
+            agents,
+            first_error.map(|e| e.to_string()).unwrap_or_default()
+        ));
+    }
+    merged.worker_id = worker_id;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violation(file: &str, start_line: u32, end_line: u32, detail: &str) -> Violation {
+        Violation {
+            file: file.into(),
+            detail: detail.into(),
+            start_line,
+            end_line,
+            rule_id: None,
+            owners: vec![],
+        }
+    }
+
+    #[test]
+    fn test_agree_keeps_majority_findings() {
+        let violations_by_agent = vec![
+            vec![
+                violation("a.rs", 10, 12, "Magic number 42"),
+                violation("a.rs", 30, 30, "Only one agent"),
+            ],
+            vec![violation("a.rs", 12, 14, "Unexplained constant")],
+            vec![violation("b.rs", 10, 12, "Other file")],
+        ];
+        let agreed = agree(&violations_by_agent, 3);
+        assert_eq!(agreed.len(), 1);
+        assert_eq!((agreed[0].start_line, agreed[0].end_line), (10, 14));
+        assert_eq!(agreed[0].detail, "Magic number 42\n\nUnexplained constant");
+
+        // Two reports of one agent are not two votes
+        let violations_by_agent = vec![
+            vec![violation("a.rs", 1, 1, "x"), violation("a.rs", 1, 1, "x")],
+            vec![],
+        ];
+        assert!(agree(&violations_by_agent, 2).is_empty());
+        assert_eq!(majority(2), 2);
+        assert_eq!(majority(3), 2);
+    }
+}
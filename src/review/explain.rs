@@ -0,0 +1,271 @@
+use super::render::{self, TraceFile, ViolationFile, get_fence_backticks};
+use crate::config::Config;
+use crate::rule::tip::Tip;
+use crate::tool::think::Think;
+use crate::types::Violation;
+use tiny_loop::Agent;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+/// Lines of surrounding context to show around the violation snippet
+const SNIPPET_CONTEXT_LINES: u32 = 5;
+
+const SYSTEM_PROMPT: &str = r"You are a code reviewer explaining a violation reported in a previous review.
+Answer the user's questions about why the code violates the rule and how to make it compliant.
+You can read related files if needed. Keep answers concise and reference concrete lines.";
+
+/// Follow-up instruction appended when resuming a traced review session
+const RESUME_PROMPT: &str = "The review is complete and no more reports are needed. \
+A developer has follow-up questions about the violation below. \
+Answer in plain text without calling the report tool.";
+
+/// List all violations with their index and fingerprint, for selecting one to explain
+pub fn format_violation_list(violation_file: &ViolationFile) -> String {
+    let violations = render::flatten_violations(&violation_file.violations);
+    if violations.is_empty() {
+        return "No violations found".to_string();
+    }
+    violations
+        .iter()
+        .enumerate()
+        .map(|(i, (rule, v))| {
+            format!(
+                "{}. [{}] {}:{}-{} ({}): {}",
+                i + 1,
+                v.fingerprint(rule),
+                v.file,
+                v.start_line,
+                v.end_line,
+                rule,
+                v.detail
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Select a violation by 1-based index or fingerprint prefix (must be unambiguous)
+pub fn select_violation<'a>(
+    violations: &[(&'a str, &'a Violation)],
+    selector: &str,
+) -> Result<(&'a str, &'a Violation), String> {
+    if let Ok(index) = selector.parse::<usize>()
+        && index >= 1
+        && index <= violations.len()
+    {
+        return Ok(violations[index - 1]);
+    }
+
+    let matches: Vec<_> = violations
+        .iter()
+        .filter(|(rule, v)| v.fingerprint(rule).starts_with(selector))
+        .collect();
+    match matches.as_slice() {
+        [one] => Ok(**one),
+        [] => Err(format!("No violation matches '{}'", selector)),
+        _ => Err(format!(
+            "Fingerprint prefix '{}' is ambiguous ({} matches)",
+            selector,
+            matches.len()
+        )),
+    }
+}
+
+/// Build the first question about the selected violation
+fn build_explain_prompt(
+    rule: &str,
+    violation: &Violation,
+    instruction: Option<&str>,
+    tip: Option<&Tip>,
+) -> String {
+    let mut body = String::new();
+
+    if let Some(instruction) = instruction {
+        body.push_str("## Rule\n\n");
+        let fence = get_fence_backticks(instruction);
+        body.push_str(&format!(
+            "{}md\n{}\n{}\n\n",
+            fence,
+            instruction.trim(),
+            fence
+        ));
+    } else {
+        body.push_str(&format!("## Rule\n\n{}\n\n", rule));
+    }
+
+    body.push_str(&format!(
+        "## Violation\n\n- File: {}\n- Lines: {}-{}\n- Detail: {}\n\n",
+        violation.file, violation.start_line, violation.end_line, violation.detail
+    ));
+
+    if let Some(snippet) = crate::util::read_snippet(
+        &violation.file,
+        violation.start_line,
+        violation.end_line,
+        SNIPPET_CONTEXT_LINES,
+    ) {
+        let fence = get_fence_backticks(&snippet);
+        body.push_str(&format!("## Code\n\n{}\n{}\n{}\n\n", fence, snippet, fence));
+    }
+
+    if let Some(tip) = tip.filter(|t| !t.is_empty()) {
+        body.push_str(&format!("## Tip\n\n{}\n\n", tip.to_plain()));
+    }
+
+    body.push_str("Why is this a violation?");
+    body
+}
+
+/// Re-open an agent session scoped to one violation and answer follow-up questions interactively.
+///
+/// If a trace file is given and contains the worker that reported the violation,
+/// its conversation is replayed into the agent history so the agent keeps its original context.
+pub async fn explain(
+    config: &Config,
+    api_key: &str,
+    violation_file: &ViolationFile,
+    trace_file: Option<&TraceFile>,
+    selector: &str,
+) -> Result<(), String> {
+    let violations = render::flatten_violations(&violation_file.violations);
+    let (rule, violation) = select_violation(&violations, selector)?;
+    info!(
+        "Explaining violation of '{}' in {}:{}-{}",
+        rule, violation.file, violation.start_line, violation.end_line
+    );
+
+    let llm = crate::llm::create_provider(
+        api_key,
+        &config.llm.endpoint(),
+        &config.llm.model,
+        &config.llm.headers,
+        &config.llm.body,
+        config.llm.tool_protocol,
+        config.llm.provider,
+    )
+    .map(|llm| llm.with_retry(config.llm.retry.clone()))
+    .map_err(|e| format!("Failed to create LLM provider: {}", e))?;
+
+    // Reuse the reporting worker's conversation when a trace is available
+    let trace_entry = trace_file.and_then(|t| {
+        t.entries
+            .iter()
+            .find(|e| e.rule.name == rule && e.files.contains(&violation.file))
+    });
+    let instruction = trace_entry
+        .map(|e| e.rule.instruction.as_str())
+        .or_else(|| {
+            config
+                .rules
+                .iter()
+                .find(|r| r.name == rule)
+                .map(|r| r.instruction.as_str())
+        });
+
+    let mut agent = Agent::new(llm);
+    let mut prompt =
+        build_explain_prompt(rule, violation, instruction, violation_file.tips.get(rule));
+    if let Some(entry) = trace_entry {
+        debug!(
+            "Resuming trace of worker {} ({} messages)",
+            entry.worker_id,
+            entry.messages.len()
+        );
+        for message in &entry.messages {
+            agent.history.add(message.clone());
+        }
+        prompt = format!("{}\n\n{}", RESUME_PROMPT, prompt);
+    } else {
+        agent = agent.system(SYSTEM_PROMPT);
+    }
+    let mut agent = crate::llm::register_common_tools(
+        agent,
+        &config.review.allowed_shell_commands,
+        config.review.shell.unwrap_or_default(),
+        None,
+        Think::new(),
+        &config.review.tools,
+        &CancellationToken::new(),
+    );
+
+    let mut stdin = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    loop {
+        let answer = agent
+            .chat(prompt)
+            .await
+            .map_err(|e| format!("LLM request failed: {}", e))?;
+        println!("\n{}\n", answer.trim());
+
+        // Prompt for a follow-up question; empty line, "exit", or EOF ends the session
+        stdout.write_all(b"> ").await.ok();
+        stdout.flush().await.ok();
+        let Ok(Some(line)) = stdin.next_line().await else {
+            return Ok(());
+        };
+        let line = line.trim();
+        if line.is_empty() || line == "exit" || line == "quit" {
+            return Ok(());
+        }
+        prompt = line.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn violation_file() -> ViolationFile {
+        let violation = |line: u32| Violation {
+            file: "a.rs".into(),
+            detail: format!("issue {}", line),
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
+        };
+        ViolationFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            violations: BTreeMap::from([(
+                "a.rs".to_string(),
+                BTreeMap::from([("Rule".to_string(), vec![violation(1), violation(2)])]),
+            )]),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: Vec::new(),
+            incomplete: Vec::new(),
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_select_violation_by_index() {
+        let file = violation_file();
+        let violations = render::flatten_violations(&file.violations);
+        let (rule, v) = select_violation(&violations, "2").unwrap();
+        assert_eq!(rule, "Rule");
+        assert_eq!(v.start_line, 2);
+        assert!(select_violation(&violations, "0").is_err());
+    }
+
+    #[test]
+    fn test_select_violation_by_fingerprint() {
+        let file = violation_file();
+        let violations = render::flatten_violations(&file.violations);
+        let fingerprint = violations[0].1.fingerprint("Rule");
+        let (_, v) = select_violation(&violations, &fingerprint[..8]).unwrap();
+        assert_eq!(v.start_line, 1);
+        assert!(select_violation(&violations, "zzzz").is_err());
+    }
+
+    #[test]
+    fn test_format_violation_list() {
+        let list = format_violation_list(&violation_file());
+        assert!(list.starts_with("1. ["));
+        assert!(list.contains("a.rs:2-2 (Rule): issue 2"));
+    }
+}
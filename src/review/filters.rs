@@ -0,0 +1,176 @@
+use super::render::ViolationsByFile;
+use crate::types::Violation;
+use globset::{Glob, GlobMatcher};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use toml_scaffold::TomlScaffold;
+use tracing::warn;
+
+/// Post-processing of collected violations, applied before output and gating.
+///
+/// Tunes noise without editing rule instructions, e.g.
+/// `drop = [{ rule = "No Magic Numbers", path = "benches/**" }]`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+pub struct FiltersConfig {
+    /// Remove matching violations from the results (optional)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drop: Vec<ViolationFilter>,
+    /// Report matching violations without failing the run, as if their rule were non-blocking (optional)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub downgrade: Vec<ViolationFilter>,
+}
+
+/// Violations matched by rule and file path; omitted fields match any violation
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+pub struct ViolationFilter {
+    /// Rule name or id (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// Glob pattern of file paths, e.g. `benches/**` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Filter with its path glob compiled
+struct Matcher<'a> {
+    rule: Option<&'a str>,
+    path: Option<GlobMatcher>,
+}
+
+impl Matcher<'_> {
+    fn matches(&self, rule: &str, violation: &Violation) -> bool {
+        self.rule
+            .is_none_or(|r| r == rule || violation.rule_id.as_deref() == Some(r))
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|glob| glob.is_match(&violation.file))
+    }
+}
+
+/// Compile filters, skipping those with an invalid path glob
+fn matchers<'a>(filters: &'a [ViolationFilter], kind: &str) -> Vec<Matcher<'a>> {
+    filters
+        .iter()
+        .filter_map(|filter| {
+            let path = match filter.path.as_deref().map(Glob::new).transpose() {
+                Ok(glob) => glob.map(|g| g.compile_matcher()),
+                Err(e) => {
+                    warn!("Ignoring {} filter with invalid path: {}", kind, e);
+                    return None;
+                }
+            };
+            Some(Matcher {
+                rule: filter.rule.as_deref(),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Outcome of applying filters, for logging
+#[derive(Debug, Default, PartialEq)]
+pub struct Filtered {
+    pub dropped: usize,
+    pub downgraded: usize,
+}
+
+impl FiltersConfig {
+    pub fn is_empty(&self) -> bool {
+        self.drop.is_empty() && self.downgrade.is_empty()
+    }
+
+    /// Drop matching violations, and keep a rule in `blocking_rules` only while it has
+    /// violations not downgraded
+    pub fn apply(
+        &self,
+        violations_by_file: &mut ViolationsByFile,
+        blocking_rules: &mut BTreeSet<String>,
+    ) -> Filtered {
+        let mut filtered = Filtered::default();
+        if self.is_empty() {
+            return filtered;
+        }
+        let drop = matchers(&self.drop, "drop");
+        let downgrade = matchers(&self.downgrade, "downgrade");
+
+        let mut still_blocking = BTreeSet::new();
+        for by_rule in violations_by_file.values_mut() {
+            for (rule, violations) in by_rule.iter_mut() {
+                let before = violations.len();
+                violations.retain(|v| !drop.iter().any(|m| m.matches(rule, v)));
+                filtered.dropped += before - violations.len();
+                for violation in violations.iter() {
+                    if downgrade.iter().any(|m| m.matches(rule, violation)) {
+                        filtered.downgraded += 1;
+                    } else {
+                        still_blocking.insert(rule.clone());
+                    }
+                }
+            }
+            by_rule.retain(|_, violations| !violations.is_empty());
+        }
+        violations_by_file.retain(|_, by_rule| !by_rule.is_empty());
+        blocking_rules.retain(|rule| still_blocking.contains(rule));
+        filtered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_apply_filters() {
+        let violation = |file: &str| Violation {
+            file: file.into(),
+            detail: "detail".into(),
+            start_line: 1,
+            end_line: 1,
+            rule_id: Some("no-magic-numbers".into()),
+            owners: vec![],
+        };
+        let mut violations_by_file = BTreeMap::from([
+            (
+                "benches/a.rs".to_string(),
+                BTreeMap::from([(
+                    "No Magic Numbers".to_string(),
+                    vec![violation("benches/a.rs")],
+                )]),
+            ),
+            (
+                "src/legacy/b.rs".to_string(),
+                BTreeMap::from([(
+                    "No Magic Numbers".to_string(),
+                    vec![violation("src/legacy/b.rs")],
+                )]),
+            ),
+        ]);
+        let mut blocking_rules = BTreeSet::from(["No Magic Numbers".to_string()]);
+        let filters = FiltersConfig {
+            drop: vec![ViolationFilter {
+                rule: Some("No Magic Numbers".into()),
+                path: Some("benches/**".into()),
+            }],
+            downgrade: vec![ViolationFilter {
+                rule: Some("no-magic-numbers".into()),
+                path: Some("src/legacy/**".into()),
+            }],
+        };
+        let filtered = filters.apply(&mut violations_by_file, &mut blocking_rules);
+        assert_eq!(
+            filtered,
+            Filtered {
+                dropped: 1,
+                downgraded: 1
+            }
+        );
+        assert_eq!(
+            violations_by_file.keys().collect::<Vec<_>>(),
+            ["src/legacy/b.rs"]
+        );
+        assert!(blocking_rules.is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use super::render::TraceEntry;
+use crate::llm::replay::ReplayProvider;
+use crate::rule::body::RuleBody;
+use crate::tool::report::{Report, ReportArgs};
+use crate::types::Violation;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+use tiny_loop::Agent;
+use tiny_loop::tool::{Tool, ToolExecutor};
+use tiny_loop::types::{
+    AssistantMessage, Message, TimedMessage, ToolCall, ToolMessage, ToolResult,
+};
+
+/// One worker's conversation from a trace, replayable as a regression test:
+/// the prompt it received, the responses to script a model with, the tool results
+/// recorded, and the violations it reported
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Fixture {
+    pub version: String,
+    pub worker_id: String,
+    pub rule: RuleBody,
+    pub files: Vec<String>,
+    /// Messages before the first response: system prompt and task
+    pub prompt: Vec<Message>,
+    /// Model responses in order
+    pub responses: Vec<AssistantMessage>,
+    /// Tool results by tool call id
+    pub tool_results: BTreeMap<String, String>,
+    /// Violations reported through the `report` tool
+    pub expected_violations: Vec<Violation>,
+}
+
+impl Fixture {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read fixture {}: {}", path, e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse fixture {}: {}", path, e))
+    }
+
+    /// Replay the recorded responses through an agent, answering tool calls with the
+    /// recorded results and `report` calls with the report tool, and return the
+    /// violations it records in file and line order
+    pub async fn replay(&self) -> Result<Vec<Violation>, String> {
+        let report = Report::new().focus_files(&self.files);
+        let mut agent =
+            Agent::new(ReplayProvider::new(self.responses.clone())).executor(ReplayExecutor {
+                report: report.clone(),
+                tool_results: self.tool_results.clone(),
+            });
+        for message in &self.prompt {
+            agent.history.add(TimedMessage {
+                message: message.clone(),
+                timestamp: SystemTime::now(),
+                elapsed: Duration::ZERO,
+            });
+        }
+        // A trace cut short, e.g. by a timeout, ends without a final answer
+        for _ in 0..self.responses.len() {
+            let step = agent
+                .step()
+                .await
+                .map_err(|e| format!("Replay of worker {} failed: {}", self.worker_id, e))?;
+            if step.is_some() {
+                break;
+            }
+        }
+        let mut violations = report.violations.lock().await.clone();
+        violations.sort_by(|a, b| (&a.file, a.start_line).cmp(&(&b.file, b.start_line)));
+        Ok(violations)
+    }
+
+    /// Replay the fixture and describe how its violations differ from the expected ones
+    pub async fn check(&self) -> Result<(), String> {
+        let line =
+            |v: &Violation| format!("{}:{}-{}: {}", v.file, v.start_line, v.end_line, v.detail);
+        let actual: Vec<String> = self.replay().await?.iter().map(line).collect();
+        let expected: Vec<String> = self.expected_violations.iter().map(line).collect();
+        if actual == expected {
+            return Ok(());
+        }
+        Err(format!(
+            "Expected violations:\n{}\nReplayed violations:\n{}",
+            expected.join("\n"),
+            actual.join("\n")
+        ))
+    }
+}
+
+/// Tool executor answering calls with the results recorded in a fixture, except `report`
+/// calls, which the report tool records as in a review
+struct ReplayExecutor {
+    report: Report,
+    tool_results: BTreeMap<String, String>,
+}
+
+#[async_trait]
+impl ToolExecutor for ReplayExecutor {
+    fn add(&mut self, _name: String, _tool: Box<dyn Tool + Sync>) -> Option<Box<dyn Tool + Sync>> {
+        None
+    }
+
+    async fn execute(&self, calls: Vec<ToolCall>) -> Vec<ToolResult> {
+        let mut results = Vec::with_capacity(calls.len());
+        for call in calls {
+            let content = if call.function.name == ReportArgs::TOOL_NAME {
+                self.report.report(&call.function.arguments).await
+            } else {
+                self.tool_results
+                    .get(&call.id)
+                    .cloned()
+                    .unwrap_or_else(|| format!("No recorded result for tool call {}", call.id))
+            };
+            results.push(ToolResult {
+                tool_message: ToolMessage {
+                    tool_call_id: call.id,
+                    content,
+                },
+                timestamp: SystemTime::now(),
+                elapsed: Duration::ZERO,
+            });
+        }
+        results
+    }
+}
+
+/// Violations from the `report` calls of a conversation, in file and line order
+pub fn reported_violations(messages: &[TimedMessage]) -> Vec<Violation> {
+    let mut violations: Vec<Violation> = messages
+        .iter()
+        .filter_map(|m| match &m.message {
+            Message::Assistant(m) => m.tool_calls.as_ref(),
+            _ => None,
+        })
+        .flatten()
+        .filter(|call| call.function.name == ReportArgs::TOOL_NAME)
+        .filter_map(|call| serde_json::from_str::<ReportArgs>(&call.function.arguments).ok())
+        .flat_map(|args| args.violations)
+        .collect();
+    violations.sort_by(|a, b| (&a.file, a.start_line).cmp(&(&b.file, b.start_line)));
+    violations
+}
+
+/// Extract the fixture of a worker from trace entries
+pub fn extract(entries: &[TraceEntry], worker_id: &str) -> Result<Fixture, String> {
+    let entry = entries
+        .iter()
+        .find(|e| e.worker_id == worker_id)
+        .ok_or_else(|| {
+            let ids: Vec<&str> = entries.iter().map(|e| e.worker_id.as_str()).collect();
+            format!(
+                "No worker '{}' in the trace (available: {})",
+                worker_id,
+                ids.join(", ")
+            )
+        })?;
+
+    let mut prompt = Vec::new();
+    let mut responses = Vec::new();
+    let mut tool_results = BTreeMap::new();
+    for timed in &entry.messages {
+        match &timed.message {
+            Message::Assistant(message) => responses.push(message.clone()),
+            Message::Tool(message) => {
+                tool_results.insert(message.tool_call_id.clone(), message.content.clone());
+            }
+            message if responses.is_empty() => prompt.push(message.clone()),
+            // Messages injected mid-conversation, e.g. compaction notices, are not replayed
+            _ => {}
+        }
+    }
+    if responses.is_empty() {
+        return Err(format!("Worker '{}' has no responses to replay", worker_id));
+    }
+
+    Ok(Fixture {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        worker_id: entry.worker_id.clone(),
+        rule: entry.rule.clone(),
+        files: entry.files.clone(),
+        prompt,
+        responses,
+        tool_results,
+        expected_violations: reported_violations(&entry.messages),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_loop::types::{FunctionCall, UserMessage};
+
+    #[tokio::test]
+    async fn test_extract_and_replay_fixture() {
+        let timed = |message| TimedMessage {
+            message,
+            timestamp: SystemTime::UNIX_EPOCH,
+            elapsed: Duration::ZERO,
+        };
+        let call = |id: &str, name: &str, arguments: &str| {
+            timed(Message::Assistant(AssistantMessage {
+                content: String::new(),
+                tool_calls: Some(vec![ToolCall {
+                    id: id.into(),
+                    call_type: "function".into(),
+                    function: FunctionCall {
+                        name: name.into(),
+                        arguments: arguments.into(),
+                    },
+                }]),
+            }))
+        };
+        let result = |id: &str, content: &str| {
+            timed(Message::Tool(ToolMessage {
+                content: content.into(),
+                tool_call_id: id.into(),
+            }))
+        };
+        let entry = TraceEntry {
+            worker_id: "3".into(),
+            rule: RuleBody::no_magic_numbers(),
+            files: vec!["src/main.rs".into()],
+            elapsed_secs: 1.0,
+            resource_bytes: 0,
+            compactions: vec![],
+            usage: Default::default(),
+            tools: vec![],
+            messages: vec![
+                timed(Message::User(UserMessage {
+                    content: "review".into(),
+                })),
+                call("a", "read", r#"{"path": "src/main.rs"}"#),
+                result("a", "1: let x = 42;"),
+                call(
+                    "b",
+                    "report",
+                    r#"{"violations": [{"file": "src/main.rs", "detail": "Magic 42", "start_line": 1, "end_line": 1}]}"#,
+                ),
+                result("b", "Reported 1 violation"),
+            ],
+        };
+
+        let fixture = extract(std::slice::from_ref(&entry), "3").unwrap();
+        assert_eq!(fixture.prompt.len(), 1);
+        assert_eq!(fixture.responses.len(), 2);
+        assert_eq!(fixture.tool_results["a"], "1: let x = 42;");
+        assert_eq!(fixture.expected_violations[0].detail, "Magic 42");
+
+        fixture.check().await.unwrap();
+
+        let err = extract(&[entry], "9").unwrap_err();
+        assert_eq!(err, "No worker '9' in the trace (available: 3)");
+    }
+}
@@ -0,0 +1,228 @@
+use super::scope::build_globset;
+use crate::rule::generated::GeneratedConfig;
+use crate::tool::read::{Read, Snapshot};
+use crate::types::Violation;
+use std::collections::HashMap;
+
+/// Directories holding generated files, matched case-insensitively
+const GENERATED_DIRS: [&str; 2] = ["generated", "__generated__"];
+
+/// File name parts of generated files before the extension, e.g. `schema.generated.ts`
+const GENERATED_NAME_PARTS: [&str; 3] = [".generated.", "_generated.", "-generated."];
+
+/// Path suffixes of common code generators
+const GENERATED_SUFFIXES: [&str; 8] = [
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+];
+
+/// Header markers of generated files, matched case-insensitively
+const GENERATED_MARKERS: [&str; 5] = [
+    "@generated",
+    "do not edit",
+    "code generated by",
+    "auto-generated",
+    "autogenerated",
+];
+
+/// Lines at the top of a file searched for a generated marker
+const MARKER_LINES: usize = 10;
+
+/// Whether a path looks generated by its name alone: under a generated directory, or with a
+/// generated name part or codegen suffix
+fn is_generated_path(file: &str) -> bool {
+    let file = file.to_lowercase();
+    let mut components = file.split('/');
+    let name = components.next_back().unwrap_or_default();
+    components.any(|dir| GENERATED_DIRS.contains(&dir))
+        || GENERATED_NAME_PARTS.iter().any(|part| name.contains(part))
+        || GENERATED_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
+/// Whether the top of a file carries a generated marker
+fn has_generated_marker(content: &str) -> bool {
+    content.lines().take(MARKER_LINES).any(|line| {
+        let line = line.to_lowercase();
+        GENERATED_MARKERS.iter().any(|marker| line.contains(marker))
+    })
+}
+
+/// First changed line of a file in its diff, 1 if unknown. Reads unified diffs as well as
+/// compact diffs with line numbers, whose hunks start with a bare `@@`.
+fn first_changed_line(diff: Option<&String>) -> u32 {
+    let Some(diff) = diff else {
+        return 1;
+    };
+    // Line in the new file of the next unified hunk line
+    let mut next: Option<u32> = None;
+    let mut numbered = false;
+    let mut last_numbered: Option<u32> = None;
+    let mut removed = false;
+    for line in diff.lines() {
+        if let Some((_, start)) = crate::util::parse_hunk_header(line) {
+            (next, numbered) = (Some(start as u32), false);
+            continue;
+        }
+        if line == "@@" {
+            (next, numbered) = (None, true);
+            continue;
+        }
+        if numbered {
+            // `12 +text` or `12  text`, removed lines are unnumbered: `   -text`
+            let line = line.trim_start();
+            if line.starts_with('-') {
+                match last_numbered {
+                    Some(number) => return number + 1,
+                    None => removed = true,
+                }
+            } else if let Some((number, rest)) = line.split_once(' ')
+                && let Ok(number) = number.parse::<u32>()
+            {
+                if removed || rest.starts_with('+') {
+                    return number.max(1);
+                }
+                last_numbered = Some(number);
+            }
+            continue;
+        }
+        let Some(number) = next else {
+            continue;
+        };
+        match line.chars().next() {
+            Some('+' | '-') => return number.max(1),
+            Some(' ') => next = Some(number + 1),
+            _ => {}
+        }
+    }
+    1
+}
+
+/// Report generated files among `files` that changed while none of the generator inputs
+/// in `all_changed_files` did
+pub async fn check(
+    config: &GeneratedConfig,
+    rule_name: &str,
+    files: &[String],
+    all_changed_files: &[String],
+    diffs: &HashMap<String, String>,
+    snapshot: Snapshot,
+) -> Vec<Violation> {
+    let Some(manifests) = build_globset(&config.manifests, rule_name, "manifest") else {
+        return vec![];
+    };
+    let Some(extra) = build_globset(&config.files, rule_name, "generated file") else {
+        return vec![];
+    };
+    let changed_manifests: Vec<&String> = all_changed_files
+        .iter()
+        .filter(|f| manifests.is_match(f))
+        .collect();
+    if !changed_manifests.is_empty() {
+        return vec![];
+    }
+
+    let read = Read::new(snapshot);
+    let mut violations = Vec::new();
+    for file in files {
+        let generated = is_generated_path(file)
+            || extra.is_match(file)
+            || read
+                .content(file)
+                .await
+                .is_ok_and(|content| has_generated_marker(&content));
+        if !generated {
+            continue;
+        }
+        let line = first_changed_line(diffs.get(file));
+        let detail = if config.manifests.is_empty() {
+            "Generated file was edited by hand; change its generator input and regenerate it instead"
+                .to_string()
+        } else {
+            format!(
+                "Generated file changed without changes to its generator inputs ({}); change an input and regenerate instead of editing by hand",
+                config.manifests.join(", ")
+            )
+        };
+        violations.push(Violation {
+            file: file.clone(),
+            detail,
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
+        });
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_heuristics() {
+        assert!(is_generated_path("src/api/Generated/client.ts"));
+        assert!(is_generated_path("api/v1/user.pb.go"));
+        assert!(is_generated_path("web/__generated__/schema.ts"));
+        assert!(is_generated_path("src/schema.generated.ts"));
+        assert!(!is_generated_path("src/main.rs"));
+        assert!(!is_generated_path("src/regenerated.rs"));
+        assert!(!is_generated_path("docs/generated-code-policy.md"));
+        assert!(has_generated_marker(
+            "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage v1\n"
+        ));
+        assert!(!has_generated_marker("fn main() {}\n"));
+        let diff = "--- a/x.go\n+++ b/x.go\n@@ -10,3 +12,4 @@ func x()\n a\n+b\n".to_string();
+        assert_eq!(first_changed_line(Some(&diff)), 13);
+        let compact = "@@\n 8  let a = 1;\n   -let b = 2;\n 9 +let b = 3;\n".to_string();
+        assert_eq!(first_changed_line(Some(&compact)), 9);
+        let removed_first = "@@\n   -let a = 1;\n 4  let b = 2;\n".to_string();
+        assert_eq!(first_changed_line(Some(&removed_first)), 4);
+        assert_eq!(first_changed_line(None), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_generated() {
+        let config = GeneratedConfig {
+            manifests: vec!["proto/**".into()],
+            files: vec!["schema/*.sql".into()],
+        };
+        let files = [
+            "api/user.pb.go".to_string(),
+            "schema/init.sql".to_string(),
+            "src/main.rs".to_string(),
+        ];
+        let diffs = HashMap::new();
+        let violations = check(
+            &config,
+            "Rule",
+            &files,
+            &files,
+            &diffs,
+            Snapshot::WorkingTree,
+        )
+        .await;
+        let flagged: Vec<&str> = violations.iter().map(|v| v.file.as_str()).collect();
+        assert_eq!(flagged, ["api/user.pb.go", "schema/init.sql"]);
+        assert!(violations[0].detail.contains("proto/**"));
+
+        let mut changed = files.to_vec();
+        changed.push("proto/user.proto".into());
+        let violations = check(
+            &config,
+            "Rule",
+            &files,
+            &changed,
+            &diffs,
+            Snapshot::WorkingTree,
+        )
+        .await;
+        assert!(violations.is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use tracing::warn;
+
+/// Default path for the run history, one JSON summary per line
+pub const DEFAULT_HISTORY_PATH: &str = ".firekeeper/history.jsonl";
+
+/// Path of the last run's results, read by `results://previous` resources
+pub const DEFAULT_PREVIOUS_RESULTS_PATH: &str = ".firekeeper/previous-results.json";
+
+/// Summary of one review run
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub version: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub base: String,
+    pub files: usize,
+    pub tasks: usize,
+    pub failed_tasks: usize,
+    pub elapsed_secs: f64,
+    /// Violation count per reviewed rule, including rules with no violations
+    pub violations: BTreeMap<String, usize>,
+    /// Estimated LLM cost in USD, if known
+    #[serde(default)]
+    pub cost: Option<f64>,
+    /// Hash of the task cache keys per reviewed rule, equal across runs on identical diffs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, String>,
+    /// Hash of the violation fingerprints per reviewed rule
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub findings: BTreeMap<String, String>,
+    /// Rules with task results reused from the task cache rather than reviewed again
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub cached: BTreeSet<String>,
+}
+
+/// Append a run summary to the history file, creating parent directories as needed
+pub fn append(path: &str, entry: &HistoryEntry) -> Result<(), String> {
+    if let Some(parent) = std::path::Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    writeln!(file, "{}", serde_json::to_string(entry).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Save a run's results for `results://previous`, creating parent directories as needed.
+///
+/// Violations the saved results have in files this run did not review are kept, so a run
+/// over other files does not drop findings that were never re-checked.
+pub fn save_results(
+    path: &str,
+    violation_file: &super::render::ViolationFile,
+    reviewed_files: &[String],
+) -> Result<(), String> {
+    let mut violation_file = violation_file.clone();
+    if std::path::Path::new(path).exists() {
+        match super::render::ViolationFile::load(path) {
+            Ok(previous) => {
+                for (file, by_rule) in previous.violations {
+                    if !reviewed_files.contains(&file) {
+                        violation_file.violations.entry(file).or_insert(by_rule);
+                    }
+                }
+                for (rule, tip) in previous.tips {
+                    violation_file.tips.entry(rule).or_insert(tip);
+                }
+            }
+            Err(e) => warn!("Replacing unreadable previous results: {}", e),
+        }
+    }
+    if let Some(parent) = std::path::Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&violation_file).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Load run summaries, skipping malformed lines
+pub fn load(path: &str) -> Result<Vec<HistoryEntry>, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Per-rule totals across runs
+#[derive(Debug, Default, PartialEq)]
+struct RuleStats {
+    violations: usize,
+    runs_reviewed: usize,
+    runs_fired: usize,
+}
+
+/// Format trends across runs: most-violated rules, average cost, and rules that never fire
+pub fn format_stats(entries: &[HistoryEntry]) -> String {
+    let (Some(first), Some(last)) = (entries.first(), entries.last()) else {
+        return "No runs recorded".to_string();
+    };
+
+    let mut by_rule: BTreeMap<&str, RuleStats> = BTreeMap::new();
+    for entry in entries {
+        for (rule, count) in &entry.violations {
+            let stats = by_rule.entry(rule).or_default();
+            stats.violations += count;
+            stats.runs_reviewed += 1;
+            if *count > 0 {
+                stats.runs_fired += 1;
+            }
+        }
+    }
+
+    let total_violations: usize = by_rule.values().map(|s| s.violations).sum();
+    let costs: Vec<f64> = entries.iter().filter_map(|e| e.cost).collect();
+
+    let mut output = format!(
+        "Runs: {} ({} to {})\n",
+        entries.len(),
+        first.timestamp.format("%Y-%m-%d"),
+        last.timestamp.format("%Y-%m-%d")
+    );
+    output.push_str(&format!(
+        "Average violations per run: {:.1}\n",
+        total_violations as f64 / entries.len() as f64
+    ));
+    if costs.is_empty() {
+        output.push_str("Average cost per run: unknown\n");
+    } else {
+        output.push_str(&format!(
+            "Average cost per run: ${:.4} ({} runs with cost)\n",
+            costs.iter().sum::<f64>() / costs.len() as f64,
+            costs.len()
+        ));
+    }
+
+    let mut fired: Vec<_> = by_rule.iter().filter(|(_, s)| s.violations > 0).collect();
+    fired.sort_by(|a, b| b.1.violations.cmp(&a.1.violations).then(a.0.cmp(b.0)));
+    if !fired.is_empty() {
+        output.push_str("\nMost violated rules:\n");
+        let width = fired.iter().map(|(r, _)| r.len()).max().unwrap_or(0);
+        for (rule, stats) in &fired {
+            output.push_str(&format!(
+                "  {:<width$}  {} violations in {}/{} runs\n",
+                rule,
+                stats.violations,
+                stats.runs_fired,
+                stats.runs_reviewed,
+                width = width
+            ));
+        }
+    }
+
+    let never_fired: Vec<_> = by_rule.iter().filter(|(_, s)| s.violations == 0).collect();
+    if !never_fired.is_empty() {
+        output.push_str("\nRules that never fired (candidates for removal):\n");
+        for (rule, stats) in never_fired {
+            output.push_str(&format!(
+                "  {} (reviewed in {} runs)\n",
+                rule, stats.runs_reviewed
+            ));
+        }
+    }
+
+    output.trim_end().to_string()
+}
+
+/// Repeat reviews of identical diffs by a rule, and how many changed its findings
+#[derive(Debug, Default, PartialEq)]
+struct Flakiness {
+    repeats: usize,
+    flips: usize,
+}
+
+/// Per rule, compare each run with the rule's previous run: when both reviewed identical
+/// inputs, any difference in findings means the rule is unstable
+fn flakiness(entries: &[HistoryEntry]) -> BTreeMap<&str, Flakiness> {
+    let mut previous: BTreeMap<&str, (&str, Option<&str>)> = BTreeMap::new();
+    let mut by_rule: BTreeMap<&str, Flakiness> = BTreeMap::new();
+    for entry in entries {
+        for (rule, input) in &entry.inputs {
+            // Cached results repeat the earlier findings without reviewing again
+            if entry.cached.contains(rule) {
+                continue;
+            }
+            let findings = entry.findings.get(rule).map(String::as_str);
+            if let Some((last_input, last_findings)) =
+                previous.insert(rule, (input.as_str(), findings))
+                && last_input == input
+            {
+                let stats = by_rule.entry(rule).or_default();
+                stats.repeats += 1;
+                if last_findings != findings {
+                    stats.flips += 1;
+                }
+            }
+        }
+    }
+    by_rule
+}
+
+/// Format rules whose findings flipped between consecutive runs on identical diffs
+pub fn format_flaky(entries: &[HistoryEntry]) -> String {
+    let by_rule = flakiness(entries);
+    let repeats: usize = by_rule.values().map(|s| s.repeats).sum();
+    let mut flaky: Vec<_> = by_rule.iter().filter(|(_, s)| s.flips > 0).collect();
+    if flaky.is_empty() {
+        return format!(
+            "No flaky rules in {} runs ({} repeat reviews of identical diffs)",
+            entries.len(),
+            repeats
+        );
+    }
+    flaky.sort_by(|a, b| {
+        let rate = |s: &Flakiness| s.flips as f64 / s.repeats as f64;
+        rate(b.1).total_cmp(&rate(a.1)).then(a.0.cmp(b.0))
+    });
+
+    let mut output = "Flaky rules (findings changed on identical diffs):\n".to_string();
+    let width = flaky.iter().map(|(r, _)| r.len()).max().unwrap_or(0);
+    for (rule, stats) in &flaky {
+        output.push_str(&format!(
+            "  {:<width$}  {}/{} repeat reviews differed\n",
+            rule,
+            stats.flips,
+            stats.repeats,
+            width = width
+        ));
+    }
+    output.push_str(
+        "\nPin sampling in the [llm] body (e.g. `body = { temperature = 0, seed = 42 }`), \
+         or tighten the instructions of these rules so they leave less to judgment.",
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(violations: &[(&str, usize)], cost: Option<f64>) -> HistoryEntry {
+        HistoryEntry {
+            version: env!("CARGO_PKG_VERSION").into(),
+            timestamp: chrono::Utc::now(),
+            base: "HEAD^".into(),
+            files: 1,
+            tasks: 1,
+            failed_tasks: 0,
+            elapsed_secs: 1.0,
+            violations: violations
+                .iter()
+                .map(|(r, c)| (r.to_string(), *c))
+                .collect(),
+            cost,
+            inputs: BTreeMap::new(),
+            findings: BTreeMap::new(),
+            cached: BTreeSet::new(),
+        }
+    }
+
+    fn reviewed(runs: &[(&str, &str, &str)]) -> HistoryEntry {
+        HistoryEntry {
+            inputs: runs
+                .iter()
+                .map(|(rule, input, _)| (rule.to_string(), input.to_string()))
+                .collect(),
+            findings: runs
+                .iter()
+                .map(|(rule, _, findings)| (rule.to_string(), findings.to_string()))
+                .collect(),
+            ..entry(&[], None)
+        }
+    }
+
+    #[test]
+    fn test_format_stats_empty() {
+        assert_eq!(format_stats(&[]), "No runs recorded");
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let entries = vec![
+            entry(&[("A", 2), ("B", 0), ("C", 1)], Some(0.02)),
+            entry(&[("A", 1), ("B", 0)], None),
+        ];
+        let stats = format_stats(&entries);
+        assert!(stats.contains("Runs: 2"));
+        assert!(stats.contains("Average violations per run: 2.0"));
+        assert!(stats.contains("Average cost per run: $0.0200 (1 runs with cost)"));
+        assert!(stats.find("A  3 violations in 2/2 runs").unwrap() < stats.find("C  1").unwrap());
+        assert!(stats.contains("  B (reviewed in 2 runs)"));
+    }
+
+    #[test]
+    fn test_format_flaky() {
+        let entries = vec![
+            reviewed(&[("A", "d1", "f1"), ("B", "d1", "f1")]),
+            reviewed(&[("A", "d1", "f2"), ("B", "d1", "f1")]),
+            reviewed(&[("A", "d2", "f3")]),
+            reviewed(&[("A", "d2", "f3"), ("B", "d1", "f2")]),
+        ];
+        let by_rule = flakiness(&entries);
+        assert_eq!(
+            by_rule["A"],
+            Flakiness {
+                repeats: 2,
+                flips: 1
+            }
+        );
+        assert_eq!(
+            by_rule["B"],
+            Flakiness {
+                repeats: 2,
+                flips: 1
+            }
+        );
+        let output = format_flaky(&entries);
+        assert!(output.contains("  A  1/2 repeat reviews differed"));
+        assert!(output.contains("seed"));
+        assert_eq!(
+            format_flaky(&entries[..1]),
+            "No flaky rules in 1 runs (0 repeat reviews of identical diffs)"
+        );
+    }
+
+    #[test]
+    fn test_flakiness_skips_cached_rules() {
+        let cached = HistoryEntry {
+            cached: BTreeSet::from(["A".to_string()]),
+            ..reviewed(&[("A", "d1", "f1"), ("B", "d1", "f2")])
+        };
+        let entries = vec![
+            reviewed(&[("A", "d1", "f2"), ("B", "d1", "f1")]),
+            cached,
+            reviewed(&[("A", "d1", "f2")]),
+        ];
+        let by_rule = flakiness(&entries);
+        assert_eq!(
+            by_rule["A"],
+            Flakiness {
+                repeats: 1,
+                flips: 0
+            }
+        );
+        assert_eq!(by_rule["B"].flips, 1);
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir =
+            crate::util::temp::TempDir::new(&format!("firekeeper-history-{}", std::process::id()));
+        let path = dir.path().join("history.jsonl");
+        let path = path.to_str().unwrap();
+        append(path, &entry(&[("A", 1)], None)).unwrap();
+        append(path, &entry(&[("A", 0)], None)).unwrap();
+        let entries = load(path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].violations["A"], 1);
+    }
+
+    #[test]
+    fn test_save_results_keeps_unreviewed_files() {
+        use crate::review::render::ViolationFile;
+        let dir =
+            crate::util::temp::TempDir::new(&format!("firekeeper-previous-{}", std::process::id()));
+        let path = dir.path().join("previous-results.json");
+        let path = path.to_str().unwrap();
+        let results = |files: &[&str]| -> ViolationFile {
+            let violations: serde_json::Map<_, _> = files
+                .iter()
+                .map(|file| (file.to_string(), serde_json::json!({ "A": [] })))
+                .collect();
+            serde_json::from_value(serde_json::json!({
+                "version": "0.0.0",
+                "violations": violations,
+                "tips": {},
+            }))
+            .unwrap()
+        };
+        save_results(path, &results(&["a.rs", "b.rs"]), &[]).unwrap();
+        // b.rs was reviewed again and is clean now; a.rs was not reviewed
+        save_results(path, &results(&["c.rs"]), &["b.rs".into(), "c.rs".into()]).unwrap();
+        let saved = ViolationFile::load(path).unwrap();
+        assert_eq!(
+            saved.violations.keys().collect::<Vec<_>>(),
+            ["a.rs", "c.rs"]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+/// Fixed strings of violation reports in one language
+#[derive(Debug, PartialEq)]
+pub struct Strings {
+    /// Language name used to instruct agents, in English
+    pub name: &'static str,
+    pub no_violations: &'static str,
+    /// Heading for a file's violations, `{}` is replaced with the path
+    pub violations_in: &'static str,
+    pub rule: &'static str,
+    pub lines: &'static str,
+    pub tip: &'static str,
+    pub error: &'static str,
+    pub warning: &'static str,
+    pub severity: &'static str,
+    pub files: &'static str,
+    pub violations: &'static str,
+    /// Files skipped by non-blocking rules in a sampled changeset
+    pub not_reviewed: &'static str,
+    /// Heading for the files not reviewed, with their reasons
+    pub skipped: &'static str,
+    /// Files left out by `--include`/`--exclude`
+    pub skipped_excluded: &'static str,
+    /// Files no rule's scope matches
+    pub skipped_out_of_scope: &'static str,
+    /// Files matching `review.vendored` or marked `linguist-vendored`
+    pub skipped_vendored: &'static str,
+    /// Lock and generated files, reviewed without their diffs
+    pub skipped_diff_omitted: &'static str,
+    pub owner: &'static str,
+    /// Heading for violations in files without owners
+    pub unowned: &'static str,
+    /// Heading for the tips aggregated at the end of the console report
+    pub how_to_fix: &'static str,
+    /// Summary of violations hidden by `max_violations`, `{}` is replaced with the count
+    pub more_similar: &'static str,
+    /// Heading for the rules run with `--quarantine` or `quarantined = true`
+    pub quarantined: &'static str,
+    /// Notice of failed tasks, `{}` is replaced with the count
+    pub tasks_failed: &'static str,
+    /// Notice ending a job summary cut at GitHub's size limit
+    pub summary_truncated: &'static str,
+}
+
+pub const EN: Strings = Strings {
+    name: "English",
+    no_violations: "No violations found",
+    violations_in: "Violations in {}",
+    rule: "Rule",
+    lines: "Lines",
+    tip: "Tip",
+    error: "error",
+    warning: "warning",
+    severity: "Severity",
+    files: "Files",
+    violations: "Violations",
+    not_reviewed: "Not reviewed by non-blocking rules (sampled changeset)",
+    skipped: "Skipped",
+    skipped_excluded: "excluded by --include/--exclude",
+    skipped_out_of_scope: "no rule in scope",
+    skipped_vendored: "vendored or third-party code",
+    skipped_diff_omitted: "diff omitted (lock or generated file)",
+    owner: "Owner",
+    unowned: "Unowned",
+    how_to_fix: "How to fix",
+    more_similar: "and {} more similar issues",
+    quarantined: "Quarantined rules (reported without blocking)",
+    tasks_failed: "{} task(s) failed, results are incomplete",
+    summary_truncated: "Summary truncated at the 1 MiB job summary limit; see the workflow logs for all violations",
+};
+
+const JA: Strings = Strings {
+    name: "Japanese",
+    no_violations: "違反は見つかりませんでした",
+    violations_in: "{} の違反",
+    rule: "ルール",
+    lines: "行",
+    tip: "ヒント",
+    error: "エラー",
+    warning: "警告",
+    severity: "重大度",
+    files: "ファイル",
+    violations: "違反",
+    not_reviewed: "ブロッキングでないルールで未レビュー（サンプリングされた変更）",
+    skipped: "スキップ",
+    skipped_excluded: "--include/--exclude で除外",
+    skipped_out_of_scope: "対象のルールなし",
+    skipped_vendored: "ベンダーまたはサードパーティのコード",
+    skipped_diff_omitted: "差分を省略（ロックファイルまたは生成ファイル）",
+    owner: "担当者",
+    unowned: "担当者なし",
+    how_to_fix: "修正方法",
+    more_similar: "他 {} 件の類似した問題",
+    quarantined: "隔離中のルール（ブロックせずに報告）",
+    tasks_failed: "{} 件のタスクが失敗したため、結果は不完全です",
+    summary_truncated: "ジョブサマリーの上限 1 MiB で切り詰めました。すべての違反はワークフローのログを参照してください",
+};
+
+const ZH: Strings = Strings {
+    name: "Simplified Chinese",
+    no_violations: "未发现违规",
+    violations_in: "{} 中的违规",
+    rule: "规则",
+    lines: "行",
+    tip: "提示",
+    error: "错误",
+    warning: "警告",
+    severity: "严重性",
+    files: "文件",
+    violations: "违规",
+    not_reviewed: "非阻塞规则未审查（变更已抽样）",
+    skipped: "已跳过",
+    skipped_excluded: "被 --include/--exclude 排除",
+    skipped_out_of_scope: "没有适用的规则",
+    skipped_vendored: "第三方或内置依赖代码",
+    skipped_diff_omitted: "已省略差异（锁文件或生成的文件）",
+    owner: "负责人",
+    unowned: "无负责人",
+    how_to_fix: "修复方法",
+    more_similar: "以及另外 {} 个类似问题",
+    quarantined: "隔离中的规则（仅报告，不阻塞）",
+    tasks_failed: "{} 个任务失败，结果不完整",
+    summary_truncated: "摘要已在作业摘要的 1 MiB 上限处截断；所有违规请查看工作流日志",
+};
+
+const KO: Strings = Strings {
+    name: "Korean",
+    no_violations: "위반 사항이 없습니다",
+    violations_in: "{}의 위반 사항",
+    rule: "규칙",
+    lines: "줄",
+    tip: "팁",
+    error: "오류",
+    warning: "경고",
+    severity: "심각도",
+    files: "파일",
+    violations: "위반",
+    not_reviewed: "비차단 규칙으로 검토되지 않음 (샘플링된 변경 사항)",
+    skipped: "건너뜀",
+    skipped_excluded: "--include/--exclude로 제외됨",
+    skipped_out_of_scope: "적용되는 규칙 없음",
+    skipped_vendored: "벤더 또는 서드파티 코드",
+    skipped_diff_omitted: "diff 생략 (잠금 파일 또는 생성된 파일)",
+    owner: "담당자",
+    unowned: "담당자 없음",
+    how_to_fix: "수정 방법",
+    more_similar: "외 {}개의 유사한 문제",
+    quarantined: "격리된 규칙 (차단 없이 보고)",
+    tasks_failed: "{}개의 작업이 실패하여 결과가 불완전합니다",
+    summary_truncated: "작업 요약의 1 MiB 제한에서 잘렸습니다. 모든 위반 사항은 워크플로 로그를 확인하세요",
+};
+
+const ES: Strings = Strings {
+    name: "Spanish",
+    no_violations: "No se encontraron infracciones",
+    violations_in: "Infracciones en {}",
+    rule: "Regla",
+    lines: "Líneas",
+    tip: "Consejo",
+    error: "error",
+    warning: "advertencia",
+    severity: "Gravedad",
+    files: "Archivos",
+    violations: "Infracciones",
+    not_reviewed: "No revisado por reglas no bloqueantes (cambios muestreados)",
+    skipped: "Omitidos",
+    skipped_excluded: "excluido por --include/--exclude",
+    skipped_out_of_scope: "ninguna regla aplicable",
+    skipped_vendored: "código de terceros o incluido (vendored)",
+    skipped_diff_omitted: "diff omitido (archivo de bloqueo o generado)",
+    owner: "Responsable",
+    unowned: "Sin responsable",
+    how_to_fix: "Cómo corregir",
+    more_similar: "y {} problemas similares más",
+    quarantined: "Reglas en cuarentena (se informan sin bloquear)",
+    tasks_failed: "{} tarea(s) fallaron, los resultados están incompletos",
+    summary_truncated: "Resumen truncado en el límite de 1 MiB de los resúmenes de trabajos; consulta los registros del flujo de trabajo para ver todas las infracciones",
+};
+
+const DE: Strings = Strings {
+    name: "German",
+    no_violations: "Keine Verstöße gefunden",
+    violations_in: "Verstöße in {}",
+    rule: "Regel",
+    lines: "Zeilen",
+    tip: "Tipp",
+    error: "Fehler",
+    warning: "Warnung",
+    severity: "Schweregrad",
+    files: "Dateien",
+    violations: "Verstöße",
+    not_reviewed: "Nicht von nicht blockierenden Regeln geprüft (Stichprobe der Änderungen)",
+    skipped: "Übersprungen",
+    skipped_excluded: "durch --include/--exclude ausgeschlossen",
+    skipped_out_of_scope: "keine zutreffende Regel",
+    skipped_vendored: "Fremdcode oder eingebundene Abhängigkeit (vendored)",
+    skipped_diff_omitted: "Diff ausgelassen (Lock- oder generierte Datei)",
+    owner: "Verantwortlich",
+    unowned: "Ohne Verantwortliche",
+    how_to_fix: "So beheben",
+    more_similar: "und {} weitere ähnliche Probleme",
+    quarantined: "Regeln in Quarantäne (gemeldet, ohne zu blockieren)",
+    tasks_failed: "{} Aufgabe(n) fehlgeschlagen, die Ergebnisse sind unvollständig",
+    summary_truncated: "Zusammenfassung am Limit von 1 MiB für Job-Zusammenfassungen gekürzt; alle Verstöße stehen in den Workflow-Logs",
+};
+
+const FR: Strings = Strings {
+    name: "French",
+    no_violations: "Aucune violation trouvée",
+    violations_in: "Violations dans {}",
+    rule: "Règle",
+    lines: "Lignes",
+    tip: "Conseil",
+    error: "erreur",
+    warning: "avertissement",
+    severity: "Gravité",
+    files: "Fichiers",
+    violations: "Violations",
+    not_reviewed: "Non examiné par les règles non bloquantes (modifications échantillonnées)",
+    skipped: "Ignorés",
+    skipped_excluded: "exclu par --include/--exclude",
+    skipped_out_of_scope: "aucune règle applicable",
+    skipped_vendored: "code tiers ou embarqué (vendored)",
+    skipped_diff_omitted: "diff omis (fichier de verrouillage ou généré)",
+    owner: "Responsable",
+    unowned: "Sans responsable",
+    how_to_fix: "Comment corriger",
+    more_similar: "et {} autres problèmes similaires",
+    quarantined: "Règles en quarantaine (signalées sans bloquer)",
+    tasks_failed: "{} tâche(s) en échec, les résultats sont incomplets",
+    summary_truncated: "Résumé tronqué à la limite de 1 Mio des résumés de job ; consultez les journaux du workflow pour toutes les violations",
+};
+
+/// Report strings for a language code such as `ja` or `zh-CN`, falling back to English
+pub fn strings(language: Option<&str>) -> &'static Strings {
+    let primary = language
+        .and_then(|l| l.split(['-', '_']).next())
+        .map(str::to_ascii_lowercase);
+    match primary.as_deref() {
+        Some("ja") => &JA,
+        Some("zh") => &ZH,
+        Some("ko") => &KO,
+        Some("es") => &ES,
+        Some("de") => &DE,
+        Some("fr") => &FR,
+        _ => &EN,
+    }
+}
+
+/// Instruction appended to the system prompt so agents write violation details in `language`.
+/// Codes without localized report strings are passed to the agent as-is.
+pub fn agent_instruction(language: &str) -> String {
+    let strings = strings(Some(language));
+    let name = if strings == &EN && !language.to_ascii_lowercase().starts_with("en") {
+        language
+    } else {
+        strings.name
+    };
+    format!(
+        "Write violation details in {}, keeping code identifiers, file paths, and quoted code unchanged.",
+        name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(strings(None), &EN);
+        assert_eq!(strings(Some("ja")), &JA);
+        assert_eq!(strings(Some("zh-CN")), &ZH);
+        assert_eq!(strings(Some("pt-BR")), &EN);
+    }
+
+    #[test]
+    fn test_agent_instruction() {
+        assert!(agent_instruction("ja").contains("in Japanese,"));
+        assert!(agent_instruction("pt-BR").contains("in pt-BR,"));
+    }
+}
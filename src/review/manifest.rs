@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// File name of the manifest, written next to the first results file
+pub const MANIFEST_FILE: &str = "run.json";
+
+/// Header names whose values are replaced in the recorded config
+const SECRET_HEADER_HINTS: &[&str] = &["auth", "key", "token", "secret", "cookie"];
+
+/// Keys whose values are replaced wherever they appear in the recorded config: notification
+/// URLs embed their credentials, `llm.body` may carry keys, and token commands may inline them
+const SECRET_KEYS: &[&str] = &["url", "body"];
+
+/// Prefix of keys whose values are replaced, e.g. `token_command`
+const SECRET_KEY_PREFIX: &str = "token";
+
+const REDACTED: &str = "<redacted>";
+
+/// Record of a review run, to reproduce and audit its results
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RunManifest {
+    pub version: String,
+    /// `git --version`, if available
+    pub git_version: Option<String>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Config after profiles and overrides, with secrets redacted
+    pub config: Value,
+    pub base: String,
+    /// Commit the changes were compared against, if any
+    pub base_sha: Option<String>,
+    pub head_sha: Option<String>,
+    pub changed_files: Vec<String>,
+    /// Files left after --include/--exclude
+    pub review_files: Vec<String>,
+    pub tasks: Vec<PlannedTask>,
+    pub outputs: Vec<String>,
+    pub trace: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<String>,
+    pub sarif: Option<String>,
+    pub badge: Option<String>,
+}
+
+/// Task in the run's plan
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlannedTask {
+    pub rule: String,
+    pub rule_id: String,
+    pub files: Vec<String>,
+}
+
+/// Manifest path next to the first results file, trace, SARIF log or badge, if any is written
+pub fn manifest_path<'a>(artifacts: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let first = artifacts.into_iter().next()?;
+    let dir = Path::new(first).parent().unwrap_or(Path::new(""));
+    Some(dir.join(MANIFEST_FILE).to_string_lossy().to_string())
+}
+
+/// Replace the values of secret-looking headers in every `headers` table, and of every
+/// `url`, `body` and `token*` key
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if value.is_null() || value.as_object().is_some_and(|map| map.is_empty()) {
+                    continue;
+                }
+                if SECRET_KEYS.contains(&key.as_str()) || key.starts_with(SECRET_KEY_PREFIX) {
+                    *value = Value::String(REDACTED.to_string());
+                } else if key == "headers"
+                    && let Value::Object(headers) = value
+                {
+                    for (name, value) in headers.iter_mut() {
+                        let name = name.to_lowercase();
+                        if SECRET_HEADER_HINTS.iter().any(|hint| name.contains(hint)) {
+                            *value = Value::String(REDACTED.to_string());
+                        }
+                    }
+                } else {
+                    redact_secrets(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+impl RunManifest {
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Failed to write run manifest {}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(
+            manifest_path(["report/results.json", "trace.json"]).as_deref(),
+            Some("report/run.json")
+        );
+        assert_eq!(manifest_path(["results.md"]).as_deref(), Some("run.json"));
+        assert_eq!(manifest_path([]), None);
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        let mut config = json!({
+            "llm": {
+                "base_url": "https://openrouter.ai/api/v1",
+                "headers": {"Authorization": "Bearer sk-1", "X-Title": "firekeeper.ai"},
+                "body": {"api_key": "sk-3"},
+            },
+            "profiles": {"ci": {"llm": {"headers": {"X-Api-Key": "sk-2"}}}},
+            "publish": {
+                "token_command": "echo ghp-1",
+                "slack": {"url": "https://hooks.slack.com/services/T0/B0/secret"},
+                "teams": {"url": null},
+            },
+        });
+        redact_secrets(&mut config);
+        assert_eq!(
+            config,
+            json!({
+                "llm": {
+                    "base_url": "https://openrouter.ai/api/v1",
+                    "headers": {"Authorization": "<redacted>", "X-Title": "firekeeper.ai"},
+                    "body": "<redacted>",
+                },
+                "profiles": {"ci": {"llm": {"headers": {"X-Api-Key": "<redacted>"}}}},
+                "publish": {
+                    "token_command": "<redacted>",
+                    "slack": {"url": "<redacted>"},
+                    "teams": {"url": null},
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use super::usage::TokenUsage;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{debug, info, warn};
+
+/// Upper bounds of the latency histogram buckets (seconds)
+const LATENCY_BUCKETS: [f64; 9] = [1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Bytes read of a scrape request, enough for its request line
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Seconds to wait for a scrape request before dropping the connection, so idle clients
+/// do not hold connections open
+const READ_TIMEOUT_SECS: u64 = 5;
+
+/// Latency histogram with cumulative buckets, as Prometheus expects
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, secs: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += secs;
+    }
+
+    fn render(&self, output: &mut String, name: &str, help: &str) {
+        output.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} histogram\n",
+            name, help, name
+        ));
+        for (count, bound) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            output.push_str(&format!("{}_bucket{{le=\"{}\"}} {}\n", name, bound, count));
+        }
+        output.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, self.count));
+        output.push_str(&format!(
+            "{}_sum {}\n{}_count {}\n",
+            name, self.sum, name, self.count
+        ));
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    reviews: u64,
+    tasks: u64,
+    provider_errors: u64,
+    violations_by_rule: BTreeMap<String, u64>,
+    usage: TokenUsage,
+    review_latency: Histogram,
+    task_latency: Histogram,
+}
+
+/// Counters of a long-lived process, rendered in the Prometheus text format
+#[derive(Clone, Default)]
+pub struct Metrics {
+    state: Arc<Mutex<State>>,
+}
+
+impl Metrics {
+    /// Record a completed review request
+    pub fn review(&self, elapsed_secs: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.reviews += 1;
+        state.review_latency.observe(elapsed_secs);
+    }
+
+    /// Record a completed task with its violations and token usage
+    pub fn task(&self, rule: &str, violations: usize, usage: &TokenUsage, elapsed_secs: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.tasks += 1;
+        *state
+            .violations_by_rule
+            .entry(rule.to_string())
+            .or_default() += violations as u64;
+        state.usage.add(usage);
+        state.task_latency.observe(elapsed_secs);
+    }
+
+    /// Record a task that failed on an LLM provider error
+    pub fn provider_error(&self) {
+        self.state.lock().unwrap().provider_errors += 1;
+    }
+
+    /// Metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut output = String::new();
+        let mut counter = |name: &str, help: &str, samples: &[(String, u64)]| {
+            output.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} counter\n",
+                name, help, name
+            ));
+            for (labels, value) in samples {
+                output.push_str(&format!("{}{} {}\n", name, labels, value));
+            }
+        };
+        counter(
+            "firekeeper_reviews_total",
+            "Review requests completed",
+            &[(String::new(), state.reviews)],
+        );
+        counter(
+            "firekeeper_tasks_total",
+            "Review tasks completed",
+            &[(String::new(), state.tasks)],
+        );
+        counter(
+            "firekeeper_provider_errors_total",
+            "Review tasks failed on an LLM provider error",
+            &[(String::new(), state.provider_errors)],
+        );
+        let violations: Vec<(String, u64)> = state
+            .violations_by_rule
+            .iter()
+            .map(|(rule, count)| (format!("{{rule=\"{}\"}}", escape_label(rule)), *count))
+            .collect();
+        counter(
+            "firekeeper_violations_total",
+            "Violations found per rule",
+            &violations,
+        );
+        let usage = state.usage;
+        counter(
+            "firekeeper_llm_requests_total",
+            "LLM requests made",
+            &[(String::new(), usage.requests as u64)],
+        );
+        counter(
+            "firekeeper_tokens_total",
+            "Tokens by kind, as reported by the provider or estimated",
+            &[
+                ("{kind=\"prompt\"}".into(), usage.prompt_tokens as u64),
+                (
+                    "{kind=\"completion\"}".into(),
+                    usage.completion_tokens as u64,
+                ),
+            ],
+        );
+        state.review_latency.render(
+            &mut output,
+            "firekeeper_review_duration_seconds",
+            "Duration of review requests",
+        );
+        state.task_latency.render(
+            &mut output,
+            "firekeeper_task_duration_seconds",
+            "Duration of review tasks",
+        );
+        output
+    }
+
+    /// Serve `GET /metrics` on `addr` until the process exits
+    pub async fn listen(self, addr: &str) -> Result<(), String> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| format!("Failed to bind metrics endpoint to {}: {}", addr, e))?;
+        info!("Serving metrics on http://{}/metrics", addr);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, peer) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        warn!("Failed to accept metrics connection: {}", e);
+                        continue;
+                    }
+                };
+                debug!("Metrics request from {}", peer);
+                let metrics = self.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0; MAX_REQUEST_BYTES];
+                    let read = tokio::time::timeout(
+                        std::time::Duration::from_secs(READ_TIMEOUT_SECS),
+                        stream.read(&mut buf),
+                    );
+                    let Ok(n) = read.await else {
+                        debug!("Metrics request from {} timed out", peer);
+                        return;
+                    };
+                    let n = n.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]);
+                    let response = http_response(&request, &metrics);
+                    if let Err(e) = stream.write_all(response.as_bytes()).await {
+                        debug!("Failed to write metrics response: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+}
+
+/// HTTP response to a request: the metrics for `GET /metrics`, 404 otherwise
+fn http_response(request: &str, metrics: &Metrics) -> String {
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            metrics.render(),
+        ),
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// Escape a label value: backslashes, double quotes, and newlines
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = Metrics::default();
+        let usage = TokenUsage {
+            requests: 2,
+            prompt_tokens: 100,
+            tool_result_tokens: 10,
+            completion_tokens: 20,
+            ..Default::default()
+        };
+        metrics.task("No \"Magic\" Numbers", 3, &usage, 4.0);
+        metrics.provider_error();
+        metrics.review(12.0);
+
+        let output = metrics.render();
+        assert!(output.contains("firekeeper_reviews_total 1\n"));
+        assert!(output.contains("firekeeper_provider_errors_total 1\n"));
+        assert!(
+            output.contains("firekeeper_violations_total{rule=\"No \\\"Magic\\\" Numbers\"} 3\n")
+        );
+        assert!(output.contains("firekeeper_tokens_total{kind=\"prompt\"} 100\n"));
+        assert!(output.contains("firekeeper_task_duration_seconds_bucket{le=\"2.5\"} 0\n"));
+        assert!(output.contains("firekeeper_task_duration_seconds_bucket{le=\"5\"} 1\n"));
+        assert!(output.contains("firekeeper_review_duration_seconds_count 1\n"));
+
+        let response = http_response("GET /metrics HTTP/1.1\r\n\r\n", &metrics);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(http_response("GET / HTTP/1.1\r\n", &metrics).starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1,3 +1,36 @@
+pub mod audit;
+pub mod bench;
+pub mod bundle;
+pub mod cargo;
+pub mod compaction;
+pub mod consensus;
+pub mod explain;
+pub mod filters;
+pub mod fixture;
+pub mod generated;
+pub mod history;
+pub mod locale;
+pub mod manifest;
+pub mod metrics;
 pub mod orchestrator;
+pub mod overview;
+pub mod owners;
+pub mod plan;
+pub mod queue;
+pub mod release;
 pub mod render;
+pub mod resource;
+pub mod sampling;
+pub mod sarif;
+pub mod scope;
+pub mod serve;
+pub mod suggest;
+pub mod task_cache;
+pub mod terminal;
+pub mod trace_diff;
+pub mod triage;
+pub mod usage;
+pub mod validate;
+pub mod watch;
 pub mod worker;
+pub mod workspace;
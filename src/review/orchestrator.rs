@@ -1,44 +1,108 @@
-use super::{render, worker};
+use super::resource::ResourceLoader;
+use super::scope::{build_globset, filter_files_by_scope, scope_directories};
+use super::{
+    audit, bundle, consensus, filters, history, locale, manifest, overview, owners, plan, queue,
+    release, render, sampling, sarif, task_cache, terminal, triage, usage, worker,
+};
+use crate::config::SamplingConfig;
+use crate::llm::ProviderProbe;
 use crate::rule::body::RuleBody;
+use crate::rule::tip::Tip;
+use crate::telemetry::{self, TelemetryConfig};
+use crate::tokens::Tokenizer;
+use crate::tool::read::Snapshot;
+use crate::types::Violation;
 use crate::util;
 use futures::future::join_all;
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{Glob, GlobSetBuilder};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{debug, error, info, trace, warn};
 
 const EXIT_FAILURE: i32 = 1;
 
+/// Exit code of `firekeeper ci` when tasks failed or did not finish
+const EXIT_INCOMPLETE: i32 = 2;
+
+/// Directories listed in the summary of a rule's tasks
+const MAX_SUMMARY_DIRECTORIES: usize = 10;
+
+/// Share of the conversation budget (`max_history_tokens`) the diffs of one task may fill,
+/// leaving room for the prompt, resources and tool results
+const TASK_DIFF_PERCENT: usize = 50;
+
+/// Parallel workers per CPU; workers mostly wait on the LLM, so several share a core
+const WORKERS_PER_CPU: usize = 4;
+
+/// Assumed LLM requests per minute from one busy worker (an agent turn every ~10 seconds)
+const REQUESTS_PER_WORKER_PER_MINUTE: u32 = 6;
+
+/// Directory the results and traces collected so far are saved to when an output cannot
+/// be written or tasks failed
+pub const LAST_RUN_DIR: &str = ".firekeeper/last-run";
+
+/// Worker result tagged with the index of its task, and the tokens its agents used,
+/// whether or not they failed
+type TaskResult = (
+    usize,
+    Result<worker::WorkerResult, Box<dyn std::error::Error>>,
+    usage::TokenUsage,
+);
+
 /// Orchestrate and run code review tasks
 ///
 /// This function coordinates the entire review process:
-/// - Resolves the base commit for comparison
 /// - Gets changed files, commit messages, and generates diffs
 /// - Splits work into tasks based on rules and file scopes
 /// - Executes workers in parallel (with optional concurrency limit)
 /// - Collects and outputs results with worker_id, all_files, and commits
 /// - Optionally writes trace of agent conversations to file
+#[allow(clippy::too_many_arguments)]
 pub async fn orchestrate_and_run(
     rules: &[RuleBody],
-    diff_base: &str,
+    base: util::Base,
+    include: &[String],
+    exclude: &[String],
     max_files_per_task: usize,
     max_parallel_workers: Option<usize>,
-    timeout_secs: u64,
-    base_url: &str,
-    api_key: &str,
-    model: &str,
-    headers: &HashMap<String, String>,
-    body: &Value,
+    settings: &worker::WorkerSettings,
+    prices: &HashMap<String, usage::ModelPrice>,
+    max_total_tokens: Option<usize>,
     dry_run: bool,
-    output: Option<&str>,
+    outputs: &[String],
     trace: Option<&str>,
+    bundle: Option<&str>,
+    badge: Option<&str>,
+    sarif: Option<&str>,
+    annotations: bool,
+    pr_comment: Option<(&crate::github::Api, &crate::github::PullRequest)>,
     config_path: &str,
-    global_resources: &[String],
-    allowed_shell_commands: &[String],
+    suppressions_path: &str,
+    resolved_config: &Value,
+    resources: &ResourceLoader,
+    max_violations: Option<usize>,
+    telemetry: &TelemetryConfig,
+    sampling: &SamplingConfig,
+    vendored: &[String],
+    repo_overview: bool,
+    diff_style: util::DiffStyle,
+    diff_line_numbers: bool,
+    retry_from: Option<&render::ViolationFile>,
+    task_plan: Option<&plan::TaskPlan>,
+    queue_path: Option<&str>,
+    release: Option<&release::ReleaseAudit>,
+    cache: Option<&task_cache::TaskCache>,
+    owners: &owners::Owners,
+    filters: &filters::FiltersConfig,
+    group_by_owner: bool,
+    fail_on_incomplete: bool,
+    audit_config: &audit::AuditConfig,
 ) {
-    let base = util::Base::parse(diff_base);
+    let start_time = std::time::Instant::now();
+    let model = settings.model.as_str();
+    let language = settings.language.as_deref();
     debug!("Resolved base: {:?}", base);
 
     debug!("Getting changed files for base");
@@ -47,7 +111,11 @@ pub async fn orchestrate_and_run(
     trace!("Changed files: {:?}", changed_files);
 
     debug!("Generating diffs for {} files", changed_files.len());
-    let diffs = util::get_diffs(&base, &changed_files);
+    let diffs = util::restyle_diffs(
+        util::get_diffs(&base, &changed_files),
+        diff_style,
+        diff_line_numbers,
+    );
 
     debug!("Getting commit messages for base");
     let commit_messages = util::get_commit_messages(&base);
@@ -56,18 +124,276 @@ pub async fn orchestrate_and_run(
         "Orchestrating tasks with max_files_per_task: {}",
         max_files_per_task
     );
-    let tasks = orchestrate(rules, &changed_files, max_files_per_task);
-    let total_tasks = tasks.len();
-    info!("Created {} tasks", total_tasks);
-
+    // Narrow the files to review; all changed files are still listed as context
+    let review_files =
+        filter_files_by_globs(&changed_files, include, exclude).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(EXIT_FAILURE);
+        });
+    if review_files.len() != changed_files.len() {
+        info!(
+            "Reviewing {} of {} changed files after --include/--exclude",
+            review_files.len(),
+            changed_files.len()
+        );
+    }
+    let vendored = vendored_files(&review_files, vendored);
+    if !vendored.is_empty() {
+        info!(
+            "{} vendored file(s) are only reviewed by rules with vendored = true",
+            vendored.len()
+        );
+    }
+    // A queued review resumes with the tasks it has not completed, on the same changes
+    let head_sha = util::rev_parse("HEAD");
+    let existing_queue = queue_path.and_then(|path| {
+        let mut queue = queue::ReviewQueue::load(path).unwrap_or_else(|e| {
+            error!("{}", e);
+            util::temp::exit(EXIT_FAILURE);
+        })?;
+        if let Err(e) = queue.check(path, &base.to_string(), head_sha.as_deref()) {
+            error!("{}", e);
+            util::temp::exit(EXIT_FAILURE);
+        }
+        // Rules added to the config since the queue was created are queued too
+        let unqueued: HashSet<&str> = queue
+            .unqueued_rules(rules)
+            .iter()
+            .map(|rule| rule.name.as_str())
+            .collect();
+        if !unqueued.is_empty() {
+            let tasks: Vec<_> =
+                orchestrate(rules, &review_files, None, &vendored, max_files_per_task)
+                    .into_iter()
+                    .filter(|(rule, _)| unqueued.contains(rule.name.as_str()))
+                    .collect();
+            if !tasks.is_empty() {
+                info!(
+                    "Queued {} task(s) of rule(s) added since the queue was created",
+                    tasks.len()
+                );
+                queue.extend(&tasks);
+            }
+        }
+        Some(queue)
+    });
+    let queued = existing_queue
+        .as_ref()
+        .map(|queue| queue.pending_tasks(rules));
+    let sample = sampling
+        .threshold
+        .filter(|&threshold| {
+            retry_from.is_none()
+                && task_plan.is_none()
+                && queued.is_none()
+                && review_files.len() > threshold
+        })
+        .map(|threshold| {
+            let sample = sampling::sample_files(&review_files, &diffs, sampling.sample_files);
+            warn!(
+                "{} files exceed the sampling threshold of {}: non-blocking rules review a weighted sample of {} files and skip {} (listed in --output results)",
+                review_files.len(),
+                threshold,
+                sample.files.len(),
+                sample.skipped.len()
+            );
+            sample
+        });
+    if let Some(task_plan) = task_plan.filter(|_| retry_from.is_none()) {
+        if let Err(e) = task_plan.check(rules, &review_files) {
+            error!("{}", e);
+            util::temp::exit(EXIT_FAILURE);
+        }
+        if task_plan.model != model {
+            warn!(
+                "Plan was estimated for model '{}', running with '{}'",
+                task_plan.model, model
+            );
+        }
+    }
+    let tasks = match (retry_from, task_plan, &queued) {
+        (Some(previous), _, _) => retry_tasks(rules, &previous.incomplete),
+        (None, Some(task_plan), _) => task_plan.tasks(rules),
+        (None, None, Some(queued)) => queued
+            .iter()
+            .map(|(_, rule, files)| (*rule, files.clone()))
+            .collect(),
+        (None, None, None) => {
+            let tasks = orchestrate(
+                rules,
+                &review_files,
+                sample.as_ref().map(|s| s.files.as_slice()),
+                &vendored,
+                max_files_per_task,
+            );
+            let tasks = match release {
+                Some(_) => {
+                    interleave_by_rule(release::batch_by_directory(tasks, max_files_per_task))
+                }
+                None => tasks,
+            };
+            let tokenizer = Tokenizer::for_model(model);
+            let owned = diffs.clone();
+            let diff_tokens = tokio::task::spawn_blocking(move || {
+                owned
+                    .into_iter()
+                    .map(|(file, diff)| {
+                        let tokens = tokenizer.count(&diff);
+                        (file, tokens)
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .await
+            .unwrap_or_default();
+            split_by_tokens(
+                tasks,
+                &diff_tokens,
+                settings.max_history_tokens * TASK_DIFF_PERCENT / 100,
+            )
+        }
+    };
+    let fresh_skipped = if retry_from.is_none() && task_plan.is_none() && queued.is_none() {
+        skipped_files(
+            &changed_files,
+            &review_files,
+            &tasks,
+            sample.as_ref().map_or(&[][..], |s| s.skipped.as_slice()),
+            &vendored,
+        )
+    } else {
+        vec![]
+    };
+    if !fresh_skipped.is_empty() {
+        info!(
+            "{} changed file(s) skipped or reviewed without diffs (listed in --output results)",
+            fresh_skipped.len()
+        );
+    }
     if dry_run {
         info!("Dry run - {} tasks to execute:", tasks.len());
         for (i, (rule, files)) in tasks.iter().enumerate() {
             info!("  Task {}: rule='{}', files={:?}", i, rule.name, files);
         }
+        let task_plan = plan::TaskPlan::new(model, &tasks, &diffs);
+        info!("Estimated prompt tokens: {}", task_plan.estimated_tokens());
+        for path in outputs {
+            if !path.ends_with(".json") {
+                warn!(
+                    "Dry run plans are only written to .json outputs, skipping {}",
+                    path
+                );
+                continue;
+            }
+            match task_plan.write(path) {
+                Ok(()) => info!("Plan written to {}", path),
+                Err(e) => {
+                    error!("{}", e);
+                    util::temp::exit(EXIT_FAILURE);
+                }
+            }
+        }
         return;
     }
 
+    // Resources of each task, loaded once and shared with its workers
+    let snapshot = if matches!(base, util::Base::Staged) {
+        Snapshot::Index
+    } else {
+        Snapshot::WorkingTree
+    };
+    let resources = resources
+        .clone()
+        .with_base(base.git_ref())
+        .with_snapshot(snapshot.clone());
+    let contexts = task_cache::TaskContexts::load(resolved_config, &resources, &tasks).await;
+    let rule_inputs = rule_inputs(model, &tasks, &diffs, &contexts);
+    // Skip the tasks whose prompt inputs were reviewed by an earlier run
+    let (tasks, cached, cached_rules) = match cache {
+        Some(cache) => {
+            let (tasks, cached, rules) = cache.take_cached(model, tasks, &diffs, &contexts);
+            (tasks, Some(cached), rules)
+        }
+        None => (tasks, None, BTreeSet::new()),
+    };
+    let total_tasks = tasks.len();
+    let task_keys: Vec<(String, Vec<String>)> = tasks
+        .iter()
+        .map(|(rule, files)| (rule.name.clone(), files.clone()))
+        .collect();
+    info!("Created {} tasks", total_tasks);
+    let reviewed_rules: BTreeSet<String> = tasks.iter().map(|(r, _)| r.name.clone()).collect();
+
+    // Results of queued tasks completed by earlier invocations, merged into this run's
+    let queue_previous = existing_queue
+        .as_ref()
+        .map(|queue| queue.completed_results(rules));
+    let queue_indices: Vec<usize> = match &queued {
+        Some(queued) => queued.iter().map(|(i, _, _)| *i).collect(),
+        None => (0..tasks.len()).collect(),
+    };
+    let queue = queue_path.map(|path| {
+        let queue = existing_queue.unwrap_or_else(|| {
+            info!("Queued {} tasks in {}", tasks.len(), path);
+            queue::ReviewQueue::new(&base.to_string(), head_sha.clone(), &tasks)
+        });
+        if let Err(e) = queue.save(path) {
+            error!("{}", e);
+            util::temp::exit(EXIT_FAILURE);
+        }
+        Arc::new(Mutex::new(queue))
+    });
+
+    // Record the plan before running, so interrupted runs are auditable too
+    let artifacts = outputs
+        .iter()
+        .map(String::as_str)
+        .chain(trace)
+        .chain(sarif)
+        .chain(badge);
+    let manifest_path = manifest::manifest_path(artifacts);
+    let run_manifest = (manifest_path.is_some() || bundle.is_some()).then(|| {
+        let mut config = resolved_config.clone();
+        manifest::redact_secrets(&mut config);
+        manifest::RunManifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_version: util::git_version(),
+            timestamp: chrono::Utc::now(),
+            config,
+            base: base.to_string(),
+            base_sha: base.sha(),
+            head_sha: util::rev_parse("HEAD"),
+            changed_files: changed_files.clone(),
+            review_files: review_files.clone(),
+            tasks: tasks
+                .iter()
+                .map(|(rule, files)| manifest::PlannedTask {
+                    rule: rule.name.clone(),
+                    rule_id: rule.id(),
+                    files: files.clone(),
+                })
+                .collect(),
+            outputs: outputs.to_vec(),
+            trace: trace.map(str::to_string),
+            bundle: bundle.map(str::to_string),
+            sarif: sarif.map(str::to_string),
+            badge: badge.map(str::to_string),
+        }
+    });
+    if let (Some(manifest_path), Some(run_manifest)) = (manifest_path, &run_manifest) {
+        match run_manifest.write(&manifest_path) {
+            Ok(()) => info!("Run manifest written to {}", manifest_path),
+            Err(e) => warn!("{}", e),
+        }
+    }
+
+    // Shared once so workers skip rediscovering the layout with ls/find calls
+    let overview: Arc<str> = if repo_overview {
+        debug!("Building repository overview");
+        overview::repo_overview().into()
+    } else {
+        Arc::from("")
+    };
+
     // Setup signal handlers for graceful shutdown (SIGINT/SIGTERM)
     // When triggered, sets shutdown flag that workers poll during execution
     // Workers stop mid-execution and return partial results including trace data
@@ -93,39 +419,155 @@ pub async fn orchestrate_and_run(
     });
 
     debug!("Creating worker futures for {} tasks", tasks.len());
-    let trace_enabled = trace.is_some();
+    let trace_enabled = trace.is_some() || bundle.is_some();
+    let budget = usage::TokenBudget::new(max_total_tokens);
+    let chunks = task_chunks(&tasks);
+    let sequential_rules: Vec<Option<&str>> = tasks
+        .iter()
+        .zip(&chunks)
+        .map(|((rule, _), chunk)| (rule.sequential && chunk.is_some()).then_some(&*rule.name))
+        .collect();
+    // Tasks of a sequential rule share the violations reported so far
+    let mut sequential: HashMap<&str, Arc<Mutex<Vec<Violation>>>> = HashMap::new();
     let futures: Vec<_> = tasks
         .into_iter()
+        .zip(chunks)
         .enumerate()
-        .map(|(i, (rule, files))| {
+        .map(|(i, ((rule, files), chunk))| {
+            let reported = (rule.sequential && chunk.is_some())
+                .then(|| sequential.entry(&rule.name).or_default().clone());
+            let queue = queue.clone();
+            let queue_index = queue_indices[i];
             let worker_id = i.to_string();
             let all_files = changed_files.clone();
             let commits = commit_messages.clone();
-            let headers = headers.clone();
-            let body = body.clone();
+            let overview = overview.clone();
             let shutdown_clone = shutdown.clone();
-            let is_root = matches!(base, util::Base::Root);
-            let resources = global_resources.to_vec();
-            let allowed_cmds = allowed_shell_commands.to_vec();
-            worker::worker(
-                worker_id,
-                rule,
-                files,
-                all_files,
-                commits,
-                base_url,
-                api_key,
-                model,
-                headers,
-                body,
-                diffs.clone(),
-                trace_enabled,
-                shutdown_clone,
-                is_root,
-                resources,
-                allowed_cmds,
-                timeout_secs,
-            )
+            let is_root = base.is_whole_file();
+            let snapshot = snapshot.clone();
+            let resources = resources.clone();
+            let diffs = diffs.clone();
+            let budget = budget.clone();
+            let contexts = &contexts;
+            async move {
+                let mut reported = match &reported {
+                    Some(reported) => Some(reported.lock().await),
+                    None => None,
+                };
+                if budget.is_exhausted() || *shutdown_clone.lock().await {
+                    return None;
+                }
+                let chunk = chunk.map(|chunk| worker::Chunk {
+                    reported: reported.as_deref().cloned().unwrap_or_default(),
+                    ..chunk
+                });
+                // Consensus agents of a task run one after another, within its worker slot
+                let agents = rule.consensus.unwrap_or(1).max(1);
+                let mut agent_results = Vec::with_capacity(agents);
+                let mut task_usage = usage::TokenUsage::default();
+                for agent in 0..agents {
+                    let agent_model = if rule.consensus_models.is_empty() {
+                        model
+                    } else {
+                        &rule.consensus_models[agent % rule.consensus_models.len()]
+                    };
+                    let agent_id = if agents > 1 {
+                        format!("{}.{}", worker_id, agent)
+                    } else {
+                        worker_id.clone()
+                    };
+                    let agent_usage = Arc::<std::sync::Mutex<usage::TokenUsage>>::default();
+                    let task = worker::Task {
+                        worker_id: agent_id,
+                        rule,
+                        files: files.clone(),
+                        chunk: chunk.clone(),
+                        all_changed_files: all_files.clone(),
+                        commit_messages: commits.clone(),
+                        overview: overview.clone(),
+                        diffs: diffs.clone(),
+                        is_root_base: is_root,
+                        snapshot: snapshot.clone(),
+                        resources: contexts.loaded(rule, &files),
+                    };
+                    agent_results.push(
+                        worker::worker(
+                            task,
+                            &settings.with_model(agent_model),
+                            resources.clone(),
+                            trace_enabled,
+                            shutdown_clone.clone(),
+                            agent_usage.clone(),
+                        )
+                        .await
+                        .map(|mut result| {
+                            result.usage = result.usage.priced(prices.get(agent_model));
+                            result
+                        }),
+                    );
+                    let agent_usage = *agent_usage.lock().unwrap();
+                    task_usage.add(&agent_usage.priced(prices.get(agent_model)));
+                }
+                let result = if agents > 1 {
+                    consensus::merge(worker_id, agent_results)
+                } else {
+                    agent_results.remove(0)
+                };
+                budget.spend(&task_usage);
+                if let (Some(reported), Ok(result)) = (&mut reported, &result) {
+                    reported.extend(result.violations.iter().cloned());
+                }
+                // Record each completed task right away, so a killed run loses no progress
+                if let (Some(queue), Some(path), Ok(result)) = (&queue, queue_path, &result)
+                    && result.incomplete.is_none()
+                {
+                    let mut queue = queue.lock().await;
+                    let violations = result
+                        .violations
+                        .iter()
+                        .map(|v| Violation {
+                            rule_id: Some(rule.id()),
+                            ..v.clone()
+                        })
+                        .collect();
+                    queue.complete(queue_index, violations);
+                    if let Err(e) = queue.save(path) {
+                        warn!("{}", e);
+                    }
+                }
+                if let (Some(cache), Ok(result)) = (cache, &result)
+                    && result.incomplete.is_none()
+                {
+                    cache.store(model, rule, &files, &diffs, contexts, &result.violations);
+                }
+                Some((i, result, task_usage))
+            }
+        })
+        .collect();
+
+    // Chain the tasks of each sequential rule into one future, run in turn in one worker
+    // slot, so they neither wait for each other in slots of their own nor run at once
+    let mut groups: Vec<Vec<_>> = Vec::new();
+    let mut group_of_rule: HashMap<&str, usize> = HashMap::new();
+    for (future, rule) in futures.into_iter().zip(sequential_rules) {
+        match rule.and_then(|rule| group_of_rule.get(rule)) {
+            Some(&group) => groups[group].push(future),
+            None => {
+                if let Some(rule) = rule {
+                    group_of_rule.insert(rule, groups.len());
+                }
+                groups.push(vec![future]);
+            }
+        }
+    }
+    let futures: Vec<_> = groups
+        .into_iter()
+        .map(|group| async move {
+            let mut results = Vec::with_capacity(group.len());
+            for future in group {
+                results.push(future.await);
+            }
+            results
         })
         .collect();
 
@@ -135,108 +577,588 @@ pub async fn orchestrate_and_run(
         info!("Running workers with unlimited parallelism");
     }
 
-    // Execute workers with optional concurrency limit
-    let results = execute_workers(futures, max_parallel_workers, shutdown.clone()).await;
+    // Execute workers with optional concurrency limit; tasks left once the budget is
+    // spent do not start
+    let results: Vec<TaskResult> = execute_workers(futures, max_parallel_workers, shutdown.clone())
+        .await
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect();
 
-    let (_succeeded, failed, _was_interrupted) =
-        log_results(&results, total_tasks, &shutdown).await;
+    let (_succeeded, failed, was_interrupted) = log_results(&results, total_tasks, &shutdown).await;
+    let not_started = match budget.limit() {
+        Some(limit) if budget.is_exhausted() && !was_interrupted => {
+            warn!(
+                "Token budget of {} (llm.max_total_tokens) spent with {} tokens: {} task(s) not started",
+                limit,
+                budget.used(),
+                total_tasks - results.len()
+            );
+            format!("not started: token budget of {} spent", limit)
+        }
+        _ => "not started before shutdown".to_string(),
+    };
 
-    let grouped = group_violations(results);
+    let mut incomplete = incomplete_tasks(&task_keys, &results, &not_started);
+    let usage_by_rule = usage_by_rule(&results, &task_keys);
+    let suppressed = triage::load_suppressed_fingerprints(suppressions_path);
+    let mut grouped = group_violations(
+        results.into_iter().map(|(_, result, _)| result).collect(),
+        &suppressed,
+    );
+    let strings = locale::strings(language);
+    let mut skipped = fresh_skipped.as_slice();
+    for previous in queue_previous.iter().chain(&cached) {
+        merge_previous(&mut grouped, previous, &task_keys, rules);
+    }
+    if let Some(previous) = retry_from {
+        merge_previous(&mut grouped, previous, &task_keys, rules);
+        // Tasks that could not be retried stay incomplete
+        incomplete.extend(
+            previous
+                .incomplete
+                .iter()
+                .filter(|task| {
+                    !task_keys
+                        .iter()
+                        .any(|(rule, files)| *rule == task.rule && *files == task.files)
+                })
+                .cloned(),
+        );
+        skipped = &previous.skipped;
+        info!(
+            "Merged {} retried task(s) with previous results",
+            total_tasks
+        );
+    }
+    if group_by_owner && owners.is_empty() {
+        warn!("No CODEOWNERS file or [owners] table found; all violations are unowned");
+    }
+    let filtered = filters.apply(
+        &mut grouped.violations_by_file,
+        &mut grouped.blocking_rules_with_violations,
+    );
+    if filtered.dropped > 0 || filtered.downgraded > 0 {
+        info!(
+            "Filters dropped {} and downgraded {} violation(s)",
+            filtered.dropped, filtered.downgraded
+        );
+    }
+    owners.annotate(&mut grouped.violations_by_file);
+    let mut truncated = cap_violations(&mut grouped.violations_by_file, rules, max_violations);
+    if let Some(previous) = retry_from {
+        // Rules not retried keep the counts hidden from their previous results
+        for (rule, count) in &previous.truncated {
+            if !task_keys.iter().any(|(retried, _)| retried == rule) {
+                *truncated.entry(rule.clone()).or_default() += count;
+            }
+        }
+    }
+    for (rule, count) in &truncated {
+        info!(
+            "Rule '{}' reached max_violations; {} more violation(s) summarized",
+            rule, count
+        );
+    }
+    if let (Some(queue), Some(path)) = (&queue, queue_path) {
+        // Tasks of rules removed from the config are never run, so they are not waited for
+        let remaining = queue.lock().await.pending_tasks(rules).len();
+        if remaining == 0 {
+            info!("All queued tasks completed");
+            if let Err(e) = std::fs::remove_file(path) {
+                warn!("Failed to remove completed queue {}: {}", path, e);
+            }
+        } else {
+            info!(
+                "{} queued task(s) remain; run again with --continue to resume",
+                remaining
+            );
+        }
+    } else if !incomplete.is_empty() {
+        warn!(
+            "{} task(s) incomplete; re-run them with --retry-from on the JSON output",
+            incomplete.len()
+        );
+    }
 
-    // Output results to file or console
-    if let Some(output_path) = output {
-        write_output(
-            output_path,
+    let results = render::ViolationFile {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        violations: grouped.violations_by_file.clone(),
+        tips: grouped.tips_by_rule.clone(),
+        language: language.map(str::to_string),
+        skipped: skipped.to_vec(),
+        incomplete: incomplete.clone(),
+        truncated: truncated.clone(),
+        quarantined: rules
+            .iter()
+            .filter(|rule| rule.quarantined && reviewed_rules.contains(&rule.name))
+            .map(|rule| rule.name.clone())
+            .collect(),
+        usage: usage_by_rule
+            .iter()
+            .map(|(rule, (_, usage))| (rule.clone(), *usage))
+            .collect(),
+    };
+
+    // Failed writes are reported at the end, after the results are saved elsewhere
+    let mut write_errors = Vec::new();
+
+    // Output results to files or console
+    for output_path in outputs {
+        write_errors.extend(write_output(output_path, &results, group_by_owner, release).err());
+    }
+    if outputs.is_empty() {
+        if group_by_owner && !grouped.violations_by_file.is_empty() {
+            println!(
+                "{}",
+                render::format_by_owner(
+                    &grouped.violations_by_file,
+                    &grouped.tips_by_rule,
+                    strings
+                )
+            );
+        } else {
+            print_violations(
+                &grouped.violations_by_file,
+                &grouped.tips_by_rule,
+                rules,
+                strings,
+            );
+        }
+        if let Some(summary) = render::format_truncated(&truncated, strings) {
+            println!("{}", summary.trim_start());
+        }
+        if let Some(summary) =
+            render::format_quarantined(&results.quarantined, &grouped.violations_by_file, strings)
+        {
+            println!("{}", summary.trim_start());
+        }
+    }
+
+    // Write trace if enabled
+    if let Some(trace_path) = trace {
+        write_errors.extend(write_trace(trace_path, &grouped.all_traces).err());
+    }
+
+    // Write the bundle of results, trace, and config snapshot if enabled
+    if let Some(bundle_path) = bundle {
+        write_errors.extend(
+            bundle::write(
+                bundle_path,
+                &results,
+                run_manifest.as_ref(),
+                &grouped.all_traces,
+            )
+            .err(),
+        );
+    }
+
+    // Write shields.io badge if enabled
+    if let Some(badge_path) = badge {
+        write_errors.extend(
+            write_badge(
+                badge_path,
+                &grouped.violations_by_file,
+                &grouped.blocking_rules_with_violations,
+                failed,
+            )
+            .err(),
+        );
+    }
+
+    // Write SARIF if enabled
+    if let Some(sarif_path) = sarif {
+        write_errors.extend(write_sarif(sarif_path, &grouped.violations_by_file, rules).err());
+    }
+    for e in &write_errors {
+        error!("{}", e);
+    }
+    if !write_errors.is_empty() || failed > 0 {
+        match save_last_run(LAST_RUN_DIR, &results, &grouped.all_traces) {
+            Ok(()) => warn!(
+                "Results collected so far saved to {}/ (re-run failed tasks with --retry-from {}/results.json)",
+                LAST_RUN_DIR, LAST_RUN_DIR
+            ),
+            Err(e) => error!("Failed to save results collected so far: {}", e),
+        }
+    }
+
+    // Annotate the reported lines in the GitHub Actions run
+    if annotations {
+        let blocking_rules = rules
+            .iter()
+            .filter(|r| r.blocks())
+            .map(|r| r.name.clone())
+            .collect();
+        for annotation in render::format_annotations(
             &grouped.violations_by_file,
+            &blocking_rules,
             &grouped.tips_by_rule,
+        ) {
+            println!("{}", annotation);
+        }
+    }
+
+    // Show results on the GitHub Actions run page, also without permission to comment
+    if let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY").filter(|p| !p.is_empty()) {
+        let blocking_rules = grouped
+            .blocking_rules_with_violations
+            .iter()
+            .cloned()
+            .collect();
+        let mut summary = render::format_step_summary(
+            &grouped.violations_by_file,
+            &blocking_rules,
+            failed,
+            strings,
         );
-    } else {
-        print_violations(&grouped.violations_by_file, &grouped.tips_by_rule);
+        summary.extend(render::format_truncated(&truncated, strings));
+        summary.extend(render::format_quarantined(
+            &results.quarantined,
+            &grouped.violations_by_file,
+            strings,
+        ));
+        summary.extend(render::format_skipped(skipped, strings));
+        summary.push('\n');
+        let summary = render::truncate_step_summary(summary, strings);
+        if let Err(e) = append_file(std::path::Path::new(&path), &summary) {
+            warn!("Failed to write the job summary: {}", e);
+        }
     }
 
-    // Write trace if enabled
-    if let Some(trace_path) = trace {
-        write_trace(trace_path, &grouped.all_traces);
+    // Publish results to the pull request if enabled
+    if let Some((api, pr)) = pr_comment {
+        let mut body = format!(
+            "## Firekeeper Review\n\n{}",
+            format_markdown(
+                &grouped.violations_by_file,
+                &grouped.tips_by_rule,
+                group_by_owner,
+                strings
+            )
+        );
+        body.extend(render::format_truncated(&truncated, strings));
+        body.extend(render::format_quarantined(
+            &results.quarantined,
+            &grouped.violations_by_file,
+            strings,
+        ));
+        body.extend(render::format_skipped(skipped, strings));
+        if let Err(e) = api.post_comment(pr, &body).await {
+            error!("{}", e);
+        }
     }
 
-    // Exit with error if blocking rules have violations
-    check_blocking_violations(&grouped.blocking_rules_with_violations, config_path);
+    // Record run summary for `firekeeper stats`
+    let history_entry = history::HistoryEntry {
+        cost: usage::total_cost(&results.usage),
+        cached: cached_rules,
+        ..history_entry(
+            &base,
+            &review_files,
+            total_tasks,
+            failed,
+            start_time.elapsed().as_secs_f64(),
+            rule_inputs,
+            &grouped.violations_by_file,
+        )
+    };
+    if let Err(e) = history::append(history::DEFAULT_HISTORY_PATH, &history_entry) {
+        warn!("Failed to record run history: {}", e);
+    }
+    // Keep results for `results://previous` resources of the next run
+    if let Err(e) = history::save_results(
+        history::DEFAULT_PREVIOUS_RESULTS_PATH,
+        &results,
+        &review_files,
+    ) {
+        warn!("Failed to save results for the next run: {}", e);
+    }
+    telemetry::send(
+        telemetry,
+        &telemetry::RunMetrics::from_history(&history_entry, model),
+    )
+    .await;
+
+    if !usage_by_rule.is_empty() {
+        info!(
+            "Token usage by rule:\n{}",
+            usage::format_usage_table(&usage_by_rule)
+        );
+    }
+    if let Some(cost) = usage::total_cost(&results.usage) {
+        info!("Cost of the run: {}", usage::format_cost(cost));
+    }
+
+    // Fail if blocking rules have violations, then if any workers failed
+    let exit_code = check_blocking_violations(&grouped.blocking_rules_with_violations, config_path)
+        .or_else(|| (!write_errors.is_empty()).then_some(EXIT_FAILURE))
+        .or_else(|| check_worker_failures(failed, incomplete.len(), fail_on_incomplete))
+        .unwrap_or(0);
+
+    let head = release.map_or("HEAD", |release| release.head.as_str());
+    let audit_entry = audit::AuditEntry {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: chrono::Utc::now(),
+        actor: audit::actor(),
+        base: base.to_string(),
+        base_commit: match &base {
+            util::Base::Commit(rev) => util::rev_parse(rev),
+            _ => None,
+        },
+        head: util::rev_parse(head),
+        config_hash: audit::config_hash(resolved_config),
+        rules: reviewed_rules.iter().cloned().collect(),
+        blocking_rules: grouped
+            .blocking_rules_with_violations
+            .iter()
+            .cloned()
+            .collect(),
+        failed_tasks: failed,
+        exit_code,
+    };
+    audit::record(audit_config, &audit_entry).await;
+
+    if exit_code != 0 {
+        util::temp::exit(exit_code);
+    }
+}
 
-    // Exit with error if any workers failed
-    check_worker_failures(failed);
+/// Summarize a run for the history file
+fn history_entry(
+    base: &util::Base,
+    files: &[String],
+    tasks: usize,
+    failed_tasks: usize,
+    elapsed_secs: f64,
+    inputs: BTreeMap<String, String>,
+    violations_by_file: &render::ViolationsByFile,
+) -> history::HistoryEntry {
+    // Every reviewed rule has inputs, and is listed even without violations
+    let mut violations: BTreeMap<String, usize> = inputs.keys().map(|r| (r.clone(), 0)).collect();
+    let mut fingerprints: BTreeMap<String, Vec<String>> =
+        inputs.keys().map(|r| (r.clone(), Vec::new())).collect();
+    for (rule, violation) in render::flatten_violations(violations_by_file) {
+        *violations.entry(rule.to_string()).or_default() += 1;
+        fingerprints
+            .entry(rule.to_string())
+            .or_default()
+            .push(violation.fingerprint(rule));
+    }
+    let findings = fingerprints
+        .into_iter()
+        .filter(|(rule, _)| inputs.contains_key(rule))
+        .map(|(rule, mut fingerprints)| {
+            fingerprints.sort();
+            let parts: Vec<&str> = fingerprints.iter().map(String::as_str).collect();
+            (rule, util::hash_parts(&parts))
+        })
+        .collect();
+    history::HistoryEntry {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: chrono::Utc::now(),
+        base: base.to_string(),
+        files: files.len(),
+        tasks,
+        failed_tasks,
+        elapsed_secs,
+        violations,
+        cost: None,
+        inputs,
+        findings,
+        cached: BTreeSet::new(),
+    }
 }
 
+/// Hash of each rule's task cache keys, identifying what the rule reviewed for
+/// `firekeeper stats --flaky`
+fn rule_inputs(
+    model: &str,
+    tasks: &[(&RuleBody, Vec<String>)],
+    diffs: &HashMap<String, String>,
+    contexts: &task_cache::TaskContexts,
+) -> BTreeMap<String, String> {
+    let mut keys: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (rule, files) in tasks {
+        keys.entry(rule.name.clone())
+            .or_default()
+            .push(task_cache::task_key(model, rule, files, diffs, contexts));
+    }
+    keys.into_iter()
+        .map(|(rule, mut keys)| {
+            keys.sort();
+            let parts: Vec<&str> = keys.iter().map(String::as_str).collect();
+            (rule, util::hash_parts(&parts))
+        })
+        .collect()
+}
+
+/// Print violations to the terminal, colorized when stdout is a TTY
 fn print_violations(
-    violations_by_file: &HashMap<String, HashMap<String, Vec<crate::types::Violation>>>,
-    tips_by_rule: &HashMap<String, String>,
+    violations_by_file: &render::ViolationsByFile,
+    tips_by_rule: &BTreeMap<String, Tip>,
+    rules: &[RuleBody],
+    strings: &locale::Strings,
 ) {
     if violations_by_file.is_empty() {
         info!("No violations found");
         return;
     }
 
-    for line in render::format_violations(violations_by_file, tips_by_rule).lines() {
-        info!("{}", line);
-    }
+    let blocking_rules = rules
+        .iter()
+        .filter(|r| r.blocks())
+        .map(|r| r.name.clone())
+        .collect();
+    println!(
+        "{}",
+        terminal::format_terminal(
+            violations_by_file,
+            tips_by_rule,
+            &blocking_rules,
+            &terminal::TerminalStyle::detect(),
+            strings,
+        )
+    );
 }
 
-fn write_output(
+pub(crate) fn write_output(
     path: &str,
-    violations_by_file: &HashMap<String, HashMap<String, Vec<crate::types::Violation>>>,
-    tips_by_rule: &HashMap<String, String>,
-) {
+    results: &render::ViolationFile,
+    group_by_owner: bool,
+    release: Option<&release::ReleaseAudit>,
+) -> Result<(), String> {
     let content = if path.ends_with(".json") {
-        let violation_file = render::ViolationFile {
-            version: env!("CARGO_PKG_VERSION").to_string(),
-            violations: violations_by_file.clone(),
-            tips: tips_by_rule.clone(),
-        };
-        serde_json::to_string_pretty(&violation_file).unwrap()
+        serde_json::to_string_pretty(results).unwrap()
+    } else if let (true, Some(release)) = (path.ends_with(".md"), release) {
+        release.format_report(results)
     } else if path.ends_with(".md") {
-        render::format_violations(violations_by_file, tips_by_rule)
+        let strings = locale::strings(results.language.as_deref());
+        let mut markdown =
+            format_markdown(&results.violations, &results.tips, group_by_owner, strings);
+        markdown.extend(render::format_truncated(&results.truncated, strings));
+        markdown.extend(render::format_quarantined(
+            &results.quarantined,
+            &results.violations,
+            strings,
+        ));
+        markdown.extend(render::format_skipped(&results.skipped, strings));
+        markdown
     } else {
-        error!("Output file must end with .md or .json");
-        std::process::exit(EXIT_FAILURE);
+        return Err(format!("Output file must end with .md or .json: {}", path));
     };
 
-    if let Err(e) = std::fs::write(path, content) {
-        error!("Failed to write output file: {}", e);
-        std::process::exit(EXIT_FAILURE);
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write output file {}: {}", path, e))?;
+    info!("Results written to {}", path);
+    Ok(())
+}
+
+fn append_file(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(content.as_bytes())
+}
+
+/// Format violations as Markdown, by file or by owner
+fn format_markdown(
+    violations_by_file: &render::ViolationsByFile,
+    tips_by_rule: &BTreeMap<String, Tip>,
+    group_by_owner: bool,
+    strings: &locale::Strings,
+) -> String {
+    if group_by_owner {
+        render::format_by_owner(violations_by_file, tips_by_rule, strings)
+    } else {
+        render::format_violations(violations_by_file, tips_by_rule, strings)
     }
+}
 
-    info!("Results written to {}", path);
+/// Write violations as a SARIF log
+fn write_sarif(
+    path: &str,
+    violations_by_file: &render::ViolationsByFile,
+    rules: &[RuleBody],
+) -> Result<(), String> {
+    let sarif = sarif::format_sarif(violations_by_file, rules);
+    std::fs::write(path, serde_json::to_string_pretty(&sarif).unwrap())
+        .map_err(|e| format!("Failed to write SARIF file {}: {}", path, e))?;
+    info!("SARIF written to {}", path);
+    Ok(())
 }
 
 /// Write trace data to file in JSON or Markdown format
-fn write_trace(path: &str, traces: &[render::TraceEntry]) {
+fn write_trace(path: &str, traces: &[render::TraceEntry]) -> Result<(), String> {
     let content = if path.ends_with(".json") {
-        let trace_file = render::TraceFile {
-            version: env!("CARGO_PKG_VERSION").to_string(),
-            entries: traces.to_vec(),
-        };
-        serde_json::to_string_pretty(&trace_file).unwrap()
+        serde_json::to_string_pretty(&render::TraceFile::new(traces.to_vec())).unwrap()
     } else if path.ends_with(".md") {
         render::format_trace_markdown(traces)
     } else {
-        error!("Trace file must end with .md or .json");
-        std::process::exit(EXIT_FAILURE);
+        return Err(format!("Trace file must end with .md or .json: {}", path));
     };
 
-    if let Err(e) = std::fs::write(path, content) {
-        error!("Failed to write trace file: {}", e);
-        std::process::exit(EXIT_FAILURE);
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write trace file {}: {}", path, e))?;
+    info!("Trace written to {}", path);
+    Ok(())
+}
+
+/// Save results and traces to `dir` as `results.json` and `trace.json`, so a run whose
+/// outputs could not be written or whose tasks failed loses nothing
+fn save_last_run(
+    dir: &str,
+    results: &render::ViolationFile,
+    traces: &[render::TraceEntry],
+) -> Result<(), String> {
+    let dir = std::path::Path::new(dir);
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    write_output(
+        &dir.join("results.json").to_string_lossy(),
+        results,
+        false,
+        None,
+    )?;
+    if !traces.is_empty() {
+        write_trace(&dir.join("trace.json").to_string_lossy(), traces)?;
     }
+    Ok(())
+}
 
-    info!("Trace written to {}", path);
+/// Write shields.io endpoint badge JSON summarizing blocking and non-blocking violations
+fn write_badge(
+    path: &str,
+    violations_by_file: &render::ViolationsByFile,
+    blocking_rules_with_violations: &BTreeSet<String>,
+    failed: usize,
+) -> Result<(), String> {
+    let (mut blocking, mut non_blocking) = (0, 0);
+    for (rule, violations) in violations_by_file.values().flatten() {
+        if blocking_rules_with_violations.contains(rule) {
+            blocking += violations.len();
+        } else {
+            non_blocking += violations.len();
+        }
+    }
+
+    let badge = render::format_badge(blocking, non_blocking, failed);
+    std::fs::write(path, serde_json::to_string_pretty(&badge).unwrap())
+        .map_err(|e| format!("Failed to write badge file {}: {}", path, e))?;
+    info!("Badge written to {}", path);
+    Ok(())
 }
 
-/// Split rules and files into worker tasks
-///
-/// For each rule, filters files by scope and splits them into chunks based on
-/// max_files_per_task. Returns list of (rule, files) pairs for parallel execution.
-fn orchestrate<'a>(
+/// Split each rule's files in scope into (rule, files) tasks of at most max_files_per_task files,
+/// grouping files under the same `dir:` scope directory. `sampled_files` limit non-blocking rules.
+pub(crate) fn orchestrate<'a>(
     rules: &'a [RuleBody],
     changed_files: &[String],
+    sampled_files: Option<&[String]>,
+    vendored: &HashSet<String>,
     global_max_files_per_task: usize,
 ) -> Vec<(&'a RuleBody, Vec<String>)> {
     debug!(
@@ -245,13 +1167,20 @@ fn orchestrate<'a>(
         changed_files.len()
     );
 
-    rules
+    let tasks = rules
         .iter()
         .flat_map(|rule| {
             trace!("Processing rule: {}", rule.name);
 
             // Filter files that match this rule's scope
-            let matched_files = filter_files_by_scope(rule, changed_files);
+            let files = match sampled_files {
+                Some(sampled) if !rule.blocks() => sampled,
+                _ => changed_files,
+            };
+            let mut matched_files = filter_files_by_scope(rule, files);
+            if !rule.vendored {
+                matched_files.retain(|f| !vendored.contains(f));
+            }
             debug!("Rule '{}' matched {} files", rule.name, matched_files.len());
 
             if matched_files.is_empty() {
@@ -265,61 +1194,154 @@ fn orchestrate<'a>(
                 rule.name, max_files
             );
 
-            // Split matched files into chunks and create tasks
-            split_files(&matched_files, max_files)
-                .into_iter()
-                .map(|chunk| {
+            // Files under a scope directory are reviewed together with their directory,
+            // in chunks so a large directory doesn't overflow one worker's context
+            let directories = scope_directories(rule, &matched_files);
+            let grouped: HashSet<&String> = directories.values().flatten().collect();
+            let matched_files: Vec<String> = matched_files
+                .iter()
+                .filter(|f| !grouped.contains(f))
+                .cloned()
+                .collect();
+            let mut tasks: Vec<(&RuleBody, Vec<String>)> = directories
+                .iter()
+                .flat_map(|(dir, files)| {
+                    let chunks = split_files(files, max_files);
                     trace!(
-                        "Created chunk with {} files for rule '{}'",
-                        chunk.len(),
+                        "Created {} task(s) for directory '{}' with {} files for rule '{}'",
+                        chunks.len(),
+                        dir,
+                        files.len(),
                         rule.name
                     );
-                    (rule, chunk)
+                    chunks.into_iter().map(move |chunk| (rule, chunk))
                 })
-                .collect::<Vec<_>>()
+                .collect();
+
+            // Split matched files into chunks and create tasks
+            tasks.extend(
+                split_files(&matched_files, max_files)
+                    .into_iter()
+                    .map(|chunk| {
+                        trace!(
+                            "Created chunk with {} files for rule '{}'",
+                            chunk.len(),
+                            rule.name
+                        );
+                        (rule, chunk)
+                    }),
+            );
+            tasks
         })
-        .collect()
+        .collect();
+    interleave_by_rule(tasks)
 }
 
-fn build_globset(patterns: &[String], rule_name: &str, pattern_type: &str) -> Option<GlobSet> {
-    let mut builder = GlobSetBuilder::new();
-    for pattern in patterns {
-        match Glob::new(pattern) {
-            Ok(glob) => builder.add(glob),
-            Err(e) => {
-                warn!(
-                    "Invalid {} pattern '{}' in rule '{}': {}",
-                    pattern_type, pattern, rule_name, e
-                );
-                continue;
-            }
-        };
+/// Order tasks round-robin across rules, keeping each rule's tasks in order, so a rule
+/// split into many tasks doesn't hold every worker until it finishes and early results
+/// cover all rules
+fn interleave_by_rule(tasks: Vec<(&RuleBody, Vec<String>)>) -> Vec<(&RuleBody, Vec<String>)> {
+    let total = tasks.len();
+    let mut by_rule: Vec<std::collections::VecDeque<(&RuleBody, Vec<String>)>> = Vec::new();
+    for task in tasks {
+        match by_rule.iter_mut().find(|t| t[0].0.name == task.0.name) {
+            Some(rule_tasks) => rule_tasks.push_back(task),
+            None => by_rule.push([task].into()),
+        }
     }
-    match builder.build() {
-        Ok(gs) => Some(gs),
-        Err(e) => {
-            error!(
-                "Failed to build {} globset for rule '{}': {}",
-                pattern_type, rule_name, e
-            );
-            None
+    let mut interleaved = Vec::with_capacity(total);
+    while interleaved.len() < total {
+        for rule_tasks in &mut by_rule {
+            interleaved.extend(rule_tasks.pop_front());
         }
     }
+    interleaved
 }
 
-fn filter_files_by_scope(rule: &RuleBody, files: &[String]) -> Vec<String> {
-    let Some(globset) = build_globset(&rule.scope, &rule.name, "scope") else {
-        return vec![];
-    };
-    let Some(exclude_globset) = build_globset(&rule.exclude, &rule.name, "exclude") else {
-        return vec![];
+/// Position of each task among the tasks of its rule, for rules split into several tasks
+fn task_chunks(tasks: &[(&RuleBody, Vec<String>)]) -> Vec<Option<worker::Chunk>> {
+    let mut files_by_rule: HashMap<&str, Vec<&[String]>> = HashMap::new();
+    let mut indices = Vec::with_capacity(tasks.len());
+    for (rule, files) in tasks {
+        let siblings = files_by_rule.entry(&rule.name).or_default();
+        indices.push(siblings.len());
+        siblings.push(files);
+    }
+    let summaries: HashMap<&str, Arc<str>> = files_by_rule
+        .iter()
+        .filter(|(_, siblings)| siblings.len() > 1)
+        .map(|(rule, siblings)| (*rule, chunk_summary(siblings).into()))
+        .collect();
+    tasks
+        .iter()
+        .zip(indices)
+        .map(|((rule, _), index)| {
+            let summary = summaries.get(rule.name.as_str())?;
+            Some(worker::Chunk {
+                index,
+                count: files_by_rule[rule.name.as_str()].len(),
+                summary: summary.clone(),
+                reported: vec![],
+            })
+        })
+        .collect()
+}
+
+/// Count of a rule's files across its tasks, by directory, so each task's prompt stays
+/// the same size however many tasks the rule is split into
+fn chunk_summary(siblings: &[&[String]]) -> String {
+    let mut by_directory: BTreeMap<&str, usize> = BTreeMap::new();
+    for file in siblings.iter().copied().flatten() {
+        let directory = file.rsplit_once('/').map_or(".", |(dir, _)| dir);
+        *by_directory.entry(directory).or_default() += 1;
+    }
+    let total: usize = by_directory.values().sum();
+    let mut directories: Vec<String> = by_directory
+        .iter()
+        .take(MAX_SUMMARY_DIRECTORIES)
+        .map(|(dir, count)| format!("{} ({})", dir, count))
+        .collect();
+    if by_directory.len() > MAX_SUMMARY_DIRECTORIES {
+        directories.push(format!(
+            "and {} more directories",
+            by_directory.len() - MAX_SUMMARY_DIRECTORIES
+        ));
+    }
+    format!(
+        "Files of all tasks: {}, in {}\n",
+        total,
+        directories.join(", ")
+    )
+}
+
+/// Filter files by command line include/exclude globs (empty include matches all files).
+/// Unlike patterns in the config, an invalid glob is an error rather than skipped.
+fn filter_files_by_globs(
+    files: &[String],
+    include: &[String],
+    exclude: &[String],
+) -> Result<Vec<String>, String> {
+    let globset = |patterns: &[String], flag: &str| {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern)
+                .map_err(|e| format!("Invalid --{} pattern '{}': {}", flag, pattern, e))?;
+            builder.add(glob);
+        }
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build --{} globset: {}", flag, e))
     };
+    let include_globset = globset(include, "include")?;
+    let exclude_globset = globset(exclude, "exclude")?;
 
-    files
+    Ok(files
         .iter()
-        .filter(|f| globset.is_match(f) && !exclude_globset.is_match(f))
+        .filter(|f| {
+            (include.is_empty() || include_globset.is_match(f)) && !exclude_globset.is_match(f)
+        })
         .cloned()
-        .collect()
+        .collect())
 }
 
 fn split_files(files: &[String], max_per_task: usize) -> Vec<Vec<String>> {
@@ -328,8 +1350,8 @@ fn split_files(files: &[String], max_per_task: usize) -> Vec<Vec<String>> {
     }
 
     let total = files.len();
-    let num_chunks = (total + max_per_task - 1) / max_per_task;
-    let chunk_size = (total + num_chunks - 1) / num_chunks;
+    let num_chunks = total.div_ceil(max_per_task);
+    let chunk_size = total.div_ceil(num_chunks);
 
     files
         .chunks(chunk_size)
@@ -337,52 +1359,290 @@ fn split_files(files: &[String], max_per_task: usize) -> Vec<Vec<String>> {
         .collect()
 }
 
+/// Split tasks whose diffs exceed `max_tokens` into consecutive tasks that fit, so a few
+/// large files do not overflow one worker's context. A file larger than the budget gets
+/// a task of its own.
+fn split_by_tokens<'a>(
+    tasks: Vec<(&'a RuleBody, Vec<String>)>,
+    diff_tokens: &HashMap<String, usize>,
+    max_tokens: usize,
+) -> Vec<(&'a RuleBody, Vec<String>)> {
+    let mut split = Vec::with_capacity(tasks.len());
+    for (rule, files) in tasks {
+        let mut chunk: Vec<String> = Vec::new();
+        let mut chunk_tokens = 0;
+        for file in files {
+            let tokens = diff_tokens.get(&file).copied().unwrap_or_default();
+            if !chunk.is_empty() && chunk_tokens + tokens > max_tokens {
+                debug!(
+                    "Splitting task of rule '{}' at {} diff tokens",
+                    rule.name, chunk_tokens
+                );
+                split.push((rule, std::mem::take(&mut chunk)));
+                chunk_tokens = 0;
+            }
+            chunk_tokens += tokens;
+            chunk.push(file);
+        }
+        split.push((rule, chunk));
+    }
+    split
+}
+
+/// Tasks to re-run from a previous run's incomplete tasks, skipping rules no longer configured
+fn retry_tasks<'a>(
+    rules: &'a [RuleBody],
+    incomplete: &[render::IncompleteTask],
+) -> Vec<(&'a RuleBody, Vec<String>)> {
+    incomplete
+        .iter()
+        .filter_map(|task| {
+            let rule = rules.iter().find(|r| r.name == task.rule);
+            if rule.is_none() {
+                warn!("Cannot retry rule '{}': not in the config", task.rule);
+            }
+            rule.map(|rule| (rule, task.files.clone()))
+        })
+        .collect()
+}
+
+/// Tasks without a complete result: failed, timed out, cancelled, or never started
+fn incomplete_tasks(
+    task_keys: &[(String, Vec<String>)],
+    results: &[TaskResult],
+    not_started: &str,
+) -> Vec<render::IncompleteTask> {
+    let mut reasons = vec![Some(not_started.to_string()); task_keys.len()];
+    for (i, result, _) in results {
+        reasons[*i] = match result {
+            Ok(result) => result.incomplete.clone(),
+            Err(e) => Some(e.to_string()),
+        };
+    }
+    task_keys
+        .iter()
+        .zip(reasons)
+        .filter_map(|((rule, files), reason)| {
+            reason.map(|reason| render::IncompleteTask {
+                rule: rule.clone(),
+                files: files.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Sum token usage per rule across its tasks, failed ones included, with the number of
+/// tasks
+fn usage_by_rule(
+    results: &[TaskResult],
+    task_keys: &[(String, Vec<String>)],
+) -> BTreeMap<String, (usize, usage::TokenUsage)> {
+    let mut by_rule: BTreeMap<String, (usize, usage::TokenUsage)> = BTreeMap::new();
+    for (i, _, task_usage) in results {
+        let (tasks, usage) = by_rule.entry(task_keys[*i].0.clone()).or_default();
+        *tasks += 1;
+        usage.add(task_usage);
+    }
+    by_rule
+}
+
+/// Vendored files among `files`: those matching the `review.vendored` patterns or marked
+/// `linguist-vendored` in `.gitattributes`
+pub(crate) fn vendored_files(files: &[String], patterns: &[String]) -> HashSet<String> {
+    let mut vendored = util::vendored_by_attributes(files);
+    if let Some(globset) = build_globset(patterns, "review", "vendored") {
+        vendored.extend(files.iter().filter(|f| globset.is_match(f)).cloned());
+    }
+    vendored
+}
+
+/// Changed files left out of the review and why. A file skipped for several reasons is
+/// listed with the first of: excluded, sampled, vendored, out of scope, diff omitted.
+fn skipped_files(
+    changed_files: &[String],
+    review_files: &[String],
+    tasks: &[(&RuleBody, Vec<String>)],
+    sampled_out: &[String],
+    vendored: &HashSet<String>,
+) -> Vec<render::SkippedFile> {
+    let review_files: HashSet<&String> = review_files.iter().collect();
+    let sampled_out: HashSet<&String> = sampled_out.iter().collect();
+    let in_tasks: HashSet<&String> = tasks.iter().flat_map(|(_, files)| files).collect();
+    changed_files
+        .iter()
+        .filter_map(|file| {
+            let reason = if !review_files.contains(file) {
+                render::SkipReason::Excluded
+            } else if sampled_out.contains(file) {
+                render::SkipReason::Sampled
+            } else if !in_tasks.contains(file) && vendored.contains(file) {
+                render::SkipReason::Vendored
+            } else if !in_tasks.contains(file) {
+                render::SkipReason::OutOfScope
+            } else if !util::should_include_diff(file) {
+                render::SkipReason::DiffOmitted
+            } else {
+                return None;
+            };
+            Some(render::SkippedFile {
+                file: file.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Add a previous run's results, except for the rule and file pairs that were retried
+fn merge_previous(
+    grouped: &mut GroupedResults,
+    previous: &render::ViolationFile,
+    retried: &[(String, Vec<String>)],
+    rules: &[RuleBody],
+) {
+    let retried: HashSet<(&str, &str)> = retried
+        .iter()
+        .flat_map(|(rule, files)| files.iter().map(move |f| (rule.as_str(), f.as_str())))
+        .collect();
+    for (file, by_rule) in &previous.violations {
+        for (rule, violations) in by_rule {
+            if retried.contains(&(rule.as_str(), file.as_str())) {
+                continue;
+            }
+            grouped
+                .violations_by_file
+                .entry(file.clone())
+                .or_default()
+                .entry(rule.clone())
+                .or_default()
+                .extend(violations.iter().cloned());
+            if !violations.is_empty() && rules.iter().any(|r| r.name == *rule && r.blocks()) {
+                grouped.blocking_rules_with_violations.insert(rule.clone());
+            }
+        }
+    }
+    for (rule, tip) in &previous.tips {
+        grouped
+            .tips_by_rule
+            .entry(rule.clone())
+            .or_insert_with(|| tip.clone());
+    }
+}
+
+/// Keep the first violations of each rule up to its `max_violations` (or the global one),
+/// in file and line order. Returns the number of violations removed per rule.
+fn cap_violations(
+    violations_by_file: &mut render::ViolationsByFile,
+    rules: &[RuleBody],
+    max_violations: Option<usize>,
+) -> BTreeMap<String, usize> {
+    let mut kept: HashMap<String, usize> = HashMap::new();
+    let mut truncated = BTreeMap::new();
+    for by_rule in violations_by_file.values_mut() {
+        for (rule, violations) in by_rule.iter_mut() {
+            let limit = rules
+                .iter()
+                .find(|r| r.name == *rule)
+                .and_then(|r| r.max_violations)
+                .or(max_violations);
+            let Some(limit) = limit else {
+                continue;
+            };
+            let kept = kept.entry(rule.clone()).or_default();
+            let keep = limit.saturating_sub(*kept).min(violations.len());
+            *kept += keep;
+            if keep < violations.len() {
+                *truncated.entry(rule.clone()).or_default() += violations.len() - keep;
+                violations.truncate(keep);
+            }
+        }
+        by_rule.retain(|_, violations| !violations.is_empty());
+    }
+    violations_by_file.retain(|_, by_rule| !by_rule.is_empty());
+    truncated
+}
+
 struct GroupedResults {
-    violations_by_file: HashMap<String, HashMap<String, Vec<crate::types::Violation>>>,
-    tips_by_rule: HashMap<String, String>,
-    blocking_rules_with_violations: std::collections::HashSet<String>,
+    violations_by_file: render::ViolationsByFile,
+    tips_by_rule: BTreeMap<String, Tip>,
+    blocking_rules_with_violations: BTreeSet<String>,
     all_traces: Vec<render::TraceEntry>,
 }
 
 /// Group violations by file, then by rule name
 fn group_violations(
     results: Vec<Result<worker::WorkerResult, Box<dyn std::error::Error>>>,
+    suppressed: &HashSet<String>,
 ) -> GroupedResults {
-    let mut violations_by_file = HashMap::new();
-    let mut tips_by_rule = HashMap::new();
-    let mut blocking_rules_with_violations = std::collections::HashSet::new();
+    let mut violations_by_file = render::ViolationsByFile::new();
+    let mut tips_by_rule = BTreeMap::new();
+    let mut blocking_rules_with_violations = BTreeSet::new();
     let mut all_traces = Vec::new();
+    let mut suppressed_count = 0;
 
-    for result in results {
-        if let Ok(worker_result) = result {
-            let has_violations = !worker_result.violations.is_empty();
-            for violation in &worker_result.violations {
-                violations_by_file
-                    .entry(violation.file.clone())
-                    .or_insert_with(HashMap::new)
-                    .entry(worker_result.rule.name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(violation.clone());
-            }
-            if has_violations && worker_result.blocking {
-                blocking_rules_with_violations.insert(worker_result.rule.name.clone());
-            }
-            if let Some(tip) = &worker_result.rule.tip {
-                tips_by_rule.insert(worker_result.rule.name.clone(), tip.clone());
+    for worker_result in results.into_iter().flatten() {
+        let mut has_violations = false;
+        for violation in &worker_result.violations {
+            // Skip violations suppressed during triage
+            if suppressed.contains(&triage::suppression_fingerprint(
+                &worker_result.rule.name,
+                violation,
+            )) {
+                suppressed_count += 1;
+                continue;
             }
-            if let Some(messages) = worker_result.messages {
-                all_traces.push(render::TraceEntry {
-                    worker_id: worker_result.worker_id,
-                    rule: worker_result.rule,
-                    files: worker_result.files,
-                    elapsed_secs: worker_result.elapsed_secs,
-                    tools: worker_result.tools.unwrap_or_default(),
-                    messages,
+            has_violations = true;
+            violations_by_file
+                .entry(violation.file.clone())
+                .or_default()
+                .entry(worker_result.rule.name.clone())
+                .or_default()
+                .push(Violation {
+                    rule_id: Some(worker_result.rule.id()),
+                    ..violation.clone()
                 });
-            }
+        }
+        if has_violations && worker_result.blocking {
+            blocking_rules_with_violations.insert(worker_result.rule.name.clone());
+        }
+        if let Some(tip) = &worker_result.rule.tip {
+            tips_by_rule.insert(worker_result.rule.name.clone(), tip.clone());
+        }
+        if let Some(messages) = worker_result.messages {
+            all_traces.push(render::TraceEntry {
+                worker_id: worker_result.worker_id,
+                rule: worker_result.rule,
+                files: worker_result.files,
+                elapsed_secs: worker_result.elapsed_secs,
+                resource_bytes: worker_result.resource_bytes,
+                compactions: worker_result.compactions,
+                usage: worker_result.usage,
+                tools: worker_result.tools.unwrap_or_default(),
+                messages,
+            });
         }
     }
 
+    if suppressed_count > 0 {
+        info!("Suppressed {} violation(s)", suppressed_count);
+    }
+
+    // Sort for deterministic output regardless of worker completion order
+    for violations in violations_by_file
+        .values_mut()
+        .flat_map(|rules| rules.values_mut())
+    {
+        violations.sort_by(|a, b| {
+            (a.start_line, a.end_line, &a.detail).cmp(&(b.start_line, b.end_line, &b.detail))
+        });
+    }
+    all_traces.sort_by(|a, b| {
+        let key = |id: &str| id.parse::<usize>().unwrap_or(usize::MAX);
+        key(&a.worker_id)
+            .cmp(&key(&b.worker_id))
+            .then_with(|| a.worker_id.cmp(&b.worker_id))
+    });
+
     GroupedResults {
         violations_by_file,
         tips_by_rule,
@@ -391,14 +1651,65 @@ fn group_violations(
     }
 }
 
+/// Derive a parallel worker cap from CPU count and a requests-per-minute limit,
+/// returning the cap and the limiting factor
+fn derive_max_parallel_workers(cpus: usize, requests_per_minute: Option<u32>) -> (usize, String) {
+    let cpu_cap = cpus.max(1) * WORKERS_PER_CPU;
+    if let Some(rpm) = requests_per_minute {
+        let rpm_cap = (rpm / REQUESTS_PER_WORKER_PER_MINUTE).max(1) as usize;
+        if rpm_cap < cpu_cap {
+            return (rpm_cap, format!("{} requests/min", rpm));
+        }
+    }
+    (cpu_cap, format!("{} CPUs", cpus))
+}
+
+/// Probe the provider's model list once, unless the model's capabilities and the worker cap
+/// are all configured, and gate features on the capabilities of `llm.model`
+pub async fn probe_provider(config: &mut crate::config::Config, api_key: &str) -> ProviderProbe {
+    let llm = &config.llm;
+    let probe = if llm.capabilities.is_complete() && config.review.max_parallel_workers.is_some() {
+        ProviderProbe::default()
+    } else {
+        ProviderProbe::fetch(api_key, &llm.endpoint(), &llm.headers, llm.provider).await
+    };
+    let capabilities = llm.capabilities.or(probe.capabilities(&llm.model));
+    config.apply_capabilities(&capabilities);
+    probe
+}
+
+/// Resolve the maximum number of parallel workers: the configured value, or a cap derived from
+/// CPU count and the lower of the configured and provider-advertised requests-per-minute limits
+pub fn resolve_max_parallel_workers(
+    review: &crate::config::ReviewConfig,
+    probe: &ProviderProbe,
+) -> usize {
+    if let Some(max) = review.max_parallel_workers {
+        info!("Max parallel workers: {} (configured)", max);
+        return max;
+    }
+    let advertised = probe.requests_per_minute;
+    if let Some(rpm) = advertised {
+        debug!("Provider advertises {} requests/min", rpm);
+    }
+    let rpm = match (review.requests_per_minute, advertised) {
+        (Some(configured), Some(advertised)) => Some(configured.min(advertised)),
+        (configured, advertised) => configured.or(advertised),
+    };
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let (max, reason) = derive_max_parallel_workers(cpus, rpm);
+    info!("Max parallel workers: {} (derived from {})", max, reason);
+    max
+}
+
 /// Execute workers with optional concurrency limit
-async fn execute_workers<F>(
+async fn execute_workers<F, T>(
     futures: Vec<F>,
     max_parallel_workers: Option<usize>,
     shutdown: Arc<Mutex<bool>>,
-) -> Vec<Result<worker::WorkerResult, Box<dyn std::error::Error>>>
+) -> Vec<T>
 where
-    F: std::future::Future<Output = Result<worker::WorkerResult, Box<dyn std::error::Error>>>,
+    F: std::future::Future<Output = T>,
 {
     if let Some(max_workers) = max_parallel_workers {
         // Limit parallel execution using a worker pool
@@ -436,11 +1747,11 @@ where
 
 /// Log worker results and return success/failure counts
 async fn log_results(
-    results: &[Result<worker::WorkerResult, Box<dyn std::error::Error>>],
+    results: &[TaskResult],
     total_tasks: usize,
     shutdown: &Arc<Mutex<bool>>,
 ) -> (usize, usize, bool) {
-    for (i, result) in results.iter().enumerate() {
+    for (i, result, _) in results {
         if let Err(e) = result {
             error!("[Worker {}] Task failed: {}", i, e);
         } else {
@@ -448,7 +1759,7 @@ async fn log_results(
         }
     }
 
-    let failed = results.iter().filter(|r| r.is_err()).count();
+    let failed = results.iter().filter(|(_, r, _)| r.is_err()).count();
     let succeeded = results.len() - failed;
     let was_interrupted = *shutdown.lock().await;
     if was_interrupted {
@@ -468,11 +1779,11 @@ async fn log_results(
     (succeeded, failed, was_interrupted)
 }
 
-/// Exit with error if blocking rules have violations
+/// Exit code failing the review if blocking rules have violations
 fn check_blocking_violations(
-    blocking_rules_with_violations: &std::collections::HashSet<String>,
+    blocking_rules_with_violations: &BTreeSet<String>,
     config_path: &str,
-) {
+) -> Option<i32> {
     if !blocking_rules_with_violations.is_empty() {
         error!(
             "Blocking rules with violations: {:?}",
@@ -482,22 +1793,93 @@ fn check_blocking_violations(
             "If violations are misreported, refine rules in {}",
             config_path
         );
-        std::process::exit(EXIT_FAILURE);
+        return Some(EXIT_FAILURE);
     }
+    None
 }
 
-/// Exit with error if any workers failed
-fn check_worker_failures(failed: usize) {
+/// Exit code failing the review if any workers failed. With `fail_on_incomplete`
+/// (`firekeeper ci`), failed, timed-out and interrupted tasks exit with a distinct code,
+/// telling an incomplete review apart from blocking violations
+fn check_worker_failures(
+    failed: usize,
+    incomplete: usize,
+    fail_on_incomplete: bool,
+) -> Option<i32> {
+    if fail_on_incomplete && incomplete > 0 {
+        error!("{} task(s) incomplete", incomplete);
+        return Some(EXIT_INCOMPLETE);
+    }
     if failed > 0 {
         error!("{} worker(s) failed", failed);
-        std::process::exit(EXIT_FAILURE);
+        return Some(EXIT_FAILURE);
     }
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_derive_max_parallel_workers() {
+        assert_eq!(derive_max_parallel_workers(8, None), (32, "8 CPUs".into()));
+        assert_eq!(
+            derive_max_parallel_workers(8, Some(60)),
+            (10, "60 requests/min".into())
+        );
+        assert_eq!(derive_max_parallel_workers(2, Some(10_000)).0, 8);
+        assert_eq!(derive_max_parallel_workers(2, Some(1)).0, 1);
+    }
+
+    #[test]
+    fn test_skipped_files() {
+        let files = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let rule = RuleBody::no_magic_numbers();
+        let changed = files(&["a.rs", "b.rs", "c.md", "Cargo.lock", "d.rs", "vendor/e.rs"]);
+        let review = files(&["a.rs", "c.md", "Cargo.lock", "d.rs", "vendor/e.rs"]);
+        let tasks = vec![(&rule, files(&["a.rs", "Cargo.lock"]))];
+        let vendored = HashSet::from(["vendor/e.rs".to_string()]);
+        let skipped: Vec<(String, render::SkipReason)> =
+            skipped_files(&changed, &review, &tasks, &files(&["d.rs"]), &vendored)
+                .into_iter()
+                .map(|s| (s.file, s.reason))
+                .collect();
+        assert_eq!(
+            skipped,
+            [
+                ("b.rs".to_string(), render::SkipReason::Excluded),
+                ("c.md".to_string(), render::SkipReason::OutOfScope),
+                ("Cargo.lock".to_string(), render::SkipReason::DiffOmitted),
+                ("d.rs".to_string(), render::SkipReason::Sampled),
+                ("vendor/e.rs".to_string(), render::SkipReason::Vendored),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_orchestrate_skips_vendored() {
+        let files = ["src/a.rs".to_string(), "third_party/b.rs".into()];
+        let vendored = vendored_files(&files, &["third_party/**".into()]);
+        let license = RuleBody {
+            name: "License".into(),
+            vendored: true,
+            ..RuleBody::no_magic_numbers()
+        };
+        let rules = [RuleBody::no_magic_numbers(), license];
+        let tasks: Vec<(&str, Vec<String>)> = orchestrate(&rules, &files, None, &vendored, 10)
+            .into_iter()
+            .map(|(rule, files)| (rule.name.as_str(), files))
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                ("No Magic Numbers", vec!["src/a.rs".to_string()]),
+                ("License", files.to_vec()),
+            ]
+        );
+    }
+
     #[test]
     fn test_split_files_empty() {
         let files: Vec<String> = vec![];
@@ -541,28 +1923,462 @@ mod tests {
     }
 
     #[test]
-    fn test_filter_files_by_scope_with_exclude() {
+    fn test_split_by_tokens() {
+        let rule = RuleBody::no_magic_numbers();
+        let files: Vec<String> = ["a.rs", "b.rs", "c.rs", "d.rs"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let diff_tokens = HashMap::from([
+            ("a.rs".to_string(), 40),
+            ("b.rs".to_string(), 50),
+            ("c.rs".to_string(), 300),
+            ("d.rs".to_string(), 10),
+        ]);
+        let split = split_by_tokens(vec![(&rule, files)], &diff_tokens, 100);
+        let split: Vec<&[String]> = split.iter().map(|(_, files)| files.as_slice()).collect();
+        assert_eq!(split, [&["a.rs", "b.rs"][..], &["c.rs"][..], &["d.rs"][..]]);
+    }
+
+    #[test]
+    fn test_task_chunks() {
+        let split = RuleBody::no_magic_numbers();
+        let single = RuleBody::inline("Check", &[]);
+        let files = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let tasks = [
+            (&split, files(&["a.rs"])),
+            (&single, files(&["a.rs", "b.rs"])),
+            (&split, files(&["b.rs"])),
+        ];
+
+        let chunks: Vec<_> = task_chunks(&tasks)
+            .into_iter()
+            .map(|chunk| chunk.map(|c| (c.index, c.count, c.summary.to_string())))
+            .collect();
+        let summary = "Files of all tasks: 2, in . (2)\n".to_string();
+        assert_eq!(
+            chunks,
+            [Some((0, 2, summary.clone())), None, Some((1, 2, summary))]
+        );
+    }
+
+    #[test]
+    fn test_chunk_summary() {
+        let a = ["src/a.rs".to_string(), "src/b.rs".into()];
+        let b = ["src/c.rs".to_string(), "tests/d.rs".into()];
+        assert_eq!(
+            chunk_summary(&[&a, &b]),
+            "Files of all tasks: 4, in src (3), tests (1)\n"
+        );
+        let many: Vec<String> = (0..12).map(|i| format!("d{:02}/f.rs", i)).collect();
+        assert!(chunk_summary(&[&many]).ends_with("d09 (1), and 2 more directories\n"));
+    }
+
+    #[test]
+    fn test_dir_scope() {
         let rule = RuleBody {
-            name: "Test Rule".into(),
-            description: "Test".into(),
-            instruction: "Test".into(),
-            scope: vec!["src/**/*.rs".into()],
-            exclude: vec!["**/tests/**".into(), "**/*_test.rs".into()],
+            scope: vec!["dir:services/*/**".into(), "docs/*.md".into()],
+            exclude: vec!["**/*.lock".into()],
+            max_files_per_task: Some(2),
+            ..RuleBody::no_magic_numbers()
+        };
+        let files: Vec<String> = vec![
+            "services/payments/api.rs".into(),
+            "services/payments/db/migrate.sql".into(),
+            "services/payments/db/schema.sql".into(),
+            "services/payments/Cargo.lock".into(),
+            "services/auth/login.rs".into(),
+            "services/README.md".into(),
+            "docs/a.md".into(),
+            "docs/b.md".into(),
+        ];
+
+        // Directory groups are kept apart from other files, each split by max_files_per_task
+        let rules = [rule];
+        let tasks: Vec<Vec<String>> = orchestrate(&rules, &files, None, &HashSet::new(), 10)
+            .into_iter()
+            .map(|(_, files)| files)
+            .collect();
+        assert_eq!(
+            tasks,
+            [
+                vec!["services/auth/login.rs".to_string()],
+                vec![
+                    "services/payments/api.rs".into(),
+                    "services/payments/db/migrate.sql".into()
+                ],
+                vec!["services/payments/db/schema.sql".into()],
+                vec!["docs/a.md".into(), "docs/b.md".into()],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tasks_interleaved_across_rules() {
+        let a = RuleBody {
+            name: "A".into(),
+            max_files_per_task: Some(1),
+            ..RuleBody::no_magic_numbers()
+        };
+        let b = RuleBody {
+            name: "B".into(),
+            ..a.clone()
+        };
+        let rules = [a, b];
+        let files = ["x.rs".to_string(), "y.rs".into(), "z.rs".into()];
+        let mut tasks = orchestrate(&rules, &files[..2], None, &HashSet::new(), 10)
+            .into_iter()
+            .map(|(rule, files)| format!("{}:{}", rule.name, files[0]));
+        assert_eq!(tasks.next().unwrap(), "A:x.rs");
+        assert_eq!(tasks.next().unwrap(), "B:x.rs");
+        assert_eq!(tasks.next().unwrap(), "A:y.rs");
+
+        let order: Vec<String> = interleave_by_rule(vec![
+            (&rules[0], vec![files[0].clone()]),
+            (&rules[0], vec![files[1].clone()]),
+            (&rules[0], vec![files[2].clone()]),
+            (&rules[1], vec![files[0].clone()]),
+        ])
+        .into_iter()
+        .map(|(rule, files)| format!("{}:{}", rule.name, files[0]))
+        .collect();
+        assert_eq!(order, ["A:x.rs", "B:x.rs", "A:y.rs", "A:z.rs"]);
+    }
+
+    #[test]
+    fn test_group_violations_sorted() {
+        let rule = RuleBody {
+            id: None,
+            name: "Rule".into(),
+            description: String::new(),
+            instruction: String::new(),
+            scope: vec![],
+            exclude: vec![],
+            max_files_per_task: None,
+            blocking: Some(true),
+            tip: None,
+            resources: vec![],
+            tags: vec![],
+            tools: Default::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
+        };
+        let violation = |file: &str, line: u32| crate::types::Violation {
+            file: file.into(),
+            detail: "detail".into(),
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
+        };
+        let result = |id: &str, violations| {
+            Ok(worker::WorkerResult {
+                worker_id: id.into(),
+                rule: rule.clone(),
+                files: vec![],
+                blocking: true,
+                violations,
+                messages: Some(vec![]),
+                tools: None,
+                elapsed_secs: 0.0,
+                resource_bytes: 0,
+                compactions: vec![],
+                incomplete: None,
+                usage: Default::default(),
+            })
+        };
+
+        let grouped = group_violations(
+            vec![
+                result("10", vec![violation("b.rs", 9), violation("a.rs", 3)]),
+                result("2", vec![violation("a.rs", 1)]),
+            ],
+            &HashSet::new(),
+        );
+
+        let files: Vec<_> = grouped.violations_by_file.keys().collect();
+        assert_eq!(files, vec!["a.rs", "b.rs"]);
+        let lines: Vec<_> = grouped.violations_by_file["a.rs"]["Rule"]
+            .iter()
+            .map(|v| v.start_line)
+            .collect();
+        assert_eq!(lines, vec![1, 3]);
+        let ids: Vec<_> = grouped
+            .all_traces
+            .iter()
+            .map(|t| t.worker_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["2", "10"]);
+    }
+
+    #[test]
+    fn test_group_violations_suppressed() {
+        let rule = RuleBody {
+            id: None,
+            name: "Rule".into(),
+            description: String::new(),
+            instruction: String::new(),
+            scope: vec![],
+            exclude: vec![],
             max_files_per_task: None,
-            blocking: true,
+            blocking: Some(true),
             tip: None,
             resources: vec![],
+            tags: vec![],
+            tools: Default::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
+        };
+        let violation = crate::types::Violation {
+            file: "a.rs".into(),
+            detail: "detail".into(),
+            start_line: 1,
+            end_line: 1,
+            rule_id: None,
+            owners: vec![],
+        };
+        let suppressed = HashSet::from([triage::suppression_fingerprint("Rule", &violation)]);
+        let grouped = group_violations(
+            vec![Ok(worker::WorkerResult {
+                worker_id: "1".into(),
+                rule,
+                files: vec![],
+                blocking: true,
+                violations: vec![violation],
+                messages: None,
+                tools: None,
+                elapsed_secs: 0.0,
+                resource_bytes: 0,
+                compactions: vec![],
+                incomplete: None,
+                usage: Default::default(),
+            })],
+            &suppressed,
+        );
+
+        assert!(grouped.violations_by_file.is_empty());
+        assert!(grouped.blocking_rules_with_violations.is_empty());
+    }
+
+    #[test]
+    fn test_retry_merges_previous_results() {
+        let rule = RuleBody {
+            name: "Rule".into(),
+            tip: None,
+            ..RuleBody::no_magic_numbers()
+        };
+        let violation = |file: &str, line: u32| crate::types::Violation {
+            file: file.into(),
+            detail: "detail".into(),
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
         };
+        let task_keys = vec![
+            ("Rule".to_string(), vec!["a.rs".to_string()]),
+            ("Rule".to_string(), vec!["b.rs".to_string()]),
+            ("Rule".to_string(), vec!["c.rs".to_string()]),
+        ];
+        let results: Vec<TaskResult> = vec![
+            (
+                0,
+                Err("provider error".into()),
+                usage::TokenUsage {
+                    requests: 1,
+                    prompt_tokens: 100,
+                    ..Default::default()
+                },
+            ),
+            (
+                1,
+                Ok(worker::WorkerResult {
+                    worker_id: "1".into(),
+                    rule: rule.clone(),
+                    files: vec!["b.rs".into()],
+                    blocking: rule.is_blocking(),
+                    violations: vec![violation("b.rs", 2)],
+                    messages: None,
+                    tools: None,
+                    elapsed_secs: 0.0,
+                    resource_bytes: 0,
+                    compactions: vec![],
+                    incomplete: Some("timed out after 300s".into()),
+                    usage: Default::default(),
+                }),
+                usage::TokenUsage {
+                    requests: 1,
+                    prompt_tokens: 50,
+                    completion_tokens: 10,
+                    ..Default::default()
+                },
+            ),
+        ];
+        let (tasks, usage) = &usage_by_rule(&results, &task_keys)["Rule"];
+        assert_eq!((*tasks, usage.requests, usage.total()), (2, 2, 160));
+        let reasons: Vec<_> = incomplete_tasks(&task_keys, &results, "not started before shutdown")
+            .into_iter()
+            .map(|t| (t.files[0].clone(), t.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("a.rs".to_string(), "provider error".to_string()),
+                ("b.rs".to_string(), "timed out after 300s".to_string()),
+                (
+                    "c.rs".to_string(),
+                    "not started before shutdown".to_string()
+                ),
+            ]
+        );
 
+        // Retrying b.rs replaces its partial violations and keeps a.rs as reported
+        let previous = render::ViolationFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            violations: BTreeMap::from([
+                (
+                    "a.rs".to_string(),
+                    BTreeMap::from([("Rule".to_string(), vec![violation("a.rs", 1)])]),
+                ),
+                (
+                    "b.rs".to_string(),
+                    BTreeMap::from([("Rule".to_string(), vec![violation("b.rs", 2)])]),
+                ),
+            ]),
+            tips: BTreeMap::from([("Rule".to_string(), "tip".into())]),
+            language: None,
+            skipped: vec![],
+            incomplete: vec![],
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        let mut grouped = group_violations(
+            results.into_iter().skip(1).map(|(_, r, _)| r).collect(),
+            &HashSet::new(),
+        );
+        merge_previous(
+            &mut grouped,
+            &previous,
+            &task_keys[1..2],
+            std::slice::from_ref(&rule),
+        );
+        assert_eq!(grouped.violations_by_file["a.rs"]["Rule"].len(), 1);
+        assert_eq!(grouped.violations_by_file["b.rs"]["Rule"].len(), 1);
+        assert_eq!(grouped.tips_by_rule["Rule"], Tip::from("tip"));
+        assert_eq!(
+            grouped.blocking_rules_with_violations.contains("Rule"),
+            rule.is_blocking()
+        );
+    }
+
+    #[test]
+    fn test_filter_files_by_globs() {
         let files = vec![
-            "src/main.rs".into(),
-            "src/lib.rs".into(),
-            "src/tests/helper.rs".into(),
-            "src/util_test.rs".into(),
-            "src/util.rs".into(),
+            "src/payments/a.rs".to_string(),
+            "src/payments/a_test.rs".to_string(),
+            "src/main.rs".to_string(),
         ];
+        assert_eq!(filter_files_by_globs(&files, &[], &[]).unwrap(), files);
+        assert_eq!(
+            filter_files_by_globs(
+                &files,
+                &["src/payments/**".to_string()],
+                &["**/*_test.rs".to_string()]
+            )
+            .unwrap(),
+            vec!["src/payments/a.rs"]
+        );
+        assert_eq!(
+            filter_files_by_globs(&files, &[], &["src/payments/**".to_string()]).unwrap(),
+            vec!["src/main.rs"]
+        );
+        assert_eq!(
+            filter_files_by_globs(&files, &[], &["src/[a".to_string()]).unwrap_err(),
+            "Invalid --exclude pattern 'src/[a': error parsing glob 'src/[a': unclosed character class; missing ']'"
+        );
+    }
+
+    #[test]
+    fn test_cap_violations() {
+        let violation = |file: &str, line: u32| crate::types::Violation {
+            file: file.into(),
+            detail: "detail".into(),
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
+        };
+        let capped = RuleBody {
+            name: "Capped".into(),
+            max_violations: Some(2),
+            ..RuleBody::no_magic_numbers()
+        };
+        let mut violations_by_file = BTreeMap::from([
+            (
+                "a.rs".to_string(),
+                BTreeMap::from([
+                    ("Capped".to_string(), vec![violation("a.rs", 1)]),
+                    ("Other".to_string(), vec![violation("a.rs", 1)]),
+                ]),
+            ),
+            (
+                "b.rs".to_string(),
+                BTreeMap::from([(
+                    "Capped".to_string(),
+                    vec![violation("b.rs", 1), violation("b.rs", 2)],
+                )]),
+            ),
+            (
+                "c.rs".to_string(),
+                BTreeMap::from([("Capped".to_string(), vec![violation("c.rs", 1)])]),
+            ),
+        ]);
+        let truncated = cap_violations(&mut violations_by_file, &[capped], Some(10));
+        assert_eq!(truncated, BTreeMap::from([("Capped".to_string(), 2)]));
+        assert_eq!(violations_by_file["a.rs"].len(), 2);
+        assert_eq!(violations_by_file["b.rs"]["Capped"].len(), 1);
+        assert!(!violations_by_file.contains_key("c.rs"));
+    }
+
+    #[test]
+    fn test_save_last_run() {
+        let results = render::ViolationFile {
+            version: "0".into(),
+            violations: BTreeMap::new(),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: vec![],
+            incomplete: vec![],
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        assert_eq!(
+            write_output("results.txt", &results, false, None).unwrap_err(),
+            "Output file must end with .md or .json: results.txt"
+        );
 
-        let result = filter_files_by_scope(&rule, &files);
-        assert_eq!(result, vec!["src/main.rs", "src/lib.rs", "src/util.rs"]);
+        let temp =
+            crate::util::temp::TempDir::new(&format!("firekeeper-last-run-{}", std::process::id()));
+        let dir = temp.path().to_string_lossy().to_string();
+        save_last_run(&dir, &results, &[]).unwrap();
+        let saved = render::ViolationFile::load(&format!("{}/results.json", dir)).unwrap();
+        assert_eq!(saved.version, "0");
+        assert!(!std::path::Path::new(&dir).join("trace.json").exists());
     }
 }
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+use tracing::{debug, warn};
+
+/// Root manifests excerpted in the overview, in display order
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "Gemfile",
+    "composer.json",
+    "CMakeLists.txt",
+];
+
+/// Lines kept from each manifest, enough for name, version and main dependencies
+const MANIFEST_LINES: usize = 40;
+
+/// Top-level entries listed before the rest are summarized
+const MAX_TOP_LEVEL_ENTRIES: usize = 50;
+
+/// Languages listed in the breakdown
+const MAX_LANGUAGES: usize = 10;
+
+/// Generate the repository overview for the current directory from `git ls-files`.
+///
+/// Returns an empty string (with a warning) outside a git repository.
+pub fn repo_overview() -> String {
+    let output = match Command::new("git").args(["ls-files"]).output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            warn!(
+                "Skipping repository overview: git ls-files failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return String::new();
+        }
+        Err(e) => {
+            warn!("Skipping repository overview: {}", e);
+            return String::new();
+        }
+    };
+    let files: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let overview = build_overview(&files, |path| std::fs::read_to_string(path).ok());
+    debug!(
+        "Built repository overview of {} bytes from {} files",
+        overview.len(),
+        files.len()
+    );
+    overview
+}
+
+/// Build the overview: top-level tree, language breakdown and manifest excerpts
+pub fn build_overview(files: &[String], read: impl Fn(&str) -> Option<String>) -> String {
+    if files.is_empty() {
+        return String::new();
+    }
+    let mut output = format!(
+        "### Top-Level Layout\n\n{}\n### Languages\n\n{}",
+        top_level_tree(files),
+        language_breakdown(files)
+    );
+    for manifest in MANIFESTS {
+        if !files.iter().any(|f| f == manifest) {
+            continue;
+        }
+        let Some(content) = read(manifest) else {
+            continue;
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let mut excerpt = lines[..lines.len().min(MANIFEST_LINES)].join("\n");
+        if lines.len() > MANIFEST_LINES {
+            excerpt.push_str(&format!(
+                "\n... ({} more lines)",
+                lines.len() - MANIFEST_LINES
+            ));
+        }
+        let fence = super::render::get_fence_backticks(&excerpt);
+        output.push_str(&format!(
+            "\n### {}\n\n{}\n{}\n{}\n",
+            manifest, fence, excerpt, fence
+        ));
+    }
+    output
+}
+
+/// Top-level files and directories, with file counts for directories
+fn top_level_tree(files: &[String]) -> String {
+    // Directories count their files; top-level files stay at zero
+    let mut entries: BTreeMap<&str, usize> = BTreeMap::new();
+    for file in files {
+        match file.split_once('/') {
+            Some((dir, _)) => {
+                *entries.entry(dir).or_default() += 1;
+            }
+            None => {
+                entries.entry(file).or_default();
+            }
+        }
+    }
+    let mut output = String::new();
+    for (name, count) in entries.iter().take(MAX_TOP_LEVEL_ENTRIES) {
+        if *count > 0 {
+            output.push_str(&format!("- {}/ ({} files)\n", name, count));
+        } else {
+            output.push_str(&format!("- {}\n", name));
+        }
+    }
+    if entries.len() > MAX_TOP_LEVEL_ENTRIES {
+        output.push_str(&format!(
+            "- ... ({} more entries)\n",
+            entries.len() - MAX_TOP_LEVEL_ENTRIES
+        ));
+    }
+    output
+}
+
+/// File counts per language, most common first
+fn language_breakdown(files: &[String]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for file in files {
+        if let Some(language) = language(file) {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+    if counts.is_empty() {
+        return "- (no recognized source files)\n".to_string();
+    }
+    let total: usize = counts.values().sum();
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counts
+        .iter()
+        .take(MAX_LANGUAGES)
+        .map(|(language, count)| {
+            format!(
+                "- {}: {} files ({}%)\n",
+                language,
+                count,
+                count * 100 / total
+            )
+        })
+        .collect()
+}
+
+/// Language of a source file by extension
+fn language(file: &str) -> Option<&'static str> {
+    let extension = file.rsplit_once('.')?.1;
+    Some(match extension {
+        "rs" => "Rust",
+        "py" => "Python",
+        "js" | "mjs" | "cjs" | "jsx" => "JavaScript",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "scala" => "Scala",
+        "dart" => "Dart",
+        "ex" | "exs" => "Elixir",
+        "sh" | "bash" => "Shell",
+        "sql" => "SQL",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "html" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        "md" | "mdx" => "Markdown",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_overview() {
+        let files: Vec<String> = [
+            "Cargo.toml",
+            "README.md",
+            "src/main.rs",
+            "src/lib.rs",
+            "src/util/mod.rs",
+            "scripts/build.sh",
+        ]
+        .iter()
+        .map(|f| f.to_string())
+        .collect();
+        let overview = build_overview(&files, |path| {
+            (path == "Cargo.toml").then(|| "[package]\nname = \"demo\"".to_string())
+        });
+        assert_eq!(
+            overview,
+            "### Top-Level Layout\n\n\
+             - Cargo.toml\n- README.md\n- scripts/ (1 files)\n- src/ (3 files)\n\n\
+             ### Languages\n\n\
+             - Rust: 3 files (60%)\n- Markdown: 1 files (20%)\n- Shell: 1 files (20%)\n\n\
+             ### Cargo.toml\n\n```\n[package]\nname = \"demo\"\n```\n"
+        );
+        assert_eq!(build_overview(&[], |_| None), "");
+    }
+}
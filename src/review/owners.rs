@@ -0,0 +1,174 @@
+use super::render::ViolationsByFile;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+/// CODEOWNERS locations, in the order GitHub and GitLab look them up
+const CODEOWNERS_PATHS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// Owners for a path pattern
+struct Entry {
+    matcher: GlobSet,
+    owners: Vec<String>,
+}
+
+/// File ownership from CODEOWNERS and the `[owners]` config table.
+///
+/// The last matching entry wins: CODEOWNERS lines in file order, then config patterns
+/// from shortest to longest so the most specific configured pattern applies.
+#[derive(Default)]
+pub struct Owners {
+    entries: Vec<Entry>,
+}
+
+impl Owners {
+    /// Load the first CODEOWNERS file found, then add the configured patterns
+    pub fn load(configured: &BTreeMap<String, Vec<String>>) -> Self {
+        let codeowners = CODEOWNERS_PATHS.iter().find_map(|path| {
+            let content = std::fs::read_to_string(path).ok()?;
+            debug!("Loaded code owners from {}", path);
+            Some(content)
+        });
+        let mut patterns = codeowners
+            .map(|content| parse_codeowners(&content))
+            .unwrap_or_default();
+        let mut configured: Vec<_> = configured
+            .iter()
+            .map(|(pattern, owners)| (pattern.clone(), owners.clone()))
+            .collect();
+        configured.sort_by_key(|(pattern, _)| pattern.len());
+        patterns.extend(configured);
+        Self::new(&patterns)
+    }
+
+    /// Build from (pattern, owners) pairs in CODEOWNERS syntax, where later pairs take precedence
+    pub fn new(patterns: &[(String, Vec<String>)]) -> Self {
+        let entries = patterns
+            .iter()
+            .filter_map(|(pattern, owners)| {
+                let mut builder = GlobSetBuilder::new();
+                for glob in pattern_globs(pattern) {
+                    match GlobBuilder::new(&glob).literal_separator(true).build() {
+                        Ok(glob) => {
+                            builder.add(glob);
+                        }
+                        Err(e) => {
+                            warn!("Invalid owners pattern '{}': {}", pattern, e);
+                            return None;
+                        }
+                    }
+                }
+                Some(Entry {
+                    matcher: builder.build().ok()?,
+                    owners: owners.clone(),
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Owners of a file, empty if unowned
+    pub fn owners_of(&self, file: &str) -> &[String] {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.matcher.is_match(file))
+            .map_or(&[], |entry| entry.owners.as_slice())
+    }
+
+    /// Set the owners of every violation from its file
+    pub fn annotate(&self, violations_by_file: &mut ViolationsByFile) {
+        for (file, rules) in violations_by_file.iter_mut() {
+            let owners = self.owners_of(file);
+            for violation in rules.values_mut().flatten() {
+                violation.owners = owners.to_vec();
+            }
+        }
+    }
+}
+
+/// Parse CODEOWNERS lines into (pattern, owners) pairs, skipping comments and blank lines.
+/// A pattern without owners clears ownership, as in CODEOWNERS.
+fn parse_codeowners(content: &str) -> Vec<(String, Vec<String>)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split_once(" #").map_or(line, |(line, _)| line).trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?.to_string();
+            Some((pattern, parts.map(str::to_string).collect()))
+        })
+        .collect()
+}
+
+/// Globs matching a CODEOWNERS (gitignore-style) pattern: a directory pattern (trailing
+/// slash) matches everything under it, a pattern without a slash matches a file or directory
+/// of that name at any depth, and other patterns match paths from the repository root
+fn pattern_globs(pattern: &str) -> Vec<String> {
+    let is_dir = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    // A slash at the start or in the middle anchors the pattern to the repository root
+    let anchored = pattern.trim_start_matches("**/").contains('/');
+    let mut glob = pattern.trim_start_matches('/').to_string();
+    if !anchored && !glob.starts_with("**") {
+        glob = format!("**/{}", glob);
+    }
+    if is_dir {
+        vec![format!("{}/**", glob)]
+    } else if !anchored && !glob.ends_with("**") {
+        vec![glob.clone(), format!("{}/**", glob)]
+    } else {
+        vec![glob]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owners_of() {
+        let patterns = parse_codeowners(
+            "# Default owners\n\
+             * @org/all\n\
+             *.md @org/docs # docs team\n\
+             /src/api/ @org/api @alice\n\
+             build/ @org/build\n\
+             /src/api/generated.rs\n",
+        );
+        let owners = Owners::new(&patterns);
+        assert_eq!(owners.owners_of("main.rs"), ["@org/all"]);
+        assert_eq!(owners.owners_of("src/guide/intro.md"), ["@org/docs"]);
+        assert_eq!(owners.owners_of("src/api/users.rs"), ["@org/api", "@alice"]);
+        assert_eq!(owners.owners_of("tools/build/run.sh"), ["@org/build"]);
+        assert!(owners.owners_of("src/api/generated.rs").is_empty());
+        // Anchored patterns only match from the root
+        assert_eq!(owners.owners_of("lib/src/api/x.rs"), ["@org/all"]);
+    }
+
+    #[test]
+    fn test_pattern_globs() {
+        assert_eq!(pattern_globs("build/"), ["**/build/**"]);
+        assert_eq!(pattern_globs("/src/api/"), ["src/api/**"]);
+        assert_eq!(pattern_globs("*.md"), ["**/*.md", "**/*.md/**"]);
+        assert_eq!(pattern_globs("**/logs"), ["**/logs", "**/logs/**"]);
+        assert_eq!(
+            pattern_globs("/src/api/generated.rs"),
+            ["src/api/generated.rs"]
+        );
+        assert_eq!(pattern_globs("docs/*"), ["docs/*"]);
+        assert_eq!(pattern_globs("/docs/**"), ["docs/**"]);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::rule::body::RuleBody;
+use crate::tokens::Tokenizer;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Tasks of a review, written by `review --dry-run --output plan.json` and run with `--plan`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskPlan {
+    pub version: String,
+    pub model: String,
+    pub tasks: Vec<PlanTask>,
+}
+
+/// Task in a plan; edit or remove tasks to run a subset with `--plan`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlanTask {
+    pub rule: String,
+    pub rule_id: String,
+    pub files: Vec<String>,
+    /// Size of the initial prompt (instruction and diffs), excluding tool calls
+    #[serde(default)]
+    pub estimated_tokens: usize,
+}
+
+/// Prompt size of a task: the rule instruction and the diffs of its files, sent once
+/// per consensus agent
+pub fn estimate_tokens(
+    tokenizer: &Tokenizer,
+    rule: &RuleBody,
+    files: &[String],
+    diffs: &HashMap<String, String>,
+) -> usize {
+    let diff_tokens: usize = files
+        .iter()
+        .filter_map(|file| diffs.get(file))
+        .map(|diff| tokenizer.count(diff))
+        .sum();
+    (tokenizer.count(&rule.instruction) + diff_tokens) * rule.consensus.unwrap_or(1).max(1)
+}
+
+impl TaskPlan {
+    pub fn new(
+        model: &str,
+        tasks: &[(&RuleBody, Vec<String>)],
+        diffs: &HashMap<String, String>,
+    ) -> Self {
+        let tokenizer = Tokenizer::for_model(model);
+        TaskPlan {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            model: model.to_string(),
+            tasks: tasks
+                .iter()
+                .map(|(rule, files)| PlanTask {
+                    rule: rule.name.clone(),
+                    rule_id: rule.id(),
+                    files: files.clone(),
+                    estimated_tokens: estimate_tokens(&tokenizer, rule, files, diffs),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read plan {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse plan {}: {}", path, e))
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Failed to write plan {}: {}", path, e))
+    }
+
+    pub fn estimated_tokens(&self) -> usize {
+        self.tasks.iter().map(|task| task.estimated_tokens).sum()
+    }
+
+    /// Check the plan against the config and the files to review before running it:
+    /// every task needs a configured rule and files among `review_files`
+    pub fn check(&self, rules: &[RuleBody], review_files: &[String]) -> Result<(), String> {
+        let review_files: HashSet<&str> = review_files.iter().map(String::as_str).collect();
+        let mut problems = Vec::new();
+        for (i, task) in self.tasks.iter().enumerate() {
+            if find_rule(rules, task).is_none() {
+                problems.push(format!(
+                    "task {}: rule '{}' is not in the config",
+                    i + 1,
+                    task.rule
+                ));
+            }
+            if task.files.is_empty() {
+                problems.push(format!("task {}: no files", i + 1));
+            }
+            let unknown: Vec<&str> = task
+                .files
+                .iter()
+                .map(String::as_str)
+                .filter(|file| !review_files.contains(file))
+                .collect();
+            if !unknown.is_empty() {
+                problems.push(format!(
+                    "task {}: not among the files to review: {}",
+                    i + 1,
+                    unknown.join(", ")
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Invalid plan:\n  {}", problems.join("\n  ")))
+        }
+    }
+
+    /// Tasks of the plan whose rule is in the config; [`TaskPlan::check`] rejects the others
+    pub fn tasks<'a>(&self, rules: &'a [RuleBody]) -> Vec<(&'a RuleBody, Vec<String>)> {
+        self.tasks
+            .iter()
+            .filter_map(|task| Some((find_rule(rules, task)?, task.files.clone())))
+            .collect()
+    }
+}
+
+/// Rule of a planned task, matched by id or name
+fn find_rule<'a>(rules: &'a [RuleBody], task: &PlanTask) -> Option<&'a RuleBody> {
+    rules
+        .iter()
+        .find(|r| r.id() == task.rule_id || r.name == task.rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_round_trip() {
+        let rule = RuleBody::no_magic_numbers();
+        let diffs = HashMap::from([("a.rs".to_string(), "x".repeat(400))]);
+        let plan = TaskPlan::new(
+            "gpt-4",
+            &[(&rule, vec!["a.rs".to_string(), "b.rs".to_string()])],
+            &diffs,
+        );
+        assert_eq!(plan.tasks[0].rule_id, rule.id());
+        let tokenizer = Tokenizer::for_model("gpt-4");
+        assert_eq!(
+            plan.estimated_tokens(),
+            tokenizer.count(&rule.instruction) + tokenizer.count(&"x".repeat(400))
+        );
+
+        let json = serde_json::to_string(&plan).unwrap();
+        let mut plan: TaskPlan = serde_json::from_str(&json).unwrap();
+        plan.tasks.push(PlanTask {
+            rule: "Removed rule".to_string(),
+            rule_id: "removed-rule".to_string(),
+            files: vec![],
+            estimated_tokens: 0,
+        });
+        let rules = [RuleBody::no_hardcoded_credentials(), rule.clone()];
+        let tasks = plan.tasks(&rules);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].0.name, rule.name);
+        assert_eq!(tasks[0].1, ["a.rs", "b.rs"]);
+
+        let error = plan.check(&rules, &["a.rs".to_string()]).unwrap_err();
+        assert_eq!(
+            error,
+            "Invalid plan:\n  task 1: not among the files to review: b.rs\n  \
+             task 2: rule 'Removed rule' is not in the config\n  task 2: no files"
+        );
+        plan.tasks.pop();
+        assert!(
+            plan.check(&rules, &["a.rs".to_string(), "b.rs".to_string()])
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use super::render::{ViolationFile, ViolationsByFile};
+use crate::rule::body::RuleBody;
+use crate::rule::tip::Tip;
+use crate::types::Violation;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Default path of the task queue processed across invocations by `review --continue`
+pub const DEFAULT_QUEUE_PATH: &str = ".firekeeper/queue.json";
+
+/// Tasks of a long review, with the results of completed ones, so the review can be
+/// spread over several invocations (e.g. nightly jobs stopped by a timeout).
+///
+/// The queue is one JSON file rewritten after each task rather than a database: it holds
+/// at most a few thousand tasks written by one process, so a rename after each write is
+/// enough to survive a killed run, and the file stays readable and removable by hand.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewQueue {
+    pub version: String,
+    pub created: chrono::DateTime<chrono::Utc>,
+    /// Base the tasks were planned for
+    pub base: String,
+    /// Commit checked out when the tasks were planned
+    #[serde(default)]
+    pub head: Option<String>,
+    pub tasks: Vec<QueuedTask>,
+}
+
+/// Task in the queue
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedTask {
+    pub rule: String,
+    pub rule_id: String,
+    pub files: Vec<String>,
+    /// Violations reported, once the task completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violations: Option<Vec<Violation>>,
+}
+
+impl ReviewQueue {
+    pub fn new(base: &str, head: Option<String>, tasks: &[(&RuleBody, Vec<String>)]) -> Self {
+        ReviewQueue {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created: chrono::Utc::now(),
+            base: base.to_string(),
+            head,
+            tasks: tasks.iter().map(queued_task).collect(),
+        }
+    }
+
+    /// Rules of the config without tasks in the queue, e.g. added after it was created
+    pub fn unqueued_rules<'a>(&self, rules: &'a [RuleBody]) -> Vec<&'a RuleBody> {
+        rules
+            .iter()
+            .filter(|rule| {
+                !self
+                    .tasks
+                    .iter()
+                    .any(|task| rule.id() == task.rule_id || rule.name == task.rule)
+            })
+            .collect()
+    }
+
+    /// Queue more tasks, e.g. of rules added to the config after the queue was created
+    pub fn extend(&mut self, tasks: &[(&RuleBody, Vec<String>)]) {
+        self.tasks.extend(tasks.iter().map(queued_task));
+    }
+
+    /// Load the queue, or `None` if there is none
+    pub fn load(path: &str) -> Result<Option<Self>, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read queue {}: {}", path, e)),
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse queue {}: {}", path, e))
+    }
+
+    /// Check that the queue was planned for the same changes, so completed results are
+    /// not merged into a review of other commits
+    pub fn check(&self, path: &str, base: &str, head: Option<&str>) -> Result<(), String> {
+        if self.base != base {
+            return Err(format!(
+                "Queue {} was created for base {}, not {}; continue with that base or delete the queue",
+                path, self.base, base
+            ));
+        }
+        if self.head.as_deref() != head {
+            return Err(format!(
+                "Queue {} was created at commit {}, not {}; check out that commit or delete the queue",
+                path,
+                self.head.as_deref().unwrap_or("(unknown)"),
+                head.unwrap_or("(unknown)")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Save the queue, replacing the previous file only once fully written
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if let Some(parent) = std::path::Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let tmp = format!("{}.tmp", path);
+        std::fs::write(&tmp, serde_json::to_string(self).unwrap())
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to write queue {}: {}", path, e))
+    }
+
+    /// Indices of the tasks not completed yet
+    pub fn pending(&self) -> Vec<usize> {
+        (0..self.tasks.len())
+            .filter(|&i| self.tasks[i].violations.is_none())
+            .collect()
+    }
+
+    pub fn complete(&mut self, index: usize, violations: Vec<Violation>) {
+        self.tasks[index].violations = Some(violations);
+    }
+
+    /// Pending tasks whose rule is in the config, matched by id or name, with their indices
+    pub fn pending_tasks<'a>(
+        &self,
+        rules: &'a [RuleBody],
+    ) -> Vec<(usize, &'a RuleBody, Vec<String>)> {
+        self.pending()
+            .into_iter()
+            .filter_map(|i| {
+                let task = &self.tasks[i];
+                let rule = rules
+                    .iter()
+                    .find(|r| r.id() == task.rule_id || r.name == task.rule);
+                if rule.is_none() {
+                    warn!("Skipping queued rule '{}': not in the config", task.rule);
+                }
+                rule.map(|rule| (i, rule, task.files.clone()))
+            })
+            .collect()
+    }
+
+    /// Results of the completed tasks, to merge with the tasks run now
+    pub fn completed_results(&self, rules: &[RuleBody]) -> ViolationFile {
+        let mut violations = ViolationsByFile::new();
+        for task in &self.tasks {
+            for violation in task.violations.iter().flatten() {
+                violations
+                    .entry(violation.file.clone())
+                    .or_default()
+                    .entry(task.rule.clone())
+                    .or_default()
+                    .push(violation.clone());
+            }
+        }
+        let tips: BTreeMap<String, Tip> = rules
+            .iter()
+            .filter(|rule| self.tasks.iter().any(|task| task.rule == rule.name))
+            .filter_map(|rule| Some((rule.name.clone(), rule.tip.clone()?)))
+            .collect();
+        ViolationFile {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            violations,
+            tips,
+            language: None,
+            skipped: vec![],
+            incomplete: vec![],
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        }
+    }
+}
+
+fn queued_task((rule, files): &(&RuleBody, Vec<String>)) -> QueuedTask {
+    QueuedTask {
+        rule: rule.name.clone(),
+        rule_id: rule.id(),
+        files: files.clone(),
+        violations: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_progress() {
+        let rule = RuleBody::no_magic_numbers();
+        let tasks = [
+            (&rule, vec!["a.rs".to_string()]),
+            (&rule, vec!["b.rs".to_string()]),
+        ];
+        let mut queue = ReviewQueue::new("ROOT", Some("abc123".into()), &tasks);
+        queue.complete(
+            0,
+            vec![Violation {
+                file: "a.rs".into(),
+                detail: "Magic number".into(),
+                start_line: 1,
+                end_line: 1,
+                rule_id: None,
+                owners: vec![],
+            }],
+        );
+
+        let dir =
+            crate::util::temp::TempDir::new(&format!("firekeeper-queue-{}", std::process::id()));
+        let path = dir.path().join("queue.json");
+        let path = path.to_str().unwrap();
+        queue.save(path).unwrap();
+        let mut queue = ReviewQueue::load(path).unwrap().unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(ReviewQueue::load(path).unwrap().is_none());
+
+        let rules = [rule.clone()];
+        let pending = queue.pending_tasks(&rules);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            (pending[0].0, pending[0].2.clone()),
+            (1, vec!["b.rs".to_string()])
+        );
+        let results = queue.completed_results(&rules);
+        assert_eq!(results.violations["a.rs"][&rule.name].len(), 1);
+
+        // A rule added to the config after queueing gets tasks of its own
+        let added = RuleBody::no_hardcoded_credentials();
+        let rules = [rule.clone(), added.clone()];
+        let unqueued = queue.unqueued_rules(&rules);
+        assert_eq!(unqueued.len(), 1);
+        assert_eq!(unqueued[0].name, added.name);
+        queue.extend(&[(&added, vec!["a.rs".to_string()])]);
+        assert!(queue.unqueued_rules(&rules).is_empty());
+        assert_eq!(queue.pending_tasks(&rules).len(), 2);
+    }
+
+    #[test]
+    fn test_queue_check() {
+        let rule = RuleBody::no_magic_numbers();
+        let queue = ReviewQueue::new("main", Some("abc123".into()), &[(&rule, vec![])]);
+        assert!(queue.check("q.json", "main", Some("abc123")).is_ok());
+        assert!(
+            queue
+                .check("q.json", "HEAD^", Some("abc123"))
+                .unwrap_err()
+                .contains("created for base main, not HEAD^")
+        );
+        assert!(
+            queue
+                .check("q.json", "main", Some("def456"))
+                .unwrap_err()
+                .contains("created at commit abc123, not def456")
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use super::render::{self, ViolationFile};
+use super::task_cache::TaskCache;
+use crate::rule::body::RuleBody;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Cache namespace of task results of release audits
+const CACHE_NAMESPACE: &str = "release";
+
+/// Audit of the changes between two revisions, e.g. `review --base v1.2.0 --head v1.3.0`
+pub struct ReleaseAudit {
+    pub base: String,
+    pub head: String,
+    /// Cache of task results, shared by audits of ranges with the same changes
+    pub cache_dir: PathBuf,
+}
+
+/// Parent directory of a file, `.` at the repository root
+fn directory(file: &str) -> &str {
+    match file.rsplit_once('/') {
+        Some((dir, _)) => dir,
+        None => ".",
+    }
+}
+
+/// Regroup each rule's files by directory, so a task reviews the changes of one directory,
+/// split by max_files_per_task
+pub fn batch_by_directory(
+    tasks: Vec<(&RuleBody, Vec<String>)>,
+    global_max_files_per_task: usize,
+) -> Vec<(&RuleBody, Vec<String>)> {
+    let mut by_rule: Vec<(&RuleBody, BTreeMap<String, Vec<String>>)> = Vec::new();
+    for (rule, files) in tasks {
+        let index = match by_rule.iter().position(|(r, _)| r.name == rule.name) {
+            Some(index) => index,
+            None => {
+                by_rule.push((rule, BTreeMap::new()));
+                by_rule.len() - 1
+            }
+        };
+        for file in files {
+            by_rule[index]
+                .1
+                .entry(directory(&file).to_string())
+                .or_default()
+                .push(file);
+        }
+    }
+    by_rule
+        .into_iter()
+        .flat_map(|(rule, directories)| {
+            let max_files = rule
+                .max_files_per_task
+                .unwrap_or(global_max_files_per_task)
+                .max(1);
+            directories.into_values().flat_map(move |files| {
+                files
+                    .chunks(max_files)
+                    .map(|chunk| (rule, chunk.to_vec()))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect()
+}
+
+impl ReleaseAudit {
+    /// `base..head`, as shown in the report
+    pub fn range(&self) -> String {
+        format!("{}..{}", self.base, self.head)
+    }
+
+    /// Cache of task results, shared by audits of ranges with the same changes
+    pub fn cache(&self) -> TaskCache {
+        TaskCache::new(self.cache_dir.clone(), CACHE_NAMESPACE)
+    }
+
+    /// Format results as release notes: a summary of the violations introduced per
+    /// directory, followed by the details
+    pub fn format_report(&self, results: &ViolationFile) -> String {
+        let strings = super::locale::strings(results.language.as_deref());
+        let mut by_directory: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+        for (file, by_rule) in &results.violations {
+            for (rule, violations) in by_rule {
+                *by_directory
+                    .entry(directory(file))
+                    .or_default()
+                    .entry(rule.as_str())
+                    .or_default() += violations.len();
+            }
+        }
+        let total: usize = by_directory.values().flat_map(|r| r.values()).sum();
+
+        let mut output = format!("# Release audit {}\n\n", self.range());
+        output.push_str(&format!(
+            "{} violation(s) introduced in {} file(s) across {} directories.\n\n",
+            total,
+            results.violations.len(),
+            by_directory.len()
+        ));
+        for (dir, rules) in &by_directory {
+            output.push_str(&format!("## `{}`\n\n", dir));
+            for (rule, count) in rules {
+                output.push_str(&format!("- {}: {}\n", rule, count));
+            }
+            output.push('\n');
+        }
+        output.push_str(&render::format_violations(
+            &results.violations,
+            &results.tips,
+            strings,
+        ));
+        output.extend(render::format_truncated(&results.truncated, strings));
+        output.extend(render::format_quarantined(
+            &results.quarantined,
+            &results.violations,
+            strings,
+        ));
+        output.extend(render::format_skipped(&results.skipped, strings));
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::task_cache::TaskContexts;
+    use crate::types::Violation;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_batch_by_directory() {
+        let rule = RuleBody {
+            max_files_per_task: Some(2),
+            ..RuleBody::no_magic_numbers()
+        };
+        let files = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let tasks = vec![
+            (&rule, files(&["a/1.rs", "a/2.rs", "b/1.rs"])),
+            (&rule, files(&["a/3.rs", "main.rs"])),
+        ];
+        let batches: Vec<Vec<String>> = batch_by_directory(tasks, 10)
+            .into_iter()
+            .map(|(_, files)| files)
+            .collect();
+        assert_eq!(
+            batches,
+            [
+                files(&["main.rs"]),
+                files(&["a/1.rs", "a/2.rs"]),
+                files(&["a/3.rs"]),
+                files(&["b/1.rs"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cached_tasks_are_skipped() {
+        let dir =
+            crate::util::temp::TempDir::new(&format!("firekeeper-release-{}", std::process::id()));
+        let audit = ReleaseAudit {
+            base: "v1.2.0".into(),
+            head: "v1.3.0".into(),
+            cache_dir: dir.path().to_path_buf(),
+        };
+        let rule = RuleBody::no_magic_numbers();
+        let diffs = HashMap::from([("a.rs".to_string(), "+let x = 42;".to_string())]);
+        let files = vec!["a.rs".to_string()];
+        let violation = Violation {
+            file: "a.rs".into(),
+            detail: "Magic number 42".into(),
+            start_line: 1,
+            end_line: 1,
+            rule_id: None,
+            owners: vec![],
+        };
+        let cache = audit.cache();
+        let contexts = TaskContexts::default();
+        cache.store("model", &rule, &files, &diffs, &contexts, &[violation]);
+
+        let (tasks, cached, _) =
+            cache.take_cached("model", vec![(&rule, files)], &diffs, &contexts);
+        assert!(tasks.is_empty());
+        assert_eq!(cached.violations["a.rs"][&rule.name].len(), 1);
+        assert!(
+            audit
+                .format_report(&cached)
+                .starts_with("# Release audit v1.2.0..v1.3.0\n\n1 violation(s) introduced in 1 file(s) across 1 directories.\n\n## `.`\n\n- No Magic Numbers: 1\n")
+        );
+    }
+}
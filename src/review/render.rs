@@ -1,8 +1,13 @@
+use super::bundle;
+use super::compaction::Compaction;
+use super::locale::Strings;
+use super::usage::{self, TokenUsage};
 use crate::rule::body::RuleBody;
+use crate::rule::tip::Tip;
 use crate::types::Violation;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use tiny_loop::tool::ToolArgs;
 use tiny_loop::types::{Message, TimedMessage, ToolDefinition};
 
@@ -13,14 +18,149 @@ const ELAPSED_TIME_PRECISION: usize = 2;
 pub struct TraceFile {
     pub version: String,
     pub entries: Vec<TraceEntry>,
+    /// Token usage and cost per rule, summed over the entries
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub usage: BTreeMap<String, TokenUsage>,
+}
+
+/// Violations grouped by file, then by rule name (sorted for deterministic output)
+pub type ViolationsByFile = BTreeMap<String, BTreeMap<String, Vec<Violation>>>;
+
+/// Flatten grouped violations into (rule, violation) pairs in deterministic order (file, rule, line)
+pub fn flatten_violations(violations_by_file: &ViolationsByFile) -> Vec<(&str, &Violation)> {
+    violations_by_file
+        .values()
+        .flat_map(|rules| {
+            rules
+                .iter()
+                .flat_map(|(rule, violations)| violations.iter().map(move |v| (rule.as_str(), v)))
+        })
+        .collect()
 }
 
 /// Violation file schema containing violations and tips
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ViolationFile {
     pub version: String,
-    pub violations: HashMap<String, HashMap<String, Vec<Violation>>>,
-    pub tips: HashMap<String, String>,
+    pub violations: ViolationsByFile,
+    pub tips: BTreeMap<String, Tip>,
+    /// Language of the report (`review.language`), English if omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Changed files not reviewed, or reviewed without their diffs, and why
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedFile>,
+    /// Tasks that failed, timed out, or were interrupted, re-run by `review --retry-from`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incomplete: Vec<IncompleteTask>,
+    /// Violations left out of the report per rule after `max_violations` was reached
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub truncated: BTreeMap<String, usize>,
+    /// Rules run quarantined: their violations are reported without blocking
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub quarantined: BTreeSet<String>,
+    /// Token usage and cost per rule
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub usage: BTreeMap<String, TokenUsage>,
+}
+
+/// Changed file left out of the review
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkippedFile {
+    pub file: String,
+    pub reason: SkipReason,
+}
+
+/// Why a changed file was left out of the review
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Left out by `--include`/`--exclude`
+    Excluded,
+    /// Vendored or third-party code, reviewed only by rules with `vendored = true`
+    Vendored,
+    /// No rule's scope matches the file
+    OutOfScope,
+    /// Skipped by non-blocking rules because the changeset was sampled
+    Sampled,
+    /// Lock or generated file, reviewed without its diff in the prompt
+    DiffOmitted,
+}
+
+impl SkipReason {
+    pub fn describe(self, strings: &Strings) -> &'static str {
+        match self {
+            Self::Excluded => strings.skipped_excluded,
+            Self::Vendored => strings.skipped_vendored,
+            Self::OutOfScope => strings.skipped_out_of_scope,
+            Self::Sampled => strings.not_reviewed,
+            Self::DiffOmitted => strings.skipped_diff_omitted,
+        }
+    }
+}
+
+/// Review task that did not finish
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IncompleteTask {
+    /// Rule name
+    pub rule: String,
+    /// Files the task reviewed
+    pub files: Vec<String>,
+    /// Why the task did not finish, e.g. a provider error or timeout
+    pub reason: String,
+}
+
+/// Read a JSON file written by `review --output` or `--trace`, or its `member` of a bundle
+fn read_json<T: serde::de::DeserializeOwned>(
+    path: &str,
+    kind: &str,
+    member: &str,
+) -> Result<T, String> {
+    let content = if bundle::is_bundle(path) {
+        bundle::read_member(path, member)?
+            .ok_or_else(|| format!("Bundle {} has no {}", path, member))?
+    } else {
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {} file: {}", kind, e))?
+    };
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {} file: {}", kind, e))
+}
+
+impl ViolationFile {
+    /// Load a results file written by `review --output`, or the results of a bundle
+    pub fn load(path: &str) -> Result<Self, String> {
+        read_json(path, "results", bundle::RESULTS)
+    }
+}
+
+impl TraceFile {
+    /// Trace of the current version with the usage of its entries
+    pub fn new(entries: Vec<TraceEntry>) -> Self {
+        let usage = usage::usage_by_rule(
+            entries
+                .iter()
+                .map(|entry| (entry.rule.name.as_str(), &entry.usage)),
+        );
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            entries,
+            usage,
+        }
+    }
+
+    /// Load a trace file written by `review --trace`, or the trace of a bundle
+    pub fn load(path: &str) -> Result<Self, String> {
+        read_json(path, "trace", bundle::TRACE)
+    }
+}
+
+/// Shields.io endpoint badge schema (see https://shields.io/badges/endpoint-badge)
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BadgeFile {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    pub label: String,
+    pub message: String,
+    pub color: String,
 }
 
 /// Trace entry containing worker task details and agent conversation
@@ -34,38 +174,66 @@ pub struct TraceEntry {
     pub files: Vec<String>,
     /// Time taken to complete the task in seconds
     pub elapsed_secs: f64,
+    /// Total size of resources included in the prompt
+    #[serde(default)]
+    pub resource_bytes: usize,
+    /// Conversation compactions that evicted old tool outputs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compactions: Vec<Compaction>,
+    /// Token usage and cost
+    #[serde(default)]
+    pub usage: TokenUsage,
     /// Tool definitions available to the agent
     pub tools: Vec<ToolDefinition>,
     /// Conversation messages between agent and tools
     pub messages: Vec<TimedMessage>,
 }
 
-fn format_violation(violation: &Violation) -> String {
+fn format_violation(violation: &Violation, strings: &Strings) -> String {
     format!(
-        "- Lines {}-{}: {}\n",
-        violation.start_line, violation.end_line, violation.detail
+        "- {} {}-{}: {}\n",
+        strings.lines, violation.start_line, violation.end_line, violation.detail
     )
 }
 
-fn format_tip(tip: &str) -> Option<String> {
-    let trimmed = tip.trim();
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(format!("\n**Tip:** {}\n", trimmed))
+/// Format a tip with its fix command in a copyable block and its documentation link
+fn format_tip(tip: &Tip, strings: &Strings) -> Option<String> {
+    if tip.is_empty() {
+        return None;
+    }
+    let mut output = format!("\n**{}:** {}", strings.tip, tip.text())
+        .trim_end()
+        .to_string();
+    output.push('\n');
+    if let Some(command) = tip.command() {
+        let fence = get_fence_backticks(command);
+        output.push_str(&format!("\n{}sh\n{}\n{}\n", fence, command, fence));
     }
+    if let Some(url) = tip.docs_url() {
+        output.push_str(&format!("\n<{}>\n", url));
+    }
+    Some(output)
 }
 
-fn format_rule(rule: &str) -> String {
-    format!("## Rule: {}\n\n", rule)
+fn format_rule(rule: &str, id: Option<&str>, strings: &Strings) -> String {
+    match id {
+        Some(id) => format!("## {}: {} (`{}`)\n\n", strings.rule, rule, id),
+        None => format!("## {}: {}\n\n", strings.rule, rule),
+    }
 }
 
-fn format_rule_violations(rule: &str, violations: &[Violation], tip: Option<&str>) -> String {
-    let mut output = format_rule(rule);
+fn format_rule_violations(
+    rule: &str,
+    violations: &[Violation],
+    tip: Option<&Tip>,
+    strings: &Strings,
+) -> String {
+    let id = violations.first().and_then(|v| v.rule_id.as_deref());
+    let mut output = format_rule(rule, id, strings);
     for violation in violations {
-        output.push_str(&format_violation(violation));
+        output.push_str(&format_violation(violation, strings));
     }
-    if let Some(t) = tip.and_then(|t| format_tip(t)) {
+    if let Some(t) = tip.and_then(|t| format_tip(t, strings)) {
         output.push_str(&t);
     }
     output.push('\n');
@@ -73,27 +241,333 @@ fn format_rule_violations(rule: &str, violations: &[Violation], tip: Option<&str
 }
 
 pub fn format_violations(
-    violations_by_file: &HashMap<String, HashMap<String, Vec<Violation>>>,
-    tips_by_rule: &HashMap<String, String>,
+    violations_by_file: &ViolationsByFile,
+    tips_by_rule: &BTreeMap<String, Tip>,
+    strings: &Strings,
 ) -> String {
     if violations_by_file.is_empty() {
-        return "No violations found".to_string();
+        return strings.no_violations.to_string();
     }
 
     let mut output = String::new();
     for (file, rules) in violations_by_file {
-        output.push_str(&format!("# Violations in {}\n\n", file));
+        output.push_str(&format!(
+            "# {}\n\n",
+            strings.violations_in.replace("{}", file)
+        ));
         for (rule, violations) in rules {
             output.push_str(&format_rule_violations(
                 rule,
                 violations,
-                tips_by_rule.get(rule.as_str()).map(|s| s.as_str()),
+                tips_by_rule.get(rule.as_str()),
+                strings,
             ));
         }
     }
     output.trim_end().to_string()
 }
 
+/// Format violations grouped by owner, then file, for routing findings to teams.
+/// A violation with several owners is listed under each; unowned violations come last,
+/// followed by the tips of the violated rules.
+pub fn format_by_owner(
+    violations_by_file: &ViolationsByFile,
+    tips_by_rule: &BTreeMap<String, Tip>,
+    strings: &Strings,
+) -> String {
+    if violations_by_file.is_empty() {
+        return strings.no_violations.to_string();
+    }
+
+    type ByFile<'a> = BTreeMap<&'a str, Vec<(&'a str, &'a Violation)>>;
+    let mut by_owner: BTreeMap<&str, ByFile> = BTreeMap::new();
+    let mut unowned = ByFile::new();
+    for (rule, violation) in flatten_violations(violations_by_file) {
+        if violation.owners.is_empty() {
+            unowned
+                .entry(violation.file.as_str())
+                .or_default()
+                .push((rule, violation));
+        }
+        for owner in &violation.owners {
+            by_owner
+                .entry(owner.as_str())
+                .or_default()
+                .entry(violation.file.as_str())
+                .or_default()
+                .push((rule, violation));
+        }
+    }
+
+    let heading = |owner: &str| format!("{}: {}", strings.owner, owner);
+    let sections = by_owner
+        .iter()
+        .map(|(owner, files)| (heading(owner), files))
+        .chain((!unowned.is_empty()).then(|| (strings.unowned.to_string(), &unowned)));
+    let mut output = String::new();
+    for (heading, files) in sections {
+        output.push_str(&format!("# {}\n\n", heading));
+        for (file, violations) in files {
+            output.push_str(&format!("## {}\n\n", file));
+            for (rule, violation) in violations {
+                output.push_str(&format!(
+                    "- **{}** {} {}-{}: {}\n",
+                    rule, strings.lines, violation.start_line, violation.end_line, violation.detail
+                ));
+            }
+            output.push('\n');
+        }
+    }
+
+    let violated: BTreeSet<&str> = flatten_violations(violations_by_file)
+        .into_iter()
+        .map(|(rule, _)| rule)
+        .collect();
+    let tips: Vec<String> = violated
+        .into_iter()
+        .filter_map(|rule| {
+            let tip = format_tip(tips_by_rule.get(rule)?, strings)?;
+            Some(format!(
+                "{}{}\n",
+                format_rule(rule, None, strings),
+                tip.trim_start()
+            ))
+        })
+        .collect();
+    if !tips.is_empty() {
+        output.push_str(&format!("# {}\n\n{}", strings.how_to_fix, tips.concat()));
+    }
+    output.trim_end().to_string()
+}
+
+/// Format skipped files with their reasons as a section to append to the violations
+pub fn format_skipped(skipped: &[SkippedFile], strings: &Strings) -> Option<String> {
+    if skipped.is_empty() {
+        return None;
+    }
+    let mut output = format!("\n\n# {} ({})\n\n", strings.skipped, skipped.len());
+    for skipped in skipped {
+        output.push_str(&format!(
+            "- {}: {}\n",
+            skipped.file,
+            skipped.reason.describe(strings)
+        ));
+    }
+    Some(output.trim_end().to_string())
+}
+
+/// Format the violations left out by `max_violations` as one line per rule
+pub fn format_truncated(truncated: &BTreeMap<String, usize>, strings: &Strings) -> Option<String> {
+    if truncated.is_empty() {
+        return None;
+    }
+    let mut output = String::from("\n\n");
+    for (rule, count) in truncated {
+        output.push_str(&format!(
+            "- **{}**: {}\n",
+            rule,
+            strings.more_similar.replace("{}", &count.to_string())
+        ));
+    }
+    Some(output.trim_end().to_string())
+}
+
+/// Format the quarantined rules with their violation counts, labeling their findings
+/// as not blocking
+pub fn format_quarantined(
+    quarantined: &BTreeSet<String>,
+    violations_by_file: &ViolationsByFile,
+    strings: &Strings,
+) -> Option<String> {
+    if quarantined.is_empty() {
+        return None;
+    }
+    let mut output = format!("\n\n# {}\n\n", strings.quarantined);
+    for rule in quarantined {
+        let count: usize = violations_by_file
+            .values()
+            .filter_map(|rules| rules.get(rule))
+            .map(Vec::len)
+            .sum();
+        output.push_str(&format!("- **{}** ({})\n", rule, count));
+    }
+    Some(output.trim_end().to_string())
+}
+
+/// Summarize a run as a shields.io endpoint badge
+///
+/// Red if any blocking violations, yellow if only non-blocking violations,
+/// green if clean, and grey if any worker failed (results are incomplete).
+pub fn format_badge(blocking: usize, non_blocking: usize, failed: usize) -> BadgeFile {
+    const SHIELDS_SCHEMA_VERSION: u32 = 1;
+
+    let (message, color) = if failed > 0 {
+        (format!("{} failed", failed), "lightgrey")
+    } else if blocking > 0 {
+        (format!("{} blocking", blocking), "red")
+    } else if non_blocking > 0 {
+        (
+            format!("0 blocking, {} non-blocking", non_blocking),
+            "yellow",
+        )
+    } else {
+        ("0 blocking".to_string(), "brightgreen")
+    };
+
+    BadgeFile {
+        schema_version: SHIELDS_SCHEMA_VERSION,
+        label: "firekeeper".to_string(),
+        message,
+        color: color.to_string(),
+    }
+}
+
+/// Format violations as GitHub Actions workflow commands, annotating the reported lines.
+/// Violations of blocking rules are errors, others warnings. The fix command of a rule's
+/// tip is appended so it can be acted on from the annotation.
+pub fn format_annotations(
+    violations_by_file: &ViolationsByFile,
+    blocking_rules: &HashSet<String>,
+    tips_by_rule: &BTreeMap<String, Tip>,
+) -> Vec<String> {
+    flatten_violations(violations_by_file)
+        .into_iter()
+        .map(|(rule, violation)| {
+            let command = if blocking_rules.contains(rule) {
+                "error"
+            } else {
+                "warning"
+            };
+            let mut message = violation.detail.clone();
+            if let Some(command) = tips_by_rule.get(rule).and_then(Tip::command) {
+                message.push_str(&format!("\n\nFix: {}", command));
+            }
+            format!(
+                "::{} file={},line={},endLine={},title={}::{}",
+                command,
+                escape_annotation_property(&violation.file),
+                violation.start_line.max(1),
+                violation.end_line.max(violation.start_line).max(1),
+                escape_annotation_property(rule),
+                escape_annotation_data(&message)
+            )
+        })
+        .collect()
+}
+
+/// Size limit of a GitHub Actions job summary
+const MAX_STEP_SUMMARY_BYTES: usize = 1024 * 1024;
+
+/// Cut a job summary at the last line that fits GitHub's size limit, with a notice
+pub fn truncate_step_summary(summary: String, strings: &Strings) -> String {
+    if summary.len() <= MAX_STEP_SUMMARY_BYTES {
+        return summary;
+    }
+    let notice = format!("\n\n⚠️ {}\n", strings.summary_truncated);
+    let mut end = MAX_STEP_SUMMARY_BYTES - notice.len();
+    while !summary.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = summary[..end].rfind('\n').unwrap_or(end);
+    format!("{}{}", &summary[..end], notice)
+}
+
+/// Format violations as a GitHub Actions job summary: counts per rule with severity emoji,
+/// then each file's violations in a collapsible section
+pub fn format_step_summary(
+    violations_by_file: &ViolationsByFile,
+    blocking_rules: &HashSet<String>,
+    failed: usize,
+    strings: &Strings,
+) -> String {
+    let emoji = |rule: &str| {
+        if blocking_rules.contains(rule) {
+            "🔴"
+        } else {
+            "🟡"
+        }
+    };
+    let mut output = String::from("## Firekeeper Review\n\n");
+    if failed > 0 {
+        output.push_str(&format!(
+            "⚠️ {}\n\n",
+            strings.tasks_failed.replace("{}", &failed.to_string())
+        ));
+    }
+    if violations_by_file.is_empty() {
+        output.push_str(&format!("✅ {}\n", strings.no_violations));
+        return output;
+    }
+
+    let mut by_rule: BTreeMap<&str, usize> = BTreeMap::new();
+    for (rule, _) in flatten_violations(violations_by_file) {
+        *by_rule.entry(rule).or_default() += 1;
+    }
+    output.push_str(&format!(
+        "| | {} | {} | {} |\n| --- | --- | --- | --- |\n",
+        strings.rule, strings.severity, strings.violations
+    ));
+    for (rule, count) in &by_rule {
+        let severity = if blocking_rules.contains(*rule) {
+            strings.error
+        } else {
+            strings.warning
+        };
+        output.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            emoji(rule),
+            rule.replace('|', "\\|"),
+            severity,
+            count
+        ));
+    }
+
+    for (file, rules) in violations_by_file {
+        let count: usize = rules.values().map(Vec::len).sum();
+        let worst = if rules.keys().any(|rule| blocking_rules.contains(rule)) {
+            "🔴"
+        } else {
+            "🟡"
+        };
+        output.push_str(&format!(
+            "\n<details>\n<summary>{} <code>{}</code> ({})</summary>\n\n",
+            worst,
+            file.replace('&', "&amp;").replace('<', "&lt;"),
+            count
+        ));
+        for (rule, violations) in rules {
+            for violation in violations {
+                output.push_str(&format!(
+                    "- {} **{}** {} {}-{}: {}\n",
+                    emoji(rule),
+                    rule,
+                    strings.lines,
+                    violation.start_line,
+                    violation.end_line,
+                    violation.detail
+                ));
+            }
+        }
+        output.push_str("\n</details>\n");
+    }
+    output
+}
+
+/// Escape a workflow command message, which ends at a newline
+fn escape_annotation_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property, which also ends at `,` or `:`
+fn escape_annotation_property(value: &str) -> String {
+    escape_annotation_data(value)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
 fn format_tools(tools: &[ToolDefinition]) -> String {
     let tools_yaml = serde_yaml_ng::to_string(tools).unwrap_or_default();
     format!(
@@ -102,6 +576,21 @@ fn format_tools(tools: &[ToolDefinition]) -> String {
     )
 }
 
+fn format_compactions(compactions: &[Compaction]) -> String {
+    if compactions.is_empty() {
+        return String::new();
+    }
+    let mut output = String::from("## Compactions\n\n");
+    for c in compactions {
+        output.push_str(&format!(
+            "- At message {}: evicted {} tool outputs (~{} -> ~{} tokens)\n",
+            c.messages, c.evicted, c.tokens_before, c.tokens_after
+        ));
+    }
+    output.push('\n');
+    output
+}
+
 fn format_focused_files(files: &[String]) -> String {
     let mut output = String::from("## Focused Files\n\n");
     for file in files {
@@ -112,26 +601,25 @@ fn format_focused_files(files: &[String]) -> String {
 }
 
 fn format_tool_call(tc: &tiny_loop::types::ToolCall) -> String {
-    if tc.function.name == crate::tool::think::ThinkArgs::TOOL_NAME {
-        if let Ok(args) =
+    if tc.function.name == crate::tool::think::ThinkArgs::TOOL_NAME
+        && let Ok(args) =
             serde_json::from_str::<crate::tool::think::ThinkArgs>(&tc.function.arguments)
-        {
-            return format!(
-                "- **{}**\n\n{}\n\n",
-                tc.function.name,
-                wrap_in_ref_block(&args.reasoning)
-            );
-        }
+    {
+        return format!(
+            "- **{}**\n\n{}\n\n",
+            tc.function.name,
+            wrap_in_ref_block(&args.reasoning)
+        );
     }
-    if tc.function.name == crate::tool::sh::ShArgs::TOOL_NAME {
-        if let Ok(args) = serde_json::from_str::<crate::tool::sh::ShArgs>(&tc.function.arguments) {
-            if args.start_char.is_none() && args.num_chars.is_none() {
-                return format!(
-                    "- **{}**\n\n```sh\n{}\n```\n\n",
-                    tc.function.name, args.command
-                );
-            }
-        }
+    if tc.function.name == crate::tool::sh::ShArgs::TOOL_NAME
+        && let Ok(args) = serde_json::from_str::<crate::tool::sh::ShArgs>(&tc.function.arguments)
+        && args.start_char.is_none()
+        && args.num_chars.is_none()
+    {
+        return format!(
+            "- **{}**\n\n```sh\n{}\n```\n\n",
+            tc.function.name, args.command
+        );
     }
     let formatted_args = serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
         .ok()
@@ -249,9 +737,10 @@ pub fn format_trace_markdown(traces: &[TraceEntry]) -> String {
     let mut output = String::new();
     for trace in traces {
         output.push_str(&format!(
-            "# Worker: {} (Elapsed: {:.prec$}s)\n\n",
+            "# Worker: {} (Elapsed: {:.prec$}s, Resources: {} bytes)\n\n",
             trace.worker_id,
             trace.elapsed_secs,
+            trace.resource_bytes,
             prec = ELAPSED_TIME_PRECISION
         ));
         output.push_str(&format_trace_rule(
@@ -261,6 +750,7 @@ pub fn format_trace_markdown(traces: &[TraceEntry]) -> String {
 
         output.push_str(&format_focused_files(&trace.files));
         output.push_str(&format_tools(&trace.tools));
+        output.push_str(&format_compactions(&trace.compactions));
 
         output.push_str("## Messages\n\n");
         for (i, msg) in trace.messages.iter().enumerate() {
@@ -287,6 +777,7 @@ pub(crate) fn get_fence_backticks(content: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::review::locale::EN;
 
     #[test]
     fn test_get_fence_backticks() {
@@ -296,6 +787,85 @@ mod tests {
         assert_eq!(get_fence_backticks("````"), "`````");
     }
 
+    #[test]
+    fn test_format_step_summary() {
+        let violation = Violation {
+            file: "src/a.rs".to_string(),
+            start_line: 3,
+            end_line: 4,
+            detail: "Magic number".to_string(),
+            rule_id: None,
+            owners: vec![],
+        };
+        let violations_by_file = BTreeMap::from([(
+            violation.file.clone(),
+            BTreeMap::from([
+                ("Advice".to_string(), vec![violation.clone()]),
+                ("Blocker".to_string(), vec![violation]),
+            ]),
+        )]);
+        let blocking = HashSet::from(["Blocker".to_string()]);
+        assert_eq!(
+            format_step_summary(&violations_by_file, &blocking, 1, &EN),
+            "## Firekeeper Review\n\n\
+             ⚠️ 1 task(s) failed, results are incomplete\n\n\
+             | | Rule | Severity | Violations |\n| --- | --- | --- | --- |\n\
+             | 🟡 | Advice | warning | 1 |\n| 🔴 | Blocker | error | 1 |\n\
+             \n<details>\n<summary>🔴 <code>src/a.rs</code> (2)</summary>\n\n\
+             - 🟡 **Advice** Lines 3-4: Magic number\n\
+             - 🔴 **Blocker** Lines 3-4: Magic number\n\
+             \n</details>\n"
+        );
+        assert_eq!(
+            format_step_summary(&BTreeMap::new(), &blocking, 0, &EN),
+            "## Firekeeper Review\n\n✅ No violations found\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_step_summary() {
+        assert_eq!(truncate_step_summary("short\n".into(), &EN), "short\n");
+        let summary = "- violation ✅\n".repeat(MAX_STEP_SUMMARY_BYTES / 10);
+        let truncated = truncate_step_summary(summary, &EN);
+        assert!(truncated.len() <= MAX_STEP_SUMMARY_BYTES);
+        assert!(truncated.ends_with(&format!("✅\n\n⚠️ {}\n", EN.summary_truncated)));
+    }
+
+    #[test]
+    fn test_format_annotations() {
+        let violation = Violation {
+            file: "src/a,b.rs".to_string(),
+            start_line: 3,
+            end_line: 4,
+            detail: "50% off\nsecond line".to_string(),
+            rule_id: None,
+            owners: vec![],
+        };
+        let violations_by_file = BTreeMap::from([(
+            violation.file.clone(),
+            BTreeMap::from([
+                ("Blocking: Rule".to_string(), vec![violation.clone()]),
+                ("Advice".to_string(), vec![violation]),
+            ]),
+        )]);
+        let blocking = HashSet::from(["Blocking: Rule".to_string()]);
+        let tips = BTreeMap::from([(
+            "Advice".to_string(),
+            Tip::Detailed {
+                text: "Fix it".into(),
+                command: Some("make fmt".into()),
+                docs_url: None,
+            },
+        )]);
+        assert_eq!(
+            format_annotations(&violations_by_file, &blocking, &tips),
+            [
+                "::warning file=src/a%2Cb.rs,line=3,endLine=4,title=Advice::50%25 off%0Asecond line%0A%0AFix: make fmt",
+                "::error file=src/a%2Cb.rs,line=3,endLine=4,title=Blocking%3A Rule::50%25 off%0Asecond line",
+            ]
+        );
+    }
+
     #[test]
     fn test_format_violation() {
         let v = Violation {
@@ -303,15 +873,29 @@ mod tests {
             start_line: 10,
             end_line: 15,
             detail: "test issue".to_string(),
+            rule_id: None,
+            owners: vec![],
         };
-        assert_eq!(format_violation(&v), "- Lines 10-15: test issue\n");
+        assert_eq!(format_violation(&v, &EN), "- Lines 10-15: test issue\n");
     }
 
     #[test]
     fn test_format_tip() {
-        assert_eq!(format_tip("  tip  "), Some("\n**Tip:** tip\n".to_string()));
-        assert_eq!(format_tip(""), None);
-        assert_eq!(format_tip("   "), None);
+        assert_eq!(
+            format_tip(&"  tip  ".into(), &EN),
+            Some("\n**Tip:** tip\n".to_string())
+        );
+        assert_eq!(format_tip(&"".into(), &EN), None);
+        assert_eq!(format_tip(&"   ".into(), &EN), None);
+        let tip = Tip::Detailed {
+            text: "Re-render".into(),
+            command: Some("firekeeper config format".into()),
+            docs_url: Some("https://example.com/fmt".into()),
+        };
+        assert_eq!(
+            format_tip(&tip, &EN).unwrap(),
+            "\n**Tip:** Re-render\n\n```sh\nfirekeeper config format\n```\n\n<https://example.com/fmt>\n"
+        );
     }
 
     #[test]
@@ -330,7 +914,11 @@ mod tests {
 
     #[test]
     fn test_format_rule() {
-        assert_eq!(format_rule("TestRule"), "## Rule: TestRule\n\n");
+        assert_eq!(format_rule("TestRule", None, &EN), "## Rule: TestRule\n\n");
+        assert_eq!(
+            format_rule("TestRule", Some("test-rule"), &EN),
+            "## Rule: TestRule (`test-rule`)\n\n"
+        );
     }
 
     #[test]
@@ -341,15 +929,19 @@ mod tests {
                 start_line: 1,
                 end_line: 2,
                 detail: "issue1".to_string(),
+                rule_id: None,
+                owners: vec![],
             },
             Violation {
                 file: "test.rs".to_string(),
                 start_line: 3,
                 end_line: 4,
                 detail: "issue2".to_string(),
+                rule_id: None,
+                owners: vec![],
             },
         ];
-        let result = format_rule_violations("TestRule", &violations, Some("fix it"));
+        let result = format_rule_violations("TestRule", &violations, Some(&"fix it".into()), &EN);
         assert!(result.contains("## Rule: TestRule"));
         assert!(result.contains("Lines 1-2: issue1"));
         assert!(result.contains("Lines 3-4: issue2"));
@@ -358,9 +950,144 @@ mod tests {
 
     #[test]
     fn test_format_violations_empty() {
-        let violations = HashMap::new();
-        let tips = HashMap::new();
-        assert_eq!(format_violations(&violations, &tips), "No violations found");
+        let violations = ViolationsByFile::new();
+        let tips = BTreeMap::new();
+        assert_eq!(
+            format_violations(&violations, &tips, &EN),
+            "No violations found"
+        );
+    }
+
+    #[test]
+    fn test_format_violations_localized() {
+        let violations = ViolationsByFile::from([(
+            "a.rs".to_string(),
+            BTreeMap::from([(
+                "Rule".to_string(),
+                vec![Violation {
+                    file: "a.rs".to_string(),
+                    start_line: 1,
+                    end_line: 2,
+                    detail: "問題".to_string(),
+                    rule_id: None,
+                    owners: vec![],
+                }],
+            )]),
+        )]);
+        let tips = BTreeMap::from([("Rule".to_string(), "直す".into())]);
+        assert_eq!(
+            format_violations(
+                &violations,
+                &tips,
+                crate::review::locale::strings(Some("ja"))
+            ),
+            "# a.rs の違反\n\n## ルール: Rule\n\n- 行 1-2: 問題\n\n**ヒント:** 直す"
+        );
+    }
+
+    #[test]
+    fn test_format_skipped() {
+        assert_eq!(format_skipped(&[], &EN), None);
+        assert_eq!(
+            format_skipped(
+                &[SkippedFile {
+                    file: "vendor/a.js".into(),
+                    reason: SkipReason::Sampled
+                }],
+                &EN
+            )
+            .unwrap(),
+            "\n\n# Skipped (1)\n\n- vendor/a.js: Not reviewed by non-blocking rules (sampled changeset)"
+        );
+    }
+
+    #[test]
+    fn test_format_quarantined() {
+        let violations_by_file = BTreeMap::from([(
+            "a.rs".to_string(),
+            BTreeMap::from([(
+                "New rule".to_string(),
+                vec![Violation {
+                    file: "a.rs".into(),
+                    detail: "detail".into(),
+                    start_line: 1,
+                    end_line: 1,
+                    rule_id: None,
+                    owners: vec![],
+                }],
+            )]),
+        )]);
+        assert_eq!(
+            format_quarantined(&BTreeSet::new(), &violations_by_file, &EN),
+            None
+        );
+        let quarantined = BTreeSet::from(["New rule".to_string(), "Quiet rule".to_string()]);
+        assert_eq!(
+            format_quarantined(&quarantined, &violations_by_file, &EN).unwrap(),
+            "\n\n# Quarantined rules (reported without blocking)\n\n- **New rule** (1)\n- **Quiet rule** (0)"
+        );
+    }
+
+    #[test]
+    fn test_format_by_owner() {
+        let violation = |file: &str, owners: &[&str]| Violation {
+            file: file.into(),
+            detail: "detail".into(),
+            start_line: 1,
+            end_line: 2,
+            rule_id: None,
+            owners: owners.iter().map(|o| o.to_string()).collect(),
+        };
+        let violations_by_file = BTreeMap::from([
+            (
+                "api.rs".to_string(),
+                BTreeMap::from([(
+                    "Rule".to_string(),
+                    vec![violation("api.rs", &["@api", "@alice"])],
+                )]),
+            ),
+            (
+                "misc.rs".to_string(),
+                BTreeMap::from([("Rule".to_string(), vec![violation("misc.rs", &[])])]),
+            ),
+        ]);
+        assert_eq!(
+            format_by_owner(&violations_by_file, &BTreeMap::new(), &EN),
+            "# Owner: @alice\n\n## api.rs\n\n- **Rule** Lines 1-2: detail\n\n\
+             # Owner: @api\n\n## api.rs\n\n- **Rule** Lines 1-2: detail\n\n\
+             # Unowned\n\n## misc.rs\n\n- **Rule** Lines 1-2: detail"
+        );
+        let tips = BTreeMap::from([
+            ("Rule".to_string(), "Fix it.".into()),
+            ("Other".to_string(), "Unused.".into()),
+        ]);
+        assert!(
+            format_by_owner(&violations_by_file, &tips, &EN)
+                .ends_with("# How to fix\n\n## Rule: Rule\n\n**Tip:** Fix it.")
+        );
+    }
+
+    #[test]
+    fn test_format_badge() {
+        let badge = format_badge(0, 0, 0);
+        assert_eq!(badge.message, "0 blocking");
+        assert_eq!(badge.color, "brightgreen");
+
+        let badge = format_badge(3, 1, 0);
+        assert_eq!(badge.message, "3 blocking");
+        assert_eq!(badge.color, "red");
+
+        let badge = format_badge(0, 2, 0);
+        assert_eq!(badge.message, "0 blocking, 2 non-blocking");
+        assert_eq!(badge.color, "yellow");
+
+        let badge = format_badge(3, 0, 1);
+        assert_eq!(badge.message, "1 failed");
+        assert_eq!(badge.color, "lightgrey");
+
+        let json = serde_json::to_value(format_badge(0, 0, 0)).unwrap();
+        assert_eq!(json["schemaVersion"], 1);
+        assert_eq!(json["label"], "firekeeper");
     }
 
     #[test]
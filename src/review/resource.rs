@@ -0,0 +1,1125 @@
+use super::history::DEFAULT_PREVIOUS_RESULTS_PATH;
+use super::render::{ViolationFile, flatten_violations, get_fence_backticks};
+use super::triage::{self, Decision, TriageFile};
+use crate::config::ReviewConfig;
+use crate::mcp::McpServerConfig;
+use crate::rule::resource::{Resource, merge_resources};
+use crate::tool::read::Snapshot;
+use crate::tool::resource::{ResourceRead, ResourceSource};
+use crate::tool::sh::Shell;
+use crate::tool::skill::{SKILL_FILE, SkillRead};
+use crate::tool::utils::truncate_text_by_chars;
+use crate::util::render_template;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OnceCell};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+/// A loaded piece of a resource
+#[derive(Clone)]
+struct Section {
+    /// Key for de-duplication across resources (file path or command)
+    key: String,
+    /// Markdown to include in the prompt
+    markdown: String,
+    /// Skill directories made available to the `skill_read` tool, keyed by name
+    skills: Vec<(String, PathBuf)>,
+}
+
+impl Section {
+    fn new(key: String, markdown: String) -> Self {
+        Self {
+            key,
+            markdown,
+            skills: vec![],
+        }
+    }
+}
+
+/// Sections of one resource URI, or why it failed to load
+type Loaded = Result<Vec<Section>, String>;
+
+/// URI and character limit a resource was loaded with
+type CacheKey = (String, usize);
+
+/// Resources loaded for one worker
+#[derive(Debug, Default, Clone)]
+pub struct LoadedResources {
+    /// Markdown to include in the prompt
+    pub content: String,
+    /// Skill directories keyed by name, for the `skill_read` tool
+    pub skills: BTreeMap<String, PathBuf>,
+    /// URIs of resources listed for the `resource` tool instead of included
+    pub deferred: Vec<String>,
+}
+
+/// Loads global and rule resources for workers.
+///
+/// Each resource URI is resolved at most once per run and shared by all workers,
+/// so commands like `sh://git ls-files` and large `file://` globs are not re-run per task.
+/// URIs may reference run variables (`{base}`, `{files}`, `{rule.name}`, `{rule.id}`),
+/// in which case each distinct expansion is resolved once.
+#[derive(Clone)]
+pub struct ResourceLoader {
+    global: Vec<Resource>,
+    strict: bool,
+    max_chars: usize,
+    mcp_servers: Arc<HashMap<String, McpServerConfig>>,
+    /// Commands `sh://` resources may run, or `None` to allow any
+    sh_commands: Option<Arc<[String]>>,
+    /// Shell running `sh://` resources
+    shell: Shell,
+    /// Revision the changes are compared against, for `{base}`
+    base: Arc<str>,
+    /// Version of the files under review, which `cargo://` resources describe
+    snapshot: Snapshot,
+    /// Follow symlinks in `file://` and `skill://` globs that resolve outside the glob's root
+    follow_symlinks: bool,
+    cache: Arc<Mutex<HashMap<CacheKey, Arc<OnceCell<Loaded>>>>>,
+}
+
+/// Scheme of resources listing earlier results for the task's files
+const RESULTS_SCHEME: &str = "results://";
+
+/// `results://` target for the results of the previous run
+const PREVIOUS_RESULTS: &str = "previous";
+
+/// Task variables for resource URIs
+pub struct TaskVariables<'a> {
+    pub rule_name: &'a str,
+    pub rule_id: &'a str,
+    pub files: &'a [String],
+}
+
+impl ResourceLoader {
+    pub fn new(global: Vec<Resource>, strict: bool, max_chars: usize) -> Self {
+        Self {
+            global,
+            strict,
+            max_chars,
+            mcp_servers: Arc::default(),
+            sh_commands: None,
+            shell: Shell::default(),
+            base: Arc::from(""),
+            snapshot: Snapshot::WorkingTree,
+            follow_symlinks: false,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Set the revision `{base}` expands to
+    pub fn with_base(self, base: &str) -> Self {
+        Self {
+            base: Arc::from(base),
+            ..self
+        }
+    }
+
+    /// Set the version of the files under review, e.g. the index for staged changes
+    pub fn with_snapshot(self, snapshot: Snapshot) -> Self {
+        Self { snapshot, ..self }
+    }
+
+    /// Restrict `sh://` resources to the given commands
+    pub fn with_sh_commands(self, commands: Vec<String>) -> Self {
+        Self {
+            sh_commands: Some(commands.into()),
+            ..self
+        }
+    }
+
+    /// Create a loader for the global resources and settings of a review config
+    pub fn from_config(config: &ReviewConfig) -> Self {
+        let loader = Self {
+            mcp_servers: Arc::new(config.mcp_servers.clone()),
+            shell: config.shell.unwrap_or_default(),
+            follow_symlinks: config.follow_symlinks,
+            ..Self::new(
+                config.resources.clone(),
+                config.resources_strict,
+                config.max_resource_chars,
+            )
+        };
+        if config.allow_sh_resources {
+            return loader;
+        }
+        loader.with_sh_commands(
+            config
+                .allowed_shell_commands
+                .iter()
+                .chain(&config.sh_resource_commands)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Load global and rule resources as Markdown.
+    ///
+    /// A resource that fails to load is skipped with a warning, unless it is required
+    /// (or the loader is strict), in which case loading fails.
+    pub async fn load(
+        &self,
+        rule_resources: &[Resource],
+        task: &TaskVariables<'_>,
+    ) -> Result<LoadedResources, String> {
+        let mut resources = merge_resources(
+            self.global
+                .iter()
+                .cloned()
+                .chain(rule_resources.iter().cloned()),
+        );
+        resources.sort_by(|a, b| a.uri().cmp(b.uri()));
+
+        let files = task
+            .files
+            .iter()
+            .map(|f| shell_quote(f))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let variables = [
+            ("base", &*self.base),
+            ("files", files.as_str()),
+            ("rule.name", task.rule_name),
+            ("rule.id", task.rule_id),
+        ];
+        // Commands get every value as a single shell word
+        let (base, rule_name, rule_id) = (
+            shell_quote(&self.base),
+            shell_quote(task.rule_name),
+            shell_quote(task.rule_id),
+        );
+        let shell_variables = [
+            ("base", base.as_str()),
+            ("files", files.as_str()),
+            ("rule.name", rule_name.as_str()),
+            ("rule.id", rule_id.as_str()),
+        ];
+
+        let mut loaded = LoadedResources::default();
+        let mut keys = HashSet::new();
+        for resource in &resources {
+            let uri = &if resource.uri().starts_with("sh://") {
+                render_template(resource.uri(), &shell_variables)
+            } else {
+                render_template(resource.uri(), &variables)
+            };
+            if !resource.inline() {
+                // A required resource must load even if the agent never reads it
+                if (self.strict || resource.required())
+                    && let Err(e) = self.load_sections(uri, task.files, usize::MAX).await
+                {
+                    return Err(format!("Required resource '{}' failed: {}", uri, e));
+                }
+                loaded.deferred.push(uri.clone());
+                continue;
+            }
+            match self.load_sections(uri, task.files, self.max_chars).await {
+                Ok(sections) => {
+                    for section in sections {
+                        if keys.insert(section.key) {
+                            loaded.content.push_str(&section.markdown);
+                            loaded.skills.extend(section.skills);
+                        }
+                    }
+                }
+                Err(e) if self.strict || resource.required() => {
+                    return Err(format!("Required resource '{}' failed: {}", uri, e));
+                }
+                Err(e) => warn!("Skipping resource '{}': {}", uri, e),
+            }
+        }
+        if !loaded.deferred.is_empty() {
+            loaded.content.push_str(&format!(
+                "### On-Demand Resources\n\nRead these with the `{}` tool when relevant:\n\n",
+                ResourceRead::TOOL_NAME
+            ));
+            for uri in &loaded.deferred {
+                loaded.content.push_str(&format!("- `{}`\n", uri));
+            }
+            loaded.content.push('\n');
+        }
+        Ok(loaded)
+    }
+
+    async fn load_sections(&self, uri: &str, files: &[String], max_chars: usize) -> Loaded {
+        // Results are filtered to the task's files, so they are not shared across workers
+        match uri.strip_prefix(RESULTS_SCHEME) {
+            Some(target) => load_results_resource(target, files, max_chars),
+            None => self.load_cached(uri, max_chars).await,
+        }
+    }
+
+    async fn load_cached(&self, uri: &str, max_chars: usize) -> Loaded {
+        let cell = self
+            .cache
+            .lock()
+            .await
+            .entry((uri.to_string(), max_chars))
+            .or_default()
+            .clone();
+        cell.get_or_init(|| async {
+            debug!("Loading resource '{}'", uri);
+            load_uri(
+                uri,
+                max_chars,
+                &self.mcp_servers,
+                self.sh_commands.as_deref(),
+                self.shell,
+                self.follow_symlinks,
+                &self.snapshot,
+            )
+            .await
+        })
+        .await
+        .clone()
+    }
+}
+
+/// Resources listed for the `resource` tool are loaded without `max_resource_chars`, as
+/// the tool returns them page by page
+#[async_trait]
+impl ResourceSource for ResourceLoader {
+    async fn load(&self, uri: &str, files: &[String]) -> Result<String, String> {
+        let sections = self.load_sections(uri, files, usize::MAX).await?;
+        Ok(sections.into_iter().map(|s| s.markdown).collect())
+    }
+}
+
+/// Quote a value for a shell command when it contains characters other than
+/// letters, digits, and `-_./+@%=:,`
+fn shell_quote(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./+@%=:,".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Load file://, skill://, sh://, http(s)://, mcp://, or cargo:// URIs
+async fn load_uri(
+    uri: &str,
+    max_chars: usize,
+    mcp_servers: &HashMap<String, McpServerConfig>,
+    sh_commands: Option<&[String]>,
+    shell: Shell,
+    follow_symlinks: bool,
+    snapshot: &Snapshot,
+) -> Loaded {
+    if let Some(pattern) = uri.strip_prefix("file://") {
+        load_file_resource(pattern, max_chars, follow_symlinks)
+    } else if let Some(pattern) = uri.strip_prefix("skill://") {
+        load_skill_resource(pattern, max_chars, follow_symlinks)
+    } else if let Some(cmd) = uri.strip_prefix("sh://") {
+        load_shell_resource(cmd, max_chars, sh_commands, shell).await
+    } else if uri.starts_with("https://") || uri.starts_with("http://") {
+        load_url_resource(uri, max_chars).await
+    } else if let Some(rest) = uri.strip_prefix("mcp://") {
+        load_mcp_resource(rest, max_chars, mcp_servers).await
+    } else if let Some(target) = uri.strip_prefix("cargo://") {
+        load_cargo_resource(target, max_chars, snapshot).await
+    } else {
+        Err("unknown resource type".to_string())
+    }
+}
+
+/// Truncate resource content to `max_chars`, with a hint on how to read the rest
+fn truncate_resource(content: String, max_chars: usize, hint: &str) -> String {
+    let result = truncate_text_by_chars(content, 0, max_chars);
+    if result.truncated {
+        format!("{}\nHint: {}", result.content, hint)
+    } else {
+        result.content
+    }
+}
+
+/// Resolve path with ~ and absolute path support, returns (base_path, glob_pattern)
+fn resolve_path(pattern: &str) -> (std::path::PathBuf, String) {
+    if let Some(rest) = pattern.strip_prefix("~/") {
+        if let Some(home) = std::env::var_os("HOME") {
+            (std::path::PathBuf::from(home), rest.to_string())
+        } else {
+            (std::path::PathBuf::from("."), pattern.to_string())
+        }
+    } else if let Some(rest) = pattern.strip_prefix('/') {
+        ("/".into(), rest.to_string())
+    } else {
+        (std::path::PathBuf::from("."), pattern.to_string())
+    }
+}
+
+/// Find files matching a glob pattern
+fn find_files_by_glob(pattern: &str, follow_symlinks: bool) -> Vec<String> {
+    let (base_path, glob_pattern) = resolve_path(pattern);
+    let Ok(glob) = globset::Glob::new(&glob_pattern) else {
+        warn!("Invalid glob pattern '{}'", pattern);
+        return vec![];
+    };
+
+    let mut builder = globset::GlobSetBuilder::new();
+    builder.add(glob);
+    let Ok(globset) = builder.build() else {
+        return vec![];
+    };
+
+    let mut walk = match GlobWalk::new(&base_path, &globset, follow_symlinks) {
+        Ok(walk) => walk,
+        Err(e) => {
+            debug!("Failed to resolve {}: {}", base_path.display(), e);
+            return vec![];
+        }
+    };
+    let _ = walk.walk(&base_path);
+    walk.matches
+}
+
+/// Recursive directory walk collecting files that match a glob.
+///
+/// Symlinks are followed when they resolve inside the walk's root. Others, e.g. a link to
+/// `/etc`, are skipped with a warning unless `follow_symlinks` is set, so they cannot pull in
+/// files from outside the repository. Each directory is visited once, so symlink cycles
+/// terminate.
+struct GlobWalk<'a> {
+    globset: &'a globset::GlobSet,
+    follow_symlinks: bool,
+    /// Canonical root that symlinks must stay within, unless `follow_symlinks` is set
+    root: PathBuf,
+    /// Canonical directories already walked
+    visited: HashSet<PathBuf>,
+    matches: Vec<String>,
+}
+
+impl<'a> GlobWalk<'a> {
+    fn new(
+        root: &Path,
+        globset: &'a globset::GlobSet,
+        follow_symlinks: bool,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            globset,
+            follow_symlinks,
+            root: root.canonicalize()?,
+            visited: HashSet::new(),
+            matches: Vec::new(),
+        })
+    }
+
+    fn walk(&mut self, path: &Path) -> std::io::Result<()> {
+        if !self.visited.insert(path.canonicalize()?) {
+            debug!("Skipping symlink cycle at {}", path.display());
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let entry_path = entry.path();
+            let file_type = entry.file_type()?;
+            let (is_file, is_dir) = if file_type.is_symlink() {
+                match entry_path.canonicalize() {
+                    Ok(target) if self.follow_symlinks || target.starts_with(&self.root) => {
+                        (target.is_file(), target.is_dir())
+                    }
+                    Ok(target) => {
+                        warn!(
+                            "Skipping symlink {} to {} outside {}; set review.follow_symlinks = true to follow it",
+                            entry_path.display(),
+                            target.display(),
+                            self.root.display()
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!("Skipping dangling symlink {}: {}", entry_path.display(), e);
+                        continue;
+                    }
+                }
+            } else {
+                (file_type.is_file(), file_type.is_dir())
+            };
+
+            if is_file && let Some(path_str) = entry_path.to_str() {
+                let relative = path_str.strip_prefix("./").unwrap_or(path_str);
+                if self.globset.is_match(path_str) || self.globset.is_match(relative) {
+                    self.matches.push(path_str.to_string());
+                }
+            }
+
+            if is_dir {
+                self.walk(&entry_path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Load file:// resources
+fn load_file_resource(pattern: &str, max_chars: usize, follow_symlinks: bool) -> Loaded {
+    let mut paths = find_files_by_glob(pattern, follow_symlinks);
+    paths.sort();
+    if paths.is_empty() {
+        return Err("no files matched".to_string());
+    }
+    let mut sections = Vec::new();
+    // The files of a glob share the limit, in the order they are listed
+    let mut remaining = max_chars;
+    let mut omitted = Vec::new();
+    for path in paths {
+        if remaining == 0 {
+            omitted.push(path);
+            continue;
+        }
+        let file_content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read file {}: {}", path, e))?;
+        let chars = file_content.chars().count();
+        let file_content = truncate_resource(
+            file_content,
+            remaining,
+            &format!("Use the sh tool to read the rest of {}.", path),
+        );
+        remaining = remaining.saturating_sub(chars);
+        let lang = std::path::Path::new(&path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("");
+        let fence = get_fence_backticks(&file_content);
+        let section = format!(
+            "### {}\n\n{}{}\n{}\n{}\n\n",
+            path, fence, lang, file_content, fence
+        );
+        sections.push(Section::new(path, section));
+    }
+    if !omitted.is_empty() {
+        let list: String = omitted.iter().map(|p| format!("- {}\n", p)).collect();
+        sections.push(Section::new(
+            format!("file://{} (omitted)", pattern),
+            format!(
+                "### Omitted files of `{}`\n\nThese files exceed `review.max_resource_chars`; use the sh tool to read them:\n\n{}\n",
+                pattern, list
+            ),
+        ));
+    }
+    Ok(sections)
+}
+
+/// Parsed skill file
+struct Skill {
+    name: String,
+    description: String,
+    body: String,
+    dir: PathBuf,
+}
+
+/// Parse a skill file, naming it by its frontmatter `name` or else its directory
+fn parse_skill(path: &str) -> Result<Skill, String> {
+    let file_content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read file {}: {}", path, e))?;
+    let matter = gray_matter::Matter::<gray_matter::engine::YAML>::new();
+    let parsed = matter
+        .parse::<serde_json::Value>(&file_content)
+        .map_err(|_| format!("failed to parse frontmatter in {}", path))?;
+
+    let field = |key: &str| {
+        parsed
+            .data
+            .as_ref()
+            .and_then(|d| d.get(key))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    let dir = Path::new(path)
+        .parent()
+        .unwrap_or(Path::new("."))
+        .to_path_buf();
+    let name = field("name").unwrap_or_else(|| {
+        dir.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string())
+    });
+    Ok(Skill {
+        name,
+        description: field("description").unwrap_or_default(),
+        body: parsed.content,
+        dir,
+    })
+}
+
+/// Load skill:// resources.
+///
+/// A glob includes the instructions of each matched skill file. A directory lists the skills
+/// found under it, for the agent to load on demand with the `skill_read` tool.
+fn load_skill_resource(pattern: &str, max_chars: usize, follow_symlinks: bool) -> Loaded {
+    let (base_path, rest) = resolve_path(pattern);
+    let dir = base_path.join(&rest);
+    if dir.is_dir() {
+        return load_skill_catalog(&dir, max_chars, follow_symlinks);
+    }
+
+    let paths: Vec<String> = find_files_by_glob(pattern, follow_symlinks)
+        .into_iter()
+        .filter(|p| p.ends_with(".md"))
+        .collect();
+    if paths.is_empty() {
+        return Err("no skill files matched".to_string());
+    }
+    let mut sections = Vec::new();
+    for path in paths {
+        let skill = parse_skill(&path)?;
+        let body = truncate_resource(
+            skill.body,
+            max_chars,
+            &format!("Use {} to read the rest.", SkillRead::TOOL_NAME),
+        );
+        let fence = get_fence_backticks(&body);
+        let markdown = format!(
+            "### Skill `{}` ({})\n\n{}\n\n{}md\n{}\n{}\n\n\
+             Read files this skill references with {}(skill=\"{}\", path=\"<relative path>\").\n\n",
+            skill.name,
+            path,
+            skill.description,
+            fence,
+            body.trim(),
+            fence,
+            SkillRead::TOOL_NAME,
+            skill.name
+        );
+        sections.push(Section {
+            key: path,
+            markdown,
+            skills: vec![(skill.name, skill.dir)],
+        });
+    }
+    Ok(sections)
+}
+
+/// List skills under a directory by name and description
+fn load_skill_catalog(dir: &Path, max_chars: usize, follow_symlinks: bool) -> Loaded {
+    let mut builder = globset::GlobSetBuilder::new();
+    builder.add(globset::Glob::new(&format!("**/{}", SKILL_FILE)).unwrap());
+    let globset = builder.build().unwrap();
+    let read_error =
+        |e: std::io::Error| format!("failed to read directory {}: {}", dir.display(), e);
+    let mut walk = GlobWalk::new(dir, &globset, follow_symlinks).map_err(read_error)?;
+    walk.walk(dir).map_err(read_error)?;
+    let mut paths = walk.matches;
+    if paths.is_empty() {
+        return Err(format!("no {} files found", SKILL_FILE));
+    }
+    paths.sort();
+
+    let mut catalog = String::new();
+    let mut skills = Vec::new();
+    for path in paths {
+        let skill = parse_skill(&path)?;
+        catalog.push_str(&format!(
+            "- `{}`: {}\n",
+            skill.name,
+            skill.description.trim()
+        ));
+        skills.push((skill.name, skill.dir));
+    }
+    let catalog = truncate_resource(
+        catalog,
+        max_chars,
+        "Some skills are not listed; narrow the skill:// directory.",
+    );
+    let markdown = format!(
+        "### Skills in {}\n\n{}\n\
+         If a skill is relevant to the rule, load its instructions with {}(skill=\"<name>\"), \
+         then read files it references with the path argument.\n\n",
+        dir.display(),
+        catalog,
+        SkillRead::TOOL_NAME
+    );
+    Ok(vec![Section {
+        key: dir.display().to_string(),
+        markdown,
+        skills,
+    }])
+}
+
+/// Load sh:// resources
+/// Run sh:// resources, rejecting commands outside `allowed_commands` when given
+async fn load_shell_resource(
+    cmd: &str,
+    max_chars: usize,
+    allowed_commands: Option<&[String]>,
+    shell: Shell,
+) -> Loaded {
+    if let Some(allowed_commands) = allowed_commands {
+        crate::tool::sh::validate_command(cmd, allowed_commands, shell).map_err(|e| {
+            format!(
+                "{} (allow it with review.sh_resource_commands, or review.allow_sh_resources for trusted configs)",
+                e
+            )
+        })?;
+    }
+    // Resources are shared across workers, so they are not cancelled with one
+    let output = crate::tool::sh::run_shell_command(cmd, shell, None, &CancellationToken::new())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "command exited with {}: {}",
+            output.status,
+            output.stderr.trim()
+        ));
+    }
+    let stdout = truncate_resource(
+        output.into_text(),
+        max_chars,
+        "Use the sh tool to run a narrower command for the rest.",
+    );
+    let fence = get_fence_backticks(&stdout);
+    let section = format!("### `{}`\n\n{}\n{}\n{}\n\n", cmd, fence, stdout, fence);
+    Ok(vec![Section::new(format!("sh://{}", cmd), section)])
+}
+
+/// Load http(s):// resources as Markdown
+async fn load_url_resource(url: &str, max_chars: usize) -> Loaded {
+    let markdown = crate::tool::fetch::fetch_markdown(url, None).await?;
+    let markdown = truncate_resource(
+        markdown,
+        max_chars,
+        "Use the fetch tool with start_char to read the rest.",
+    );
+    let fence = get_fence_backticks(&markdown);
+    let section = format!("### {}\n\n{}md\n{}\n{}\n\n", url, fence, markdown, fence);
+    Ok(vec![Section::new(url.to_string(), section)])
+}
+
+/// Load results:// resources: violations an earlier review reported for the task's files.
+///
+/// `results://previous` reads the results of the last run, `results://<path>` a results file
+/// from `review --output`. Triage decisions from `firekeeper triage` are shown with each
+/// violation.
+fn load_results_resource(target: &str, files: &[String], max_chars: usize) -> Loaded {
+    let path = if target == PREVIOUS_RESULTS {
+        if !Path::new(DEFAULT_PREVIOUS_RESULTS_PATH).exists() {
+            // No earlier run to compare with, e.g. on the first review
+            debug!("No previous results at {}", DEFAULT_PREVIOUS_RESULTS_PATH);
+            return Ok(vec![]);
+        }
+        DEFAULT_PREVIOUS_RESULTS_PATH
+    } else {
+        target
+    };
+    let violation_file = ViolationFile::load(path)?;
+    let decisions: TriageFile = triage::load_or_default(triage::DEFAULT_DECISIONS_PATH)?;
+    let key = format!("{}{}", RESULTS_SCHEME, target);
+    let content = format_previous_results(&violation_file, &decisions, files);
+    let content = truncate_resource(content, max_chars, "The rest of the results is omitted.");
+    let section = format!("### Previous Review Results ({})\n\n{}\n\n", path, content);
+    Ok(vec![Section::new(key, section)])
+}
+
+/// List violations of the given files, with their triage decisions and guidance for the agent
+fn format_previous_results(
+    violation_file: &ViolationFile,
+    decisions: &TriageFile,
+    files: &[String],
+) -> String {
+    let decision_of = |fingerprint: &str| {
+        decisions
+            .decisions
+            .iter()
+            .find(|d| d.fingerprint == fingerprint)
+            .map(|d| d.decision)
+    };
+    let lines: Vec<String> = flatten_violations(&violation_file.violations)
+        .into_iter()
+        .filter(|(_, v)| files.contains(&v.file))
+        .map(|(rule, v)| {
+            let decision = match decision_of(&v.fingerprint(rule)) {
+                Some(Decision::Accepted) => " (triaged: accepted)",
+                Some(Decision::Dismissed | Decision::Suppressed) => " (triaged: false positive)",
+                None => "",
+            };
+            format!(
+                "- `{}` lines {}-{}, rule '{}'{}: {}",
+                v.file, v.start_line, v.end_line, rule, decision, v.detail
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        return "The previous review reported no violations in these files.".to_string();
+    }
+    format!(
+        "Violations the previous review reported in these files. Check whether the changes \
+         fixed them, and report one again only if it is still present. Do not report findings \
+         triaged as false positives.\n\n{}",
+        lines.join("\n")
+    )
+}
+
+/// Load mcp:// resources from a configured MCP server
+async fn load_mcp_resource(
+    rest: &str,
+    max_chars: usize,
+    mcp_servers: &HashMap<String, McpServerConfig>,
+) -> Loaded {
+    let (server, uri) = crate::mcp::parse_resource(rest)?;
+    let config = mcp_servers
+        .get(server)
+        .ok_or_else(|| format!("MCP server '{}' is not configured", server))?;
+    let text = crate::mcp::read_resource(config, uri).await?;
+    let text = truncate_resource(text, max_chars, "The rest of this resource is omitted.");
+    let fence = get_fence_backticks(&text);
+    let section = format!("### {}\n\n{}\n{}\n{}\n\n", uri, fence, text, fence);
+    Ok(vec![Section::new(format!("mcp://{}", rest), section)])
+}
+
+/// Load cargo:// resources: `cargo://metadata` condenses the Rust workspace's crates,
+/// features, and declared dependencies, as of the revision under review
+async fn load_cargo_resource(target: &str, max_chars: usize, snapshot: &Snapshot) -> Loaded {
+    if target != "metadata" {
+        return Err(format!(
+            "unknown cargo resource '{}' (supported: metadata)",
+            target
+        ));
+    }
+    let json = match snapshot {
+        // Staged manifests may differ from those on disk
+        Snapshot::Index => {
+            let dir = crate::util::checkout_index()?;
+            super::cargo::workspace_metadata(dir.path()).await?
+        }
+        Snapshot::Directory(dir) => super::cargo::workspace_metadata(dir).await?,
+        Snapshot::WorkingTree | Snapshot::Overlay(_) => {
+            super::cargo::workspace_metadata(Path::new(".")).await?
+        }
+    };
+    let summary = truncate_resource(
+        super::cargo::format_workspace(&json)?,
+        max_chars,
+        "Read the Cargo.toml files for the rest.",
+    );
+    let section = format!("### Cargo Workspace\n\n{}\n", summary);
+    Ok(vec![Section::new("cargo://metadata".to_string(), section)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_resource() {
+        assert_eq!(truncate_resource("short".into(), 10, "hint"), "short");
+        let truncated = truncate_resource("0123456789".into(), 4, "Read more.");
+        assert!(truncated.starts_with("0123"));
+        assert!(truncated.contains("truncated [4/10 chars]"));
+        assert!(truncated.ends_with("Hint: Read more."));
+    }
+
+    const TASK: TaskVariables = TaskVariables {
+        rule_name: "Rule",
+        rule_id: "rule",
+        files: &[],
+    };
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("src/main.rs"), "src/main.rs");
+        assert_eq!(shell_quote("docs/my file.md"), "'docs/my file.md'");
+        assert_eq!(shell_quote("it's.md"), "'it'\\''s.md'");
+    }
+
+    #[tokio::test]
+    async fn test_load_expands_variables() {
+        let loader = ResourceLoader::new(vec![], false, 1000).with_base("HEAD~3");
+        let files = ["a.rs".to_string(), "b c.rs".to_string()];
+        let task = TaskVariables {
+            files: &files,
+            ..TASK
+        };
+        let loaded = loader
+            .load(
+                &[Resource::from("sh://echo {base} {files} {rule.name} {x}")],
+                &task,
+            )
+            .await
+            .unwrap();
+        assert!(loaded.content.contains("HEAD~3 a.rs b c.rs Rule {x}"));
+
+        // A quote in a value cannot end the word and inject a command
+        let task = TaskVariables {
+            rule_name: "Don't; echo injected",
+            ..TASK
+        };
+        let loaded = loader
+            .load(&[Resource::from("sh://echo [{rule.name}]")], &task)
+            .await
+            .unwrap();
+        assert!(loaded.content.contains("[Don't; echo injected]"));
+        assert!(!loaded.content.contains("\ninjected"));
+    }
+
+    #[tokio::test]
+    async fn test_load_caches_per_run() {
+        let uri = "sh://date +%s%N";
+        let loader = ResourceLoader::new(vec![Resource::from(uri)], false, 100);
+        let first = loader
+            .load(&[Resource::from(uri)], &TASK)
+            .await
+            .unwrap()
+            .content;
+        assert_eq!(first.matches("### `date").count(), 1);
+        assert_eq!(loader.load(&[], &TASK).await.unwrap().content, first);
+    }
+
+    #[tokio::test]
+    async fn test_load_required() {
+        let failing = "sh://exit 1";
+        let loader = ResourceLoader::new(vec![], false, 100);
+        assert_eq!(
+            loader
+                .load(&[Resource::from(failing)], &TASK)
+                .await
+                .unwrap()
+                .content,
+            ""
+        );
+        let required = Resource::Detailed {
+            uri: failing.into(),
+            required: true,
+            inline: true,
+        };
+        assert!(loader.load(&[required], &TASK).await.is_err());
+        let strict = ResourceLoader::new(vec![], true, 100);
+        assert!(
+            strict
+                .load(&[Resource::from(failing)], &TASK)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_format_previous_results() {
+        let violation = |file: &str, line| crate::types::Violation {
+            file: file.into(),
+            detail: "Magic number".into(),
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
+        };
+        let violation_file = ViolationFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            violations: BTreeMap::from([
+                (
+                    "a.rs".to_string(),
+                    BTreeMap::from([(
+                        "Rule".to_string(),
+                        vec![violation("a.rs", 1), violation("a.rs", 2)],
+                    )]),
+                ),
+                (
+                    "b.rs".to_string(),
+                    BTreeMap::from([("Rule".to_string(), vec![violation("b.rs", 3)])]),
+                ),
+            ]),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: vec![],
+            incomplete: vec![],
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        let decisions = TriageFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            decisions: vec![triage::TriageEntry {
+                fingerprint: violation("a.rs", 2).fingerprint("Rule"),
+                rule: "Rule".into(),
+                violation: violation("a.rs", 2),
+                decision: Decision::Dismissed,
+            }],
+        };
+
+        let content = format_previous_results(&violation_file, &decisions, &["a.rs".into()]);
+        assert!(content.ends_with(
+            "- `a.rs` lines 1-1, rule 'Rule': Magic number\n\
+             - `a.rs` lines 2-2, rule 'Rule' (triaged: false positive): Magic number"
+        ));
+        assert!(!content.contains("b.rs"));
+        assert_eq!(
+            format_previous_results(&violation_file, &decisions, &["c.rs".into()]),
+            "The previous review reported no violations in these files."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_deferred() {
+        use crate::tool::resource::ResourceArgs;
+        let loader = ResourceLoader::new(vec![], false, 100);
+        let deferred = Resource::Detailed {
+            uri: "sh://echo guide".into(),
+            required: false,
+            inline: false,
+        };
+        let loaded = loader.load(&[deferred], &TASK).await.unwrap();
+        assert_eq!(loaded.deferred, ["sh://echo guide"]);
+        assert!(loaded.content.contains("- `sh://echo guide`"));
+        assert!(!loaded.content.contains("\nguide"));
+
+        let tool = ResourceRead::new(Arc::new(loader.clone()), loaded.deferred, &[]);
+        let read = |uri: &str| ResourceArgs {
+            uri: Some(uri.to_string()),
+            start_char: None,
+            num_chars: None,
+        };
+        assert!(
+            tool.clone()
+                .resource(read("sh://echo guide"))
+                .await
+                .contains("guide\n")
+        );
+        assert!(
+            tool.resource(read("sh://echo other"))
+                .await
+                .starts_with("Unknown resource 'sh://echo other'")
+        );
+
+        // Deferred resources are not capped by max_chars, and required ones must load
+        let long = Resource::Detailed {
+            uri: "sh://printf '%0300d' 0".into(),
+            required: true,
+            inline: false,
+        };
+        let loaded = loader.load(&[long], &TASK).await.unwrap();
+        let tool = ResourceRead::new(Arc::new(loader.clone()), loaded.deferred, &[]);
+        assert!(
+            tool.resource(read("sh://printf '%0300d' 0"))
+                .await
+                .contains(&"0".repeat(300))
+        );
+        let failing = Resource::Detailed {
+            uri: "sh://exit 3".into(),
+            required: true,
+            inline: false,
+        };
+        assert!(loader.load(&[failing], &TASK).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_sh_commands() {
+        let loader = ResourceLoader::new(vec![], false, 100).with_sh_commands(vec!["echo".into()]);
+        let loaded = loader
+            .load(&[Resource::from("sh://echo ok | echo fine")], &TASK)
+            .await
+            .unwrap();
+        assert!(loaded.content.contains("fine"));
+
+        let rejected = Resource::Detailed {
+            uri: "sh://echo ok; touch /tmp/firekeeper-sh-resource".into(),
+            required: true,
+            inline: true,
+        };
+        let error = loader.load(&[rejected], &TASK).await.unwrap_err();
+        assert!(error.contains("Command validation failed"));
+        assert!(!Path::new("/tmp/firekeeper-sh-resource").exists());
+    }
+
+    #[tokio::test]
+    async fn test_load_skill_directory() {
+        let temp =
+            crate::util::temp::TempDir::new(&format!("firekeeper-skills-{}", std::process::id()));
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("lint")).unwrap();
+        std::fs::create_dir_all(dir.join("style")).unwrap();
+        std::fs::write(
+            dir.join("lint").join(SKILL_FILE),
+            "---\nname: lint-rules\ndescription: Project lint conventions\n---\nBody",
+        )
+        .unwrap();
+        std::fs::write(dir.join("style").join(SKILL_FILE), "No frontmatter").unwrap();
+
+        let uri = format!("skill://{}", dir.display());
+        let loader = ResourceLoader::new(vec![], false, 1000);
+        let loaded = loader
+            .load(&[Resource::from(uri.as_str())], &TASK)
+            .await
+            .unwrap();
+
+        assert!(
+            loaded
+                .content
+                .contains("- `lint-rules`: Project lint conventions\n- `style`: \n")
+        );
+        assert!(!loaded.content.contains("Body"));
+        assert_eq!(
+            loaded.skills.keys().collect::<Vec<_>>(),
+            ["lint-rules", "style"]
+        );
+        assert_eq!(loaded.skills["style"], dir.join("style"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_glob_walk_symlinks() {
+        use std::os::unix::fs::symlink;
+        let base =
+            crate::util::temp::TempDir::new(&format!("firekeeper-links-{}", std::process::id()));
+        let root = base.path().join("repo");
+        let outside = base.path().join("outside");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(root.join("docs/a.md"), "a").unwrap();
+        std::fs::write(outside.join("secret.md"), "secret").unwrap();
+        // A cycle back to the root, a link escaping it, and a link within it
+        symlink(&root, root.join("docs/loop")).unwrap();
+        symlink(&outside, root.join("escape")).unwrap();
+        symlink(root.join("docs"), root.join("docs-link")).unwrap();
+
+        let mut builder = globset::GlobSetBuilder::new();
+        builder.add(globset::Glob::new("**/*.md").unwrap());
+        let globset = builder.build().unwrap();
+        let found = |follow_symlinks: bool| {
+            let mut walk = GlobWalk::new(&root, &globset, follow_symlinks).unwrap();
+            walk.walk(&root).unwrap();
+            let mut files: Vec<String> = walk
+                .matches
+                .iter()
+                .map(|m| {
+                    m.strip_prefix(&format!("{}/", root.display()))
+                        .unwrap()
+                        .to_string()
+                })
+                .collect();
+            files.sort();
+            files
+        };
+        // Links within the root are followed, each directory walked once whichever path
+        // reaches it first, and the link escaping the root is skipped
+        let confined = found(false);
+        assert_eq!(confined.len(), 1);
+        assert!(confined[0].ends_with("/a.md"));
+        let followed = found(true);
+        assert_eq!(followed.len(), 2);
+        assert_eq!(followed[1], "escape/secret.md");
+    }
+
+    #[test]
+    fn test_file_glob_shares_max_chars() {
+        // Relative, as absolute globs are walked from the filesystem root
+        let dir = crate::util::temp::TempDir::new_in(
+            Path::new("target"),
+            &format!("firekeeper-glob-{}", std::process::id()),
+        );
+        std::fs::create_dir_all(dir.path()).unwrap();
+        for (name, content) in [("a.md", "aaaaaa"), ("b.md", "bbbbbb"), ("c.md", "cc")] {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+        let pattern = format!("{}/*.md", dir.path().display());
+        let sections = load_file_resource(&pattern, 10, false).unwrap();
+        let markdown: Vec<&str> = sections.iter().map(|s| s.markdown.as_str()).collect();
+        assert_eq!(markdown.len(), 3);
+        assert!(markdown[0].contains("\naaaaaa\n"));
+        assert!(markdown[1].contains("bbbb\n") && markdown[1].contains("truncated [4/6 chars]"));
+        assert!(markdown[2].starts_with("### Omitted files of"));
+        assert!(markdown[2].ends_with("c.md\n\n"));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::util;
+use std::collections::HashMap;
+
+/// Files chosen for review in a sampled changeset, and the rest
+#[derive(Debug, PartialEq)]
+pub struct Sample {
+    pub files: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+/// Number of added and removed lines in a unified diff
+fn changed_lines(diff: &str) -> usize {
+    diff.lines()
+        .filter(|l| {
+            (l.starts_with('+') && !l.starts_with("+++"))
+                || (l.starts_with('-') && !l.starts_with("---"))
+        })
+        .count()
+}
+
+/// Pseudo-random number in (0, 1) derived from the path, so reruns and other builds pick
+/// the same sample
+fn unit_hash(path: &str) -> f64 {
+    (util::fnv1a_64(path.as_bytes()) as f64 + 1.0) / (u64::MAX as f64 + 2.0)
+}
+
+/// Pick `size` files by weighted sampling without replacement, favoring larger changes.
+///
+/// Uses Efraimidis-Spirakis keys `ln(u) / weight` with a per-path `u`,
+/// keeping the files with the largest keys.
+pub fn sample_files(files: &[String], diffs: &HashMap<String, String>, size: usize) -> Sample {
+    let mut keyed: Vec<(f64, &String)> = files
+        .iter()
+        .map(|file| {
+            let weight = diffs.get(file).map_or(0, |d| changed_lines(d)) as f64 + 1.0;
+            (unit_hash(file).ln() / weight, file)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+    let mut sampled: Vec<String> = keyed.iter().take(size).map(|(_, f)| (*f).clone()).collect();
+    let mut skipped: Vec<String> = keyed.iter().skip(size).map(|(_, f)| (*f).clone()).collect();
+    sampled.sort();
+    skipped.sort();
+    Sample {
+        files: sampled,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_lines() {
+        let diff = "--- a/a.rs\n+++ b/a.rs\n@@ -1,2 +1,2 @@\n-x\n+y\n+z\n context\n";
+        assert_eq!(changed_lines(diff), 3);
+    }
+
+    #[test]
+    fn test_sample_files() {
+        let files: Vec<String> = (0..50).map(|i| format!("vendor/{}.js", i)).collect();
+        let mut diffs: HashMap<String, String> = files
+            .iter()
+            .map(|f| (f.clone(), "+x\n".to_string()))
+            .collect();
+        diffs.insert("vendor/7.js".into(), "+x\n".repeat(100_000));
+
+        let sample = sample_files(&files, &diffs, 10);
+        assert_eq!(sample.files.len(), 10);
+        assert_eq!(sample.skipped.len(), 40);
+        assert!(sample.files.contains(&"vendor/7.js".to_string()));
+        assert_eq!(sample, sample_files(&files, &diffs, 10));
+    }
+}
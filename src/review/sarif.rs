@@ -0,0 +1,127 @@
+use super::render::{ViolationsByFile, flatten_violations};
+use crate::rule::body::RuleBody;
+use serde_json::{Value, json};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Format violations as a SARIF 2.1.0 log, for code scanning dashboards.
+///
+/// Violations of blocking rules are `error`s, others `warning`s. Each result carries the
+/// violation fingerprint so dashboards track it across runs.
+pub fn format_sarif(violations_by_file: &ViolationsByFile, rules: &[RuleBody]) -> Value {
+    let driver_rules: Vec<Value> = rules
+        .iter()
+        .map(|rule| {
+            let mut descriptor = json!({
+                "id": rule.id(),
+                "name": rule.name,
+                "shortDescription": { "text": rule.name },
+            });
+            if !rule.description.is_empty() {
+                descriptor["fullDescription"] = json!({ "text": rule.description });
+            }
+            if let Some(tip) = rule.tip.as_ref().filter(|t| !t.is_empty()) {
+                descriptor["help"] = json!({ "text": tip.to_plain() });
+                if let Some(url) = tip.docs_url() {
+                    descriptor["helpUri"] = json!(url);
+                }
+            }
+            descriptor
+        })
+        .collect();
+
+    let results: Vec<Value> = flatten_violations(violations_by_file)
+        .into_iter()
+        .map(|(rule_name, violation)| {
+            let rule = rules.iter().find(|r| r.name == rule_name);
+            let rule_id = violation
+                .rule_id
+                .clone()
+                .or_else(|| rule.map(RuleBody::id))
+                .unwrap_or_else(|| rule_name.to_string());
+            let level = if rule.is_some_and(|r| r.blocks()) {
+                "error"
+            } else {
+                "warning"
+            };
+            json!({
+                "ruleId": rule_id,
+                "level": level,
+                "message": { "text": violation.detail },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": violation.file },
+                        "region": {
+                            // SARIF lines are 1-based; guard against models reporting line 0
+                            "startLine": violation.start_line.max(1),
+                            "endLine": violation.end_line.max(violation.start_line).max(1),
+                        },
+                    },
+                }],
+                "partialFingerprints": { "firekeeper/v1": violation.fingerprint(rule_name) },
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "firekeeper",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/firekeeper-ai/firekeeper",
+                    "rules": driver_rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Violation;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_format_sarif() {
+        let violation = Violation {
+            file: "src/main.rs".into(),
+            detail: "Magic number 42".into(),
+            start_line: 3,
+            end_line: 3,
+            rule_id: Some("no-magic-numbers".into()),
+            owners: vec![],
+        };
+        let violations_by_file = BTreeMap::from([(
+            "src/main.rs".to_string(),
+            BTreeMap::from([("No Magic Numbers".to_string(), vec![violation.clone()])]),
+        )]);
+        let rules = [RuleBody::no_magic_numbers()];
+
+        let sarif = format_sarif(&violations_by_file, &rules);
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "no-magic-numbers");
+        assert_eq!(
+            run["results"][0],
+            json!({
+                "ruleId": "no-magic-numbers",
+                "level": "error",
+                "message": { "text": "Magic number 42" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/main.rs" },
+                        "region": { "startLine": 3, "endLine": 3 },
+                    },
+                }],
+                "partialFingerprints": {
+                    "firekeeper/v1": violation.fingerprint("No Magic Numbers"),
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Matching of rule `scope` and `exclude` patterns against changed files
+
+use crate::rule::body::RuleBody;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, HashSet};
+use tracing::{error, warn};
+
+/// Scope pattern prefix matching directories of changed files, e.g. `dir:services/payments/**`
+pub const DIR_SCOPE_PREFIX: &str = "dir:";
+
+pub fn build_globset(patterns: &[String], rule_name: &str, pattern_type: &str) -> Option<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => builder.add(glob),
+            Err(e) => {
+                warn!(
+                    "Invalid {} pattern '{}' in rule '{}': {}",
+                    pattern_type, pattern, rule_name, e
+                );
+                continue;
+            }
+        };
+    }
+    match builder.build() {
+        Ok(gs) => Some(gs),
+        Err(e) => {
+            error!(
+                "Failed to build {} globset for rule '{}': {}",
+                pattern_type, rule_name, e
+            );
+            None
+        }
+    }
+}
+
+/// Split scope patterns into file globs and `dir:` directory globs.
+///
+/// A trailing `/**` or `/` of a directory glob is dropped, so `dir:services/payments/**`
+/// and `dir:services/payments` both match the `services/payments` directory.
+fn split_scope(scope: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut file_patterns = Vec::new();
+    let mut dir_patterns = Vec::new();
+    for pattern in scope {
+        match pattern.strip_prefix(DIR_SCOPE_PREFIX) {
+            Some(dir) => {
+                let dir = dir.trim_end_matches("/**").trim_end_matches('/');
+                dir_patterns.push(dir.to_string());
+            }
+            None => file_patterns.push(pattern.clone()),
+        }
+    }
+    (file_patterns, dir_patterns)
+}
+
+/// Directories matched by the rule's `dir:` scope patterns, with the given files under each.
+///
+/// Each file belongs to its shallowest matching ancestor directory. Excluded files are skipped.
+pub fn scope_directories(rule: &RuleBody, files: &[String]) -> BTreeMap<String, Vec<String>> {
+    let (_, dir_patterns) = split_scope(&rule.scope);
+    if dir_patterns.is_empty() {
+        return BTreeMap::new();
+    }
+    // Directory globs match path components literally, so `dir:services/*` matches
+    // `services/payments` but not `services/payments/api`
+    let mut builder = GlobSetBuilder::new();
+    for pattern in &dir_patterns {
+        match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => warn!(
+                "Invalid scope pattern '{}{}' in rule '{}': {}",
+                DIR_SCOPE_PREFIX, pattern, rule.name, e
+            ),
+        }
+    }
+    let Ok(globset) = builder.build() else {
+        return BTreeMap::new();
+    };
+    let Some(exclude_globset) = build_globset(&rule.exclude, &rule.name, "exclude") else {
+        return BTreeMap::new();
+    };
+
+    let mut directories: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for file in files {
+        if exclude_globset.is_match(file) {
+            continue;
+        }
+        let dir = file
+            .match_indices('/')
+            .map(|(i, _)| &file[..i])
+            .find(|dir| globset.is_match(dir));
+        if let Some(dir) = dir {
+            directories
+                .entry(dir.to_string())
+                .or_default()
+                .push(file.clone());
+        }
+    }
+    directories
+}
+
+pub fn filter_files_by_scope(rule: &RuleBody, files: &[String]) -> Vec<String> {
+    let (file_patterns, _) = split_scope(&rule.scope);
+    let Some(globset) = build_globset(&file_patterns, &rule.name, "scope") else {
+        return vec![];
+    };
+    let Some(exclude_globset) = build_globset(&rule.exclude, &rule.name, "exclude") else {
+        return vec![];
+    };
+    let in_directory: HashSet<String> = scope_directories(rule, files)
+        .into_values()
+        .flatten()
+        .collect();
+
+    files
+        .iter()
+        .filter(|f| {
+            (globset.is_match(f) || in_directory.contains(*f)) && !exclude_globset.is_match(f)
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_files_by_scope_with_exclude() {
+        let rule = RuleBody {
+            id: None,
+            name: "Test Rule".into(),
+            description: "Test".into(),
+            instruction: "Test".into(),
+            scope: vec!["src/**/*.rs".into()],
+            exclude: vec!["**/tests/**".into(), "**/*_test.rs".into()],
+            max_files_per_task: None,
+            blocking: Some(true),
+            tip: None,
+            resources: vec![],
+            tags: vec![],
+            tools: Default::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
+        };
+
+        let files = vec![
+            "src/main.rs".into(),
+            "src/lib.rs".into(),
+            "src/tests/helper.rs".into(),
+            "src/util_test.rs".into(),
+            "src/util.rs".into(),
+        ];
+
+        let result = filter_files_by_scope(&rule, &files);
+        assert_eq!(result, vec!["src/main.rs", "src/lib.rs", "src/util.rs"]);
+    }
+
+    #[test]
+    fn test_scope_directories() {
+        let rule = RuleBody {
+            scope: vec!["dir:services/*/**".into(), "docs/*.md".into()],
+            exclude: vec!["**/*.lock".into()],
+            max_files_per_task: Some(1),
+            ..RuleBody::no_magic_numbers()
+        };
+        let files: Vec<String> = vec![
+            "services/payments/api.rs".into(),
+            "services/payments/db/schema.sql".into(),
+            "services/payments/Cargo.lock".into(),
+            "services/auth/login.rs".into(),
+            "services/README.md".into(),
+            "docs/a.md".into(),
+            "docs/b.md".into(),
+        ];
+
+        let directories = scope_directories(&rule, &files);
+        assert_eq!(
+            directories,
+            BTreeMap::from([
+                (
+                    "services/auth".into(),
+                    vec!["services/auth/login.rs".into()]
+                ),
+                (
+                    "services/payments".into(),
+                    vec![
+                        "services/payments/api.rs".to_string(),
+                        "services/payments/db/schema.sql".into()
+                    ]
+                ),
+            ])
+        );
+    }
+}
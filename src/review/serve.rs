@@ -0,0 +1,423 @@
+use super::metrics::Metrics;
+use super::resource::ResourceLoader;
+use super::worker;
+use crate::config::Config;
+use crate::tool::read::Snapshot;
+use crate::types::Violation;
+use crate::util;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+/// JSON-RPC error code for a request that is not valid JSON
+const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code for an unknown method
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for invalid method parameters
+const INVALID_PARAMS: i64 = -32602;
+
+/// JSON-RPC request or notification, one per line
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Parameters of a `review` request
+#[derive(Deserialize, Debug, Default)]
+struct ReviewParams {
+    /// Files to review as saved on disk
+    #[serde(default)]
+    files: Vec<String>,
+    /// Unsaved editor buffers to review instead of their files on disk
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    /// Only run rules with any of these tags
+    #[serde(default)]
+    rules_tags: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Buffer {
+    path: String,
+    content: String,
+}
+
+fn response(id: &Value, result: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+/// Whole-file diff of a buffer, as reviews of explicit files show them
+fn buffer_diff(path: &str, content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut diff = format!(
+        "--- /dev/null\n+++ b/{}\n@@ -0,0 +1,{} @@\n",
+        path,
+        lines.len()
+    );
+    for line in lines {
+        diff.push('+');
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    diff
+}
+
+/// `diagnostics` notifications for a completed task: one per file of the task, empty for
+/// files without violations so clients can clear stale findings
+fn diagnostics(
+    request_id: &Value,
+    rule: &crate::rule::body::RuleBody,
+    files: &[String],
+    violations: &[Violation],
+) -> Vec<Value> {
+    files
+        .iter()
+        .map(|file| {
+            let diagnostics: Vec<Value> = violations
+                .iter()
+                .filter(|v| v.file == *file)
+                .map(|v| {
+                    json!({
+                        "start_line": v.start_line,
+                        "end_line": v.end_line,
+                        "message": v.detail,
+                    })
+                })
+                .collect();
+            json!({
+                "jsonrpc": "2.0",
+                "method": "diagnostics",
+                "params": {
+                    "request_id": request_id,
+                    "file": file,
+                    "rule": rule.name,
+                    "rule_id": rule.id(),
+                    "severity": if rule.blocks() { "error" } else { "warning" },
+                    "diagnostics": diagnostics,
+                },
+            })
+        })
+        .collect()
+}
+
+/// `file:line: [rule] detail` lines of a task's violations
+fn violation_lines(rule: &crate::rule::body::RuleBody, violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|v| {
+            format!(
+                "{}:{}: [{}] {}\n",
+                v.file, v.start_line, rule.name, v.detail
+            )
+        })
+        .collect()
+}
+
+async fn write(stdout: &mut tokio::io::Stdout, text: &str) -> Result<(), String> {
+    stdout
+        .write_all(text.as_bytes())
+        .await
+        .and(stdout.flush().await)
+        .map_err(|e| format!("Failed to write to stdout: {}", e))
+}
+
+async fn send(stdout: &mut tokio::io::Stdout, message: &Value) -> Result<(), String> {
+    write(stdout, &format!("{}\n", message)).await
+}
+
+/// Where a review reports the violations of each task as it completes
+pub(super) enum Sink<'a> {
+    /// `diagnostics` notifications for the request with this id
+    Diagnostics(&'a Value),
+    /// Plain `file:line: [rule] detail` lines
+    Lines,
+}
+
+/// Review the requested files and buffers, streaming diagnostics as each task completes
+async fn review(
+    config: &Config,
+    api_key: &str,
+    parallelism: usize,
+    request_id: &Value,
+    params: ReviewParams,
+    stdout: &mut tokio::io::Stdout,
+    metrics: &Metrics,
+) -> Result<Value, String> {
+    let mut diffs = util::get_diffs(&util::Base::Files(params.files.clone()), &params.files);
+    let mut files = params.files;
+    for buffer in &params.buffers {
+        diffs.insert(
+            buffer.path.clone(),
+            buffer_diff(&buffer.path, &buffer.content),
+        );
+        if !files.contains(&buffer.path) {
+            files.push(buffer.path.clone());
+        }
+    }
+    // The read tool serves unsaved buffers; shell commands still see the files on disk
+    let snapshot = Snapshot::Overlay(Arc::new(
+        params
+            .buffers
+            .iter()
+            .map(|buffer| (buffer.path.clone(), buffer.content.clone()))
+            .collect(),
+    ));
+    let (violations, errors) = review_files(
+        config,
+        api_key,
+        parallelism,
+        &params.rules_tags,
+        files,
+        diffs,
+        snapshot,
+        Sink::Diagnostics(request_id),
+        stdout,
+        metrics,
+    )
+    .await?;
+    Ok(json!({"violations": violations, "errors": errors}))
+}
+
+/// Review `files` with their `diffs` against the rules with any of `rules_tags` (all rules
+/// if empty), reporting each task to `sink` as it completes. Returns the number of
+/// violations and the errors of failed tasks.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn review_files(
+    config: &Config,
+    api_key: &str,
+    parallelism: usize,
+    rules_tags: &[String],
+    files: Vec<String>,
+    diffs: HashMap<String, String>,
+    snapshot: Snapshot,
+    sink: Sink<'_>,
+    stdout: &mut tokio::io::Stdout,
+    metrics: &Metrics,
+) -> Result<(usize, Vec<String>), String> {
+    let start = std::time::Instant::now();
+    let rules: Vec<_> = config
+        .rules
+        .iter()
+        .filter(|rule| {
+            rules_tags.is_empty() || rule.tags.iter().any(|tag| rules_tags.contains(tag))
+        })
+        .cloned()
+        .collect();
+    let vendored = super::orchestrator::vendored_files(&files, &config.review.vendored);
+    let tasks = super::orchestrator::orchestrate(
+        &rules,
+        &files,
+        None,
+        &vendored,
+        config.review.max_files_per_task,
+    );
+    info!("Reviewing {} files in {} tasks", files.len(), tasks.len());
+
+    let shutdown = Arc::new(Mutex::new(false));
+    let resources = ResourceLoader::from_config(&config.review);
+    let settings = worker::WorkerSettings::from_config(config, api_key);
+    let diffs: HashMap<String, String> = util::restyle_diffs(
+        diffs,
+        config.review.diff_style,
+        config.review.diff_line_numbers,
+    );
+    let mut results = stream::iter(
+        tasks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (rule, task_files))| {
+                let shutdown = shutdown.clone();
+                let resources = resources.clone();
+                let all_files = files.clone();
+                let diffs = diffs.clone();
+                let settings = &settings;
+                let snapshot = snapshot.clone();
+                async move {
+                    let task = worker::Task {
+                        worker_id: i.to_string(),
+                        rule,
+                        files: task_files.clone(),
+                        chunk: None,
+                        all_changed_files: all_files,
+                        commit_messages: String::new(),
+                        overview: Arc::from(""),
+                        diffs,
+                        is_root_base: true,
+                        snapshot,
+                        resources: None,
+                    };
+                    let result =
+                        worker::worker(task, settings, resources, false, shutdown, Arc::default())
+                            .await;
+                    (rule, task_files, result)
+                }
+            }),
+    )
+    .buffer_unordered(parallelism);
+
+    let mut violations = 0;
+    let mut errors = Vec::new();
+    while let Some((rule, task_files, result)) = results.next().await {
+        match result {
+            Ok(result) => {
+                metrics.task(
+                    &rule.name,
+                    result.violations.len(),
+                    &result.usage,
+                    result.elapsed_secs,
+                );
+                violations += result.violations.len();
+                match sink {
+                    Sink::Diagnostics(request_id) => {
+                        for notification in
+                            diagnostics(request_id, rule, &task_files, &result.violations)
+                        {
+                            send(stdout, &notification).await?;
+                        }
+                    }
+                    Sink::Lines => {
+                        write(stdout, &violation_lines(rule, &result.violations)).await?
+                    }
+                }
+            }
+            Err(e) => {
+                if crate::llm::error::is_provider_failure(e.as_ref()) {
+                    metrics.provider_error();
+                }
+                warn!("Rule '{}' failed: {}", rule.name, e);
+                errors.push(format!("{}: {}", rule.name, e));
+            }
+        }
+    }
+    metrics.review(start.elapsed().as_secs_f64());
+    Ok((violations, errors))
+}
+
+/// Serve review requests over stdio as newline-delimited JSON-RPC 2.0, until `shutdown`
+/// or the end of input.
+///
+/// `review` takes `{files, buffers: [{path, content}], rules_tags}`, sends a `diagnostics`
+/// notification per file as each task completes, and responds with the violation count.
+/// With `metrics_addr`, Prometheus metrics of the reviews are served at `/metrics` on it.
+pub async fn serve(
+    config: &mut Config,
+    api_key: &str,
+    metrics_addr: Option<&str>,
+) -> Result<(), String> {
+    let probe = super::orchestrator::probe_provider(config, api_key).await;
+    let config = &*config;
+    let parallelism = super::orchestrator::resolve_max_parallel_workers(&config.review, &probe);
+    let metrics = Metrics::default();
+    if let Some(addr) = metrics_addr {
+        metrics.clone().listen(addr).await?;
+    }
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    info!("Serving reviews over stdio");
+
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Failed to read stdin: {}", e))?
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                send(
+                    &mut stdout,
+                    &error_response(&Value::Null, PARSE_ERROR, &e.to_string()),
+                )
+                .await?;
+                continue;
+            }
+        };
+        debug!("Received '{}' request", request.method);
+        let id = request.id.unwrap_or(Value::Null);
+        let reply = match request.method.as_str() {
+            "review" => match serde_json::from_value::<ReviewParams>(request.params) {
+                Ok(params) => {
+                    match review(
+                        config,
+                        api_key,
+                        parallelism,
+                        &id,
+                        params,
+                        &mut stdout,
+                        &metrics,
+                    )
+                    .await
+                    {
+                        Ok(result) => response(&id, result),
+                        Err(e) => return Err(e),
+                    }
+                }
+                Err(e) => error_response(&id, INVALID_PARAMS, &e.to_string()),
+            },
+            "shutdown" => {
+                send(&mut stdout, &response(&id, Value::Null)).await?;
+                break;
+            }
+            method => error_response(
+                &id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method '{}'", method),
+            ),
+        };
+        send(&mut stdout, &reply).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_diff() {
+        assert_eq!(
+            buffer_diff("src/a.rs", "fn a() {}\nlet x = 42;\n"),
+            "--- /dev/null\n+++ b/src/a.rs\n@@ -0,0 +1,2 @@\n+fn a() {}\n+let x = 42;\n"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_clear_files_without_violations() {
+        let rule = crate::rule::body::RuleBody::no_magic_numbers();
+        let violation = Violation {
+            file: "a.rs".into(),
+            detail: "Magic number 42".into(),
+            start_line: 2,
+            end_line: 2,
+            rule_id: None,
+            owners: vec![],
+        };
+        let notifications = diagnostics(
+            &json!(1),
+            &rule,
+            &["a.rs".to_string(), "b.rs".to_string()],
+            &[violation],
+        );
+        assert_eq!(
+            notifications[0]["params"]["diagnostics"][0]["message"],
+            "Magic number 42"
+        );
+        assert_eq!(notifications[0]["params"]["request_id"], 1);
+        assert_eq!(notifications[1]["params"]["diagnostics"], json!([]));
+    }
+}
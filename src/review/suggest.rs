@@ -0,0 +1,494 @@
+use super::render::{self, ViolationFile, get_fence_backticks};
+use super::triage::{Decision, TriageFile};
+use crate::config::Config;
+use crate::rule::body::RuleBody;
+use crate::tool::utils::truncate_text_by_chars;
+use crate::types::Violation;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tiny_loop::Agent;
+use tracing::{info, warn};
+
+/// Lines of surrounding context to show around each example
+const SNIPPET_CONTEXT_LINES: u32 = 2;
+
+const SYSTEM_PROMPT: &str = r"You maintain instructions for an LLM code reviewer.
+Given a rule instruction and triaged review findings, revise the instruction so that
+findings like the false positives are no longer reported, while findings like the
+true positives are still reported. Keep the original structure, intent, and tone.
+Make the smallest change that fixes the false positives.
+Respond with only the revised instruction text, without explanation or code fences.";
+
+/// Characters of each incident writeup or checklist sent to the model
+const MAX_SOURCE_CHARS: usize = 50_000;
+
+const INCIDENT_SYSTEM_PROMPT: &str = r"You write rules for an LLM code reviewer that checks diffs.
+Given incident postmortems and review checklists, derive rules that would have caught
+the root causes during code review. Each rule must be checkable from a diff and the
+repository, not from runtime behavior. Skip causes a reviewer cannot see in code, and
+rules that duplicate the existing rules.";
+
+const LINT_SYSTEM_PROMPT: &str = r"You write rules for an LLM code reviewer that checks diffs.
+Given linter configurations (e.g. .eslintrc, clippy.toml), infer the team's coding policies
+and derive rules for the intent behind them that the linters cannot express, such as
+architecture boundaries, naming meaning, error handling and API usage conventions.
+Do not restate what the configured lints already check mechanically, and skip rules that
+duplicate the existing rules.";
+
+/// Answer format appended to the system prompts that derive new rules
+const RULES_FORMAT: &str = r#"Respond with only TOML `[[rules]]` tables, without explanation or code fences. Each rule has:
+- name: short title
+- description: the incident, checklist item, or lint settings it is derived from
+- instruction: what to check, and what not to report, as Markdown
+- scope: glob patterns of files the rule applies to, e.g. ["**/*.rs"]
+- blocking: true only for severe, unambiguous issues"#;
+
+/// Triaged findings for one rule
+#[derive(Debug, Default)]
+pub struct Feedback<'a> {
+    /// Accepted violations (true positives)
+    pub accepted: Vec<&'a Violation>,
+    /// Dismissed or suppressed violations (false positives)
+    pub rejected: Vec<&'a Violation>,
+}
+
+/// Join violations from a results file with their triage decisions, grouped by rule.
+/// Violations without a decision are ignored.
+pub fn collect_feedback<'a>(
+    violation_file: &'a ViolationFile,
+    decisions: &TriageFile,
+) -> BTreeMap<&'a str, Feedback<'a>> {
+    let by_fingerprint: HashMap<&str, Decision> = decisions
+        .decisions
+        .iter()
+        .map(|d| (d.fingerprint.as_str(), d.decision))
+        .collect();
+
+    let mut feedback: BTreeMap<&str, Feedback> = BTreeMap::new();
+    for (rule, violation) in render::flatten_violations(&violation_file.violations) {
+        let Some(decision) = by_fingerprint.get(violation.fingerprint(rule).as_str()) else {
+            continue;
+        };
+        let entry = feedback.entry(rule).or_default();
+        match decision {
+            Decision::Accepted => entry.accepted.push(violation),
+            Decision::Dismissed | Decision::Suppressed => entry.rejected.push(violation),
+        }
+    }
+    feedback
+}
+
+fn format_examples(violations: &[&Violation]) -> String {
+    let mut body = String::new();
+    for v in violations {
+        body.push_str(&format!(
+            "### {}:{}-{}\n\n{}\n\n",
+            v.file, v.start_line, v.end_line, v.detail
+        ));
+        if let Some(snippet) =
+            crate::util::read_snippet(&v.file, v.start_line, v.end_line, SNIPPET_CONTEXT_LINES)
+        {
+            let fence = get_fence_backticks(&snippet);
+            body.push_str(&format!("{}\n{}\n{}\n\n", fence, snippet, fence));
+        }
+    }
+    body
+}
+
+/// Build the refinement request for one rule
+fn build_refine_prompt(instruction: &str, feedback: &Feedback) -> String {
+    let fence = get_fence_backticks(instruction);
+    let mut body = format!(
+        "## Current Instruction\n\n{}md\n{}\n{}\n\n",
+        fence,
+        instruction.trim(),
+        fence
+    );
+    body.push_str("## False Positives\n\n");
+    body.push_str(&format_examples(&feedback.rejected));
+    if !feedback.accepted.is_empty() {
+        body.push_str("## True Positives\n\n");
+        body.push_str(&format_examples(&feedback.accepted));
+    }
+    body.trim_end().to_string()
+}
+
+/// Strip a code fence the model may wrap around the instruction despite being asked not to
+fn strip_fence(text: &str) -> &str {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix("```")
+        && let Some((_, inner)) = rest.split_once('\n')
+        && let Some(inner) = inner.trim_end().strip_suffix("```")
+    {
+        return inner.trim();
+    }
+    text
+}
+
+#[derive(Serialize)]
+struct RulesPatch<'a> {
+    rules: &'a [RuleBody],
+}
+
+/// `[[rules]]` tables answered by the model
+#[derive(Deserialize)]
+struct CandidateRules {
+    #[serde(default)]
+    rules: Vec<RuleBody>,
+}
+
+/// Format refined rules as `[[rules]]` tables, to replace the rules of the same name
+pub fn format_patch(rules: &[RuleBody]) -> String {
+    if rules.is_empty() {
+        return String::new();
+    }
+    format!(
+        "# Refined rules suggested by `firekeeper suggest`.\n\
+         # Replace the [[rules]] entries with the same name in firekeeper.toml.\n\n{}",
+        toml::to_string(&RulesPatch { rules }).unwrap()
+    )
+}
+
+/// Format candidate rules as `[[rules]]` tables, to add to the config. `flag` is the
+/// `suggest` option they were derived with, e.g. `--from-file`.
+pub fn format_candidates(rules: &[RuleBody], flag: &str) -> String {
+    if rules.is_empty() {
+        return String::new();
+    }
+    format!(
+        "# Candidate rules suggested by `firekeeper suggest {}`.\n\
+         # Review them, then add the [[rules]] entries to firekeeper.toml.\n\n{}",
+        flag,
+        toml::to_string(&RulesPatch { rules }).unwrap()
+    )
+}
+
+/// Read an incident writeup or checklist from a file path or http(s):// URL
+pub async fn load_source(source: &str) -> Result<String, String> {
+    if source.starts_with("https://") || source.starts_with("http://") {
+        crate::tool::fetch::fetch_markdown(source, None).await
+    } else {
+        std::fs::read_to_string(source).map_err(|e| format!("Failed to read {}: {}", source, e))
+    }
+}
+
+/// Code fence language of a source, from its extension, defaulting to Markdown
+fn source_language(source: &str) -> &'static str {
+    let name = source.rsplit('/').next().unwrap_or(source);
+    match name.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("toml") => "toml",
+        Some("json") => "json",
+        Some("yml" | "yaml") => "yaml",
+        Some("js" | "cjs" | "mjs") => "js",
+        // Extensionless dotfiles like `.eslintrc` are JSON or YAML; JSON is the common case
+        Some(_) if name.starts_with('.') && name.matches('.').count() == 1 => "json",
+        _ => "md",
+    }
+}
+
+/// Build the rule derivation request from incident writeups, checklists or lint configs
+fn build_sources_prompt(sources: &[(String, String)], existing: &[RuleBody]) -> String {
+    let mut body = String::new();
+    for (source, content) in sources {
+        let content = truncate_text_by_chars(content.clone(), 0, MAX_SOURCE_CHARS).content;
+        let fence = get_fence_backticks(&content);
+        body.push_str(&format!(
+            "## {}\n\n{}{}\n{}\n{}\n\n",
+            source,
+            fence,
+            source_language(source),
+            content.trim(),
+            fence
+        ));
+    }
+    if !existing.is_empty() {
+        body.push_str("## Existing Rules\n\n");
+        for rule in existing {
+            body.push_str(&format!("- {}\n", rule.name));
+        }
+    }
+    body.trim_end().to_string()
+}
+
+/// Parse the model's `[[rules]]` tables, dropping rules already in the config.
+/// Suggested rules are non-blocking unless the model sets `blocking`, so they can be
+/// tried out before they fail builds.
+fn parse_candidates(answer: &str, existing: &[RuleBody]) -> Result<Vec<RuleBody>, String> {
+    let mut answer: toml::Table = toml::from_str(strip_fence(answer))
+        .map_err(|e| format!("Invalid rules from the model: {}", e))?;
+    if let Some(toml::Value::Array(rules)) = answer.get_mut("rules") {
+        for rule in rules.iter_mut().filter_map(toml::Value::as_table_mut) {
+            rule.entry("blocking")
+                .or_insert(toml::Value::Boolean(false));
+        }
+    }
+    let candidates: CandidateRules = toml::Value::Table(answer)
+        .try_into()
+        .map_err(|e| format!("Invalid rules from the model: {}", e))?;
+    Ok(candidates
+        .rules
+        .into_iter()
+        .filter(|rule| {
+            let exists = existing.iter().any(|r| r.id() == rule.id());
+            if exists {
+                warn!("Rule '{}' is already in the config, skipping", rule.name);
+            }
+            !exists && !rule.instruction.trim().is_empty()
+        })
+        .collect())
+}
+
+/// Ask the model for new rules derived from the sources, dropping those already in the config
+async fn derive_rules(
+    config: &Config,
+    api_key: &str,
+    system: &str,
+    sources: &[(String, String)],
+) -> Result<Vec<RuleBody>, String> {
+    let llm = crate::llm::create_provider(
+        api_key,
+        &config.llm.endpoint(),
+        &config.llm.model,
+        &config.llm.headers,
+        &config.llm.body,
+        config.llm.tool_protocol,
+        config.llm.provider,
+    )
+    .map(|llm| llm.with_retry(config.llm.retry.clone()))
+    .map_err(|e| format!("Failed to create LLM provider: {}", e))?;
+    let mut agent = Agent::new(llm).system(format!("{}\n{}", system, RULES_FORMAT));
+    let answer = agent
+        .chat(build_sources_prompt(sources, &config.rules))
+        .await
+        .map_err(|e| format!("LLM request failed: {}", e))?;
+
+    let candidates = parse_candidates(&answer, &config.rules)?;
+    if candidates.is_empty() {
+        info!("No new rules derived from the given files");
+    }
+    Ok(candidates)
+}
+
+/// Derive candidate rules from incident postmortems and review checklists, returned as TOML
+pub async fn suggest_from_sources(
+    config: &Config,
+    api_key: &str,
+    sources: &[(String, String)],
+) -> Result<String, String> {
+    info!(
+        "Deriving rules from {} incident writeup(s) or checklist(s)",
+        sources.len()
+    );
+    let candidates = derive_rules(config, api_key, INCIDENT_SYSTEM_PROMPT, sources).await?;
+    Ok(format_candidates(&candidates, "--from-file"))
+}
+
+/// Derive candidate rules for the intent-level policies behind linter configurations,
+/// which the linters themselves cannot check, returned as TOML
+pub async fn suggest_from_lint(
+    config: &Config,
+    api_key: &str,
+    sources: &[(String, String)],
+) -> Result<String, String> {
+    info!("Deriving rules from {} lint config(s)", sources.len());
+    let candidates = derive_rules(config, api_key, LINT_SYSTEM_PROMPT, sources).await?;
+    Ok(format_candidates(&candidates, "--from-lint"))
+}
+
+/// Propose instruction refinements for rules with false positives, returned as a TOML patch
+pub async fn suggest(
+    config: &Config,
+    api_key: &str,
+    violation_file: &ViolationFile,
+    decisions: &TriageFile,
+) -> Result<String, String> {
+    let feedback = collect_feedback(violation_file, decisions);
+    let mut refined = Vec::new();
+
+    for (rule_name, feedback) in &feedback {
+        if feedback.rejected.is_empty() {
+            continue;
+        }
+        let Some(rule) = config.rules.iter().find(|r| r.name == *rule_name) else {
+            warn!("Rule '{}' is not in the config, skipping", rule_name);
+            continue;
+        };
+        info!(
+            "Refining '{}' ({} false positives, {} true positives)",
+            rule_name,
+            feedback.rejected.len(),
+            feedback.accepted.len()
+        );
+
+        let llm = crate::llm::create_provider(
+            api_key,
+            &config.llm.endpoint(),
+            &config.llm.model,
+            &config.llm.headers,
+            &config.llm.body,
+            config.llm.tool_protocol,
+            config.llm.provider,
+        )
+        .map(|llm| llm.with_retry(config.llm.retry.clone()))
+        .map_err(|e| format!("Failed to create LLM provider: {}", e))?;
+        let mut agent = Agent::new(llm).system(SYSTEM_PROMPT);
+        let answer = agent
+            .chat(build_refine_prompt(&rule.instruction, feedback))
+            .await
+            .map_err(|e| format!("LLM request failed: {}", e))?;
+
+        let instruction = strip_fence(&answer);
+        if instruction.is_empty() {
+            warn!("Empty suggestion for '{}', skipping", rule_name);
+            continue;
+        }
+        refined.push(RuleBody {
+            instruction: format!("{}\n", instruction),
+            ..rule.clone()
+        });
+    }
+
+    if refined.is_empty() {
+        info!("No dismissed or suppressed violations to learn from");
+    }
+    Ok(format_patch(&refined))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::triage::TriageEntry;
+
+    fn violation(line: u32) -> Violation {
+        Violation {
+            file: "a.rs".into(),
+            detail: format!("issue {}", line),
+            start_line: line,
+            end_line: line,
+            rule_id: None,
+            owners: vec![],
+        }
+    }
+
+    #[test]
+    fn test_collect_feedback() {
+        let violation_file = ViolationFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            violations: BTreeMap::from([(
+                "a.rs".to_string(),
+                BTreeMap::from([(
+                    "Rule".to_string(),
+                    vec![violation(1), violation(2), violation(3)],
+                )]),
+            )]),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: Vec::new(),
+            incomplete: Vec::new(),
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        let decision = |line: u32, decision| TriageEntry {
+            fingerprint: violation(line).fingerprint("Rule"),
+            rule: "Rule".into(),
+            violation: violation(line),
+            decision,
+        };
+        let decisions = TriageFile {
+            version: env!("CARGO_PKG_VERSION").into(),
+            decisions: vec![
+                decision(1, Decision::Accepted),
+                decision(2, Decision::Suppressed),
+            ],
+        };
+
+        let feedback = collect_feedback(&violation_file, &decisions);
+        let rule = &feedback["Rule"];
+        assert_eq!(rule.accepted.len(), 1);
+        assert_eq!(rule.rejected.len(), 1);
+        assert_eq!(rule.rejected[0].start_line, 2);
+    }
+
+    #[test]
+    fn test_build_refine_prompt() {
+        let rejected = violation(2);
+        let feedback = Feedback {
+            accepted: vec![],
+            rejected: vec![&rejected],
+        };
+        let prompt = build_refine_prompt("Check things", &feedback);
+        assert!(prompt.contains("```md\nCheck things\n```"));
+        assert!(prompt.contains("## False Positives\n\n### a.rs:2-2\n\nissue 2"));
+        assert!(!prompt.contains("## True Positives"));
+    }
+
+    #[test]
+    fn test_strip_fence() {
+        assert_eq!(strip_fence("```md\nRevised\n```"), "Revised");
+        assert_eq!(strip_fence("  Revised\n"), "Revised");
+    }
+
+    #[test]
+    fn test_build_incident_prompt() {
+        let sources = [(
+            "postmortem.md".to_string(),
+            "Root cause: retries without backoff\n".to_string(),
+        )];
+        let prompt = build_sources_prompt(&sources, &[RuleBody::no_magic_numbers()]);
+        assert!(
+            prompt
+                .starts_with("## postmortem.md\n\n```md\nRoot cause: retries without backoff\n```")
+        );
+        assert!(prompt.ends_with("## Existing Rules\n\n- No Magic Numbers"));
+
+        let lint = [(
+            "web/.eslintrc".to_string(),
+            r#"{"rules": {"no-restricted-imports": ["error", "lodash"]}}"#.to_string(),
+        )];
+        assert!(build_sources_prompt(&lint, &[]).starts_with("## web/.eslintrc\n\n```json\n"));
+        assert_eq!(source_language("clippy.toml"), "toml");
+        assert_eq!(source_language(".eslintrc.yml"), "yaml");
+        assert_eq!(source_language("https://wiki/incidents/42"), "md");
+    }
+
+    #[test]
+    fn test_parse_candidates() {
+        let answer = r#"```toml
+[[rules]]
+name = "Retry Backoff"
+instruction = "Check that retries use exponential backoff."
+scope = ["**/*.rs"]
+
+[[rules]]
+name = "No Secrets"
+instruction = "Check that no credentials are committed."
+blocking = true
+
+[[rules]]
+name = "No Magic Numbers"
+instruction = "Duplicate"
+```"#;
+        let rules = parse_candidates(answer, &[RuleBody::no_magic_numbers()]).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].name, "Retry Backoff");
+        assert!(rules[1].is_blocking());
+        assert!(!rules[0].is_blocking());
+        assert!(format_candidates(&rules, "--from-file").contains("name = \"Retry Backoff\""));
+        assert!(parse_candidates("not toml [", &[]).is_err());
+    }
+
+    #[test]
+    fn test_format_patch() {
+        let rule = RuleBody::inline("Line one\nLine two\n", &[]);
+        let patch = format_patch(&[rule]);
+        assert!(patch.contains("[[rules]]"));
+        assert!(patch.contains("name = \"Inline Rule\""));
+        let parsed: toml::Value = toml::from_str(&patch).unwrap();
+        assert_eq!(
+            parsed["rules"][0]["instruction"].as_str(),
+            Some("Line one\nLine two\n")
+        );
+        assert_eq!(format_patch(&[]), "");
+    }
+}
@@ -0,0 +1,263 @@
+use super::render::{self, ViolationFile};
+use super::resource::{LoadedResources, ResourceLoader, TaskVariables};
+use crate::rule::body::RuleBody;
+use crate::types::Violation;
+use crate::util;
+use futures::future::join_all;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// Cache namespace of task results of reviews
+pub const RESULTS_NAMESPACE: &str = "results";
+
+/// Tasks to run, cached results, and the rules with cached results
+type TakenTasks<'a> = (
+    Vec<(&'a RuleBody, Vec<String>)>,
+    ViolationFile,
+    BTreeSet<String>,
+);
+
+/// On-disk cache of task results, so re-running a review on unchanged changes does not
+/// call the LLM again for the same rule and files
+pub struct TaskCache {
+    pub dir: PathBuf,
+    pub namespace: &'static str,
+}
+
+/// Prompt inputs of tasks besides their rule, model, files, and diffs: the review
+/// settings, the `[llm] body`, and each task's resources as loaded
+#[derive(Default)]
+pub struct TaskContexts {
+    settings: String,
+    resources: HashMap<(String, Vec<String>), String>,
+    /// Resources of each task that loaded, handed to its workers instead of loading again
+    loaded: HashMap<(String, Vec<String>), LoadedResources>,
+}
+
+impl TaskContexts {
+    /// Hash the settings of a resolved config and load the resources of each task
+    pub async fn load(
+        resolved_config: &Value,
+        loader: &ResourceLoader,
+        tasks: &[(&RuleBody, Vec<String>)],
+    ) -> Self {
+        // `[review]` holds the language, prompts, tools, and global resources
+        let settings = util::hash_parts(&[
+            &resolved_config["review"].to_string(),
+            &resolved_config["llm"]["body"].to_string(),
+        ]);
+        let results = join_all(tasks.iter().map(|(rule, files)| async move {
+            let rule_id = rule.id();
+            let task = TaskVariables {
+                rule_name: &rule.name,
+                rule_id: &rule_id,
+                files,
+            };
+            (
+                (rule.name.clone(), files.clone()),
+                loader.load(&rule.resources, &task).await,
+            )
+        }))
+        .await;
+        let mut resources = HashMap::new();
+        let mut loaded = HashMap::new();
+        for (key, result) in results {
+            // A failed load fails the task, which is not cached
+            match result {
+                Ok(content) => {
+                    resources.insert(key.clone(), util::hash_parts(&[&content.content]));
+                    loaded.insert(key, content);
+                }
+                Err(e) => {
+                    resources.insert(key, e);
+                }
+            }
+        }
+        Self {
+            settings,
+            resources,
+            loaded,
+        }
+    }
+
+    /// Resources loaded for a task, `None` if they failed to load
+    pub fn loaded(&self, rule: &RuleBody, files: &[String]) -> Option<LoadedResources> {
+        self.loaded
+            .get(&(rule.name.clone(), files.to_vec()))
+            .cloned()
+    }
+
+    fn get(&self, rule: &RuleBody, files: &[String]) -> String {
+        let resources = self
+            .resources
+            .get(&(rule.name.clone(), files.to_vec()))
+            .map_or("", String::as_str);
+        util::hash_parts(&[&self.settings, resources])
+    }
+}
+
+/// Cache key of a task: its rule, model, files, their diffs, and its other prompt inputs
+pub(crate) fn task_key(
+    model: &str,
+    rule: &RuleBody,
+    files: &[String],
+    diffs: &HashMap<String, String>,
+    contexts: &TaskContexts,
+) -> String {
+    // The whole rule, so changing e.g. its tools or scope invalidates the results
+    let rule_json = serde_json::to_string(rule).unwrap();
+    let context = contexts.get(rule, files);
+    let mut parts = vec![model, &rule_json, &context];
+    for file in files {
+        parts.push(file);
+        parts.push(diffs.get(file).map_or("", String::as_str));
+    }
+    util::hash_parts(&parts)
+}
+
+impl TaskCache {
+    pub fn new(dir: PathBuf, namespace: &'static str) -> Self {
+        Self { dir, namespace }
+    }
+
+    /// Split off the tasks with cached results, returning the tasks to run, the cached
+    /// results, and the rules with cached results
+    pub fn take_cached<'a>(
+        &self,
+        model: &str,
+        tasks: Vec<(&'a RuleBody, Vec<String>)>,
+        diffs: &HashMap<String, String>,
+        contexts: &TaskContexts,
+    ) -> TakenTasks<'a> {
+        let mut cached = ViolationFile {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            violations: render::ViolationsByFile::new(),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: vec![],
+            incomplete: vec![],
+            truncated: BTreeMap::new(),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        let mut hits = 0;
+        let mut rules = BTreeSet::new();
+        let tasks: Vec<_> = tasks
+            .into_iter()
+            .filter(|(rule, files)| {
+                let key = task_key(model, rule, files, diffs, contexts);
+                let Some(violations) =
+                    crate::cache::get::<Vec<Violation>>(&self.dir, self.namespace, &key)
+                else {
+                    return true;
+                };
+                hits += 1;
+                rules.insert(rule.name.clone());
+                for violation in violations {
+                    cached
+                        .violations
+                        .entry(violation.file.clone())
+                        .or_default()
+                        .entry(rule.name.clone())
+                        .or_default()
+                        .push(violation);
+                }
+                if let Some(tip) = &rule.tip {
+                    cached.tips.insert(rule.name.clone(), tip.clone());
+                }
+                false
+            })
+            .collect();
+        if hits > 0 {
+            info!(
+                "Reusing cached results of {} task(s) (--no-cache to review them again)",
+                hits
+            );
+        }
+        (tasks, cached, rules)
+    }
+
+    /// Cache the violations of a completed task
+    pub fn store(
+        &self,
+        model: &str,
+        rule: &RuleBody,
+        files: &[String],
+        diffs: &HashMap<String, String>,
+        contexts: &TaskContexts,
+        violations: &[Violation],
+    ) {
+        let key = task_key(model, rule, files, diffs, contexts);
+        let violations: Vec<Violation> = violations
+            .iter()
+            .map(|v| Violation {
+                rule_id: Some(rule.id()),
+                ..v.clone()
+            })
+            .collect();
+        if let Err(e) = crate::cache::put(&self.dir, self.namespace, &key, &violations) {
+            warn!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::temp::TempDir;
+
+    #[test]
+    fn test_cached_tasks_are_skipped() {
+        let dir = TempDir::new(&format!("firekeeper-task-cache-{}", std::process::id()));
+        let cache = TaskCache::new(dir.path().to_path_buf(), RESULTS_NAMESPACE);
+        let rule = RuleBody::no_magic_numbers();
+        let diffs = HashMap::from([("a.rs".to_string(), "+let x = 42;".to_string())]);
+        let files = vec!["a.rs".to_string()];
+        let violation = Violation {
+            file: "a.rs".into(),
+            detail: "Magic number 42".into(),
+            start_line: 1,
+            end_line: 1,
+            rule_id: None,
+            owners: vec![],
+        };
+        let contexts = TaskContexts::default();
+        cache.store("model", &rule, &files, &diffs, &contexts, &[violation]);
+
+        let task = || vec![(&rule, files.clone())];
+        let (tasks, cached, rules) = cache.take_cached("model", task(), &diffs, &contexts);
+        let (uncached, _, _) = cache.take_cached("other-model", task(), &diffs, &contexts);
+        let changed = RuleBody {
+            max_files_per_task: Some(1),
+            ..rule.clone()
+        };
+        let (changed_rule, _, _) =
+            cache.take_cached("model", vec![(&changed, files.clone())], &diffs, &contexts);
+        let edited = HashMap::from([("a.rs".to_string(), "+let x = 43;".to_string())]);
+        let (changed_diff, _, _) = cache.take_cached("model", task(), &edited, &contexts);
+        let language = TaskContexts {
+            settings: util::hash_parts(&[r#"{"language":"de"}"#]),
+            ..Default::default()
+        };
+        let (changed_settings, _, _) = cache.take_cached("model", task(), &diffs, &language);
+        let resources = TaskContexts {
+            resources: HashMap::from([(
+                (rule.name.clone(), files.clone()),
+                util::hash_parts(&["style guide"]),
+            )]),
+            ..Default::default()
+        };
+        let (changed_resources, _, _) = cache.take_cached("model", task(), &diffs, &resources);
+        assert!(tasks.is_empty());
+        assert_eq!(rules, BTreeSet::from([rule.name.clone()]));
+        assert_eq!(uncached.len(), 1);
+        assert_eq!(changed_rule.len(), 1);
+        assert_eq!(changed_diff.len(), 1);
+        assert_eq!(changed_settings.len(), 1);
+        assert_eq!(changed_resources.len(), 1);
+        let cached = &cached.violations["a.rs"][&rule.name];
+        assert_eq!(cached[0].rule_id, Some(rule.id()));
+    }
+}
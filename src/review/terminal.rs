@@ -0,0 +1,393 @@
+use super::locale::Strings;
+use super::render::ViolationsByFile;
+use crate::rule::tip::Tip;
+use crate::types::Violation;
+use std::collections::{BTreeMap, HashSet};
+use std::io::IsTerminal;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+
+/// Terminal capabilities used when rendering the console report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalStyle {
+    /// Emit ANSI color codes
+    pub color: bool,
+    /// Emit OSC 8 hyperlinks for file paths
+    pub hyperlinks: bool,
+}
+
+impl TerminalStyle {
+    /// Plain text without escape sequences
+    pub const PLAIN: Self = Self {
+        color: false,
+        hyperlinks: false,
+    };
+
+    /// Detect capabilities of stdout, respecting NO_COLOR (https://no-color.org)
+    pub fn detect() -> Self {
+        if !std::io::stdout().is_terminal() || std::env::var_os("NO_COLOR").is_some() {
+            return Self::PLAIN;
+        }
+        Self {
+            color: true,
+            hyperlinks: true,
+        }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn link(&self, path: &str) -> String {
+        if !self.hyperlinks {
+            return path.to_string();
+        }
+        match std::env::current_dir() {
+            Ok(cwd) => format!(
+                "\x1b]8;;file://{}\x1b\\{}\x1b]8;;\x1b\\",
+                cwd.join(path).display(),
+                path
+            ),
+            Err(_) => path.to_string(),
+        }
+    }
+}
+
+fn severity_label(style: &TerminalStyle, strings: &Strings, blocking: bool) -> String {
+    if blocking {
+        style.paint(RED, strings.error)
+    } else {
+        style.paint(YELLOW, strings.warning)
+    }
+}
+
+/// Format violations for the terminal, grouped by rule then file, with a closing summary table
+pub fn format_terminal(
+    violations_by_file: &ViolationsByFile,
+    tips_by_rule: &BTreeMap<String, Tip>,
+    blocking_rules: &HashSet<String>,
+    style: &TerminalStyle,
+    strings: &Strings,
+) -> String {
+    if violations_by_file.is_empty() {
+        return style.paint(GREEN, strings.no_violations);
+    }
+
+    // Regroup file -> rule into rule -> file
+    let mut by_rule: BTreeMap<&str, BTreeMap<&str, &Vec<Violation>>> = BTreeMap::new();
+    for (file, rules) in violations_by_file {
+        for (rule, violations) in rules {
+            by_rule
+                .entry(rule.as_str())
+                .or_default()
+                .insert(file.as_str(), violations);
+        }
+    }
+
+    let mut output = String::new();
+    for (rule, files) in &by_rule {
+        let blocking = blocking_rules.contains(*rule);
+        let id = files
+            .values()
+            .flat_map(|violations| violations.iter())
+            .find_map(|v| v.rule_id.as_deref())
+            .map(|id| format!(" {}", style.paint(DIM, &format!("[{}]", id))))
+            .unwrap_or_default();
+        output.push_str(&format!(
+            "{} {}{}\n",
+            severity_label(style, strings, blocking),
+            style.paint(BOLD, rule),
+            id
+        ));
+        for (file, violations) in files {
+            output.push_str(&format!("  {}\n", style.paint(CYAN, &style.link(file))));
+            for v in violations.iter() {
+                output.push_str(&format!(
+                    "    {} {}\n",
+                    style.paint(DIM, &format!("{}-{}", v.start_line, v.end_line)),
+                    v.detail
+                ));
+            }
+        }
+        output.push('\n');
+    }
+
+    output.push_str(&format_summary_table(
+        &by_rule,
+        blocking_rules,
+        style,
+        strings,
+    ));
+    if let Some(how_to_fix) =
+        format_how_to_fix(&by_rule, tips_by_rule, blocking_rules, style, strings)
+    {
+        output.push_str("\n\n");
+        output.push_str(&how_to_fix);
+    }
+    output
+}
+
+/// Tip shared by one or more violated rules
+struct Fix<'a> {
+    tip: &'a Tip,
+    rules: Vec<&'a str>,
+    violations: usize,
+    blocking: bool,
+}
+
+/// Format the tips of violated rules as a closing section, deduplicated across rules and
+/// ordered by severity then violation count, so the fixes that unblock the build come first
+fn format_how_to_fix(
+    by_rule: &BTreeMap<&str, BTreeMap<&str, &Vec<Violation>>>,
+    tips_by_rule: &BTreeMap<String, Tip>,
+    blocking_rules: &HashSet<String>,
+    style: &TerminalStyle,
+    strings: &Strings,
+) -> Option<String> {
+    let mut fixes: Vec<Fix> = Vec::new();
+    for (rule, files) in by_rule {
+        let Some(tip) = tips_by_rule.get(*rule).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        let violations = files.values().map(|v| v.len()).sum();
+        let blocking = blocking_rules.contains(*rule);
+        match fixes
+            .iter_mut()
+            .find(|fix| fix.tip.to_plain() == tip.to_plain())
+        {
+            Some(fix) => {
+                fix.rules.push(rule);
+                fix.violations += violations;
+                fix.blocking |= blocking;
+            }
+            None => fixes.push(Fix {
+                tip,
+                rules: vec![rule],
+                violations,
+                blocking,
+            }),
+        }
+    }
+    if fixes.is_empty() {
+        return None;
+    }
+    fixes.sort_by(|a, b| {
+        b.blocking
+            .cmp(&a.blocking)
+            .then(b.violations.cmp(&a.violations))
+            .then(a.rules.cmp(&b.rules))
+    });
+
+    let mut output = style.paint(BOLD, &format!("{}:", strings.how_to_fix));
+    output.push('\n');
+    for fix in &fixes {
+        output.push_str(&format!(
+            "  {} {} ({})\n",
+            severity_label(style, strings, fix.blocking),
+            style.paint(BOLD, &fix.rules.join(", ")),
+            fix.violations
+        ));
+        for line in fix.tip.text().lines() {
+            output.push_str(&format!("    {}\n", line));
+        }
+        if let Some(command) = fix.tip.command() {
+            output.push_str(&format!("    $ {}\n", style.paint(BOLD, command)));
+        }
+        if let Some(url) = fix.tip.docs_url() {
+            output.push_str(&format!("    {}\n", url));
+        }
+    }
+    Some(output.trim_end().to_string())
+}
+
+/// Format summary table with one row per rule: severity, affected files, violation count
+fn format_summary_table(
+    by_rule: &BTreeMap<&str, BTreeMap<&str, &Vec<Violation>>>,
+    blocking_rules: &HashSet<String>,
+    style: &TerminalStyle,
+    strings: &Strings,
+) -> String {
+    let headers = [
+        strings.rule,
+        strings.severity,
+        strings.files,
+        strings.violations,
+    ];
+
+    let rows: Vec<[String; 4]> = by_rule
+        .iter()
+        .map(|(rule, files)| {
+            let blocking = blocking_rules.contains(*rule);
+            [
+                rule.to_string(),
+                if blocking {
+                    strings.error
+                } else {
+                    strings.warning
+                }
+                .to_string(),
+                files.len().to_string(),
+                files.values().map(|v| v.len()).sum::<usize>().to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.map(|h| h.chars().count());
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    let pad = |cell: &str, width: usize| format!("{:<width$}", cell, width = width);
+    let mut output = String::new();
+    let header: Vec<String> = headers.iter().zip(widths).map(|(h, w)| pad(h, w)).collect();
+    output.push_str(&style.paint(BOLD, header.join("  ").trim_end()));
+    output.push('\n');
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(i, (cell, w))| {
+                let padded = pad(cell, w);
+                // Colorize the severity column after padding so alignment is preserved
+                if i == 1 {
+                    severity_label(style, strings, cell == strings.error) + &padded[cell.len()..]
+                } else {
+                    padded
+                }
+            })
+            .collect();
+        output.push_str(cells.join("  ").trim_end());
+        output.push('\n');
+    }
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::review::locale::EN;
+
+    fn violation(file: &str, start: u32, end: u32, detail: &str) -> Violation {
+        Violation {
+            file: file.to_string(),
+            detail: detail.to_string(),
+            start_line: start,
+            end_line: end,
+            rule_id: None,
+            owners: vec![],
+        }
+    }
+
+    #[test]
+    fn test_format_terminal_empty() {
+        let result = format_terminal(
+            &ViolationsByFile::new(),
+            &BTreeMap::new(),
+            &HashSet::new(),
+            &TerminalStyle::PLAIN,
+            &EN,
+        );
+        assert_eq!(result, "No violations found");
+    }
+
+    #[test]
+    fn test_format_terminal_groups_by_rule_then_file() {
+        let mut violations = ViolationsByFile::new();
+        violations.insert(
+            "b.rs".to_string(),
+            BTreeMap::from([(
+                "Rule A".to_string(),
+                vec![violation("b.rs", 3, 4, "second")],
+            )]),
+        );
+        violations.insert(
+            "a.rs".to_string(),
+            BTreeMap::from([
+                ("Rule A".to_string(), vec![violation("a.rs", 1, 2, "first")]),
+                ("Rule B".to_string(), vec![violation("a.rs", 5, 5, "other")]),
+            ]),
+        );
+        let tips = BTreeMap::from([("Rule A".to_string(), "fix it".into())]);
+        let blocking = HashSet::from(["Rule A".to_string()]);
+
+        let result = format_terminal(&violations, &tips, &blocking, &TerminalStyle::PLAIN, &EN);
+        let rule_a = result.find("error Rule A").unwrap();
+        let rule_b = result.find("warning Rule B").unwrap();
+        assert!(rule_a < rule_b);
+        assert!(result.find("  a.rs").unwrap() < result.find("  b.rs").unwrap());
+        assert!(result.contains("    1-2 first"));
+        assert!(result.ends_with("How to fix:\n  error Rule A (2)\n    fix it"));
+        assert!(!result.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_format_summary_table() {
+        let violations = vec![violation("a.rs", 1, 1, "x"), violation("a.rs", 2, 2, "y")];
+        let by_rule = BTreeMap::from([("Rule", BTreeMap::from([("a.rs", &violations)]))]);
+        let blocking = HashSet::from(["Rule".to_string()]);
+        let result = format_summary_table(&by_rule, &blocking, &TerminalStyle::PLAIN, &EN);
+        assert_eq!(
+            result,
+            "Rule  Severity  Files  Violations\nRule  error     1      2"
+        );
+    }
+
+    #[test]
+    fn test_format_how_to_fix() {
+        let one = vec![violation("a.rs", 1, 1, "x")];
+        let two = vec![violation("a.rs", 1, 1, "x"), violation("a.rs", 2, 2, "y")];
+        let by_rule = BTreeMap::from([
+            ("Advice", BTreeMap::from([("a.rs", &two)])),
+            ("Secrets", BTreeMap::from([("a.rs", &one)])),
+            ("Tokens", BTreeMap::from([("a.rs", &one)])),
+            ("Untipped", BTreeMap::from([("a.rs", &one)])),
+        ]);
+        let tips = BTreeMap::from([
+            (
+                "Advice".to_string(),
+                Tip::Detailed {
+                    text: "Consider it.".into(),
+                    command: Some("make fix".into()),
+                    docs_url: Some("https://example.com".into()),
+                },
+            ),
+            (
+                "Secrets".to_string(),
+                "Use env vars.\nRotate keys.\n".into(),
+            ),
+            ("Tokens".to_string(), "Use env vars.\nRotate keys.".into()),
+        ]);
+        let blocking = HashSet::from(["Tokens".to_string()]);
+        let result =
+            format_how_to_fix(&by_rule, &tips, &blocking, &TerminalStyle::PLAIN, &EN).unwrap();
+        assert_eq!(
+            result,
+            "How to fix:\n\
+             \x20 error Secrets, Tokens (2)\n\
+             \x20   Use env vars.\n\
+             \x20   Rotate keys.\n\
+             \x20 warning Advice (2)\n\
+             \x20   Consider it.\n\
+             \x20   $ make fix\n\
+             \x20   https://example.com"
+        );
+    }
+
+    #[test]
+    fn test_link_plain() {
+        assert_eq!(TerminalStyle::PLAIN.link("src/main.rs"), "src/main.rs");
+    }
+}
@@ -0,0 +1,314 @@
+use super::fixture;
+use super::render::TraceEntry;
+use crate::tool::report::ReportArgs;
+use std::collections::BTreeMap;
+use tiny_loop::types::Message;
+
+/// Characters of tool call arguments kept in a conversation step
+const MAX_STEP_CHARS: usize = 80;
+
+/// Workers are aligned across runs by rule and reviewed files
+type TaskKey = (String, Vec<String>);
+
+fn task_key(entry: &TraceEntry) -> TaskKey {
+    let mut files = entry.files.clone();
+    files.sort();
+    (entry.rule.id(), files)
+}
+
+/// Line-level change in an aligned sequence
+#[derive(Debug, PartialEq)]
+enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Align two sequences by their longest common subsequence
+fn diff_lines<'a>(old: &'a [String], new: &'a [String]) -> Vec<Change<'a>> {
+    // lcs[i][j]: common length of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push(Change::Same(&old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            changes.push(Change::Added(&new[j]));
+            j += 1;
+        } else {
+            changes.push(Change::Removed(&old[i]));
+            i += 1;
+        }
+    }
+    changes
+}
+
+/// Render changes as a `diff` code block, or `None` if nothing changed
+fn format_changes(changes: &[Change]) -> Option<String> {
+    if changes.iter().all(|c| matches!(c, Change::Same(_))) {
+        return None;
+    }
+    let lines: Vec<String> = changes
+        .iter()
+        .map(|change| match change {
+            Change::Same(line) => format!("  {}", line),
+            Change::Removed(line) => format!("- {}", line),
+            Change::Added(line) => format!("+ {}", line),
+        })
+        .collect();
+    let body = lines.join("\n");
+    let fence = super::render::get_fence_backticks(&body);
+    Some(format!("{}diff\n{}\n{}\n\n", fence, body, fence))
+}
+
+/// One line per tool call, e.g. `sh: git diff HEAD` or `report: 2 violation(s)`
+fn conversation_steps(entry: &TraceEntry) -> Vec<String> {
+    entry
+        .messages
+        .iter()
+        .filter_map(|m| match &m.message {
+            Message::Assistant(m) => m.tool_calls.as_ref(),
+            _ => None,
+        })
+        .flatten()
+        .map(|call| {
+            let name = &call.function.name;
+            if name == ReportArgs::TOOL_NAME {
+                let count = serde_json::from_str::<ReportArgs>(&call.function.arguments)
+                    .map_or(0, |args| args.violations.len());
+                return format!("{}: {} violation(s)", name, count);
+            }
+            let args: String = call
+                .function
+                .arguments
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let mut step = format!("{}: {}", name, args);
+            if step.chars().count() > MAX_STEP_CHARS {
+                step = step.chars().take(MAX_STEP_CHARS).collect::<String>() + "...";
+            }
+            step
+        })
+        .collect()
+}
+
+/// Violations from the worker's `report` calls, one line each
+fn reported_violations(entry: &TraceEntry) -> Vec<String> {
+    fixture::reported_violations(&entry.messages)
+        .iter()
+        .map(|v| format!("{}:{}-{}: {}", v.file, v.start_line, v.end_line, v.detail))
+        .collect()
+}
+
+fn format_task_heading(key: &TaskKey, entry: &TraceEntry) -> String {
+    format!(
+        "## {} (`{}`): {}\n\n",
+        entry.rule.name,
+        key.0,
+        key.1.join(", ")
+    )
+}
+
+/// Changes between the workers of two runs for the same task, or `None` if the same
+fn format_task_diff(key: &TaskKey, old: &TraceEntry, new: &TraceEntry) -> Option<String> {
+    let lines = |s: &str| s.lines().map(str::to_string).collect::<Vec<_>>();
+    let instruction = format_changes(&diff_lines(
+        &lines(&old.rule.instruction),
+        &lines(&new.rule.instruction),
+    ));
+    let (old_violations, new_violations) = (reported_violations(old), reported_violations(new));
+    let violations = format_changes(&diff_lines(&old_violations, &new_violations));
+    let conversation = format_changes(&diff_lines(
+        &conversation_steps(old),
+        &conversation_steps(new),
+    ));
+    if instruction.is_none() && violations.is_none() && conversation.is_none() {
+        return None;
+    }
+
+    let mut output = format_task_heading(key, new);
+    output.push_str(&format!(
+        "- Violations: {} -> {}\n- Messages: {} -> {}\n- Elapsed: {:.1}s -> {:.1}s\n- Estimated tokens: {} -> {}\n\n",
+        old_violations.len(),
+        new_violations.len(),
+        old.messages.len(),
+        new.messages.len(),
+        old.elapsed_secs,
+        new.elapsed_secs,
+        old.usage.total(),
+        new.usage.total(),
+    ));
+    for (title, section) in [
+        ("Instruction", instruction),
+        ("Reported Violations", violations),
+        ("Tool Calls", conversation),
+    ] {
+        if let Some(section) = section {
+            output.push_str(&format!("### {}\n\n{}", title, section));
+        }
+    }
+    Some(output)
+}
+
+/// Compare two traces of the same review, e.g. before and after editing a rule.
+///
+/// Workers are aligned by rule id and files. For each task that changed, shows the
+/// instruction, reported violations and tool calls as diffs; tasks run in only one trace
+/// are listed with their violations.
+pub fn format_trace_diff(old: &[TraceEntry], new: &[TraceEntry]) -> String {
+    let old: BTreeMap<TaskKey, &TraceEntry> = old.iter().map(|e| (task_key(e), e)).collect();
+    let new: BTreeMap<TaskKey, &TraceEntry> = new.iter().map(|e| (task_key(e), e)).collect();
+
+    let mut changed = Vec::new();
+    let mut unchanged = 0;
+    for (key, new_entry) in &new {
+        if let Some(old_entry) = old.get(key) {
+            match format_task_diff(key, old_entry, new_entry) {
+                Some(diff) => changed.push(diff),
+                None => unchanged += 1,
+            }
+        }
+    }
+    let only = |from: &BTreeMap<TaskKey, &TraceEntry>, to: &BTreeMap<TaskKey, &TraceEntry>| {
+        from.iter()
+            .filter(|(key, _)| !to.contains_key(*key))
+            .map(|(key, entry)| {
+                let mut output = format_task_heading(key, entry);
+                for violation in reported_violations(entry) {
+                    output.push_str(&format!("- {}\n", violation));
+                }
+                output
+            })
+            .collect::<Vec<_>>()
+    };
+    let removed = only(&old, &new);
+    let added = only(&new, &old);
+
+    let total_violations = |entries: &BTreeMap<TaskKey, &TraceEntry>| -> usize {
+        entries.values().map(|e| reported_violations(e).len()).sum()
+    };
+    let mut output = format!(
+        "# Trace Diff\n\n- Tasks: {} changed, {} unchanged, {} only in old, {} only in new\n- Violations: {} -> {}\n\n",
+        changed.len(),
+        unchanged,
+        removed.len(),
+        added.len(),
+        total_violations(&old),
+        total_violations(&new),
+    );
+    output.push_str(&changed.concat());
+    for (title, tasks) in [("Only in Old", removed), ("Only in New", added)] {
+        if !tasks.is_empty() {
+            output.push_str(&format!("# {}\n\n{}\n", title, tasks.join("\n")));
+        }
+    }
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::body::RuleBody;
+    use std::time::{Duration, SystemTime};
+    use tiny_loop::types::{AssistantMessage, FunctionCall, TimedMessage, ToolCall, UserMessage};
+
+    fn strings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let old = strings(&["a", "b", "c"]);
+        let new = strings(&["a", "c", "d"]);
+        assert_eq!(
+            diff_lines(&old, &new),
+            [
+                Change::Same("a"),
+                Change::Removed("b"),
+                Change::Same("c"),
+                Change::Added("d"),
+            ]
+        );
+    }
+
+    fn entry(instruction: &str, calls: &[(&str, &str)]) -> TraceEntry {
+        let timed = |message| TimedMessage {
+            message,
+            timestamp: SystemTime::UNIX_EPOCH,
+            elapsed: Duration::ZERO,
+        };
+        let mut messages = vec![timed(Message::User(UserMessage {
+            content: "review".into(),
+        }))];
+        messages.extend(calls.iter().map(|(name, arguments)| {
+            timed(Message::Assistant(AssistantMessage {
+                content: String::new(),
+                tool_calls: Some(vec![ToolCall {
+                    id: "1".into(),
+                    call_type: "function".into(),
+                    function: FunctionCall {
+                        name: name.to_string(),
+                        arguments: arguments.to_string(),
+                    },
+                }]),
+            }))
+        }));
+        TraceEntry {
+            worker_id: "0".into(),
+            rule: RuleBody {
+                instruction: instruction.into(),
+                ..RuleBody::no_magic_numbers()
+            },
+            files: vec!["src/main.rs".into()],
+            elapsed_secs: 1.0,
+            resource_bytes: 0,
+            compactions: vec![],
+            usage: Default::default(),
+            tools: vec![],
+            messages,
+        }
+    }
+
+    #[test]
+    fn test_format_trace_diff() {
+        let report = r#"{"violations": [{"file": "src/main.rs", "detail": "Magic 42", "start_line": 3, "end_line": 3}]}"#;
+        let old = entry(
+            "Check numbers.",
+            &[("sh", r#"{"command": "git diff"}"#), ("report", report)],
+        );
+        let new = entry(
+            "Check numbers.\nIgnore tests.",
+            &[("sh", r#"{"command": "git diff"}"#)],
+        );
+
+        let diff = format_trace_diff(std::slice::from_ref(&old), &[new]);
+        assert!(diff.starts_with(
+            "# Trace Diff\n\n- Tasks: 1 changed, 0 unchanged, 0 only in old, 0 only in new\n- Violations: 1 -> 0\n\n"
+        ));
+        assert!(diff.contains("```diff\n  Check numbers.\n+ Ignore tests.\n```"));
+        assert!(diff.contains("```diff\n- src/main.rs:3-3: Magic 42\n```"));
+        assert!(
+            diff.contains(
+                "```diff\n  sh: {\"command\": \"git diff\"}\n- report: 1 violation(s)\n```"
+            )
+        );
+
+        assert!(
+            format_trace_diff(std::slice::from_ref(&old), std::slice::from_ref(&old))
+                .contains("0 changed, 1 unchanged")
+        );
+    }
+}
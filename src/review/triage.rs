@@ -0,0 +1,374 @@
+use super::render::{self, ViolationFile};
+use crate::types::Violation;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, IsTerminal, Write};
+use tracing::{info, warn};
+
+/// Default path for triage decisions
+pub const DEFAULT_DECISIONS_PATH: &str = ".firekeeper/triage.json";
+/// Default path for suppressed violation fingerprints, applied by `firekeeper review`
+pub const DEFAULT_SUPPRESSIONS_PATH: &str = ".firekeeper/suppressions.json";
+
+/// Lines of surrounding context to show around the violation snippet
+const SNIPPET_CONTEXT_LINES: u32 = 3;
+
+/// Triage decision for a reported violation
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// True positive, keep reporting
+    Accepted,
+    /// False positive for this run only
+    Dismissed,
+    /// False positive, hide in future reviews
+    Suppressed,
+}
+
+/// A triaged violation
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TriageEntry {
+    pub fingerprint: String,
+    pub rule: String,
+    pub violation: Violation,
+    pub decision: Decision,
+}
+
+/// Triage decisions file schema
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TriageFile {
+    pub version: String,
+    pub decisions: Vec<TriageEntry>,
+}
+
+/// A suppressed violation, identified by fingerprint
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Suppression {
+    pub fingerprint: String,
+    pub rule: String,
+    pub file: String,
+}
+
+/// Suppressions file schema
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SuppressionFile {
+    pub version: String,
+    pub suppressions: Vec<Suppression>,
+}
+
+impl TriageFile {
+    /// Record a decision, replacing any previous decision for the same fingerprint
+    pub fn record(&mut self, entry: TriageEntry) {
+        self.decisions
+            .retain(|d| d.fingerprint != entry.fingerprint);
+        self.decisions.push(entry);
+    }
+}
+
+impl SuppressionFile {
+    /// Add a suppression unless the fingerprint is already suppressed
+    pub fn add(&mut self, suppression: Suppression) {
+        if !self
+            .suppressions
+            .iter()
+            .any(|s| s.fingerprint == suppression.fingerprint)
+        {
+            self.suppressions.push(suppression);
+        }
+    }
+
+    /// Remove the suppression for a fingerprint, if any
+    pub fn remove(&mut self, fingerprint: &str) {
+        self.suppressions.retain(|s| s.fingerprint != fingerprint);
+    }
+}
+
+/// Load a JSON file, returning the default value if it does not exist
+pub fn load_or_default<T: Default + for<'de> Deserialize<'de>>(path: &str) -> Result<T, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => {
+            serde_json::from_str(&content).map_err(|e| format!("Invalid file {}: {}", path, e))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path, e)),
+    }
+}
+
+/// Write a JSON file, creating parent directories as needed
+pub fn save<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    if let Some(parent) = std::path::Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value).unwrap())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Fingerprint of a suppressed violation: rule, file, and the detail with case and whitespace
+/// normalized, without line numbers so the suppression survives edits above the code
+pub fn suppression_fingerprint(rule: &str, violation: &Violation) -> String {
+    let detail = violation
+        .detail
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    crate::util::hash_parts(&[rule, &violation.file, detail.trim_end_matches('.')])
+}
+
+/// Load suppressed fingerprints for review, ignoring a missing file
+pub fn load_suppressed_fingerprints(path: &str) -> HashSet<String> {
+    match load_or_default::<SuppressionFile>(path) {
+        Ok(file) => file
+            .suppressions
+            .into_iter()
+            .map(|s| s.fingerprint)
+            .collect(),
+        Err(e) => {
+            warn!("Ignoring suppressions: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+/// User input during triage
+#[derive(Debug, PartialEq)]
+enum Action {
+    Decide(Decision),
+    Skip,
+    Back,
+    Quit,
+}
+
+fn parse_action(input: &str) -> Option<Action> {
+    match input.trim().to_lowercase().as_str() {
+        "a" | "accept" => Some(Action::Decide(Decision::Accepted)),
+        "d" | "dismiss" => Some(Action::Decide(Decision::Dismissed)),
+        "s" | "suppress" => Some(Action::Decide(Decision::Suppressed)),
+        "" | "n" | "next" | "skip" => Some(Action::Skip),
+        "b" | "back" => Some(Action::Back),
+        "q" | "quit" => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+fn format_card(index: usize, total: usize, rule: &str, violation: &Violation) -> String {
+    let mut card = format!(
+        "[{}/{}] {}\n{}:{}-{}\n\n",
+        index + 1,
+        total,
+        rule,
+        violation.file,
+        violation.start_line,
+        violation.end_line
+    );
+    if let Some(snippet) = crate::util::read_snippet(
+        &violation.file,
+        violation.start_line,
+        violation.end_line,
+        SNIPPET_CONTEXT_LINES,
+    ) {
+        card.push_str(&snippet);
+        card.push_str("\n\n");
+    }
+    card.push_str(&violation.detail);
+    card.push('\n');
+    card
+}
+
+/// Step through violations interactively and record triage decisions.
+///
+/// Decisions are saved after every step so quitting midway keeps progress.
+/// Suppressed violations are also written to the suppressions file, which
+/// `firekeeper review` applies to hide them in future runs.
+pub fn triage(
+    violation_file: &ViolationFile,
+    decisions_path: &str,
+    suppressions_path: &str,
+    revisit: bool,
+) -> Result<(), String> {
+    let mut decisions: TriageFile = load_or_default(decisions_path)?;
+    let mut suppressions: SuppressionFile = load_or_default(suppressions_path)?;
+    decisions.version = env!("CARGO_PKG_VERSION").to_string();
+    suppressions.version = env!("CARGO_PKG_VERSION").to_string();
+
+    let decided: HashSet<String> = decisions
+        .decisions
+        .iter()
+        .map(|d| d.fingerprint.clone())
+        .collect();
+    let pending: Vec<_> = render::flatten_violations(&violation_file.violations)
+        .into_iter()
+        .filter(|(rule, v)| revisit || !decided.contains(&v.fingerprint(rule)))
+        .collect();
+    if pending.is_empty() {
+        info!("No violations to triage");
+        return Ok(());
+    }
+
+    let clear_screen = std::io::stdout().is_terminal();
+    let mut stdin = std::io::stdin().lock();
+    let mut i = 0;
+    while i < pending.len() {
+        let (rule, violation) = pending[i];
+        if clear_screen {
+            print!("\x1b[2J\x1b[H");
+        }
+        println!("{}", format_card(i, pending.len(), rule, violation));
+        print!("[a]ccept [d]ismiss [s]uppress [n]ext [b]ack [q]uit > ");
+        std::io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        match parse_action(&line) {
+            Some(Action::Decide(decision)) => {
+                if decision == Decision::Suppressed {
+                    suppressions.add(Suppression {
+                        fingerprint: suppression_fingerprint(rule, violation),
+                        rule: rule.to_string(),
+                        file: violation.file.clone(),
+                    });
+                } else {
+                    suppressions.remove(&suppression_fingerprint(rule, violation));
+                }
+                decisions.record(TriageEntry {
+                    fingerprint: violation.fingerprint(rule),
+                    rule: rule.to_string(),
+                    violation: violation.clone(),
+                    decision,
+                });
+                save(decisions_path, &decisions)?;
+                save(suppressions_path, &suppressions)?;
+                i += 1;
+            }
+            Some(Action::Skip) => i += 1,
+            Some(Action::Back) => i = i.saturating_sub(1),
+            Some(Action::Quit) => break,
+            None => println!("Unknown action: {}", line.trim()),
+        }
+    }
+
+    info!(
+        "Triage saved to {} ({} suppressed in {})",
+        decisions_path,
+        suppressions.suppressions.len(),
+        suppressions_path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(fingerprint: &str, decision: Decision) -> TriageEntry {
+        TriageEntry {
+            fingerprint: fingerprint.into(),
+            rule: "Rule".into(),
+            violation: Violation {
+                file: "a.rs".into(),
+                detail: "detail".into(),
+                start_line: 1,
+                end_line: 1,
+                rule_id: None,
+                owners: vec![],
+            },
+            decision,
+        }
+    }
+
+    #[test]
+    fn test_record_replaces_previous_decision() {
+        let mut file = TriageFile::default();
+        file.record(entry("abc", Decision::Accepted));
+        file.record(entry("abc", Decision::Suppressed));
+        file.record(entry("def", Decision::Dismissed));
+        assert_eq!(file.decisions.len(), 2);
+        assert_eq!(file.decisions[0].fingerprint, "abc");
+        assert_eq!(file.decisions[0].decision, Decision::Suppressed);
+    }
+
+    #[test]
+    fn test_suppression_add_and_remove() {
+        let suppression = || Suppression {
+            fingerprint: "abc".into(),
+            rule: "Rule".into(),
+            file: "a.rs".into(),
+        };
+        let mut file = SuppressionFile::default();
+        file.add(suppression());
+        file.add(suppression());
+        assert_eq!(file.suppressions.len(), 1);
+        file.remove("abc");
+        assert!(file.suppressions.is_empty());
+    }
+
+    #[test]
+    fn test_suppression_fingerprint_ignores_lines() {
+        let violation = Violation {
+            file: "a.rs".into(),
+            detail: "Magic number 42.".into(),
+            start_line: 10,
+            end_line: 12,
+            rule_id: None,
+            owners: vec![],
+        };
+        let moved = Violation {
+            detail: "magic  number 42".into(),
+            start_line: 20,
+            end_line: 22,
+            ..violation.clone()
+        };
+        let fingerprint = suppression_fingerprint("Rule", &violation);
+        assert_eq!(fingerprint, suppression_fingerprint("Rule", &moved));
+        assert_ne!(
+            fingerprint,
+            suppression_fingerprint("Other Rule", &violation)
+        );
+        let other_file = Violation {
+            file: "b.rs".into(),
+            ..violation.clone()
+        };
+        assert_ne!(fingerprint, suppression_fingerprint("Rule", &other_file));
+    }
+
+    #[test]
+    fn test_load_suppressed_fingerprints() {
+        let dir = crate::util::temp::TempDir::new(&format!(
+            "firekeeper-suppressions-{}",
+            std::process::id()
+        ));
+        let path = dir.path().join("suppressions.json");
+        let path = path.to_str().unwrap();
+        let mut file = SuppressionFile::default();
+        file.add(Suppression {
+            fingerprint: "abc".into(),
+            rule: "Rule".into(),
+            file: "a.rs".into(),
+        });
+        save(path, &file).unwrap();
+        let suppressed = load_suppressed_fingerprints(path);
+        assert_eq!(suppressed, HashSet::from(["abc".to_string()]));
+        drop(dir);
+        assert!(load_suppressed_fingerprints(path).is_empty());
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(
+            parse_action("a\n"),
+            Some(Action::Decide(Decision::Accepted))
+        );
+        assert_eq!(
+            parse_action("Suppress"),
+            Some(Action::Decide(Decision::Suppressed))
+        );
+        assert_eq!(parse_action(""), Some(Action::Skip));
+        assert_eq!(parse_action("q"), Some(Action::Quit));
+        assert_eq!(parse_action("x"), None);
+    }
+}
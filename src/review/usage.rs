@@ -0,0 +1,417 @@
+use super::compaction::{message_chars, message_tokens};
+use crate::llm::UsageSlot;
+use crate::tokens::Tokenizer;
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tiny_loop::llm::LLMProvider;
+use tiny_loop::types::{LLMResponse, Message, ToolDefinition};
+use toml_scaffold::TomlScaffold;
+
+/// Token usage of a worker: the counts the provider reported with each response where
+/// it exposes them (Anthropic, streaming), estimated from the messages otherwise
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    /// LLM requests made
+    pub requests: usize,
+    /// Requests whose token counts the provider reported; the others are estimated
+    #[serde(default)]
+    pub reported_requests: usize,
+    /// Tokens sent across all requests, including the resent history
+    pub prompt_tokens: usize,
+    /// Tokens of tool results added to the conversation (estimated)
+    pub tool_result_tokens: usize,
+    /// Tokens generated by the model
+    pub completion_tokens: usize,
+    /// Cost in USD of the requests to models priced in `llm.prices`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.requests += other.requests;
+        self.reported_requests += other.reported_requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.tool_result_tokens += other.tool_result_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost = match (self.cost, other.cost) {
+            (Some(cost), Some(other)) => Some(cost + other),
+            (cost, other) => cost.or(other),
+        };
+    }
+
+    /// This usage with its cost at `price`, if the model has one
+    pub fn priced(self, price: Option<&ModelPrice>) -> Self {
+        Self {
+            cost: price.map(|price| price.cost(&self)),
+            ..self
+        }
+    }
+}
+
+/// Price of a model in USD per million tokens
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, JsonSchema, TomlScaffold,
+)]
+pub struct ModelPrice {
+    /// Price of a million prompt tokens
+    pub prompt: f64,
+    /// Price of a million completion tokens
+    pub completion: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt
+            + usage.completion_tokens as f64 * self.completion)
+            / 1e6
+    }
+}
+
+/// Sum usage per rule
+pub fn usage_by_rule<'a>(
+    usages: impl IntoIterator<Item = (&'a str, &'a TokenUsage)>,
+) -> BTreeMap<String, TokenUsage> {
+    let mut by_rule: BTreeMap<String, TokenUsage> = BTreeMap::new();
+    for (rule, usage) in usages {
+        by_rule.entry(rule.to_string()).or_default().add(usage);
+    }
+    by_rule
+}
+
+/// Total cost of the usage per rule, if any rule's model is priced
+pub fn total_cost(by_rule: &BTreeMap<String, TokenUsage>) -> Option<f64> {
+    by_rule
+        .values()
+        .filter_map(|usage| usage.cost)
+        .reduce(|total, cost| total + cost)
+}
+
+/// Tokens the workers of a run may use together, spent as their tasks complete
+#[derive(Debug, Clone, Default)]
+pub struct TokenBudget {
+    limit: Option<usize>,
+    used: Arc<AtomicUsize>,
+}
+
+impl TokenBudget {
+    /// Budget of `limit` tokens, unlimited if `None`
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit,
+            used: Arc::default(),
+        }
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Record the tokens of a completed task
+    pub fn spend(&self, usage: &TokenUsage) {
+        self.used.fetch_add(usage.total(), Ordering::Relaxed);
+    }
+
+    /// Whether the limit is reached, so no further task should start
+    pub fn is_exhausted(&self) -> bool {
+        self.limit.is_some_and(|limit| self.used() >= limit)
+    }
+}
+
+/// Provider wrapper that counts the tokens of each request and response
+pub struct MeteredProvider<P> {
+    inner: P,
+    tokenizer: Tokenizer,
+    usage: Arc<Mutex<TokenUsage>>,
+    /// Messages already counted for tool results
+    seen: usize,
+    /// Size and tokens of each message sent, recounted only when compaction changed it
+    counted: Vec<(usize, usize)>,
+    /// Tokens of the tool definitions, sent unchanged with every request
+    tool_tokens: Option<usize>,
+    /// Counts the provider reported for its last response, preferred over estimates
+    reported: Option<UsageSlot>,
+}
+
+impl<P> MeteredProvider<P> {
+    pub fn new(inner: P, tokenizer: Tokenizer) -> Self {
+        Self {
+            inner,
+            tokenizer,
+            usage: Arc::default(),
+            seen: 0,
+            counted: Vec::new(),
+            tool_tokens: None,
+            reported: None,
+        }
+    }
+
+    /// Take the token counts the provider reports in `slot` instead of estimating them
+    pub fn with_reported(mut self, slot: UsageSlot) -> Self {
+        self.reported = Some(slot);
+        self
+    }
+
+    /// Count into shared `usage` totals, readable even when the agent fails
+    pub fn with_usage(mut self, usage: Arc<Mutex<TokenUsage>>) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+/// Tokens of each message, reusing the counts of an earlier request in `counted` for
+/// messages that are unchanged
+fn count_messages(
+    tokenizer: &Tokenizer,
+    counted: &mut Vec<(usize, usize)>,
+    messages: &[Message],
+) -> Vec<usize> {
+    let mut tokens = Vec::with_capacity(messages.len());
+    for (index, message) in messages.iter().enumerate() {
+        let chars = message_chars(message);
+        tokens.push(match counted.get(index) {
+            Some(&(counted_chars, n)) if counted_chars == chars => n,
+            _ => {
+                let n = message_tokens(tokenizer, message);
+                counted.truncate(index);
+                counted.push((chars, n));
+                n
+            }
+        });
+    }
+    tokens
+}
+
+/// Run CPU-bound token counting off the async runtime, as BPE encoding of a long
+/// conversation would stall other workers
+async fn count_blocking<T: Send + 'static>(
+    count: impl FnOnce() -> T + Send + 'static,
+) -> tiny_loop::Result<T> {
+    tokio::task::spawn_blocking(count)
+        .await
+        .map_err(|e| tiny_loop::Error::Custom(format!("Token counting failed: {}", e)))
+}
+
+#[async_trait]
+impl<P: LLMProvider> LLMProvider for MeteredProvider<P> {
+    async fn call(
+        &mut self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+    ) -> tiny_loop::Result<LLMResponse> {
+        let tokenizer = self.tokenizer;
+        let mut counted = std::mem::take(&mut self.counted);
+        let owned = messages.to_vec();
+        let definitions = self.tool_tokens.is_none().then(|| tools.to_vec());
+        let (counted, tokens, definition_tokens) = count_blocking(move || {
+            let tokens = count_messages(&tokenizer, &mut counted, &owned);
+            let definition_tokens = definitions.map(|tools| {
+                tools
+                    .iter()
+                    .map(|t| serde_json::to_string(t).map_or(0, |s| tokenizer.count(&s)))
+                    .sum::<usize>()
+            });
+            (counted, tokens, definition_tokens)
+        })
+        .await?;
+        self.counted = counted;
+        let tool_tokens = *self
+            .tool_tokens
+            .get_or_insert(definition_tokens.unwrap_or_default());
+        let mut prompt_tokens = tool_tokens;
+        let mut tool_result_tokens = 0;
+        for (i, (message, tokens)) in messages.iter().zip(tokens).enumerate() {
+            prompt_tokens += tokens;
+            if i >= self.seen && matches!(message, Message::Tool(_)) {
+                tool_result_tokens += tokens;
+            }
+        }
+        self.seen = messages.len();
+
+        let response = self.inner.call(messages, tools).await?;
+        let reply = Message::Assistant(response.message.clone());
+        let mut completion_tokens =
+            count_blocking(move || message_tokens(&tokenizer, &reply)).await?;
+        let reported = self
+            .reported
+            .as_ref()
+            .and_then(|slot| slot.lock().unwrap().take());
+        if let Some(reported) = reported {
+            prompt_tokens = reported.prompt_tokens;
+            completion_tokens = reported.completion_tokens;
+        }
+        let mut usage = self.usage.lock().unwrap();
+        usage.requests += 1;
+        usage.reported_requests += usize::from(reported.is_some());
+        usage.prompt_tokens += prompt_tokens;
+        usage.tool_result_tokens += tool_result_tokens;
+        usage.completion_tokens += completion_tokens;
+        Ok(response)
+    }
+}
+
+/// Format tokens compactly, e.g. `950`, `12.3k`, `4.5M`
+fn format_tokens(tokens: usize) -> String {
+    match tokens {
+        0..1_000 => tokens.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", tokens as f64 / 1e3),
+        _ => format!("{:.1}M", tokens as f64 / 1e6),
+    }
+}
+
+/// Format a cost in USD, e.g. `$0.0123`
+pub fn format_cost(cost: f64) -> String {
+    format!("${:.4}", cost)
+}
+
+/// Table of usage per rule (tasks, usage), largest share of total tokens first, with
+/// a cost column when any model is priced
+pub fn format_usage_table(by_rule: &BTreeMap<String, (usize, TokenUsage)>) -> String {
+    let total: usize = by_rule.values().map(|(_, u)| u.total()).sum();
+    let mut rows: Vec<_> = by_rule.iter().collect();
+    rows.sort_by(|a, b| b.1.1.total().cmp(&a.1.1.total()).then(a.0.cmp(b.0)));
+    let priced = by_rule.values().any(|(_, u)| u.cost.is_some());
+
+    let mut header = vec![
+        "Rule",
+        "Tasks",
+        "Requests",
+        "Prompt",
+        "Tool results",
+        "Completion",
+        "Share",
+    ];
+    if priced {
+        header.push("Cost");
+    }
+    let mut table: Vec<Vec<String>> = vec![header.iter().map(|h| h.to_string()).collect()];
+    for (rule, (tasks, usage)) in rows {
+        let share = (usage.total() * 100).checked_div(total).unwrap_or(0);
+        let mut row = vec![
+            rule.clone(),
+            tasks.to_string(),
+            usage.requests.to_string(),
+            format_tokens(usage.prompt_tokens),
+            format_tokens(usage.tool_result_tokens),
+            format_tokens(usage.completion_tokens),
+            format!("{}%", share),
+        ];
+        if priced {
+            row.push(usage.cost.map_or("-".to_string(), format_cost));
+        }
+        table.push(row);
+    }
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| {
+            table
+                .iter()
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    table
+        .iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(col, cell)| {
+                    if col == 0 {
+                        format!("{:<width$}", cell, width = widths[col])
+                    } else {
+                        format!("{:>width$}", cell, width = widths[col])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_budget() {
+        let usage = TokenUsage {
+            prompt_tokens: 600,
+            completion_tokens: 100,
+            ..Default::default()
+        };
+        let budget = TokenBudget::new(Some(1_000));
+        budget.clone().spend(&usage);
+        assert!(!budget.is_exhausted());
+        budget.spend(&usage);
+        assert_eq!(budget.used(), 1_400);
+        assert!(budget.is_exhausted());
+
+        let unlimited = TokenBudget::new(None);
+        unlimited.spend(&usage);
+        assert!(!unlimited.is_exhausted());
+    }
+
+    #[test]
+    fn test_format_usage_table() {
+        let usage = |prompt, completion| TokenUsage {
+            requests: 2,
+            prompt_tokens: prompt,
+            tool_result_tokens: prompt / 2,
+            completion_tokens: completion,
+            ..Default::default()
+        };
+        let by_rule = BTreeMap::from([
+            ("Small".to_string(), (1, usage(900, 100))),
+            ("Large".to_string(), (3, usage(8_000, 1_000))),
+        ]);
+        assert_eq!(
+            format_usage_table(&by_rule),
+            "Rule   Tasks  Requests  Prompt  Tool results  Completion  Share\n\
+             Large      3         2    8.0k          4.0k        1.0k    90%\n\
+             Small      1         2     900           450         100    10%"
+        );
+
+        let price = ModelPrice {
+            prompt: 1.0,
+            completion: 10.0,
+        };
+        let by_rule = BTreeMap::from([
+            (
+                "Priced".to_string(),
+                (1, usage(8_000, 1_000).priced(Some(&price))),
+            ),
+            ("Unpriced".to_string(), (1, usage(900, 100).priced(None))),
+        ]);
+        assert_eq!(by_rule["Priced"].1.cost, Some(0.018));
+        assert_eq!(
+            format_usage_table(&by_rule),
+            "Rule      Tasks  Requests  Prompt  Tool results  Completion  Share     Cost\n\
+             Priced        1         2    8.0k          4.0k        1.0k    90%  $0.0180\n\
+             Unpriced      1         2     900           450         100    10%        -"
+        );
+
+        let mut total = by_rule["Priced"].1;
+        total.add(&by_rule["Unpriced"].1);
+        assert_eq!(total.cost, Some(0.018));
+        assert_eq!(
+            total_cost(&usage_by_rule([("A", &total), ("B", &total)])),
+            Some(0.036)
+        );
+    }
+}
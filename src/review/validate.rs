@@ -0,0 +1,373 @@
+use super::render::{TraceEntry, TraceFile, ViolationFile};
+use serde_json::{Map, Value, json};
+use tracing::{info, warn};
+
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Kind of JSON file written by `firekeeper review`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    /// Results from `review --output`
+    Results,
+    /// Trace from `review --trace`
+    Trace,
+}
+
+impl std::fmt::Display for FileKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Results => write!(f, "results"),
+            Self::Trace => write!(f, "trace"),
+        }
+    }
+}
+
+/// Outcome of checking a results or trace file against the current schema
+#[derive(Debug)]
+pub struct Validation {
+    pub kind: FileKind,
+    /// Version of firekeeper that wrote the file, if recorded
+    pub version: Option<String>,
+    /// Incompatibilities that stop the file from loading
+    pub errors: Vec<String>,
+    /// Differences that do not stop the file from loading, e.g. an older minor version
+    pub warnings: Vec<String>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Detect whether a JSON value is a results or trace file
+pub fn detect_kind(value: &Value) -> Result<FileKind, String> {
+    let Some(object) = value.as_object() else {
+        return Err("Expected a JSON object".to_string());
+    };
+    if object.contains_key("entries") {
+        Ok(FileKind::Trace)
+    } else if object.contains_key("violations") {
+        Ok(FileKind::Results)
+    } else {
+        Err("Not a results or trace file: no `violations` or `entries` field".to_string())
+    }
+}
+
+/// `major.minor.patch` as numbers; missing or non-numeric parts are 0
+fn parse_version(version: &str) -> (u64, u64, u64) {
+    let mut parts = version
+        .split(['.', '-', '+'])
+        .map(|part| part.parse().unwrap_or(0));
+    let mut next = || parts.next().unwrap_or(0);
+    (next(), next(), next())
+}
+
+/// Versions are compatible when major and minor match
+fn is_compatible(version: &str) -> bool {
+    let (major, minor, _) = parse_version(version);
+    let (current_major, current_minor, _) = parse_version(CURRENT_VERSION);
+    (major, minor) == (current_major, current_minor)
+}
+
+/// Check a results or trace file against the current schema version.
+///
+/// Trace entries are checked one by one, so all incompatible entries are reported.
+pub fn validate(value: &Value) -> Result<Validation, String> {
+    let kind = detect_kind(value)?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    match version.as_deref() {
+        // Results files report a missing version when deserialized below
+        None if kind == FileKind::Trace => errors.push("missing field `version`".to_string()),
+        None => {}
+        Some(version) if !is_compatible(version) => {
+            let direction = if parse_version(version) < parse_version(CURRENT_VERSION) {
+                "older"
+            } else {
+                "newer"
+            };
+            warnings.push(format!(
+                "written by {} firekeeper {} (current: {})",
+                direction, version, CURRENT_VERSION
+            ));
+        }
+        Some(_) => {}
+    }
+
+    match kind {
+        FileKind::Results => {
+            if let Err(e) = serde_json::from_value::<ViolationFile>(value.clone()) {
+                errors.push(e.to_string());
+            }
+        }
+        FileKind::Trace => match value["entries"].as_array() {
+            Some(entries) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    if let Err(e) = serde_json::from_value::<TraceEntry>(entry.clone()) {
+                        errors.push(format!("entries[{}]: {}", i, e));
+                    }
+                }
+            }
+            None => errors.push("`entries` is not an array".to_string()),
+        },
+    }
+
+    Ok(Validation {
+        kind,
+        version,
+        errors,
+        warnings,
+    })
+}
+
+/// Insert `default` under `key` if missing, recording the change
+fn insert_default(
+    object: &mut Map<String, Value>,
+    key: &str,
+    default: Value,
+    location: &str,
+    changes: &mut Vec<String>,
+) {
+    if !object.contains_key(key) {
+        object.insert(key.to_string(), default);
+        changes.push(format!("added missing `{}{}`", location, key));
+    }
+}
+
+/// Upgrade a results or trace file written by an older firekeeper to the current schema.
+///
+/// Fills fields older versions did not write with their defaults and sets `version`.
+/// Files written by a newer version are left as they are. Returns the applied changes,
+/// empty if the file needed none.
+pub fn migrate(value: &mut Value) -> Result<Vec<String>, String> {
+    let kind = detect_kind(value)?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    if version
+        .as_deref()
+        .is_some_and(|v| parse_version(v) > parse_version(CURRENT_VERSION))
+    {
+        return Ok(vec![]);
+    }
+
+    let mut changes = Vec::new();
+    let Some(object) = value.as_object_mut() else {
+        return Ok(changes);
+    };
+    match kind {
+        FileKind::Results => {
+            insert_default(object, "tips", json!({}), "", &mut changes);
+            // Skipped files were plain paths, all skipped by sampling
+            if let Some(skipped) = object.get_mut("skipped").and_then(Value::as_array_mut)
+                && skipped.iter().any(Value::is_string)
+            {
+                for entry in skipped.iter_mut() {
+                    if let Some(file) = entry.as_str() {
+                        *entry = json!({"file": file, "reason": "sampled"});
+                    }
+                }
+                changes.push("added reasons to `skipped` files".to_string());
+            }
+        }
+        FileKind::Trace => {
+            let entries = object
+                .get_mut("entries")
+                .and_then(Value::as_array_mut)
+                .into_iter()
+                .flatten();
+            for (i, entry) in entries.enumerate() {
+                let Some(entry) = entry.as_object_mut() else {
+                    continue;
+                };
+                let location = format!("entries[{}].", i);
+                for (key, default) in [
+                    ("worker_id", json!(i.to_string())),
+                    ("files", json!([])),
+                    ("elapsed_secs", json!(0.0)),
+                    ("tools", json!([])),
+                    ("messages", json!([])),
+                ] {
+                    insert_default(entry, key, default, &location, &mut changes);
+                }
+            }
+        }
+    }
+
+    if version.as_deref() != Some(CURRENT_VERSION) {
+        changes.push(format!(
+            "updated `version` from {} to {}",
+            version.as_deref().unwrap_or("none"),
+            CURRENT_VERSION
+        ));
+        object.insert("version".to_string(), json!(CURRENT_VERSION));
+    }
+    Ok(changes)
+}
+
+/// Validate a results or trace file, upgrading it in place first with `migrate`.
+///
+/// Logs warnings and applied changes; fails with the incompatibilities left.
+pub fn validate_file(path: &str, migrate_file: bool) -> Result<(), String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut value: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    if migrate_file {
+        let changes = migrate(&mut value)?;
+        if !changes.is_empty() {
+            std::fs::write(path, serde_json::to_string_pretty(&value).unwrap())
+                .map_err(|e| format!("Failed to write migrated file: {}", e))?;
+            for change in &changes {
+                info!("{}: {}", path, change);
+            }
+        }
+    }
+
+    let validation = validate(&value)?;
+    for warning in &validation.warnings {
+        warn!("{}: {}", path, warning);
+    }
+    if !validation.is_valid() {
+        let mut message = format!("incompatible {} file", validation.kind);
+        for error in &validation.errors {
+            message.push_str(&format!("\n  - {}", error));
+        }
+        if !migrate_file {
+            message.push_str("\nRun with --migrate to upgrade files from older versions");
+        }
+        return Err(message);
+    }
+    info!(
+        "{}: valid {} file (version {})",
+        path,
+        validation.kind,
+        validation.version.as_deref().unwrap_or("unknown")
+    );
+    Ok(())
+}
+
+/// Results or trace file, upgraded in memory from an older version if needed
+pub enum ReviewFile {
+    Results(ViolationFile),
+    Trace(TraceFile),
+}
+
+impl ReviewFile {
+    /// Parse a results or trace file, migrating it in memory.
+    ///
+    /// Returns the file and the migration changes; fails with the validation errors.
+    pub fn parse(content: &str) -> Result<(Self, Vec<String>), String> {
+        let mut value: Value =
+            serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let changes = migrate(&mut value)?;
+        let file = match detect_kind(&value)? {
+            FileKind::Results => serde_json::from_value(value.clone()).map(Self::Results),
+            FileKind::Trace => serde_json::from_value(value.clone()).map(Self::Trace),
+        };
+        match file {
+            Ok(file) => Ok((file, changes)),
+            Err(_) => {
+                let validation = validate(&value)?;
+                Err(format!(
+                    "Incompatible {} file:\n{}",
+                    validation.kind,
+                    validation
+                        .errors
+                        .iter()
+                        .map(|e| format!("  - {}", e))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.5.0"), (0, 5, 0));
+        assert_eq!(parse_version("1.2.3-rc.1"), (1, 2, 3));
+        assert_eq!(parse_version("1"), (1, 0, 0));
+        assert!(parse_version("0.4.9") < parse_version("0.5.0"));
+    }
+
+    #[test]
+    fn test_validate_results() {
+        let value = json!({"version": CURRENT_VERSION, "violations": {}, "tips": {}});
+        let validation = validate(&value).unwrap();
+        assert_eq!(validation.kind, FileKind::Results);
+        assert!(validation.is_valid());
+        assert!(validation.warnings.is_empty());
+
+        let value = json!({"version": "0.1.0", "violations": {}});
+        let validation = validate(&value).unwrap();
+        assert_eq!(validation.errors, ["missing field `tips`"]);
+        assert!(validation.warnings[0].starts_with("written by older firekeeper 0.1.0"));
+
+        assert!(validate(&json!({"foo": 1})).is_err());
+    }
+
+    #[test]
+    fn test_migrate_results() {
+        let mut value = json!({"version": "0.1.0", "violations": {}});
+        let changes = migrate(&mut value).unwrap();
+        assert_eq!(
+            changes,
+            [
+                "added missing `tips`".to_string(),
+                format!("updated `version` from 0.1.0 to {}", CURRENT_VERSION),
+            ]
+        );
+        assert!(validate(&value).unwrap().is_valid());
+        assert!(migrate(&mut value).unwrap().is_empty());
+
+        let mut value =
+            json!({"version": "0.1.0", "violations": {}, "tips": {}, "skipped": ["a.rs"]});
+        assert_eq!(
+            migrate(&mut value).unwrap()[0],
+            "added reasons to `skipped` files"
+        );
+        assert_eq!(
+            value["skipped"],
+            json!([{"file": "a.rs", "reason": "sampled"}])
+        );
+
+        // Newer files are not downgraded
+        let mut value = json!({"version": "99.0.0", "violations": {}});
+        assert!(migrate(&mut value).unwrap().is_empty());
+        assert_eq!(value["version"], "99.0.0");
+    }
+
+    #[test]
+    fn test_migrate_trace() {
+        let rule = serde_json::to_value(crate::rule::body::RuleBody::no_magic_numbers()).unwrap();
+        let mut value = json!({
+            "version": "0.4.0",
+            "entries": [{"worker_id": "0", "rule": rule, "files": ["a.rs"], "elapsed_secs": 1.0, "messages": []}],
+        });
+        assert_eq!(
+            validate(&value).unwrap().errors,
+            ["entries[0]: missing field `tools`"]
+        );
+
+        let changes = migrate(&mut value).unwrap();
+        assert_eq!(changes[0], "added missing `entries[0].tools`");
+        assert!(matches!(
+            ReviewFile::parse(&value.to_string()),
+            Ok((ReviewFile::Trace(_), _))
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+use super::metrics::Metrics;
+use super::serve::{self, Sink};
+use crate::config::Config;
+use crate::tool::read::Snapshot;
+use crate::util;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Options of `firekeeper watch`
+pub struct WatchOptions<'a> {
+    /// Seconds between checks of the working tree
+    pub interval_secs: u64,
+    /// Only run rules with any of these tags (all rules if empty)
+    pub rules_tags: &'a [String],
+    /// Serve Prometheus metrics of the reviews at `/metrics` on this address
+    pub metrics_addr: Option<&'a str>,
+}
+
+/// Files among `files` whose content differs from their last review in `reviewed`,
+/// recording their new content hashes. Files no longer changed are forgotten, so they are
+/// reviewed again once they change.
+fn changed_since(files: &[String], reviewed: &mut HashMap<String, String>) -> Vec<String> {
+    reviewed.retain(|file, _| files.contains(file));
+    files
+        .iter()
+        .filter(|file| {
+            // Deleted files have nothing left to review
+            let Ok(content) = std::fs::read(file) else {
+                return false;
+            };
+            let hash = util::hash_parts(&[&String::from_utf8_lossy(&content)]);
+            reviewed.insert((*file).clone(), hash.clone()) != Some(hash)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Review uncommitted changes against HEAD, then review each changed file again whenever
+/// its content changes, printing `file:line: [rule] detail` lines until interrupted
+pub async fn watch(
+    config: &mut Config,
+    api_key: &str,
+    options: WatchOptions<'_>,
+) -> Result<(), String> {
+    let probe = super::orchestrator::probe_provider(config, api_key).await;
+    let config = &*config;
+    let parallelism = super::orchestrator::resolve_max_parallel_workers(&config.review, &probe);
+    let metrics = Metrics::default();
+    if let Some(addr) = options.metrics_addr {
+        metrics.clone().listen(addr).await?;
+    }
+    let base = util::Base::Commit("HEAD".into());
+    let mut reviewed = HashMap::new();
+    let mut stdout = tokio::io::stdout();
+    info!(
+        "Watching uncommitted changes every {}s, press Ctrl+C to stop",
+        options.interval_secs
+    );
+
+    loop {
+        let files = changed_since(&util::get_changed_files(&base), &mut reviewed);
+        if !files.is_empty() {
+            info!("Reviewing {} changed files", files.len());
+            let diffs = util::restyle_diffs(
+                util::get_diffs(&base, &files),
+                config.review.diff_style,
+                config.review.diff_line_numbers,
+            );
+            let (violations, errors) = serve::review_files(
+                config,
+                api_key,
+                parallelism,
+                options.rules_tags,
+                files,
+                diffs,
+                Snapshot::WorkingTree,
+                Sink::Lines,
+                &mut stdout,
+                &metrics,
+            )
+            .await?;
+            info!(
+                "Found {} violations, {} tasks failed",
+                violations,
+                errors.len()
+            );
+        }
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(std::time::Duration::from_secs(options.interval_secs)) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::temp::TempDir;
+
+    #[test]
+    fn test_changed_since() {
+        let dir = TempDir::new(&format!("firekeeper-watch-{}", std::process::id()));
+        std::fs::create_dir_all(dir.path()).unwrap();
+        let a = dir.path().join("a.rs").to_string_lossy().to_string();
+        let b = dir.path().join("b.rs").to_string_lossy().to_string();
+        std::fs::write(&a, "let a = 1;\n").unwrap();
+        std::fs::write(&b, "let b = 1;\n").unwrap();
+        let files = vec![a.clone(), b.clone()];
+
+        let mut reviewed = HashMap::new();
+        assert_eq!(changed_since(&files, &mut reviewed), files);
+        assert!(changed_since(&files, &mut reviewed).is_empty());
+        std::fs::write(&b, "let b = 2;\n").unwrap();
+        assert_eq!(
+            changed_since(&files, &mut reviewed),
+            std::slice::from_ref(&b)
+        );
+
+        // A file reverted to HEAD and changed again is reviewed again
+        assert!(changed_since(std::slice::from_ref(&a), &mut reviewed).is_empty());
+        assert_eq!(changed_since(&files, &mut reviewed), [b]);
+    }
+}
@@ -1,173 +1,37 @@
+use crate::config::{Config, PromptsConfig};
+use crate::llm::stream::{Partial, StreamProgress};
+use crate::llm::{Capabilities, ProviderKind, RetryConfig, ToolProtocol};
+use crate::review::compaction::{CompactingHistory, Compaction};
 use crate::review::render::get_fence_backticks;
+use crate::review::resource::{LoadedResources, ResourceLoader, TaskVariables};
+use crate::review::usage::{MeteredProvider, TokenUsage};
+use crate::rule::tools::ToolsConfig;
+use crate::tokens::Tokenizer;
 use crate::tool::diff::Diff;
-use crate::tool::report::Report;
+use crate::tool::read::{Read, Snapshot};
+use crate::tool::report::{Report, ReportArgs, THINK_FIRST, parse_report, report_tool_def};
+use crate::tool::resource::ResourceRead;
+use crate::tool::rule_info::RuleInfo;
+use crate::tool::sh::Shell;
+use crate::tool::skill::SkillRead;
+use crate::tool::think::Think;
+use crate::util;
 use crate::{rule::body::RuleBody, types::Violation};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tiny_loop::Agent;
-use tiny_loop::tool::ToolArgs;
-use tiny_loop::types::{Message, TimedMessage, ToolDefinition, UserMessage};
+use tiny_loop::types::{AssistantMessage, Message, TimedMessage, ToolDefinition, UserMessage};
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, trace, warn};
 
+/// Files listed per directory in the directory context section
+const MAX_DIRECTORY_FILES: usize = 200;
+
 /// Polling interval for checking shutdown flag during agent chat (milliseconds)
 const SHUTDOWN_POLL_INTERVAL_MS: u64 = 100;
 
-/// Resolve path with ~ and absolute path support, returns (base_path, glob_pattern)
-fn resolve_path(pattern: &str) -> (std::path::PathBuf, String) {
-    if let Some(rest) = pattern.strip_prefix("~/") {
-        if let Some(home) = std::env::var_os("HOME") {
-            (std::path::PathBuf::from(home), rest.to_string())
-        } else {
-            (std::path::PathBuf::from("."), pattern.to_string())
-        }
-    } else if pattern.starts_with('/') {
-        ("/".into(), pattern[1..].to_string())
-    } else {
-        (std::path::PathBuf::from("."), pattern.to_string())
-    }
-}
-
-/// Load resources from file://, skill://, or sh:// URIs
-async fn load_resources(resources: &[String]) -> String {
-    let mut content = String::new();
-    let mut loaded_files = std::collections::HashSet::new();
-
-    for resource in resources {
-        if let Some(pattern) = resource.strip_prefix("file://") {
-            load_file_resource(pattern, &mut content, &mut loaded_files);
-        } else if let Some(pattern) = resource.strip_prefix("skill://") {
-            load_skill_resource(pattern, &mut content, &mut loaded_files);
-        } else if let Some(cmd) = resource.strip_prefix("sh://") {
-            load_shell_resource(cmd, &mut content).await;
-        } else {
-            warn!("Unknown resource type: {}", resource);
-        }
-    }
-    content
-}
-
-/// Find files matching a glob pattern
-fn find_files_by_glob(pattern: &str) -> Vec<String> {
-    let (base_path, glob_pattern) = resolve_path(pattern);
-    let Ok(glob) = globset::Glob::new(&glob_pattern) else {
-        warn!("Invalid glob pattern '{}'", pattern);
-        return vec![];
-    };
-
-    let mut builder = globset::GlobSetBuilder::new();
-    builder.add(glob);
-    let Ok(globset) = builder.build() else {
-        return vec![];
-    };
-
-    let mut matches = Vec::new();
-    let _ = glob_recursive(&base_path, &globset, &mut matches, 0);
-    matches
-}
-
-fn glob_recursive(
-    path: &std::path::Path,
-    globset: &globset::GlobSet,
-    matches: &mut Vec<String>,
-    depth: usize,
-) -> std::io::Result<()> {
-    for entry in std::fs::read_dir(path)? {
-        let entry = entry?;
-        let entry_path = entry.path();
-
-        if entry_path.is_file() {
-            if let Some(path_str) = entry_path.to_str() {
-                let relative = path_str.strip_prefix("./").unwrap_or(path_str);
-                if globset.is_match(path_str) || globset.is_match(relative) {
-                    matches.push(path_str.to_string());
-                }
-            }
-        }
-
-        if entry_path.is_dir() {
-            glob_recursive(&entry_path, globset, matches, depth + 1)?;
-        }
-    }
-
-    Ok(())
-}
-
-/// Load file:// resources
-fn load_file_resource(
-    pattern: &str,
-    content: &mut String,
-    loaded_files: &mut std::collections::HashSet<String>,
-) {
-    for path in find_files_by_glob(pattern) {
-        if !loaded_files.insert(path.clone()) {
-            continue;
-        }
-        match std::fs::read_to_string(&path) {
-            Ok(file_content) => {
-                let lang = std::path::Path::new(&path)
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("");
-                let fence = get_fence_backticks(&file_content);
-                content.push_str(&format!(
-                    "### {}\n\n{}{}\n{}\n{}\n\n",
-                    path, fence, lang, file_content, fence
-                ));
-            }
-            Err(e) => warn!("Failed to read file {}: {}", path, e),
-        }
-    }
-}
-
-/// Load skill:// resources
-fn load_skill_resource(
-    pattern: &str,
-    content: &mut String,
-    loaded_files: &mut std::collections::HashSet<String>,
-) {
-    for path in find_files_by_glob(pattern) {
-        if !loaded_files.insert(path.clone()) || !path.ends_with(".md") {
-            continue;
-        }
-        let Ok(file_content) = std::fs::read_to_string(&path) else {
-            warn!("Failed to read file {}", path);
-            continue;
-        };
-
-        let matter = gray_matter::Matter::<gray_matter::engine::YAML>::new();
-        let Ok(parsed) = matter.parse::<serde_json::Value>(&file_content) else {
-            warn!("Failed to parse frontmatter in {}", path);
-            continue;
-        };
-
-        if let Some(data) = parsed.data {
-            if let Ok(yaml) = serde_yaml_ng::to_string(&data) {
-                let fence = get_fence_backticks(&yaml);
-                content.push_str(&format!(
-                    "### {}\n\nOnly frontmatter loaded. To enable the skill, read the whole md file.\n\n{}yaml\n{}\n{}\n\n",
-                    path, fence, yaml, fence
-                ));
-            }
-        }
-    }
-}
-
-/// Load sh:// resources
-async fn load_shell_resource(cmd: &str, content: &mut String) {
-    match crate::tool::sh::execute_shell_command(cmd).await {
-        Ok(stdout) => {
-            let fence = get_fence_backticks(&stdout);
-            content.push_str(&format!(
-                "### `{}`\n\n{}\n{}\n{}\n\n",
-                cmd, fence, stdout, fence
-            ));
-        }
-        Err(e) => warn!("Failed to execute command 'sh://{}': {}", cmd, e),
-    }
-}
-
 /// Worker result containing violations and optional trace messages
 pub struct WorkerResult {
     pub worker_id: String,
@@ -178,17 +42,62 @@ pub struct WorkerResult {
     pub messages: Option<Vec<TimedMessage>>,
     pub tools: Option<Vec<ToolDefinition>>,
     pub elapsed_secs: f64,
+    /// Total size of resources included in the prompt
+    pub resource_bytes: usize,
+    /// Conversation compactions, in order
+    pub compactions: Vec<Compaction>,
+    /// Why the worker stopped before finishing (timeout or shutdown), with partial results
+    pub incomplete: Option<String>,
+    /// Token usage, priced by the orchestrator
+    pub usage: TokenUsage,
+}
+
+/// Position of a task among the tasks of its rule, when the rule's files are split
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// 0-based index of this task among the rule's tasks
+    pub index: usize,
+    /// Number of the rule's tasks
+    pub count: usize,
+    /// Summary of the files of all the rule's tasks, shared by its tasks
+    pub summary: Arc<str>,
+    /// Violations reported by earlier tasks, when the rule's tasks run one at a time
+    pub reported: Vec<Violation>,
+}
+
+/// Build the section telling a worker about the other tasks of its rule
+fn build_chunk_section(chunk: &Chunk) -> String {
+    let mut section = format!(
+        "## Task {} of {}\n\nThis rule's files are split across {} tasks reviewed separately. \
+         The other tasks review their own focus files; do not repeat repository-wide checks for \
+         them, and only report violations in your focus files.\n\n{}\n",
+        chunk.index + 1,
+        chunk.count,
+        chunk.count,
+        chunk.summary
+    );
+    if !chunk.reported.is_empty() {
+        section.push_str("Already reported by earlier tasks (do not report again):\n\n");
+        for v in &chunk.reported {
+            section.push_str(&format!(
+                "- {}:{}-{}: {}\n",
+                v.file, v.start_line, v.end_line, v.detail
+            ));
+        }
+        section.push('\n');
+    }
+    section
 }
 
 /// Build diffs section for focused files
 fn build_diffs_section(files: &[String], diffs: &HashMap<String, String>) -> String {
     let mut diffs_content = String::new();
     for file in files {
-        if crate::util::should_include_diff(file) {
-            if let Some(diff) = diffs.get(file) {
-                diffs_content.push_str(diff);
-                diffs_content.push('\n');
-            }
+        if crate::util::should_include_diff(file)
+            && let Some(diff) = diffs.get(file)
+        {
+            diffs_content.push_str(diff);
+            diffs_content.push('\n');
         }
     }
     if diffs_content.is_empty() {
@@ -204,8 +113,33 @@ fn build_diffs_section(files: &[String], diffs: &HashMap<String, String>) -> Str
     }
 }
 
+/// Build directory context section for rules scoped with `dir:` patterns
+fn build_directories_section(directories: &[(String, Vec<String>)]) -> String {
+    let mut section = String::new();
+    for (dir, files) in directories {
+        section.push_str(&format!("## Directory Context: {}\n\n", dir));
+        section.push_str(
+            "This rule applies to the whole directory; consider all of its files, not only the changed ones.\n\n",
+        );
+        for file in files.iter().take(MAX_DIRECTORY_FILES) {
+            section.push_str(&format!("- {}\n", file));
+        }
+        if files.len() > MAX_DIRECTORY_FILES {
+            section.push_str(&format!(
+                "- ... and {} more\n",
+                files.len() - MAX_DIRECTORY_FILES
+            ));
+        }
+        section.push('\n');
+    }
+    section
+}
+
 /// Build user message: simplified if focus files match all changed files
+#[allow(clippy::too_many_arguments)]
 fn build_user_message(
+    template: &str,
+    overview: &str,
     files: &[String],
     all_changed_files: &[String],
     commit_messages: &str,
@@ -213,80 +147,104 @@ fn build_user_message(
     rule_instruction: &str,
     diffs: &HashMap<String, String>,
     resources_content: &str,
+    directories: &[(String, Vec<String>)],
+    chunk: Option<&Chunk>,
 ) -> String {
-    let mut body = String::new();
+    // Repository overview section, identical across workers
+    let overview_section = if overview.is_empty() {
+        String::new()
+    } else {
+        format!("## Repository Overview\n\n{}\n", overview)
+    };
 
     // Commit messages section
+    let mut commits_section = String::new();
     if !is_root_base && !commit_messages.is_empty() {
-        body.push_str("## Commit Messages\n\n");
+        commits_section.push_str("## Commit Messages\n\n");
         let fence = get_fence_backticks(commit_messages);
-        body.push_str(&format!("{}\n{}\n{}\n\n", fence, commit_messages, fence));
+        commits_section.push_str(&format!("{}\n{}\n{}\n\n", fence, commit_messages, fence));
     }
 
     // Files section
+    let mut files_section = String::new();
     if !is_root_base {
         if files == all_changed_files {
-            body.push_str("## Changed Files\n\n");
+            files_section.push_str("## Changed Files\n\n");
             for file in files {
-                body.push_str(&format!("- {}\n", file));
+                files_section.push_str(&format!("- {}\n", file));
             }
-            body.push('\n');
+            files_section.push('\n');
         } else {
-            body.push_str("## All Changed Files\n\n");
+            files_section.push_str("## All Changed Files\n\n");
             for file in all_changed_files {
-                body.push_str(&format!("- {}\n", file));
+                files_section.push_str(&format!("- {}\n", file));
             }
-            body.push('\n');
-            body.push_str("## Focus Files\n\n");
+            files_section.push('\n');
+            files_section.push_str("## Focus Files\n\n");
             for file in files {
-                body.push_str(&format!("- {}\n", file));
+                files_section.push_str(&format!("- {}\n", file));
             }
-            body.push('\n');
-            body.push_str("Note: For most cases, only read the focused files.\n\n");
+            files_section.push('\n');
+            files_section.push_str("Note: For most cases, only read the focused files.\n\n");
         }
     } else if files != all_changed_files {
-        body.push_str("## Focus Files\n\n");
+        files_section.push_str("## Focus Files\n\n");
         for file in files {
-            body.push_str(&format!("- {}\n", file));
+            files_section.push_str(&format!("- {}\n", file));
         }
-        body.push('\n');
-        body.push_str("Note: For most cases, only read the focused files.\n\n");
+        files_section.push('\n');
+        files_section.push_str("Note: For most cases, only read the focused files.\n\n");
+    }
+    files_section.push_str(&build_directories_section(directories));
+    if let Some(chunk) = chunk {
+        files_section.push_str(&build_chunk_section(chunk));
     }
 
     // Rule section
-    body.push_str("## Rule\n\n");
     let fence = get_fence_backticks(rule_instruction);
-    body.push_str(&format!(
-        "{}md\n{}\n{}\n\n",
+    let rule_section = format!(
+        "## Rule\n\n{}md\n{}\n{}\n\n",
         fence,
         rule_instruction.trim(),
         fence
-    ));
+    );
 
     // Diffs section
-    body.push_str("## Diffs\n\n");
-    body.push_str(&build_diffs_section(files, diffs));
+    let diffs_section = format!("## Diffs\n\n{}", build_diffs_section(files, diffs));
 
     // Resources section
-    if !resources_content.is_empty() {
-        body.push_str("## Resources\n\n");
-        body.push_str(resources_content);
-    }
+    let resources_section = if resources_content.is_empty() {
+        String::new()
+    } else {
+        format!("## Resources\n\n{}", resources_content)
+    };
 
-    body
+    util::render_template(
+        template,
+        &[
+            ("overview", &overview_section),
+            ("commit_messages", &commits_section),
+            ("files", &files_section),
+            ("rule", &rule_section),
+            ("diffs", &diffs_section),
+            ("resources", &resources_section),
+        ],
+    )
 }
 
 /// Run agent loop with cancellation support
 /// Uses tokio::select to race between agent chat completion and shutdown signal
 /// Polls shutdown flag every 100ms to allow graceful cancellation mid-execution
+/// Cancels `cancel` on shutdown or timeout, stopping the tools in flight
 async fn run_agent_with_cancellation(
     mut agent: Agent,
     user_message: String,
     shutdown: Arc<Mutex<bool>>,
+    cancel: &CancellationToken,
     timeout_secs: u64,
     worker_id: &str,
     rule_name: &str,
-) -> Result<(bool, Agent), Box<dyn std::error::Error>> {
+) -> Result<(Option<String>, Agent), Box<dyn std::error::Error>> {
     debug!(
         "[Worker {}] Starting agent loop for rule '{}'",
         worker_id, rule_name
@@ -306,22 +264,28 @@ async fn run_agent_with_cancellation(
     };
     let timeout_future = tokio::time::sleep(tokio::time::Duration::from_secs(timeout_secs));
 
-    let cancelled = tokio::select! {
+    let incomplete = tokio::select! {
         result = chat_future => {
-            result?;
-            false
+            // Keep provider failures typed, so callers can tell them from other failures
+            result.map_err(|e| match e.downcast::<tiny_loop::Error>() {
+                Ok(e) => Box::new(e) as Box<dyn std::error::Error>,
+                Err(e) => e.into(),
+            })?;
+            None
         }
         _ = shutdown_check => {
             warn!("[Worker {}] Cancelled due to shutdown", worker_id);
-            true
+            cancel.cancel();
+            Some("cancelled by shutdown".to_string())
         }
         _ = timeout_future => {
             warn!("[Worker {}] Timeout after {}s", worker_id, timeout_secs);
-            true
+            cancel.cancel();
+            Some(format!("timed out after {}s", timeout_secs))
         }
     };
 
-    Ok((cancelled, agent))
+    Ok((incomplete, agent))
 }
 
 async fn run_agent_loop(agent: &mut Agent, user_message: String) -> anyhow::Result<()> {
@@ -337,49 +301,45 @@ async fn run_agent_loop(agent: &mut Agent, user_message: String) -> anyhow::Resu
     let mut seen_report_locations = std::collections::HashSet::new();
 
     loop {
-        if let Some(_) = agent.step().await? {
+        if agent.step().await?.is_some() {
             return Ok(());
         }
 
         // Check for empty report
         for timed_msg in agent.history.get_all() {
-            if let Message::Assistant(am) = &timed_msg.message {
-                if let Some(tool_calls) = &am.tool_calls {
-                    for tc in tool_calls {
-                        if tc.function.name == crate::tool::report::ReportArgs::TOOL_NAME {
-                            if let Ok(args) = serde_json::from_str::<crate::tool::report::ReportArgs>(
-                                &tc.function.arguments,
-                            ) {
-                                if args.violations.is_empty() {
-                                    debug!("Early stop due to empty violation");
-                                    return Ok(());
-                                }
-                            }
-                        }
+            if let Message::Assistant(am) = &timed_msg.message
+                && let Some(tool_calls) = &am.tool_calls
+            {
+                for tc in tool_calls {
+                    if tc.function.name == ReportArgs::TOOL_NAME
+                        && let parsed = parse_report(&tc.function.arguments)
+                        && parsed.violations.is_empty()
+                        && parsed.errors.is_empty()
+                    {
+                        debug!("Early stop due to empty violation");
+                        return Ok(());
                     }
                 }
             }
         }
 
         // Check for duplicated report locations
-        if let Some(timed_msg) = agent.history.get_all().last() {
-            if let Message::Assistant(am) = &timed_msg.message {
-                if let Some(tool_calls) = &am.tool_calls {
-                    for tc in tool_calls {
-                        if tc.function.name == crate::tool::report::ReportArgs::TOOL_NAME {
-                            if let Ok(args) = serde_json::from_str::<crate::tool::report::ReportArgs>(
-                                &tc.function.arguments,
-                            ) {
-                                for v in &args.violations {
-                                    let key = format!("{}:{}:{}", v.file, v.start_line, v.end_line);
-                                    if !seen_report_locations.insert(key) {
-                                        warn!(
-                                            "Duplicate report for same location detected, might be dead loop"
-                                        );
-                                        return Ok(());
-                                    }
-                                }
-                            }
+        if let Some(timed_msg) = agent.history.get_all().last()
+            && let Message::Assistant(am) = &timed_msg.message
+            && let Some(tool_calls) = &am.tool_calls
+        {
+            for tc in tool_calls {
+                // Reports deferred until after `think` are expected to be repeated
+                if tc.function.name == ReportArgs::TOOL_NAME
+                    && !tool_result_is(agent, &tc.id, THINK_FIRST)
+                {
+                    for v in &parse_report(&tc.function.arguments).violations {
+                        let key = format!("{}:{}:{}", v.file, v.start_line, v.end_line);
+                        if !seen_report_locations.insert(key) {
+                            warn!(
+                                "Duplicate report for same location detected, might be dead loop"
+                            );
+                            return Ok(());
                         }
                     }
                 }
@@ -387,24 +347,30 @@ async fn run_agent_loop(agent: &mut Agent, user_message: String) -> anyhow::Resu
         }
 
         // Check for duplicated tool calls
-        if let Some(timed_msg) = agent.history.get_all().last() {
-            if let Message::Assistant(am) = &timed_msg.message {
-                if let Some(tool_calls) = &am.tool_calls {
-                    for tc in tool_calls {
-                        let key = format!("{}:{}", tc.function.name, tc.function.arguments);
-                        if !seen_tool_calls.insert(key) {
-                            debug!("Early stop with duplicated tool call, might be dead loop");
-                            return Err(anyhow::anyhow!(
-                                "Duplicated tool call detected, might be dead loop"
-                            ));
-                        }
-                    }
+        if let Some(timed_msg) = agent.history.get_all().last()
+            && let Message::Assistant(am) = &timed_msg.message
+            && let Some(tool_calls) = &am.tool_calls
+        {
+            for tc in tool_calls {
+                let key = format!("{}:{}", tc.function.name, tc.function.arguments);
+                if !seen_tool_calls.insert(key) {
+                    debug!("Early stop with duplicated tool call, might be dead loop");
+                    return Err(anyhow::anyhow!(
+                        "Duplicated tool call detected, might be dead loop"
+                    ));
                 }
             }
         }
     }
 }
 
+/// Whether the result of the tool call with `id` is exactly `content`
+fn tool_result_is(agent: &Agent, id: &str, content: &str) -> bool {
+    agent.history.get_all().iter().any(|timed_msg| {
+        matches!(&timed_msg.message, Message::Tool(tm) if tm.tool_call_id == id && tm.content == content)
+    })
+}
+
 /// Collect trace data if enabled
 fn collect_trace_data(
     trace_enabled: bool,
@@ -421,6 +387,31 @@ fn collect_trace_data(
     }
 }
 
+/// Assistant message recording a reply cut off mid-stream. Tool calls are listed by name
+/// only, as their arguments may be incomplete and they have no results.
+fn partial_message(partial: &Partial) -> Option<TimedMessage> {
+    if partial.is_empty() {
+        return None;
+    }
+    let mut content = partial.content.clone();
+    if !partial.tool_calls.is_empty() {
+        content.push_str(&format!(
+            "\n\n[Interrupted while calling: {}]",
+            partial.tool_calls.join(", ")
+        ));
+    } else {
+        content.push_str("\n\n[Interrupted]");
+    }
+    Some(TimedMessage {
+        message: Message::Assistant(AssistantMessage {
+            content: content.trim_start().to_string(),
+            tool_calls: None,
+        }),
+        timestamp: std::time::SystemTime::now(),
+        elapsed: std::time::Duration::ZERO,
+    })
+}
+
 /// Log worker completion status
 fn log_completion(cancelled: bool, worker_id: &str, rule_name: &str, elapsed: f64) {
     if cancelled {
@@ -436,29 +427,113 @@ fn log_completion(cancelled: bool, worker_id: &str, rule_name: &str, elapsed: f6
     }
 }
 
+/// Model and review settings shared by the workers of a run
+#[derive(Clone)]
+pub struct WorkerSettings {
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub headers: HashMap<String, String>,
+    pub body: Value,
+    pub tool_protocol: ToolProtocol,
+    pub provider: ProviderKind,
+    pub stream: bool,
+    pub retry: RetryConfig,
+    pub allowed_shell_commands: Vec<String>,
+    pub shell: Option<Shell>,
+    pub tools: ToolsConfig,
+    pub require_think: bool,
+    pub prompts: PromptsConfig,
+    pub language: Option<String>,
+    pub max_history_tokens: usize,
+    pub timeout_secs: u64,
+}
+
+impl WorkerSettings {
+    pub fn from_config(config: &Config, api_key: &str) -> Self {
+        Self {
+            base_url: config.llm.endpoint(),
+            api_key: api_key.to_string(),
+            model: config.llm.model.clone(),
+            headers: config.llm.headers.clone(),
+            body: config.llm.body.clone(),
+            tool_protocol: config.llm.tool_protocol,
+            provider: config.llm.provider,
+            stream: config.llm.stream,
+            retry: config.llm.retry.clone(),
+            allowed_shell_commands: config.review.allowed_shell_commands.clone(),
+            shell: config.review.shell,
+            tools: config.review.tools.clone(),
+            require_think: config.review.require_think,
+            prompts: config.review.prompts.clone(),
+            language: config.review.language.clone(),
+            max_history_tokens: config.review.max_history_tokens,
+            timeout_secs: config.review.timeout,
+        }
+    }
+
+    /// These settings with another model, e.g. for a consensus agent or a bench run
+    pub fn with_model(&self, model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// These settings with features gated on the model's capabilities
+    pub fn with_capabilities(mut self, capabilities: &Capabilities) -> Self {
+        crate::config::gate_features(
+            capabilities,
+            &mut self.tool_protocol,
+            &mut self.body,
+            &mut self.max_history_tokens,
+        );
+        self
+    }
+}
+
+/// Rule and files a worker reviews, with the context of the changes
+pub struct Task<'a> {
+    pub worker_id: String,
+    pub rule: &'a RuleBody,
+    pub files: Vec<String>,
+    pub chunk: Option<Chunk>,
+    pub all_changed_files: Vec<String>,
+    pub commit_messages: String,
+    pub overview: Arc<str>,
+    pub diffs: HashMap<String, String>,
+    pub is_root_base: bool,
+    pub snapshot: Snapshot,
+    /// Resources already loaded for the task, which the worker loads otherwise
+    pub resources: Option<LoadedResources>,
+}
+
 /// Run a review worker for a specific rule and set of files
 ///
 /// Returns a WorkerResult containing violations found and optionally the agent conversation trace.
 /// The worker can be cancelled via the shutdown flag, in which case it returns partial results.
+/// Tokens are counted into `usage`, so callers can account for them when the worker fails.
 pub async fn worker(
-    worker_id: String,
-    rule: &RuleBody,
-    files: Vec<String>,
-    all_changed_files: Vec<String>,
-    commit_messages: String,
-    base_url: &str,
-    api_key: &str,
-    model: &str,
-    headers: HashMap<String, String>,
-    body: Value,
-    diffs: HashMap<String, String>,
+    task: Task<'_>,
+    settings: &WorkerSettings,
+    resources: ResourceLoader,
     trace_enabled: bool,
     shutdown: Arc<Mutex<bool>>,
-    is_root_base: bool,
-    global_resources: Vec<String>,
-    allowed_shell_commands: Vec<String>,
-    timeout_secs: u64,
+    usage: Arc<std::sync::Mutex<TokenUsage>>,
 ) -> Result<WorkerResult, Box<dyn std::error::Error>> {
+    let Task {
+        worker_id,
+        rule,
+        files,
+        chunk,
+        all_changed_files,
+        commit_messages,
+        overview,
+        diffs,
+        is_root_base,
+        snapshot,
+        resources: preloaded,
+    } = task;
     let start = std::time::Instant::now();
     info!(
         "[Worker {}] Reviewing {} files for rule '{}': {:?}",
@@ -468,44 +543,174 @@ pub async fn worker(
         files
     );
 
+    // Generated file checks compare changed paths, without a conversation
+    if let Some(generated) = &rule.generated {
+        let violations = crate::review::generated::check(
+            generated,
+            &rule.name,
+            &files,
+            &all_changed_files,
+            &diffs,
+            snapshot,
+        )
+        .await;
+        return Ok(WorkerResult {
+            worker_id,
+            rule: rule.clone(),
+            files,
+            blocking: rule.blocks(),
+            violations,
+            messages: trace_enabled.then(Vec::new),
+            tools: None,
+            elapsed_secs: start.elapsed().as_secs_f64(),
+            resource_bytes: 0,
+            compactions: vec![],
+            incomplete: None,
+            usage: TokenUsage::default(),
+        });
+    }
+
     // Setup LLM provider
     debug!(
         "[Worker {}] Creating OpenAI provider with model: {}",
-        worker_id, model
+        worker_id, settings.model
+    );
+    let tokenizer = Tokenizer::for_model(&settings.model);
+    let progress = StreamProgress::new(format!("[Worker {}]", worker_id));
+    let llm = if settings.stream && settings.provider != ProviderKind::Anthropic {
+        crate::llm::create_streaming_provider(
+            &settings.api_key,
+            &settings.base_url,
+            &settings.model,
+            &settings.headers,
+            &settings.body,
+            settings.tool_protocol,
+            settings.provider,
+            progress.clone(),
+        )?
+    } else {
+        if settings.stream {
+            debug!(
+                "[Worker {}] Streaming is only supported for OpenAI-compatible and Azure providers",
+                worker_id
+            );
+        }
+        crate::llm::create_provider(
+            &settings.api_key,
+            &settings.base_url,
+            &settings.model,
+            &settings.headers,
+            &settings.body,
+            settings.tool_protocol,
+            settings.provider,
+        )?
+    }
+    .with_retry(settings.retry.clone());
+    let reported = llm.reported_usage();
+    let llm = MeteredProvider::new(llm, tokenizer)
+        .with_reported(reported)
+        .with_usage(usage.clone());
+
+    // Load resources, unless the orchestrator already did
+    let loaded = match preloaded {
+        Some(loaded) => loaded,
+        None => {
+            let rule_id = rule.id();
+            let task = TaskVariables {
+                rule_name: &rule.name,
+                rule_id: &rule_id,
+                files: &files,
+            };
+            resources.load(&rule.resources, &task).await?
+        }
+    };
+    let resources_content = loaded.content;
+    debug!(
+        "[Worker {}] Loaded {} bytes of resources, {} skills",
+        worker_id,
+        resources_content.len(),
+        loaded.skills.len()
     );
-    let llm = crate::llm::create_provider(api_key, base_url, model, &headers, &body)?;
 
     // Setup stateful tools for reporting violations and getting diffs
-    let report = Report::new();
+    let tools = settings.tools.with(&rule.tools);
+    let think = Think::new();
+    let report = Report::new().focus_files(&files);
+    let report = if settings.require_think && tools.think() {
+        report.require_think(think.clone())
+    } else {
+        report
+    };
     let diff = Diff::new(diffs.clone());
 
     // Create agent with system prompt and bind tools
-    let agent = Agent::new(llm)
-        .system(
-            r"You are a code reviewer. Your task is to review code changes against a specific rule.
-Focus only on the files provided and only check for violations of the given rule.
-You can read related files if needed, but only report issues related to the provided files and rule.
-
-Workflow:
-1. Review the provided diffs to understand what changed
-2. Read other related diffs or files if needed for context
-3. Use the 'think' tool to reason about whether the changes violate the rule
-4. Use the 'report' tool to report all violations found, then exit without summary",
-        )
-        .bind(diff.clone(), Diff::diff)
-        .bind(report.clone(), Report::report);
+    let system_prompt = match &settings.language {
+        Some(language) => format!(
+            "{}\n\n{}",
+            settings.prompts.system,
+            crate::review::locale::agent_instruction(language)
+        ),
+        None => settings.prompts.system.clone(),
+    };
+    let history = CompactingHistory::new(settings.max_history_tokens, tokenizer);
+    let compactions = history.compactions();
+    let shell = rule.shell.or(settings.shell).unwrap_or_default();
+    debug!("[Worker {}] Tools: {:?}", worker_id, tools);
+    let mut agent = Agent::new(llm)
+        .history(history)
+        .system(system_prompt)
+        .external(vec![report_tool_def()], {
+            let report = report.clone();
+            move |_, args: String| {
+                let report = report.clone();
+                async move { report.report(&args).await }
+            }
+        });
+    if tools.diff() {
+        agent = agent.bind(diff.clone(), Diff::diff);
+    }
+    if tools.read() {
+        agent = agent.bind(Read::new(snapshot.clone()), Read::read);
+    }
 
-    let agent = crate::llm::register_common_tools(agent, &allowed_shell_commands);
+    // Cancelled when the worker stops, killing commands and aborting fetches in flight
+    let cancel = CancellationToken::new();
+    let mut agent = crate::llm::register_common_tools(
+        agent,
+        &settings.allowed_shell_commands,
+        shell,
+        snapshot.dir(),
+        think,
+        &tools,
+        &cancel,
+    );
+    if tools.rule_info() {
+        agent = agent.bind(RuleInfo::new(rule), RuleInfo::rule_info);
+    }
+    if tools.skill() && !loaded.skills.is_empty() {
+        agent = agent.bind(SkillRead::new(loaded.skills), SkillRead::skill_read);
+    }
+    if tools.resource() && !loaded.deferred.is_empty() {
+        agent = agent.bind(
+            ResourceRead::new(Arc::new(resources), loaded.deferred, &files),
+            ResourceRead::resource,
+        );
+    }
 
-    // Load resources
-    let mut all_resources = global_resources.clone();
-    all_resources.extend(rule.resources.clone());
-    all_resources.sort();
-    all_resources.dedup();
-    let resources_content = load_resources(&all_resources).await;
+    // List whole directories for `dir:` scopes
+    let directories: Vec<(String, Vec<String>)> =
+        crate::review::scope::scope_directories(rule, &files)
+            .into_keys()
+            .map(|dir| {
+                let listing = crate::util::git::list_files(&dir);
+                (dir, listing)
+            })
+            .collect();
 
     // Build user message
     let user_message = build_user_message(
+        &settings.prompts.user,
+        &overview,
         &files,
         &all_changed_files,
         &commit_messages,
@@ -513,6 +718,8 @@ Workflow:
         &rule.instruction,
         &diffs,
         &resources_content,
+        &directories,
+        chunk.as_ref(),
     );
     trace!(
         "[Worker {}] Adding user message with {} files",
@@ -522,34 +729,121 @@ Workflow:
     trace!("[Worker {}] User message: {}", worker_id, user_message);
 
     // Run agent loop to review code with cancellation support and timeout
-    let (cancelled, agent) = run_agent_with_cancellation(
+    let (incomplete, agent) = run_agent_with_cancellation(
         agent,
         user_message,
         shutdown,
-        timeout_secs,
+        &cancel,
+        settings.timeout_secs,
         &worker_id,
         &rule.name,
     )
     .await?;
 
-    // Collect trace data if enabled (even if cancelled)
-    let (messages, tools) = collect_trace_data(trace_enabled, &agent);
+    // Collect trace data if enabled (even if cancelled), with the reply cut off mid-stream
+    let (mut messages, tools) = collect_trace_data(trace_enabled, &agent);
+    if incomplete.is_some()
+        && let Some(messages) = &mut messages
+        && let Some(message) = partial_message(&progress.partial())
+    {
+        messages.push(message);
+    }
 
     // Extract violations from report tool's shared state
     let violations = report.violations.lock().await.clone();
+    let compactions = compactions.lock().unwrap().clone();
+    if !compactions.is_empty() {
+        debug!(
+            "[Worker {}] Compacted conversation {} times",
+            worker_id,
+            compactions.len()
+        );
+    }
 
     let elapsed = start.elapsed().as_secs_f64();
 
-    log_completion(cancelled, &worker_id, &rule.name, elapsed);
+    log_completion(incomplete.is_some(), &worker_id, &rule.name, elapsed);
 
     Ok(WorkerResult {
         worker_id,
         rule: rule.clone(),
         files,
-        blocking: rule.blocking,
+        blocking: rule.blocks(),
         violations,
         messages,
         tools,
         elapsed_secs: elapsed,
+        resource_bytes: resources_content.len(),
+        compactions,
+        incomplete,
+        usage: *usage.lock().unwrap(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_message() {
+        assert!(partial_message(&Partial::default()).is_none());
+        let message = partial_message(&Partial {
+            content: "Line 12 hardcodes".into(),
+            tool_calls: vec!["think".into()],
+        })
+        .unwrap();
+        let Message::Assistant(message) = message.message else {
+            panic!("expected an assistant message");
+        };
+        assert_eq!(
+            message.content,
+            "Line 12 hardcodes\n\n[Interrupted while calling: think]"
+        );
+        assert!(message.tool_calls.is_none());
+    }
+
+    #[test]
+    fn test_build_chunk_section() {
+        let chunk = Chunk {
+            index: 1,
+            count: 3,
+            summary: "Files of all tasks: 4, in src (4)\n".into(),
+            reported: vec![Violation {
+                file: "a.rs".into(),
+                detail: "Duplicated helper".into(),
+                start_line: 4,
+                end_line: 9,
+                rule_id: None,
+                owners: vec![],
+            }],
+        };
+        let section = build_chunk_section(&chunk);
+        assert!(section.starts_with("## Task 2 of 3\n\n"));
+        assert!(section.contains("focus files.\n\nFiles of all tasks: 4, in src (4)\n\n"));
+        assert!(section.ends_with("(do not report again):\n\n- a.rs:4-9: Duplicated helper\n\n"));
+    }
+
+    #[test]
+    fn test_default_user_message_layout() {
+        let files = vec!["a.rs".to_string()];
+        let message = build_user_message(
+            &PromptsConfig::default().user,
+            "",
+            &files,
+            &files,
+            "Fix bug",
+            false,
+            "No panics",
+            &HashMap::new(),
+            "",
+            &[],
+            None,
+        );
+        assert!(message.starts_with(
+            "## Commit Messages\n\n```\nFix bug\n```\n\n## Changed Files\n\n- a.rs\n\n## Rule\n\n"
+        ));
+        assert!(message.contains("## Diffs\n\n"));
+        assert!(!message.contains("## Task"));
+        assert!(!message.contains("## Resources"));
+    }
+}
@@ -0,0 +1,425 @@
+use super::render::{self, ViolationFile};
+use crate::cli::ReviewArgs;
+use crate::util::temp::TempDir;
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::{error, info, warn};
+
+/// Exit code of a review with blocking violations, which outranks incomplete reviews
+const EXIT_FAILURE: i32 = 1;
+
+/// Name results of a repository are namespaced by: its directory name, suffixed when
+/// several repositories share it
+fn namespace(repo: &str, used: &mut HashSet<String>) -> String {
+    let name = std::path::absolute(repo)
+        .ok()
+        .and_then(|path| path.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| repo.to_string());
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{}-{}", name, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Absolute path of a repository's own file, e.g. `results.json` -> `/work/results.api.json`.
+/// Paths are made absolute first, as each repository's review runs in its directory.
+fn suffixed(path: &str, namespace: &str) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.into());
+    let name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => format!(
+            "{}.{}.{}",
+            stem.to_string_lossy(),
+            namespace,
+            ext.to_string_lossy()
+        ),
+        _ => format!("{}.{}", path.to_string_lossy(), namespace),
+    };
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+/// Command line of one repository's review, built from the combined review's arguments.
+/// Files a review writes or reads per run (trace, badge, SARIF, plan, retry and file
+/// lists, and dry-run plans) get a path of their own per repository; results are written
+/// to `output`, and the bundle is written for the combined review only. The API key is
+/// passed in the environment rather than on the command line.
+fn child_args(
+    args: &ReviewArgs,
+    log_level: &str,
+    config: &str,
+    namespace: &str,
+    output: Option<&str>,
+) -> Vec<String> {
+    let mut child: Vec<String> = vec!["--log-level".into(), log_level.into(), "review".into()];
+    let mut option = |name: &str, value: &str| {
+        child.push(name.into());
+        child.push(value.into());
+    };
+    let own = |path: &String| suffixed(path, namespace);
+    if !args.base.is_empty() {
+        option("--base", &args.base);
+    }
+    if let Some(head) = &args.head {
+        option("--head", head);
+    }
+    for file in &args.files {
+        option("--file", file);
+    }
+    if let Some(files_from) = args.files_from.as_ref().map(own) {
+        option("--files-from", &files_from);
+    }
+    if !args.rules_tags.is_empty() {
+        option("--rules-tags", &args.rules_tags.join(","));
+    }
+    for rule in &args.quarantine {
+        option("--quarantine", rule);
+    }
+    for glob in &args.include {
+        option("--include", glob);
+    }
+    for glob in &args.exclude {
+        option("--exclude", glob);
+    }
+    if let Some(instruction) = &args.inline_rule {
+        option("--inline-rule", instruction);
+    }
+    for glob in &args.inline_scope {
+        option("--inline-scope", glob);
+    }
+    option("--config", config);
+    if let Some(profile) = &args.profile {
+        option("--profile", profile);
+    }
+    for value in &args.config_overrides {
+        option("--config-override", value);
+    }
+    if args.dry_run {
+        for path in &args.output {
+            option("--output", &own(path));
+        }
+    } else if let Some(output) = output {
+        option("--output", output);
+    }
+    if let Some(trace) = args.trace.as_ref().map(own) {
+        option("--trace", &trace);
+    }
+    if let Some(badge) = args.badge.as_ref().map(own) {
+        option("--badge", &badge);
+    }
+    option("--suppressions", &args.suppressions);
+    if let Some(sarif) = args.sarif.as_ref().map(own) {
+        option("--sarif", &sarif);
+    }
+    if let Some(group_by) = args.group_by.and_then(|g| g.to_possible_value()) {
+        option("--group-by", group_by.get_name());
+    }
+    if let Some(retry_from) = args.retry_from.as_ref().map(own) {
+        option("--retry-from", &retry_from);
+    }
+    if let Some(plan) = args.plan.as_ref().map(own) {
+        option("--plan", &plan);
+    }
+    for (set, flag) in [
+        (args.staged, "--staged"),
+        (args.auto, "--auto"),
+        (args.dry_run, "--dry-run"),
+        (args.annotations, "--annotations"),
+        (args.continue_queue, "--continue"),
+        (args.no_cache, "--no-cache"),
+    ] {
+        if set {
+            child.push(flag.into());
+        }
+    }
+    child
+}
+
+/// Reason a review cannot be split per repository, if any
+fn unsupported(args: &ReviewArgs) -> Option<&'static str> {
+    if args.pr.is_some() {
+        Some("--pr reviews one pull request and cannot be combined with several --repo paths")
+    } else if args.files_from.as_deref() == Some("-") {
+        Some("--files-from - cannot be shared by several --repo paths; pass a file per repository")
+    } else {
+        None
+    }
+}
+
+/// Add a repository's results to the combined results, prefixing its paths with `namespace`
+fn merge(combined: &mut ViolationFile, namespace: &str, results: ViolationFile) {
+    let prefixed = |file: &str| format!("{}/{}", namespace, file);
+    for (file, by_rule) in results.violations {
+        let target = combined.violations.entry(prefixed(&file)).or_default();
+        for (rule, violations) in by_rule {
+            target
+                .entry(rule)
+                .or_default()
+                .extend(violations.into_iter().map(|mut v| {
+                    v.file = prefixed(&v.file);
+                    v
+                }));
+        }
+    }
+    for (rule, tip) in results.tips {
+        combined.tips.entry(rule).or_insert(tip);
+    }
+    combined
+        .skipped
+        .extend(results.skipped.into_iter().map(|mut s| {
+            s.file = prefixed(&s.file);
+            s
+        }));
+    combined
+        .incomplete
+        .extend(results.incomplete.into_iter().map(|mut task| {
+            task.files = task.files.iter().map(|f| prefixed(f)).collect();
+            task
+        }));
+    for (rule, count) in results.truncated {
+        *combined.truncated.entry(rule).or_default() += count;
+    }
+    combined.quarantined.extend(results.quarantined);
+    combined.language = combined.language.take().or(results.language);
+}
+
+/// Review several repositories with the same arguments, one after another, and write a
+/// combined report with file paths prefixed by repository. Returns the exit code: blocking
+/// violations in any repository fail the run, then any other failure.
+pub async fn review_repos(repos: &[String], args: &ReviewArgs, log_level: &str) -> i32 {
+    if let Some(reason) = unsupported(args) {
+        error!("{}", reason);
+        return EXIT_FAILURE;
+    }
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            error!("Failed to locate the firekeeper executable: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    // A config present here is shared by all repositories, otherwise each reads its own
+    let config = if Path::new(&args.config).exists() {
+        std::path::absolute(&args.config)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| args.config.clone())
+    } else {
+        args.config.clone()
+    };
+
+    let mut combined = ViolationFile {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        violations: render::ViolationsByFile::new(),
+        tips: Default::default(),
+        language: None,
+        skipped: vec![],
+        incomplete: vec![],
+        truncated: Default::default(),
+        quarantined: Default::default(),
+        usage: Default::default(),
+    };
+    // Results of repositories without a JSON output to keep them next to
+    let results_dir = TempDir::new(&format!("firekeeper-repos-{}", std::process::id()));
+    if let Err(e) = std::fs::create_dir_all(results_dir.path()) {
+        error!("Failed to create {}: {}", results_dir.path().display(), e);
+        return EXIT_FAILURE;
+    }
+    let mut used = HashSet::new();
+    let mut codes = Vec::new();
+    for (i, repo) in repos.iter().enumerate() {
+        let namespace = namespace(repo, &mut used);
+        info!("Reviewing {} ({}/{})", repo, i + 1, repos.len());
+        // Results are kept next to a JSON output, e.g. for --retry-from
+        let kept = args
+            .output
+            .iter()
+            .find(|path| path.ends_with(".json"))
+            .map(|path| suffixed(path, &namespace));
+        let output = kept.clone().unwrap_or_else(|| {
+            results_dir
+                .path()
+                .join(format!("{}.json", i))
+                .to_string_lossy()
+                .to_string()
+        });
+        let mut command = tokio::process::Command::new(&exe);
+        command.args(["-C", repo]).args(child_args(
+            args,
+            log_level,
+            &config,
+            &namespace,
+            Some(&output),
+        ));
+        if let Some(api_key) = &args.api_key {
+            command.env("FIREKEEPER_LLM_API_KEY", api_key);
+        }
+        let code = match command.status().await {
+            Ok(status) => status.code().unwrap_or(EXIT_FAILURE),
+            Err(e) => {
+                error!("Failed to review {}: {}", repo, e);
+                EXIT_FAILURE
+            }
+        };
+        if code != 0 {
+            warn!("Review of {} exited with {}", repo, code);
+        }
+        codes.push(code);
+        if args.dry_run {
+            continue;
+        }
+        match ViolationFile::load(&output) {
+            Ok(results) => merge(&mut combined, &namespace, results),
+            Err(e) => error!("No results for {}: {}", repo, e),
+        }
+    }
+
+    if !args.dry_run {
+        let group_by_owner = args.group_by == Some(crate::cli::GroupBy::Owner);
+        for path in &args.output {
+            if let Err(e) = super::orchestrator::write_output(path, &combined, group_by_owner, None)
+            {
+                error!("{}", e);
+                codes.push(EXIT_FAILURE);
+            }
+        }
+        // Traces stay with each repository's run, so the combined bundle holds the results
+        if let Some(path) = &args.bundle
+            && let Err(e) = super::bundle::write(path, &combined, None, &[])
+        {
+            error!("{}", e);
+            codes.push(EXIT_FAILURE);
+        }
+        if args.output.is_empty() {
+            let strings = super::locale::strings(combined.language.as_deref());
+            let mut report = if group_by_owner {
+                render::format_by_owner(&combined.violations, &combined.tips, strings)
+            } else {
+                render::format_violations(&combined.violations, &combined.tips, strings)
+            };
+            report.extend(render::format_truncated(&combined.truncated, strings));
+            report.extend(render::format_quarantined(
+                &combined.quarantined,
+                &combined.violations,
+                strings,
+            ));
+            report.extend(render::format_skipped(&combined.skipped, strings));
+            println!("{}", report);
+        }
+    }
+    if codes.contains(&EXIT_FAILURE) {
+        EXIT_FAILURE
+    } else {
+        codes.into_iter().find(|&code| code != 0).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Violation;
+    use clap::Parser;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_child_args() {
+        let args = ReviewArgs::try_parse_from([
+            "review",
+            "--output",
+            "all.md",
+            "--bundle",
+            "all.fkbundle",
+            "--base",
+            "main",
+            "--trace",
+            "/tmp/trace.json",
+            "--rules-tags",
+            "fast,security",
+            "--api-key",
+            "secret",
+            "--no-cache",
+        ])
+        .unwrap();
+        assert_eq!(
+            child_args(
+                &args,
+                "debug",
+                "/work/firekeeper.toml",
+                "api",
+                Some("/tmp/1.json")
+            ),
+            [
+                "--log-level",
+                "debug",
+                "review",
+                "--base",
+                "main",
+                "--rules-tags",
+                "fast,security",
+                "--config",
+                "/work/firekeeper.toml",
+                "--output",
+                "/tmp/1.json",
+                "--trace",
+                "/tmp/trace.api.json",
+                "--suppressions",
+                &args.suppressions,
+                "--no-cache",
+            ]
+        );
+
+        let dry_run =
+            ReviewArgs::try_parse_from(["review", "--dry-run", "--output", "/tmp/plan.json"])
+                .unwrap();
+        let child = child_args(&dry_run, "info", "firekeeper.toml", "web", None);
+        assert!(child.ends_with(&["--dry-run".to_string()]));
+        assert!(child.contains(&"/tmp/plan.web.json".to_string()));
+        assert!(unsupported(&dry_run).is_none());
+    }
+
+    #[test]
+    fn test_merge_namespaces_paths() {
+        let mut used = HashSet::new();
+        assert_eq!(namespace("services/api", &mut used), "api");
+        assert_eq!(namespace("other/api/", &mut used), "api-2");
+
+        let violation = Violation {
+            file: "src/main.rs".into(),
+            detail: "Magic number".into(),
+            start_line: 1,
+            end_line: 1,
+            rule_id: None,
+            owners: vec![],
+        };
+        let results = |truncated: usize| ViolationFile {
+            version: "0".into(),
+            violations: BTreeMap::from([(
+                "src/main.rs".to_string(),
+                BTreeMap::from([("Rule".to_string(), vec![violation.clone()])]),
+            )]),
+            tips: BTreeMap::new(),
+            language: None,
+            skipped: vec![],
+            incomplete: vec![],
+            truncated: BTreeMap::from([("Rule".to_string(), truncated)]),
+            quarantined: Default::default(),
+            usage: Default::default(),
+        };
+        let mut combined = ViolationFile {
+            violations: BTreeMap::new(),
+            ..results(0)
+        };
+        merge(&mut combined, "api", results(2));
+        merge(&mut combined, "web", results(2));
+        assert_eq!(
+            combined.violations.keys().collect::<Vec<_>>(),
+            ["api/src/main.rs", "web/src/main.rs"]
+        );
+        assert_eq!(
+            combined.violations["web/src/main.rs"]["Rule"][0].file,
+            "web/src/main.rs"
+        );
+        assert_eq!(combined.truncated["Rule"], 4);
+    }
+}
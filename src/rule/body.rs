@@ -1,17 +1,37 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use toml_scaffold::TomlScaffold;
 
+use super::generated::GeneratedConfig;
+use super::resource::Resource;
+use super::tip::Tip;
+use super::tools::ToolsConfig;
+use crate::tool::sh::Shell;
+
 #[derive(Deserialize, Serialize, Debug, Clone, JsonSchema, TomlScaffold)]
 pub struct RuleBody {
+    /// Stable rule id for joining results across runs (optional, derived from the name if omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Human-readable rule name, invisible to LLM
     pub name: String,
     /// Human-readable description, invisible to LLM (optional)
     #[serde(default)]
     pub description: String,
-    /// Detailed instructions for the LLM on how to check this rule
+    /// Detailed instructions for the LLM on how to check this rule (optional for `generated` rules
+    /// or with `instruction_file`)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub instruction: String,
-    /// Glob patterns to match files this rule applies to (optional, defaults to ["**/*"])
+    /// Markdown file with the instructions, relative to the config file (optional, replaces
+    /// `instruction`). Frontmatter can add `tags` and set `severity` (`error` blocks, `warning`
+    /// does not).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruction_file: Option<String>,
+    /// Glob patterns to match files this rule applies to (optional, defaults to ["**/*"]).
+    /// Patterns prefixed with `dir:` (e.g. "dir:services/*/**") match directories instead:
+    /// changed files under a matched directory are reviewed together (up to max_files_per_task
+    /// per task), with the directory listing.
     #[serde(default = "default_scope")]
     pub scope: Vec<String>,
     /// Glob patterns to exclude from the matched scope (optional, defaults to [])
@@ -24,21 +44,96 @@ pub struct RuleBody {
     pub max_files_per_task: Option<usize>,
     /// Rule-specific resources to include in review context.
     #[serde(default)]
-    pub resources: Vec<String>,
-    /// Whether violations should block the pipeline (exit 1) (optional, defaults to true)
-    #[serde(default = "default_blocking")]
-    pub blocking: bool,
-    /// Tip for downstream processors (e.g. coding agents) to fix violations (optional)
+    pub resources: Vec<Resource>,
+    /// Whether violations should block the pipeline (exit 1) (optional, defaults to the
+    /// `severity` in the frontmatter of `instruction_file`, or true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocking: Option<bool>,
+    /// Tip for downstream processors (e.g. coding agents) to fix violations (optional),
+    /// either text or a table with `text`, a fix `command`, and a `docs_url`
+    #[serde(default)]
+    pub tip: Option<Tip>,
+    /// Tags for selecting a subset of rules with `--rules-tags` (optional, e.g. ["fast"])
     #[serde(default)]
-    pub tip: Option<String>,
+    pub tags: Vec<String>,
+    /// Built-in tools to disable for this rule, over the global `review.tools`
+    /// (optional, e.g. { fetch = false, sh = false, think = false })
+    #[serde(default, skip_serializing_if = "ToolsConfig::is_empty")]
+    pub tools: ToolsConfig,
+    /// Review this rule's tasks one at a time instead of in parallel (optional, defaults to false).
+    /// Later tasks see the violations earlier ones reported, avoiding duplicate reports from
+    /// repository-wide checks (e.g. duplication), at the cost of a longer run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sequential: bool,
+    /// Report at most this many violations, summarizing the rest as "and N more similar issues"
+    /// (optional, overrides global `review.max_violations`). Hidden violations still block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_violations: Option<usize>,
+    /// Shell running this rule's `sh` tool (optional, overrides global `review.shell`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<Shell>,
+    /// Experimental: review each task with this many independent agents and only report
+    /// violations a majority of them agree on (optional, e.g. 3). Multiplies the cost of the
+    /// rule for fewer false positives, e.g. on blocking security rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consensus: Option<usize>,
+    /// Models of the consensus agents, assigned in turn (optional, defaults to `llm.model`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub consensus_models: Vec<String>,
+    /// Run the rule without blocking and label its violations as quarantined (optional,
+    /// defaults to false), e.g. to let a new or flaky rule soak in CI before it gates merges.
+    /// Also set per run with `--quarantine`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quarantined: bool,
+    /// Also review vendored files (optional, defaults to false): files matching
+    /// `review.vendored` or marked `linguist-vendored` in `.gitattributes`, e.g. for a
+    /// license or security rule that must cover third-party code
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub vendored: bool,
+    /// Report hand edits to generated files in scope without running the agent (optional),
+    /// e.g. { manifests = ["proto/**/*.proto"] }: generated files may only change along with
+    /// their generator inputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated: Option<GeneratedConfig>,
 }
 
-pub fn default_scope() -> Vec<String> {
-    vec!["**/*".to_string()]
+/// Frontmatter of an `instruction_file`
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct InstructionFrontmatter {
+    tags: Vec<String>,
+    severity: Option<Severity>,
+}
+
+/// Severity set in instruction frontmatter: `error` blocks, `warning` does not
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
 }
 
-fn default_blocking() -> bool {
-    true
+/// Lowercase ASCII letters and digits, with other runs of characters collapsed to `-`.
+/// Names without ASCII letters or digits get a `rule-` id from their hash.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        format!("rule-{}", &crate::util::hash_parts(&[name])[..8])
+    } else {
+        slug.to_string()
+    }
+}
+
+pub fn default_scope() -> Vec<String> {
+    vec!["**/*".to_string()]
 }
 
 fn default_non_code_exclude() -> Vec<String> {
@@ -62,8 +157,137 @@ fn default_lock_and_ignore_exclude() -> Vec<String> {
 }
 
 impl RuleBody {
+    /// Stable id: the configured `id`, or a slug of the name (e.g. `no-magic-numbers`)
+    pub fn id(&self) -> String {
+        self.id.clone().unwrap_or_else(|| slugify(&self.name))
+    }
+
+    /// Whether the rule is configured as blocking, true unless set otherwise
+    pub fn is_blocking(&self) -> bool {
+        self.blocking.unwrap_or(true)
+    }
+
+    /// Whether violations block the pipeline: `blocking`, unless quarantined
+    pub fn blocks(&self) -> bool {
+        self.is_blocking() && !self.quarantined
+    }
+
+    /// Read `instruction_file`, relative to `dir`, into the instruction, adding the tags and
+    /// applying the severity of its frontmatter. Rules without an instruction are rejected
+    /// unless they are `generated` rules.
+    pub fn load_instruction_file(&mut self, dir: &Path) -> Result<(), String> {
+        self.load_instruction_file_with(dir, |path| {
+            std::fs::read_to_string(path).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Like [`Self::load_instruction_file`], reading the file with `read`, e.g. from a
+    /// git revision
+    pub fn load_instruction_file_with(
+        &mut self,
+        dir: &Path,
+        read: impl Fn(&Path) -> Result<String, String>,
+    ) -> Result<(), String> {
+        let Some(file) = &self.instruction_file else {
+            if self.instruction.trim().is_empty() && self.generated.is_none() {
+                return Err(format!(
+                    "Rule '{}': instruction is empty, set `instruction`, `instruction_file` or `generated`",
+                    self.name
+                ));
+            }
+            return Ok(());
+        };
+        if !self.instruction.trim().is_empty() {
+            return Err(format!(
+                "Rule '{}': set either `instruction` or `instruction_file`, not both",
+                self.name
+            ));
+        }
+        let path = dir.join(file);
+        let content = read(&path).map_err(|e| {
+            format!(
+                "Rule '{}': failed to read instruction file {}: {}",
+                self.name,
+                path.display(),
+                e
+            )
+        })?;
+        let matter = gray_matter::Matter::<gray_matter::engine::YAML>::new();
+        let parsed = matter
+            .parse::<InstructionFrontmatter>(&content)
+            .map_err(|e| {
+                format!(
+                    "Rule '{}': invalid frontmatter in {}: {}",
+                    self.name,
+                    path.display(),
+                    e
+                )
+            })?;
+        let frontmatter = parsed.data.unwrap_or_default();
+        if parsed.content.trim().is_empty() {
+            return Err(format!(
+                "Rule '{}': instruction file {} is empty",
+                self.name,
+                path.display()
+            ));
+        }
+        let severity_blocking = frontmatter
+            .severity
+            .map(|severity| matches!(severity, Severity::Error));
+        if let (Some(configured), Some(blocking)) = (self.blocking, severity_blocking)
+            && configured != blocking
+        {
+            return Err(format!(
+                "Rule '{}': `blocking = {}` conflicts with the severity in {}",
+                self.name,
+                configured,
+                path.display()
+            ));
+        }
+        self.instruction = parsed.content.trim().to_string();
+        for tag in frontmatter.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        self.blocking = self.blocking.or(severity_blocking);
+        Ok(())
+    }
+
+    /// Ad-hoc rule from `--inline-rule`, non-blocking since it asks a one-off question
+    pub fn inline(instruction: &str, scope: &[String]) -> Self {
+        Self {
+            id: None,
+            name: "Inline Rule".into(),
+            description: String::new(),
+            instruction: instruction.into(),
+            scope: if scope.is_empty() {
+                default_scope()
+            } else {
+                scope.to_vec()
+            },
+            exclude: vec![],
+            max_files_per_task: None,
+            resources: vec![],
+            blocking: Some(false),
+            tip: None,
+            tags: vec![],
+            tools: ToolsConfig::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
+        }
+    }
+
     pub fn config_file_comments() -> Self {
         Self {
+            id: None,
             name: "Firekeeper Config Comments".into(),
             description: "Ensure firekeeper.toml has correct documentation comments".into(),
             instruction: r#"Check if firekeeper.toml has missing documentation comments.
@@ -80,15 +304,30 @@ Violation criteria - Report if:
             exclude: vec![],
             // Only 1 file needs to be reviewed
             max_files_per_task: Some(1),
-            blocking: true,
-            tip: Some(r#"Use `firekeeper config format [--config firekeeper.toml]` to re-render the config file
-"#.into()),
+            blocking: Some(true),
+            tip: Some(Tip::Detailed {
+                text: "Re-render the config file".into(),
+                command: Some("firekeeper config format".into()),
+                docs_url: None,
+            }),
             resources: vec!["file://firekeeper.toml".into()],
+            tags: vec!["fast".into()],
+            tools: ToolsConfig::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
         }
     }
 
     pub fn no_magic_numbers() -> Self {
         Self {
+            id: None,
             name: "No Magic Numbers".into(),
             description: "Prevent hardcoded numeric literals".into(),
             instruction: r#"Check for unexplained numeric literals in the provided diff.
@@ -119,18 +358,30 @@ Exemptions - Do NOT report:
             exclude: default_non_code_exclude(),
             // High value for simple rule that only checks changed files
             max_files_per_task: Some(10),
-            blocking: true,
+            blocking: Some(true),
             tip: Some(
                 r#"Define constants with descriptive names or add explanatory comments.
 "#
                 .into(),
             ),
             resources: vec![],
+            tags: vec!["fast".into()],
+            tools: ToolsConfig::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
         }
     }
 
     pub fn no_hardcoded_credentials() -> Self {
         Self {
+            id: None,
             name: "No Hardcoded Credentials".into(),
             description: "Prevent credential leaks".into(),
             instruction: r#"Check for hardcoded credentials in the provided diff.
@@ -160,7 +411,7 @@ Exemptions - Do NOT report:
             exclude: default_lock_and_ignore_exclude(),
             // High value for simple rule that only checks changed files
             max_files_per_task: Some(10),
-            blocking: true,
+            blocking: Some(true),
             tip: Some(
                 r#"Use environment variables or configuration files for credentials.
 Replace real values with placeholders in examples.
@@ -168,11 +419,23 @@ Replace real values with placeholders in examples.
                 .into(),
             ),
             resources: vec![],
+            tags: vec!["fast".into()],
+            tools: ToolsConfig::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
         }
     }
 
     pub fn no_code_duplication() -> Self {
         Self {
+            id: None,
             name: "No Code Duplication".into(),
             description: "Prevent duplicate code across files".into(),
             instruction: r#"Check if modified code duplicates existing code in other files.
@@ -201,13 +464,125 @@ Exemptions - Do NOT report:
             exclude: default_non_code_exclude(),
             // Low value for complex rule that scans many files
             max_files_per_task: Some(3),
-            blocking: true,
+            blocking: Some(true),
             tip: Some(
                 r#"Extract common code into shared functions or modules.
 "#
                 .into(),
             ),
             resources: vec!["sh://git ls-files".into()],
+            tags: vec![],
+            tools: ToolsConfig::default(),
+            sequential: false,
+            max_violations: None,
+            shell: None,
+            consensus: None,
+            consensus_models: vec![],
+            quarantined: false,
+            generated: None,
+            instruction_file: None,
+            vendored: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_id() {
+        assert_eq!(slugify("No Magic Numbers!"), "no-magic-numbers");
+        assert_eq!(slugify("  API / SDK docs "), "api-sdk-docs");
+        assert!(slugify("命名規則").starts_with("rule-"));
+        let mut rule = RuleBody::no_magic_numbers();
+        assert_eq!(rule.id(), "no-magic-numbers");
+        rule.id = Some("NUM-1".into());
+        assert_eq!(rule.id(), "NUM-1");
+    }
+
+    #[test]
+    fn test_load_instruction_file() {
+        let temp =
+            crate::util::temp::TempDir::new(&format!("firekeeper-rules-{}", std::process::id()));
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("rules")).unwrap();
+        std::fs::write(
+            dir.join("rules/numbers.md"),
+            "---\ntags: [fast, style]\nseverity: warning\n---\n\nCheck for magic numbers.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("rules/bad.md"),
+            "---\nseverity: fatal\n---\nCheck.\n",
+        )
+        .unwrap();
+
+        let from_file = |file: &str| RuleBody {
+            instruction: String::new(),
+            instruction_file: Some(file.into()),
+            blocking: None,
+            ..RuleBody::no_magic_numbers()
+        };
+        let mut rule = from_file("rules/numbers.md");
+        rule.load_instruction_file(dir).unwrap();
+        assert_eq!(rule.instruction, "Check for magic numbers.");
+        assert_eq!(rule.tags, ["fast", "style"]);
+        assert_eq!(rule.blocking, Some(false));
+
+        // Explicit settings are not overridden by the file
+        let mut both = from_file("rules/numbers.md");
+        both.instruction = "Check.".into();
+        assert!(
+            both.load_instruction_file(dir)
+                .unwrap_err()
+                .contains("not both")
+        );
+        let mut blocking = from_file("rules/numbers.md");
+        blocking.blocking = Some(true);
+        assert!(
+            blocking
+                .load_instruction_file(dir)
+                .unwrap_err()
+                .contains("conflicts with the severity")
+        );
+        blocking.blocking = Some(false);
+        assert!(blocking.load_instruction_file(dir).is_ok());
+
+        let mut empty = RuleBody::inline(" ", &[]);
+        assert!(
+            empty
+                .load_instruction_file(dir)
+                .unwrap_err()
+                .contains("instruction is empty")
+        );
+        empty.generated = Some(GeneratedConfig::default());
+        assert!(empty.load_instruction_file(dir).is_ok());
+
+        assert!(
+            from_file("rules/bad.md")
+                .load_instruction_file(dir)
+                .unwrap_err()
+                .contains("invalid frontmatter")
+        );
+        assert!(
+            from_file("rules/missing.md")
+                .load_instruction_file(dir)
+                .unwrap_err()
+                .contains("failed to read instruction file")
+        );
+    }
+
+    #[test]
+    fn test_inline_rule() {
+        let rule = RuleBody::inline("Is every error logged?", &[]);
+        assert_eq!(rule.instruction, "Is every error logged?");
+        assert_eq!(rule.scope, default_scope());
+        assert!(!rule.is_blocking() && !rule.blocks());
+        assert_eq!(rule.id, None);
+        assert_eq!(rule.id(), "inline-rule");
+
+        let scope = vec!["src/**/*.rs".to_string()];
+        assert_eq!(RuleBody::inline("Check.", &scope).scope, scope);
+    }
+}
@@ -0,0 +1,60 @@
+use super::body::RuleBody;
+use super::tip::Tip;
+use serde::Serialize;
+
+/// Machine-readable list of configured rules, for joining against review results by `id`
+#[derive(Serialize, Debug)]
+pub struct RuleCatalog {
+    pub version: String,
+    pub rules: Vec<CatalogEntry>,
+}
+
+/// Rule metadata in the catalog
+#[derive(Serialize, Debug)]
+pub struct CatalogEntry {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub instruction: String,
+    pub scope: Vec<String>,
+    pub exclude: Vec<String>,
+    pub blocking: bool,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tip: Option<Tip>,
+}
+
+impl RuleCatalog {
+    pub fn new(rules: &[RuleBody]) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            rules: rules
+                .iter()
+                .map(|rule| CatalogEntry {
+                    id: rule.id(),
+                    name: rule.name.clone(),
+                    description: rule.description.clone(),
+                    instruction: rule.instruction.clone(),
+                    scope: rule.scope.clone(),
+                    exclude: rule.exclude.clone(),
+                    blocking: rule.is_blocking(),
+                    tags: rule.tags.clone(),
+                    tip: rule.tip.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let catalog = RuleCatalog::new(&[RuleBody::no_magic_numbers()]);
+        let json = serde_json::to_value(&catalog).unwrap();
+        assert_eq!(json["rules"][0]["id"], "no-magic-numbers");
+        assert_eq!(json["rules"][0]["name"], "No Magic Numbers");
+    }
+}
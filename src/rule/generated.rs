@@ -0,0 +1,24 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml_scaffold::TomlScaffold;
+
+/// Flag hand edits to generated files instead of reviewing with the agent.
+///
+/// Changed files in the rule's scope that look generated (under a `generated` directory, a
+/// name like `schema.generated.ts`, a known codegen suffix like `.pb.go`, or a header such
+/// as `@generated` or `DO NOT EDIT`) are reported when none of the generator inputs changed.
+///
+/// ```toml
+/// generated = { manifests = ["proto/**/*.proto", "buf.gen.yaml"] }
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+pub struct GeneratedConfig {
+    /// Glob patterns of generator inputs, e.g. schemas and codegen configs (optional).
+    /// Generated files may change when any of them changed; without manifests every change
+    /// to a generated file is reported.
+    #[serde(default)]
+    pub manifests: Vec<String>,
+    /// Glob patterns of generated files the heuristics miss (optional)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
@@ -1 +1,6 @@
 pub mod body;
+pub mod catalog;
+pub mod generated;
+pub mod resource;
+pub mod tip;
+pub mod tools;
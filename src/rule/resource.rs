@@ -0,0 +1,132 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml_scaffold::TomlScaffold;
+
+/// Resource to include in review context, either a URI string or a table with options.
+///
+/// ```toml
+/// resources = [
+///     "sh://git ls-files",
+///     { uri = "file://docs/api.md", required = true },
+///     { uri = "file://docs/style-guide.md", inline = false },
+/// ]
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum Resource {
+    /// Resource URI, skipped with a warning if it fails to load
+    Uri(String),
+    /// Resource URI with options
+    Detailed {
+        /// Resource URI
+        uri: String,
+        /// Fail the task instead of continuing without the resource if it fails to load
+        #[serde(default)]
+        required: bool,
+        /// Include the resource in the prompt (default: true). Otherwise it is listed for the
+        /// agent to read on demand with the `resource` tool, and load failures are reported
+        /// to the agent.
+        #[serde(default = "default_inline")]
+        inline: bool,
+    },
+}
+
+fn default_inline() -> bool {
+    true
+}
+
+impl TomlScaffold for Resource {}
+
+impl Resource {
+    pub fn uri(&self) -> &str {
+        match self {
+            Self::Uri(uri) | Self::Detailed { uri, .. } => uri,
+        }
+    }
+
+    pub fn required(&self) -> bool {
+        matches!(self, Self::Detailed { required: true, .. })
+    }
+
+    pub fn inline(&self) -> bool {
+        !matches!(self, Self::Detailed { inline: false, .. })
+    }
+}
+
+impl From<&str> for Resource {
+    fn from(uri: &str) -> Self {
+        Self::Uri(uri.to_string())
+    }
+}
+
+/// Merge resources with the same URI, keeping the first occurrence's position.
+/// A merged resource is required if any occurrence is, and inline if any occurrence is.
+pub fn merge_resources(resources: impl IntoIterator<Item = Resource>) -> Vec<Resource> {
+    let mut merged: Vec<Resource> = Vec::new();
+    for resource in resources {
+        match merged.iter_mut().find(|r| r.uri() == resource.uri()) {
+            Some(existing) => {
+                let required = existing.required() || resource.required();
+                let inline = existing.inline() || resource.inline();
+                let uri = resource.uri().to_string();
+                *existing = if required || !inline {
+                    Resource::Detailed {
+                        uri,
+                        required,
+                        inline,
+                    }
+                } else {
+                    Resource::Uri(uri)
+                };
+            }
+            None => merged.push(resource),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Resources {
+        resources: Vec<Resource>,
+    }
+
+    #[test]
+    fn test_parse_resources() {
+        let parsed: Resources = toml::from_str(
+            r#"resources = ["sh://git ls-files", { uri = "file://a.md", required = true }, { uri = "file://b.md", inline = false }]"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.resources[0], Resource::from("sh://git ls-files"));
+        assert_eq!(parsed.resources[1].uri(), "file://a.md");
+        assert!(parsed.resources[1].required());
+        assert!(parsed.resources[1].inline());
+        assert!(!parsed.resources[2].required());
+        assert!(!parsed.resources[2].inline());
+    }
+
+    #[test]
+    fn test_merge_resources() {
+        let required = Resource::Detailed {
+            uri: "file://a.md".into(),
+            required: true,
+            inline: true,
+        };
+        let deferred = Resource::Detailed {
+            uri: "sh://ls".into(),
+            required: false,
+            inline: false,
+        };
+        let merged = merge_resources([
+            Resource::from("file://a.md"),
+            deferred.clone(),
+            required.clone(),
+            Resource::from("sh://ls"),
+            deferred,
+        ]);
+        assert_eq!(merged, vec![required, Resource::from("sh://ls")]);
+    }
+}
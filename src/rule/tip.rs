@@ -0,0 +1,113 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml_scaffold::TomlScaffold;
+
+/// Tip for fixing violations, either text or a table with a fix command and documentation.
+///
+/// ```toml
+/// tip = "Define constants with descriptive names"
+/// tip = { text = "Re-render the config file", command = "firekeeper config format" }
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum Tip {
+    /// Tip text
+    Text(String),
+    /// Tip text with a fix command and documentation link
+    Detailed {
+        /// Tip text
+        #[serde(default)]
+        text: String,
+        /// Shell command fixing the violations, run from the repository root (optional),
+        /// e.g. for remediation bots
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        command: Option<String>,
+        /// Link to documentation about the rule or fix (optional)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        docs_url: Option<String>,
+    },
+}
+
+impl TomlScaffold for Tip {}
+
+impl Tip {
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Text(text) | Self::Detailed { text, .. } => text.trim(),
+        }
+    }
+
+    pub fn command(&self) -> Option<&str> {
+        match self {
+            Self::Detailed {
+                command: Some(command),
+                ..
+            } => Some(command.trim()).filter(|c| !c.is_empty()),
+            _ => None,
+        }
+    }
+
+    pub fn docs_url(&self) -> Option<&str> {
+        match self {
+            Self::Detailed {
+                docs_url: Some(url),
+                ..
+            } => Some(url.trim()).filter(|u| !u.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Whether the tip has nothing to show
+    pub fn is_empty(&self) -> bool {
+        self.text().is_empty() && self.command().is_none() && self.docs_url().is_none()
+    }
+
+    /// Plain text of the tip with its command and link on their own lines, for prompts
+    /// and tools without markdown
+    pub fn to_plain(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        if !self.text().is_empty() {
+            lines.push(self.text().to_string());
+        }
+        if let Some(command) = self.command() {
+            lines.push(format!("Fix command: {}", command));
+        }
+        if let Some(url) = self.docs_url() {
+            lines.push(format!("Docs: {}", url));
+        }
+        lines.join("\n")
+    }
+}
+
+impl From<&str> for Tip {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tip_forms() {
+        #[derive(Deserialize)]
+        struct Rule {
+            tip: Tip,
+        }
+        let text: Rule = toml::from_str(r#"tip = " Add a comment ""#).unwrap();
+        assert_eq!(text.tip.text(), "Add a comment");
+        assert_eq!(text.tip.command(), None);
+
+        let detailed: Rule = toml::from_str(
+            r#"tip = { text = "Re-render", command = "firekeeper config format", docs_url = "https://example.com" }"#,
+        )
+        .unwrap();
+        assert_eq!(detailed.tip.command(), Some("firekeeper config format"));
+        assert_eq!(
+            detailed.tip.to_plain(),
+            "Re-render\nFix command: firekeeper config format\nDocs: https://example.com"
+        );
+        assert!(Tip::from("  ").is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml_scaffold::TomlScaffold;
+
+/// Built-in tools available to review workers, all enabled unless set to `false`.
+///
+/// Rules with a narrow check (e.g. scanning diffs for credentials) can run with fewer
+/// tools, which is faster, cheaper, and exposes less to the model.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, JsonSchema, TomlScaffold)]
+#[serde(deny_unknown_fields)]
+pub struct ToolsConfig {
+    /// Fetch web pages (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch: Option<bool>,
+    /// Run allowed shell commands (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sh: Option<bool>,
+    /// Read files in the reviewed snapshot (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read: Option<bool>,
+    /// Show diffs of other changed files (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<bool>,
+    /// Show the rule's own settings and files in scope (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_info: Option<bool>,
+    /// Record reasoning between tool calls (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub think: Option<bool>,
+    /// Read skills listed in `resources` (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<bool>,
+    /// Load resources not inlined into the prompt (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<bool>,
+}
+
+impl ToolsConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These settings, overridden by the ones set in `overrides` (e.g. a rule's)
+    pub fn with(&self, overrides: &ToolsConfig) -> ToolsConfig {
+        ToolsConfig {
+            fetch: overrides.fetch.or(self.fetch),
+            sh: overrides.sh.or(self.sh),
+            read: overrides.read.or(self.read),
+            diff: overrides.diff.or(self.diff),
+            rule_info: overrides.rule_info.or(self.rule_info),
+            think: overrides.think.or(self.think),
+            skill: overrides.skill.or(self.skill),
+            resource: overrides.resource.or(self.resource),
+        }
+    }
+
+    pub fn fetch(&self) -> bool {
+        self.fetch.unwrap_or(true)
+    }
+
+    pub fn sh(&self) -> bool {
+        self.sh.unwrap_or(true)
+    }
+
+    pub fn read(&self) -> bool {
+        self.read.unwrap_or(true)
+    }
+
+    pub fn diff(&self) -> bool {
+        self.diff.unwrap_or(true)
+    }
+
+    pub fn rule_info(&self) -> bool {
+        self.rule_info.unwrap_or(true)
+    }
+
+    pub fn think(&self) -> bool {
+        self.think.unwrap_or(true)
+    }
+
+    pub fn skill(&self) -> bool {
+        self.skill.unwrap_or(true)
+    }
+
+    pub fn resource(&self) -> bool {
+        self.resource.unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with() {
+        let global = ToolsConfig {
+            fetch: Some(false),
+            sh: Some(false),
+            ..Default::default()
+        };
+        let rule = ToolsConfig {
+            sh: Some(true),
+            read: Some(false),
+            think: Some(false),
+            ..Default::default()
+        };
+        let tools = global.with(&rule);
+        assert!(!tools.fetch());
+        assert!(tools.sh());
+        assert!(!tools.read());
+        assert!(tools.diff());
+        assert!(tools.rule_info());
+        assert!(!tools.think());
+        assert!(tools.skill());
+        assert!(tools.resource());
+        assert!(
+            ToolsConfig::default()
+                .with(&ToolsConfig::default())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unknown_tool() {
+        assert!(toml::from_str::<ToolsConfig>("rule_info = false\nthink = false").is_ok());
+        let error = toml::from_str::<ToolsConfig>("fecth = false").unwrap_err();
+        assert!(error.to_string().contains("unknown field `fecth`"));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::review::history::HistoryEntry;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml_scaffold::TomlScaffold;
+use tracing::debug;
+
+/// Timeout for sending run metrics (seconds), short so telemetry never delays a review
+const TIMEOUT_SECS: u64 = 5;
+
+/// Anonymous usage metrics, disabled unless explicitly enabled.
+///
+/// Only aggregate counts are sent: no repository, file, rule, or violation contents.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, JsonSchema, TomlScaffold)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Send run metrics after each review (defaults to false)
+    pub enabled: bool,
+    /// URL receiving run metrics as a JSON POST (required when enabled)
+    pub endpoint: Option<String>,
+}
+
+/// Anonymized metadata of one review run
+#[derive(Serialize, Debug, PartialEq)]
+pub struct RunMetrics {
+    pub version: String,
+    pub os: String,
+    /// Model family, e.g. `claude` for `anthropic/claude-sonnet-4`
+    pub model_family: String,
+    pub rules: usize,
+    pub files: usize,
+    pub tasks: usize,
+    pub failed_tasks: usize,
+    pub elapsed_secs: f64,
+    pub violations: usize,
+    pub rules_with_violations: usize,
+    /// Estimated LLM cost in USD, if known
+    pub cost: Option<f64>,
+}
+
+impl RunMetrics {
+    /// Aggregate a run summary, dropping rule names and the base
+    pub fn from_history(entry: &HistoryEntry, model: &str) -> Self {
+        Self {
+            version: entry.version.clone(),
+            os: std::env::consts::OS.to_string(),
+            model_family: model_family(model),
+            rules: entry.violations.len(),
+            files: entry.files,
+            tasks: entry.tasks,
+            failed_tasks: entry.failed_tasks,
+            elapsed_secs: entry.elapsed_secs,
+            violations: entry.violations.values().sum(),
+            rules_with_violations: entry.violations.values().filter(|&&n| n > 0).count(),
+            cost: entry.cost,
+        }
+    }
+}
+
+/// Leading name of a model without provider prefix or version, e.g. `gpt` for `openai/gpt-4o`
+pub fn model_family(model: &str) -> String {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    let family: String = name
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    if family.is_empty() {
+        "unknown".to_string()
+    } else {
+        family.to_ascii_lowercase()
+    }
+}
+
+/// Send run metrics if telemetry is enabled, ignoring failures.
+/// `DO_NOT_TRACK` disables telemetry regardless of config (https://consoledonottrack.com).
+pub async fn send(config: &TelemetryConfig, metrics: &RunMetrics) {
+    if !config.enabled || std::env::var_os("DO_NOT_TRACK").is_some_and(|v| v != "0") {
+        return;
+    }
+    let Some(endpoint) = config.endpoint.as_deref().filter(|e| !e.is_empty()) else {
+        debug!("Telemetry enabled without an endpoint, skipping");
+        return;
+    };
+    let result = reqwest::Client::new()
+        .post(endpoint)
+        .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+        .json(metrics)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status());
+    match result {
+        Ok(_) => debug!("Sent run metrics to {}", endpoint),
+        Err(e) => debug!("Failed to send run metrics: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_model_family() {
+        assert_eq!(model_family("anthropic/claude-sonnet-4"), "claude");
+        assert_eq!(model_family("gpt-4o-mini"), "gpt");
+        assert_eq!(model_family("Qwen3-Coder"), "qwen");
+        assert_eq!(model_family("4o"), "unknown");
+    }
+
+    #[test]
+    fn test_metrics_from_history() {
+        let entry = HistoryEntry {
+            version: "0.5.0".into(),
+            timestamp: chrono::Utc::now(),
+            base: "main".into(),
+            files: 4,
+            tasks: 3,
+            failed_tasks: 0,
+            elapsed_secs: 12.5,
+            violations: BTreeMap::from([("Secret rule".into(), 2), ("Other".into(), 0)]),
+            cost: None,
+            inputs: BTreeMap::new(),
+            findings: BTreeMap::new(),
+            cached: Default::default(),
+        };
+        let metrics = RunMetrics::from_history(&entry, "openai/gpt-4o");
+        assert_eq!(metrics.rules, 2);
+        assert_eq!(metrics.violations, 2);
+        assert_eq!(metrics.rules_with_violations, 1);
+        assert_eq!(metrics.model_family, "gpt");
+        let json = serde_json::to_string(&metrics).unwrap();
+        assert!(!json.contains("Secret rule") && !json.contains("main"));
+    }
+}
@@ -0,0 +1,126 @@
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::tokenizer::{self, Tokenizer as Encoding};
+
+/// Rough bytes per token of model families without a public tokenizer, by model name
+/// prefix; other families fall back to [`DEFAULT_BYTES_PER_TOKEN`]
+const FAMILY_BYTES_PER_TOKEN: &[(&str, f64)] = &[
+    ("claude", 3.5),
+    ("gemini", 4.0),
+    ("llama", 3.8),
+    ("mistral", 3.6),
+    ("codestral", 3.6),
+    ("qwen", 3.8),
+    ("deepseek", 3.8),
+];
+
+/// Bytes per token of unknown models
+const DEFAULT_BYTES_PER_TOKEN: f64 = 4.0;
+
+/// Token counter for a model: exact for OpenAI encodings, estimated for other families
+#[derive(Clone, Copy)]
+pub enum Tokenizer {
+    /// BPE encoding used by the model
+    Bpe(Encoding, &'static CoreBPE),
+    /// Average bytes per token of the model family
+    Estimate(&'static str, f64),
+}
+
+impl Tokenizer {
+    /// Tokenizer of a model, ignoring provider prefixes like `openai/`
+    pub fn for_model(model: &str) -> Self {
+        let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+        if let Some(encoding) = tokenizer::get_tokenizer(&name) {
+            let bpe = match encoding {
+                Encoding::O200kBase => tiktoken_rs::o200k_base_singleton(),
+                Encoding::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+                Encoding::P50kBase => tiktoken_rs::p50k_base_singleton(),
+                Encoding::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+                Encoding::R50kBase | Encoding::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+            };
+            return Tokenizer::Bpe(encoding, bpe);
+        }
+        FAMILY_BYTES_PER_TOKEN
+            .iter()
+            .find(|(family, _)| name.starts_with(family))
+            .map_or(
+                Tokenizer::Estimate("unknown model", DEFAULT_BYTES_PER_TOKEN),
+                |(family, bytes)| Tokenizer::Estimate(family, *bytes),
+            )
+    }
+
+    /// Number of tokens of a text
+    pub fn count(&self, text: &str) -> usize {
+        match self {
+            Tokenizer::Bpe(_, bpe) => bpe.encode_with_special_tokens(text).len(),
+            Tokenizer::Estimate(_, bytes_per_token) => {
+                (text.len() as f64 / bytes_per_token).ceil() as usize
+            }
+        }
+    }
+
+    /// Encoding or estimate used, as shown to users, e.g. `o200k_base` or `claude (estimate)`
+    pub fn name(&self) -> String {
+        match self {
+            Tokenizer::Bpe(encoding, _) => match encoding {
+                Encoding::O200kBase => "o200k_base",
+                Encoding::Cl100kBase => "cl100k_base",
+                Encoding::P50kBase => "p50k_base",
+                Encoding::P50kEdit => "p50k_edit",
+                Encoding::R50kBase => "r50k_base",
+                Encoding::Gpt2 => "gpt2",
+            }
+            .to_string(),
+            Tokenizer::Estimate(family, _) => format!("{} (estimate)", family),
+        }
+    }
+}
+
+/// Format per-file token counts, largest first, with the total
+pub fn format_counts(tokenizer: &Tokenizer, counts: &[(String, usize)]) -> String {
+    let mut counts: Vec<&(String, usize)> = counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let width = counts
+        .iter()
+        .map(|(_, n)| n.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let mut output = String::new();
+    for (file, n) in &counts {
+        output.push_str(&format!("{:>width$}  {}\n", n, file));
+    }
+    let total: usize = counts.iter().map(|(_, n)| n).sum();
+    output.push_str(&format!(
+        "{} tokens in {} file(s) ({})",
+        total,
+        counts.len(),
+        tokenizer.name()
+    ));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizer_for_model() {
+        let gpt = Tokenizer::for_model("openai/gpt-4o-mini");
+        assert_eq!(gpt.name(), "o200k_base");
+        assert_eq!(gpt.count("hello world"), 2);
+
+        let claude = Tokenizer::for_model("anthropic/claude-sonnet-4");
+        assert_eq!(claude.name(), "claude (estimate)");
+        assert_eq!(claude.count(&"x".repeat(35)), 10);
+        assert_eq!(Tokenizer::for_model("unknown").count("abcde"), 2);
+    }
+
+    #[test]
+    fn test_format_counts() {
+        let tokenizer = Tokenizer::for_model("unknown");
+        let counts = [("a.rs".to_string(), 5), ("b.rs".to_string(), 120)];
+        assert_eq!(
+            format_counts(&tokenizer, &counts),
+            "120  b.rs\n  5  a.rs\n125 tokens in 2 file(s) (unknown model (estimate))"
+        );
+    }
+}
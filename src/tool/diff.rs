@@ -1,6 +1,8 @@
 use std::{collections::HashMap, sync::Arc};
 use tiny_loop::tool::tool;
 
+use super::utils::{DEFAULT_NUM_LINES, truncate_lines_with_hint};
+
 /// Tool for retrieving git diffs of changed files
 #[derive(Clone)]
 pub struct Diff {
@@ -26,13 +28,24 @@ impl Diff {
         /// Force read files that are normally excluded.
         /// These files are usually large and not meaningful to review. (default: false)
         force_read: Option<bool>,
+        /// Optional start line of each diff, 1-indexed (default: 1)
+        start_line: Option<usize>,
+        /// Optional number of lines to return per diff (default: 500)
+        num_lines: Option<usize>,
     ) -> String {
+        let page = |diff: String| {
+            truncate_lines_with_hint(
+                &diff,
+                start_line.unwrap_or(1),
+                num_lines.unwrap_or(DEFAULT_NUM_LINES),
+            )
+        };
         if path.len() == 1 {
-            return self.diff_one(&path[0], force_read);
+            return page(self.diff_one(&path[0], force_read));
         }
 
         path.iter()
-            .map(|p| format!("=== {} ===\n{}", p, self.diff_one(p, force_read)))
+            .map(|p| format!("=== {} ===\n{}", p, page(self.diff_one(p, force_read))))
             .collect::<Vec<_>>()
             .join("\n\n")
     }
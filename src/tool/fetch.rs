@@ -1,50 +1,192 @@
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
 use tiny_loop::tool::tool;
+use tokio_util::sync::CancellationToken;
 
 use super::utils::{DEFAULT_NUM_CHARS, truncate_with_hint};
 
-/// Fetch webpages and convert HTML to Markdown
+/// Page chrome removed before conversion: scripts, styles, navigation, footers and the
+/// page-level banner. Headers of articles and sections, and forms, may be documentation
+/// content, e.g. a page title or a configuration example, so they are kept.
+const BOILERPLATE: &str = "script, style, noscript, template, nav, footer, body > header, \
+    [role=navigation], [role=banner], [role=contentinfo]";
+
+/// Elements marking the main content of a page, in order of preference
+const MAIN_CONTENT: [&str; 3] = ["main", "[role=main]", "article"];
+
+/// Paragraph text an element needs to be taken as the main content of a page without
+/// a `<main>` or `<article>`
+const MIN_CONTENT_CHARS: usize = 200;
+
+/// Tool for fetching webpages, aborting requests in flight when cancelled
+#[derive(Clone)]
+pub struct Fetch {
+    cancel: CancellationToken,
+}
+
+impl Fetch {
+    /// Create a new Fetch tool, cancelled with `cancel`
+    pub fn new(cancel: CancellationToken) -> Self {
+        Self { cancel }
+    }
+}
+
 #[tool]
-pub async fn fetch(
-    /// URLs to fetch
-    url: Vec<String>,
-    /// Optional start character index (default: 0)
-    start_char: Option<usize>,
-    /// Optional number of characters to return (default: 5000)
-    num_chars: Option<usize>,
-) -> String {
-    if url.len() == 1 {
-        return fetch_one(&url[0], start_char, num_chars).await;
+impl Fetch {
+    /// Fetch webpages and convert their main content to Markdown
+    pub async fn fetch(
+        self,
+        /// URLs to fetch
+        url: Vec<String>,
+        /// Optional CSS selector of the HTML elements to return, e.g. `#configuration`
+        /// (default: the page's main content, without navigation and footers)
+        selector: Option<String>,
+        /// Optional start character index (default: 0)
+        start_char: Option<usize>,
+        /// Optional number of characters to return (default: 5000)
+        num_chars: Option<usize>,
+    ) -> String {
+        let selector = selector.as_deref();
+        if url.len() == 1 {
+            return self
+                .fetch_one(&url[0], selector, start_char, num_chars)
+                .await;
+        }
+
+        let mut results = Vec::with_capacity(url.len());
+        for u in url {
+            let content = self.fetch_one(&u, selector, start_char, num_chars).await;
+            results.push(format!("=== {} ===\n{}", u, content));
+        }
+        results.join("\n\n")
     }
+}
 
-    let mut results = Vec::with_capacity(url.len());
-    for u in url {
-        let content = fetch_one(&u, start_char, num_chars).await;
-        results.push(format!("=== {} ===\n{}", u, content));
+impl Fetch {
+    async fn fetch_one(
+        &self,
+        url: &str,
+        selector: Option<&str>,
+        start_char: Option<usize>,
+        num_chars: Option<usize>,
+    ) -> String {
+        let markdown = tokio::select! {
+            result = fetch_markdown(url, selector) => result.unwrap_or_else(|e| e),
+            _ = self.cancel.cancelled() => format!("Fetching {} was cancelled", url),
+        };
+        truncate_with_hint(
+            markdown,
+            start_char.unwrap_or(0),
+            num_chars.unwrap_or(DEFAULT_NUM_CHARS),
+        )
     }
-    results.join("\n\n")
 }
 
-async fn fetch_one(url: &str, start_char: Option<usize>, num_chars: Option<usize>) -> String {
-    let markdown = execute_fetch(url).await;
-    truncate_with_hint(
-        markdown,
-        start_char.unwrap_or(0),
-        num_chars.unwrap_or(DEFAULT_NUM_CHARS),
-    )
+/// HTML of the elements matching `selector`, or of the page's main content: `<main>`,
+/// `<article>`, or else the element with the most paragraph text, with page chrome removed
+pub fn extract_content(html: &str, selector: Option<&str>) -> Result<String, String> {
+    let mut document = Html::parse_document(html);
+    let boilerplate = Selector::parse(BOILERPLATE).unwrap();
+    let removed: Vec<_> = document.select(&boilerplate).map(|e| e.id()).collect();
+    for id in removed {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    if let Some(selector) = selector {
+        let parsed = Selector::parse(selector)
+            .map_err(|e| format!("Invalid selector '{}': {}", selector, e))?;
+        let matched: Vec<String> = document.select(&parsed).map(|e| e.html()).collect();
+        if matched.is_empty() {
+            return Err(format!("No elements match selector '{}'", selector));
+        }
+        return Ok(matched.join("\n"));
+    }
+
+    for main in MAIN_CONTENT {
+        if let Some(element) = document.select(&Selector::parse(main).unwrap()).next() {
+            return Ok(element.html());
+        }
+    }
+
+    // Score each element by the text of its paragraphs, as readability tools do
+    let paragraphs = Selector::parse("p, pre").unwrap();
+    let mut scores = HashMap::new();
+    let mut candidates = Vec::new();
+    for paragraph in document.select(&paragraphs) {
+        if let Some(parent) = paragraph.parent().and_then(ElementRef::wrap) {
+            let score: &mut usize = scores.entry(parent.id()).or_insert_with(|| {
+                candidates.push(parent);
+                0
+            });
+            *score += paragraph.text().map(str::len).sum::<usize>();
+        }
+    }
+    let best = candidates
+        .into_iter()
+        .rev()
+        .max_by_key(|e| scores[&e.id()])
+        .filter(|e| scores[&e.id()] >= MIN_CONTENT_CHARS);
+    Ok(match best {
+        Some(element) => element.html(),
+        None => document
+            .select(&Selector::parse("body").unwrap())
+            .next()
+            .map_or_else(|| document.html(), |body| body.html()),
+    })
+}
+
+/// Response body as Markdown by content type: HTML main content converted, JSON
+/// pretty-printed in a code fence, and other text as is
+fn to_markdown(content_type: &str, text: String, selector: Option<&str>) -> Result<String, String> {
+    if content_type.contains("json") {
+        let pretty = serde_json::from_str::<serde_json::Value>(&text)
+            .map(|value| serde_json::to_string_pretty(&value).unwrap())
+            .unwrap_or(text);
+        let fence = crate::review::render::get_fence_backticks(&pretty);
+        return Ok(format!("{}json\n{}\n{}", fence, pretty, fence));
+    }
+    if content_type.is_empty() || content_type.contains("html") {
+        return Ok(html2md::parse_html(&extract_content(&text, selector)?));
+    }
+    Ok(text)
 }
 
-pub async fn execute_fetch(url: &str) -> String {
-    let response = match reqwest::get(url).await {
-        Ok(r) => r,
-        Err(e) => return format!("Error fetching URL: {}", e),
-    };
+/// Whether a response can be read as text
+fn is_text(content_type: &str) -> bool {
+    content_type.is_empty()
+        || content_type.starts_with("text/")
+        || ["json", "xml", "html", "javascript", "yaml", "toml"]
+            .iter()
+            .any(|kind| content_type.contains(kind))
+}
 
-    let html = match response.text().await {
-        Ok(h) => h,
-        Err(e) => return format!("Error reading response: {}", e),
-    };
+/// Fetch a URL as Markdown: the main content of HTML pages, or of the elements matching
+/// `selector`; JSON pretty-printed; other text as is
+pub async fn fetch_markdown(url: &str, selector: Option<&str>) -> Result<String, String> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Error fetching URL: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Error fetching URL: HTTP {}", status));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    if !is_text(&content_type) {
+        return Err(format!("Unsupported content type: {}", content_type));
+    }
 
-    html2md::parse_html(&html)
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Error reading response: {}", e))?;
+    to_markdown(&content_type, text, selector)
 }
 
 #[cfg(test)]
@@ -60,6 +202,48 @@ mod tests {
         assert!(result.contains("Content"));
     }
 
+    #[test]
+    fn test_extract_main_content() {
+        let page = "<html><body><nav><a href='/'>Home</a></nav>\
+            <div id='docs'><p>Intro</p><section id='api'><h2>API</h2><p>Call it</p></section></div>\
+            <footer>Copyright</footer></body></html>";
+        let body = extract_content(page, None).unwrap();
+        assert!(body.contains("Intro") && !body.contains("Home") && !body.contains("Copyright"));
+        let api = extract_content(page, Some("#api")).unwrap();
+        assert!(api.contains("Call it") && !api.contains("Intro"));
+        assert!(extract_content(page, Some("#missing")).is_err());
+
+        let article = format!(
+            "<body><div><p>Sidebar</p></div><div><p>{}</p><p>More</p></div></body>",
+            "x".repeat(MIN_CONTENT_CHARS)
+        );
+        let content = extract_content(&article, None).unwrap();
+        assert!(content.contains("More") && !content.contains("Sidebar"));
+        let headed = "<body><header>Site</header><main><header><h1>Guide</h1></header>\
+            <form><input placeholder='Query'></form></main></body>";
+        let content = extract_content(headed, None).unwrap();
+        assert!(content.contains("Guide") && content.contains("<form>"));
+        assert!(!content.contains("Site"));
+        let main = "<body><p>Skip</p><main><p>Main</p></main></body>";
+        assert_eq!(
+            extract_content(main, None).unwrap(),
+            "<main><p>Main</p></main>"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_by_content_type() {
+        assert_eq!(
+            to_markdown("application/json", r#"{"a":1}"#.into(), None).unwrap(),
+            "```json\n{\n  \"a\": 1\n}\n```"
+        );
+        assert_eq!(
+            to_markdown("text/plain; charset=utf-8", "<p>".into(), None).unwrap(),
+            "<p>"
+        );
+        assert!(is_text("application/xml") && !is_text("image/png"));
+    }
+
     #[test]
     fn test_fetch_html_with_truncation() {
         let html = "<p>Hello World</p>".to_string();
@@ -1,6 +1,10 @@
 pub mod diff;
 pub mod fetch;
+pub mod read;
 pub mod report;
+pub mod resource;
+pub mod rule_info;
 pub mod sh;
+pub mod skill;
 pub mod think;
 pub mod utils;
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tiny_loop::tool::tool;
+use tokio::process::Command;
+
+use super::utils::{DEFAULT_NUM_LINES, truncate_lines_with_hint};
+
+/// Version of the files under review
+#[derive(Clone, Debug, PartialEq)]
+pub enum Snapshot {
+    /// Files on disk, which diffs against a commit include
+    WorkingTree,
+    /// Files staged in the index, when reviewing staged changes
+    Index,
+    /// Files under a directory other than the repository, e.g. a bench case's changed files
+    Directory(Arc<Path>),
+    /// Files on disk with unsaved editor buffers over them, by path
+    Overlay(Arc<HashMap<String, String>>),
+}
+
+impl Snapshot {
+    /// Directory the `sh` tool runs commands in, `None` for the repository root
+    pub fn dir(&self) -> Option<&Path> {
+        match self {
+            Self::Directory(dir) => Some(dir),
+            Self::WorkingTree | Self::Index | Self::Overlay(_) => None,
+        }
+    }
+}
+
+/// Tool for reading files as they are in the changes under review
+#[derive(Clone)]
+pub struct Read {
+    snapshot: Snapshot,
+}
+
+impl Read {
+    /// Create a new Read tool serving files from the given snapshot
+    pub fn new(snapshot: Snapshot) -> Self {
+        Self { snapshot }
+    }
+}
+
+#[tool]
+impl Read {
+    /// Read a file as it is in the changes under review, with line numbers.
+    /// When reviewing staged changes this is the staged version, which may differ from the file on disk,
+    /// so prefer it over shell commands such as `cat`.
+    pub async fn read(
+        self,
+        /// File path relative to the repository root
+        path: String,
+        /// Optional start line, 1-indexed (default: 1)
+        start_line: Option<usize>,
+        /// Optional number of lines to return (default: 500)
+        num_lines: Option<usize>,
+    ) -> String {
+        match self.content(&path).await {
+            Ok(content) => truncate_lines_with_hint(
+                &number_lines(&content),
+                start_line.unwrap_or(1),
+                num_lines.unwrap_or(DEFAULT_NUM_LINES),
+            ),
+            Err(e) => e,
+        }
+    }
+}
+
+impl Read {
+    pub(crate) async fn content(&self, path: &str) -> Result<String, String> {
+        let path = path.trim_start_matches("./");
+        match &self.snapshot {
+            Snapshot::Overlay(buffers) if buffers.contains_key(path) => Ok(buffers[path].clone()),
+            Snapshot::WorkingTree | Snapshot::Overlay(_) => {
+                let root = std::env::current_dir()
+                    .map_err(|e| format!("Failed to resolve the repository root: {}", e))?;
+                confine(&root, path)?;
+                tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|e| format!("Failed to read '{}': {}", path, e))
+            }
+            Snapshot::Directory(dir) => {
+                let target = confine(dir, path)?;
+                tokio::fs::read_to_string(target)
+                    .await
+                    .map_err(|e| format!("Failed to read '{}': {}", path, e))
+            }
+            Snapshot::Index => {
+                // `:path` is the staged blob; a file missing from the index is not part of the review
+                let output = Command::new("git")
+                    .args(["show", &format!(":{}", path)])
+                    .output()
+                    .await
+                    .map_err(|e| format!("Failed to execute git: {}", e))?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "File not found in the staged changes: {} ({})",
+                        path,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            }
+        }
+    }
+}
+
+/// Resolve a path relative to `root`, rejecting paths that resolve outside it, e.g.
+/// through `..` or a symlink to `/etc`
+fn confine(root: &Path, path: &str) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve the repository root: {}", e))?;
+    let target = root
+        .join(path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    if !target.starts_with(&root) {
+        return Err(format!("Path is outside the repository: {}", path));
+    }
+    Ok(target)
+}
+
+/// Prefix each line with its 1-indexed number, for reporting violations by line
+fn number_lines(content: &str) -> String {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{}: {}", i + 1, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_lines() {
+        assert_eq!(number_lines("fn main() {\n}\n"), "1: fn main() {\n2: }");
+        assert_eq!(number_lines(""), "");
+    }
+
+    #[tokio::test]
+    async fn test_read_working_tree() {
+        let read = Read::new(Snapshot::WorkingTree);
+        let content = read.content("./Cargo.toml").await.unwrap();
+        assert!(content.starts_with("[package]"));
+        assert!(read.content("missing.rs").await.is_err());
+        assert_eq!(
+            read.content("..").await.unwrap_err(),
+            "Path is outside the repository: .."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_directory() {
+        let read = Read::new(Snapshot::Directory(Arc::from(Path::new("src/tool"))));
+        assert!(
+            read.content("read.rs")
+                .await
+                .unwrap()
+                .contains("struct Read")
+        );
+        assert!(read.content("../main.rs").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_overlay() {
+        let buffers = HashMap::from([("src/new.rs".to_string(), "fn new() {}\n".to_string())]);
+        let read = Read::new(Snapshot::Overlay(Arc::new(buffers)));
+        assert_eq!(read.content("./src/new.rs").await.unwrap(), "fn new() {}\n");
+        assert!(
+            read.content("Cargo.toml")
+                .await
+                .unwrap()
+                .starts_with("[package]")
+        );
+        assert!(read.content("..").await.is_err());
+    }
+}
@@ -1,12 +1,51 @@
+use crate::tool::think::Think;
 use crate::types::Violation;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
 use std::sync::Arc;
-use tiny_loop::tool::tool;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tiny_loop::types::{Parameters, ToolDefinition, ToolFunction};
 use tokio::sync::Mutex;
 
+/// Invalid report calls answered with corrective feedback before telling the agent to stop retrying
+const MAX_INVALID_REPORTS: usize = 3;
+
+/// Reply to a report made before any `think` call; the violations are not recorded
+pub const THINK_FIRST: &str = "Call think first to reason about whether these findings really violate the rule, then report the ones that hold up.";
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ReportArgs {
+    /// List of violations
+    pub violations: Vec<Violation>,
+}
+
+impl ReportArgs {
+    pub const TOOL_NAME: &'static str = "report";
+}
+
+pub fn report_tool_def() -> ToolDefinition {
+    ToolDefinition {
+        tool_type: "function".into(),
+        function: ToolFunction {
+            name: ReportArgs::TOOL_NAME.into(),
+            description: "Report rule violations found during review.".into(),
+            parameters: Parameters::from_type::<ReportArgs>(),
+        },
+    }
+}
+
 /// Tool for reporting rule violations found during code review
 #[derive(Clone)]
 pub struct Report {
     pub violations: Arc<Mutex<Vec<Violation>>>,
+    invalid_reports: Arc<AtomicUsize>,
+    /// Think tool whose use is required before the first report, if enforced
+    think: Option<Think>,
+    think_requested: Arc<AtomicBool>,
+    /// Files violations may be reported in, if restricted
+    focus: Option<Arc<BTreeSet<String>>>,
 }
 
 impl Report {
@@ -14,20 +53,240 @@ impl Report {
     pub fn new() -> Self {
         Self {
             violations: Arc::new(Mutex::new(Vec::new())),
+            invalid_reports: Arc::new(AtomicUsize::new(0)),
+            think: None,
+            think_requested: Arc::new(AtomicBool::new(false)),
+            focus: None,
         }
     }
-}
 
-#[tool]
-impl Report {
-    /// Report rule violations found during review.
-    pub async fn report(
-        self,
-        /// List of violations
-        violations: Vec<Violation>,
-    ) -> String {
+    /// Reject the first report of violations made before `think` was called
+    pub fn require_think(mut self, think: Think) -> Self {
+        self.think = Some(think);
+        self
+    }
+
+    /// Reject violations in files other than the given ones
+    pub fn focus_files(mut self, files: &[String]) -> Self {
+        self.focus = Some(Arc::new(files.iter().cloned().collect()));
+        self
+    }
+
+    /// Record violations from raw tool arguments.
+    ///
+    /// Valid violations are kept even if others are malformed; the reply lists each
+    /// invalid field so the agent can re-report just those.
+    pub async fn report(&self, arguments: &str) -> String {
+        let parsed = parse_report(arguments);
+        if let Some(think) = &self.think
+            && !parsed.violations.is_empty()
+            && !think.called()
+            && !self.think_requested.swap(true, Ordering::SeqCst)
+        {
+            return THINK_FIRST.into();
+        }
+        let (violations, outside): (Vec<_>, Vec<_>) = parsed
+            .violations
+            .into_iter()
+            .partition(|v| self.focus.as_ref().is_none_or(|f| f.contains(&v.file)));
+        let recorded = violations.len();
         self.violations.lock().await.extend(violations);
-        "OK".into()
+
+        let mut notes = Vec::new();
+        if let Some(focus) = &self.focus
+            && !outside.is_empty()
+        {
+            let files: BTreeSet<&str> = outside.iter().map(|v| v.file.as_str()).collect();
+            notes.push(format!(
+                "Rejected {} violation(s) in files outside this review: {}. \
+                 Only report violations in: {}. Do not report them again.",
+                outside.len(),
+                files.into_iter().collect::<Vec<_>>().join(", "),
+                focus.iter().cloned().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if !parsed.errors.is_empty() {
+            let attempts = self.invalid_reports.fetch_add(1, Ordering::SeqCst) + 1;
+            notes.push(if attempts > MAX_INVALID_REPORTS {
+                "Invalid violations were dropped. Do not retry; finish the review.".to_string()
+            } else {
+                format!(
+                    "Fix these errors and call report again with only the invalid violations:\n- {}\n\
+                     Each violation needs \"file\" (string), \"detail\" (string), \"start_line\" (integer >= 1), and \"end_line\" (integer >= start_line).",
+                    parsed.errors.join("\n- ")
+                )
+            });
+        }
+        if notes.is_empty() {
+            return "OK".into();
+        }
+        format!("Recorded {} violation(s). {}", recorded, notes.join("\n"))
+    }
+}
+
+/// Violations parsed from report arguments, with errors for entries that could not be used
+#[derive(Debug, Default)]
+pub struct ParsedReport {
+    pub violations: Vec<Violation>,
+    pub errors: Vec<String>,
+}
+
+/// Parse report arguments leniently.
+///
+/// Accepts a bare array or a single object in place of `violations`, an array encoded as a
+/// JSON string, `line` for `start_line`, numeric strings for line numbers, a missing
+/// `end_line` (defaults to `start_line`), and reversed line ranges.
+pub fn parse_report(arguments: &str) -> ParsedReport {
+    let mut parsed = ParsedReport::default();
+    let value: Value = match serde_json::from_str(arguments) {
+        Ok(value) => value,
+        Err(e) => {
+            parsed
+                .errors
+                .push(format!("arguments: invalid JSON ({})", e));
+            return parsed;
+        }
+    };
+
+    let violations = match value {
+        Value::Object(mut obj) => obj.remove("violations"),
+        Value::Array(_) => Some(value),
+        other => {
+            parsed.errors.push(format!(
+                "arguments: expected an object, got {}",
+                kind(&other)
+            ));
+            return parsed;
+        }
+    };
+    let items = match violations {
+        Some(Value::Array(items)) => items,
+        Some(obj @ Value::Object(_)) => vec![obj],
+        Some(Value::String(s)) => match serde_json::from_str(&s) {
+            Ok(Value::Array(items)) => items,
+            _ => {
+                parsed
+                    .errors
+                    .push("violations: expected an array, got a string".into());
+                return parsed;
+            }
+        },
+        Some(other) => {
+            parsed.errors.push(format!(
+                "violations: expected an array, got {}",
+                kind(&other)
+            ));
+            return parsed;
+        }
+        None => {
+            parsed.errors.push("violations: missing field".into());
+            return parsed;
+        }
+    };
+
+    for (i, item) in items.iter().enumerate() {
+        match parse_violation(item) {
+            Ok(violation) => parsed.violations.push(violation),
+            Err(errors) => parsed.errors.extend(
+                errors
+                    .into_iter()
+                    .map(|e| format!("violations[{}].{}", i, e)),
+            ),
+        }
+    }
+    parsed
+}
+
+fn parse_violation(item: &Value) -> Result<Violation, Vec<String>> {
+    let Value::Object(obj) = item else {
+        return Err(vec![format!(
+            "(entry): expected an object, got {}",
+            kind(item)
+        )]);
+    };
+    let mut errors = Vec::new();
+
+    let file = required_string(obj, "file", &mut errors)
+        .map(|f| f.strip_prefix("./").unwrap_or(&f).to_string());
+    let detail = required_string(obj, "detail", &mut errors);
+    let start_line = match obj.get("start_line").or_else(|| obj.get("line")) {
+        Some(value) => line_number(value).map_err(|e| errors.push(format!("start_line: {}", e))),
+        None => {
+            errors.push("start_line: missing field".into());
+            Err(())
+        }
+    };
+    let end_line = match obj.get("end_line") {
+        Some(value) => line_number(value)
+            .map(Some)
+            .map_err(|e| errors.push(format!("end_line: {}", e))),
+        None => Ok(None),
+    };
+
+    match (file, detail, start_line, end_line) {
+        (Some(file), Some(detail), Ok(start), Ok(end)) => {
+            let end = end.unwrap_or(start);
+            Ok(Violation {
+                file,
+                detail,
+                start_line: start.min(end),
+                end_line: start.max(end),
+                rule_id: None,
+                owners: vec![],
+            })
+        }
+        _ => Err(errors),
+    }
+}
+
+fn required_string(
+    obj: &Map<String, Value>,
+    key: &str,
+    errors: &mut Vec<String>,
+) -> Option<String> {
+    match obj.get(key) {
+        Some(Value::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Some(Value::String(_)) => {
+            errors.push(format!("{}: must not be empty", key));
+            None
+        }
+        Some(other) => {
+            errors.push(format!("{}: expected a string, got {}", key, kind(other)));
+            None
+        }
+        None => {
+            errors.push(format!("{}: missing field", key));
+            None
+        }
+    }
+}
+
+/// Parse a 1-indexed line number from an integer, integral float, or numeric string
+fn line_number(value: &Value) -> Result<u32, String> {
+    let number = match value {
+        Value::Number(n) => n.as_u64().or_else(|| {
+            n.as_f64()
+                .filter(|f| f.fract() == 0.0 && *f >= 0.0)
+                .map(|f| f as u64)
+        }),
+        Value::String(s) => s.trim().parse::<u64>().ok(),
+        _ => None,
+    };
+    match number.and_then(|n| u32::try_from(n).ok()) {
+        Some(0) => Err("line numbers start at 1, got 0".into()),
+        Some(n) => Ok(n),
+        None => Err(format!("expected a line number, got {}", value)),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
     }
 }
 
@@ -43,6 +302,8 @@ mod tests {
             detail: "test violation".to_string(),
             start_line: 1,
             end_line: 2,
+            rule_id: None,
+            owners: vec![],
         }];
 
         report.violations.lock().await.extend(violations);
@@ -62,6 +323,8 @@ mod tests {
             detail: "first".to_string(),
             start_line: 1,
             end_line: 1,
+            rule_id: None,
+            owners: vec![],
         });
 
         report.violations.lock().await.push(Violation {
@@ -69,9 +332,109 @@ mod tests {
             detail: "second".to_string(),
             start_line: 2,
             end_line: 2,
+            rule_id: None,
+            owners: vec![],
         });
 
         let stored = report.violations.lock().await;
         assert_eq!(stored.len(), 2);
     }
+
+    #[test]
+    fn test_parse_report_coerces() {
+        let parsed = parse_report(
+            r#"{"violations": "[{\"file\": \"./a.rs\", \"detail\": \"x\", \"line\": \"12\"}, {\"file\": \"b.rs\", \"detail\": \"y\", \"start_line\": 9, \"end_line\": 3.0}]"}"#,
+        );
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.violations[0].file, "a.rs");
+        assert_eq!(
+            (
+                parsed.violations[0].start_line,
+                parsed.violations[0].end_line
+            ),
+            (12, 12)
+        );
+        assert_eq!(
+            (
+                parsed.violations[1].start_line,
+                parsed.violations[1].end_line
+            ),
+            (3, 9)
+        );
+    }
+
+    #[test]
+    fn test_parse_report_errors() {
+        let parsed = parse_report(
+            r#"{"violations": [{"file": "a.rs", "detail": "ok", "start_line": 1}, {"file": "", "start_line": "abc"}]}"#,
+        );
+        assert_eq!(parsed.violations.len(), 1);
+        assert_eq!(
+            parsed.errors,
+            vec![
+                "violations[1].file: must not be empty",
+                "violations[1].detail: missing field",
+                "violations[1].start_line: expected a line number, got \"abc\"",
+            ]
+        );
+        assert_eq!(parse_report("{}").errors, vec!["violations: missing field"]);
+    }
+
+    #[tokio::test]
+    async fn test_report_feedback_and_retry_cap() {
+        let report = Report::new();
+        let invalid = r#"{"violations": [{"file": "a.rs"}]}"#;
+        assert_eq!(
+            report
+                .report(r#"{"violations": [{"file": "a.rs", "detail": "x", "start_line": 1}]}"#)
+                .await,
+            "OK"
+        );
+        for _ in 0..MAX_INVALID_REPORTS {
+            assert!(
+                report
+                    .report(invalid)
+                    .await
+                    .contains("violations[0].detail")
+            );
+        }
+        assert!(report.report(invalid).await.contains("Do not retry"));
+        assert_eq!(report.violations.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_report_requires_think_once() {
+        let think = Think::new();
+        let report = Report::new().require_think(think.clone());
+        let args = r#"{"violations": [{"file": "a.rs", "detail": "x", "start_line": 1}]}"#;
+        assert_eq!(report.report(r#"{"violations": []}"#).await, "OK");
+        assert_eq!(report.report(args).await, THINK_FIRST);
+        assert_eq!(report.report(args).await, "OK");
+        assert_eq!(report.violations.lock().await.len(), 1);
+
+        let report = Report::new().require_think(think.clone());
+        think
+            .clone()
+            .think(crate::tool::think::ThinkArgs {
+                reasoning: "Clearly a violation".into(),
+            })
+            .await;
+        assert_eq!(report.report(args).await, "OK");
+    }
+
+    #[tokio::test]
+    async fn test_report_rejects_files_outside_focus() {
+        let report = Report::new().focus_files(&["a.rs".to_string()]);
+        let reply = report
+            .report(
+                r#"{"violations": [{"file": "./a.rs", "detail": "x", "start_line": 1}, {"file": "b.rs", "detail": "y", "start_line": 2}]}"#,
+            )
+            .await;
+        assert!(reply.starts_with(
+            "Recorded 1 violation(s). Rejected 1 violation(s) in files outside this review: b.rs."
+        ));
+        let violations = report.violations.lock().await;
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].file, "a.rs");
+    }
 }
@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use tiny_loop::tool::tool;
+
+use super::utils::{DEFAULT_NUM_CHARS, truncate_with_hint};
+
+/// Loads the resources the `resource` tool reads, in full, as the tool pages through them
+#[async_trait]
+pub trait ResourceSource: Send + Sync {
+    /// Content of a resource as Markdown, with `files` of the task for task-specific ones
+    async fn load(&self, uri: &str, files: &[String]) -> Result<String, String>;
+}
+
+/// Tool for reading resources configured with `inline = false` on demand
+#[derive(Clone)]
+pub struct ResourceRead {
+    source: Arc<dyn ResourceSource>,
+    /// Resource URIs the agent may read
+    uris: Arc<[String]>,
+    /// Files of the task, for `results://` resources
+    files: Arc<[String]>,
+}
+
+impl ResourceRead {
+    pub const TOOL_NAME: &'static str = "resource";
+
+    /// Create a new ResourceRead tool for the given resource URIs
+    pub fn new(source: Arc<dyn ResourceSource>, uris: Vec<String>, files: &[String]) -> Self {
+        Self {
+            source,
+            uris: uris.into(),
+            files: files.into(),
+        }
+    }
+
+    fn list(&self) -> String {
+        format!("Available resources: {}", self.uris.join(", "))
+    }
+}
+
+#[tool]
+impl ResourceRead {
+    /// Read a resource listed in the prompt as available on demand.
+    pub async fn resource(
+        self,
+        /// Resource URI (default: list available resources)
+        uri: Option<String>,
+        /// Optional start character index (default: 0)
+        start_char: Option<usize>,
+        /// Optional number of characters to return (default: 5000)
+        num_chars: Option<usize>,
+    ) -> String {
+        let Some(uri) = uri else {
+            return self.list();
+        };
+        if !self.uris.contains(&uri) {
+            return format!("Unknown resource '{}'. {}", uri, self.list());
+        }
+        match self.source.load(&uri, &self.files).await {
+            Ok(content) => truncate_with_hint(
+                content,
+                start_char.unwrap_or(0),
+                num_chars.unwrap_or(DEFAULT_NUM_CHARS),
+            ),
+            Err(e) => format!("Failed to load resource '{}': {}", uri, e),
+        }
+    }
+}
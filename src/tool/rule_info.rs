@@ -0,0 +1,82 @@
+use crate::review::scope::filter_files_by_scope;
+use crate::rule::body::RuleBody;
+use serde_json::json;
+use std::sync::Arc;
+use tiny_loop::tool::tool;
+
+/// Tool returning the structured metadata of the rule under review
+#[derive(Clone)]
+pub struct RuleInfo {
+    rule: Arc<RuleBody>,
+}
+
+impl RuleInfo {
+    /// Create a new RuleInfo tool for the given rule
+    pub fn new(rule: &RuleBody) -> Self {
+        Self {
+            rule: Arc::new(rule.clone()),
+        }
+    }
+}
+
+#[tool]
+impl RuleInfo {
+    /// Get the rule's metadata: id, description, scope and exclude globs, tags, tip, and whether it blocks.
+    /// Pass files to check them against the scope; files out of scope need no review.
+    pub async fn rule_info(
+        self,
+        /// Optional file paths to check against the rule's scope
+        files: Option<Vec<String>>,
+    ) -> String {
+        let mut info = json!({
+            "id": self.rule.id(),
+            "name": self.rule.name,
+            "description": self.rule.description,
+            "scope": self.rule.scope,
+            "exclude": self.rule.exclude,
+            "tags": self.rule.tags,
+            "tip": self.rule.tip,
+            "blocking": self.rule.blocks(),
+            "quarantined": self.rule.quarantined,
+        });
+        if let Some(files) = files {
+            let in_scope = filter_files_by_scope(&self.rule, &files);
+            let out_of_scope: Vec<&String> =
+                files.iter().filter(|f| !in_scope.contains(f)).collect();
+            info["in_scope"] = json!(in_scope);
+            info["out_of_scope"] = json!(out_of_scope);
+        }
+        serde_json::to_string_pretty(&info).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rule_info_checks_scope() {
+        let rule = RuleBody {
+            scope: vec!["src/**/*.rs".to_string()],
+            exclude: vec!["**/generated/**".to_string()],
+            ..RuleBody::no_magic_numbers()
+        };
+        let info = RuleInfo::new(&rule)
+            .rule_info(RuleInfoArgs {
+                files: Some(vec![
+                    "src/main.rs".to_string(),
+                    "src/generated/schema.rs".to_string(),
+                    "README.md".to_string(),
+                ]),
+            })
+            .await;
+        let info: serde_json::Value = serde_json::from_str(&info).unwrap();
+        assert_eq!(info["id"], rule.id());
+        assert_eq!(info["scope"], json!(["src/**/*.rs"]));
+        assert_eq!(info["in_scope"], json!(["src/main.rs"]));
+        assert_eq!(
+            info["out_of_scope"],
+            json!(["src/generated/schema.rs", "README.md"])
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::process::Stdio;
 use tiny_loop::types::{Parameters, ToolDefinition, ToolFunction};
 use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
+use tokio_util::sync::CancellationToken;
 
 use super::utils::{DEFAULT_NUM_CHARS, truncate_with_hint};
 
@@ -23,15 +25,80 @@ impl ShArgs {
     pub const TOOL_NAME: &'static str = "sh";
 }
 
-pub fn sh_tool_def(allowed_commands: &[String]) -> ToolDefinition {
+/// Shell running commands of the `sh` tool and `sh://` resources
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    /// POSIX `sh`, the default on Unix
+    Sh,
+    Bash,
+    Zsh,
+    /// PowerShell 7+
+    Pwsh,
+    /// Windows PowerShell, the default on Windows
+    Powershell,
+    /// Windows Command Prompt
+    Cmd,
+}
+
+impl toml_scaffold::TomlScaffold for Shell {}
+
+impl Default for Shell {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::Powershell
+        } else {
+            Self::Sh
+        }
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program())
+    }
+}
+
+impl Shell {
+    pub(crate) fn program(self) -> &'static str {
+        match self {
+            Self::Sh => "sh",
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Pwsh => "pwsh",
+            Self::Powershell => "powershell",
+            Self::Cmd => "cmd",
+        }
+    }
+
+    /// Flag passing a command string to the shell
+    pub(crate) fn command_flag(self) -> &'static str {
+        match self {
+            Self::Sh | Self::Bash | Self::Zsh => "-c",
+            Self::Pwsh | Self::Powershell => "-Command",
+            Self::Cmd => "/C",
+        }
+    }
+
+    /// Syntax the validator parses commands with
+    fn syntax(self) -> sheath::Shell {
+        match self {
+            Self::Sh | Self::Bash | Self::Zsh => sheath::Shell::Sh,
+            Self::Pwsh | Self::Powershell => sheath::Shell::PowerShell,
+            Self::Cmd => sheath::Shell::Batch,
+        }
+    }
+}
+
+pub fn sh_tool_def(allowed_commands: &[String], shell: Shell) -> ToolDefinition {
     let commands_str = allowed_commands.join(", ");
     ToolDefinition {
         tool_type: "function".into(),
         function: ToolFunction {
             name: ShArgs::TOOL_NAME.into(),
             description: format!(
-                "Execute an allowlisted shell command. Supports pipes and redirections.\nAllowed commands: {}.",
-                commands_str
+                "Execute an allowlisted {} command. Supports pipes and redirections.\nAllowed commands: {}.",
+                shell, commands_str
             ),
             parameters: Parameters::from_type::<ShArgs>(),
         },
@@ -53,8 +120,14 @@ impl std::fmt::Display for ShError {
     }
 }
 
-pub async fn execute_sh_args(args: ShArgs, allowed_commands: &[String]) -> String {
-    match execute_sh_raw(args.command, allowed_commands).await {
+pub async fn execute_sh_args(
+    args: ShArgs,
+    allowed_commands: &[String],
+    shell: Shell,
+    dir: Option<&Path>,
+    cancel: &CancellationToken,
+) -> String {
+    match execute_sh_raw(args.command, allowed_commands, shell, dir, cancel).await {
         Ok(result) => truncate_with_hint(
             result,
             args.start_char.unwrap_or(0),
@@ -64,26 +137,107 @@ pub async fn execute_sh_args(args: ShArgs, allowed_commands: &[String]) -> Strin
     }
 }
 
-pub async fn execute_shell_command(command: &str) -> Result<String, ShError> {
-    let mut child = if cfg!(windows) {
-        Command::new("powershell")
-            .arg("-Command")
-            .arg(command)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| ShError::ExecutionError(e.to_string()))?
-    } else {
-        Command::new("sh")
-            .arg("-c")
-            .arg(command)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+/// Exit status and captured output of a shell command
+pub(crate) struct ShOutput {
+    pub status: std::process::ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl ShOutput {
+    /// Format as tool output, including the status and stderr when relevant
+    pub fn into_text(self) -> String {
+        if !self.status.success() {
+            format!(
+                "Command failed with status {}\nstdout:\n{}\nstderr:\n{}",
+                self.status, self.stdout, self.stderr
+            )
+        } else if !self.stderr.is_empty() {
+            format!("{}\nstderr:\n{}", self.stdout, self.stderr)
+        } else {
+            self.stdout
+        }
+    }
+}
+
+pub async fn execute_shell_command(
+    command: &str,
+    shell: Shell,
+    dir: Option<&Path>,
+    cancel: &CancellationToken,
+) -> Result<String, ShError> {
+    run_shell_command(command, shell, dir, cancel)
+        .await
+        .map(ShOutput::into_text)
+}
+
+/// Running shell command, killed with the processes it started unless it exits first,
+/// e.g. when the worker running it is cancelled
+struct RunningCommand {
+    child: Child,
+    exited: bool,
+}
+
+impl RunningCommand {
+    fn kill(&mut self) {
+        if self.exited {
+            return;
+        }
+        // The command leads its own process group, which includes pipelines and
+        // subprocesses it started
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            // SAFETY: kill(2) has no memory safety requirements
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+        let _ = self.child.start_kill();
+    }
+}
+
+impl Drop for RunningCommand {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// Run a command via the given shell in `dir` (default: the current directory), capturing
+/// its output. The command and the processes it started are killed when `cancel` is
+/// cancelled or the returned future is dropped.
+pub(crate) async fn run_shell_command(
+    command: &str,
+    shell: Shell,
+    dir: Option<&Path>,
+    cancel: &CancellationToken,
+) -> Result<ShOutput, ShError> {
+    let mut builder = Command::new(shell.program());
+    builder
+        .arg(shell.command_flag())
+        .arg(command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = dir {
+        builder.current_dir(dir);
+    }
+    #[cfg(unix)]
+    builder.process_group(0);
+    let mut running = RunningCommand {
+        child: builder
             .spawn()
-            .map_err(|e| ShError::ExecutionError(e.to_string()))?
+            .map_err(|e| ShError::ExecutionError(format!("{}: {}", shell, e)))?,
+        exited: false,
     };
 
-    match child.wait().await {
+    let result = tokio::select! {
+        result = running.child.wait() => result,
+        _ = cancel.cancelled() => {
+            return Err(ShError::ExecutionError("cancelled".to_string()));
+        }
+    };
+    running.exited = true;
+    let child = &mut running.child;
+    match result {
         Ok(status) => {
             let mut stdout = String::new();
             let mut stderr = String::new();
@@ -95,16 +249,11 @@ pub async fn execute_shell_command(command: &str) -> Result<String, ShError> {
                 let _ = err.read_to_string(&mut stderr).await;
             }
 
-            if !status.success() {
-                Ok(format!(
-                    "Command failed with status {}\nstdout:\n{}\nstderr:\n{}",
-                    status, stdout, stderr
-                ))
-            } else if !stderr.is_empty() {
-                Ok(format!("{}\nstderr:\n{}", stdout, stderr))
-            } else {
-                Ok(stdout)
-            }
+            Ok(ShOutput {
+                status,
+                stdout,
+                stderr,
+            })
         }
         Err(e) => Err(ShError::ExecutionError(format!(
             "Failed to wait for command: {}",
@@ -113,21 +262,68 @@ pub async fn execute_shell_command(command: &str) -> Result<String, ShError> {
     }
 }
 
+/// Check that a command only runs allowed commands, parsed with the shell's syntax
+pub(crate) fn validate_command(
+    command: &str,
+    allowed_commands: &[String],
+    shell: Shell,
+) -> Result<(), ShError> {
+    sheath::Validator::new()
+        .shell(shell.syntax())
+        .allow(allowed_commands.iter().map(|s| s.as_str()))
+        .validate(command)
+        .map_err(ShError::ValidationError)
+}
+
 pub async fn execute_sh_raw(
     command: String,
     allowed_commands: &[String],
+    shell: Shell,
+    dir: Option<&Path>,
+    cancel: &CancellationToken,
 ) -> Result<String, ShError> {
-    let validator = if cfg!(windows) {
-        sheath::Validator::new()
-            .shell(sheath::Shell::PowerShell)
-            .allow(allowed_commands.iter().map(|s| s.as_str()))
-    } else {
-        sheath::Validator::new().allow(allowed_commands.iter().map(|s| s.as_str()))
-    };
+    validate_command(&command, allowed_commands, shell)?;
+    execute_shell_command(&command, shell, dir, cancel).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_command_with_shell_syntax() {
+        let allowed = ["ls".to_string(), "grep".to_string()];
+        assert!(validate_command("ls | grep foo", &allowed, Shell::Bash).is_ok());
+        assert!(validate_command("ls; rm -rf /", &allowed, Shell::Zsh).is_err());
 
-    validator
-        .validate(&command)
-        .map_err(ShError::ValidationError)?;
+        let allowed = ["Get-ChildItem".to_string(), "Select-String".to_string()];
+        assert!(
+            validate_command("Get-ChildItem | Select-String foo", &allowed, Shell::Pwsh).is_ok()
+        );
+        assert!(validate_command("Remove-Item foo", &allowed, Shell::Pwsh).is_err());
 
-    execute_shell_command(&command).await
+        let shell: Shell = serde_json::from_str("\"pwsh\"").unwrap();
+        assert_eq!(shell, Shell::Pwsh);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_kills_command() {
+        let dir = crate::util::temp::TempDir::new(&format!("firekeeper-sh-{}", std::process::id()));
+        std::fs::create_dir_all(dir.path()).unwrap();
+        let marker = dir.path().join("marker");
+        let command = format!("(sleep 1; touch {}) | cat", marker.display());
+        let cancel = CancellationToken::new();
+        let run = run_shell_command(&command, Shell::Sh, None, &cancel);
+        let (result, _) = tokio::join!(run, async {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            cancel.cancel();
+        });
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Failed to execute command: cancelled"
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
 }
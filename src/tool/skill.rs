@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tiny_loop::tool::tool;
+
+use super::utils::{DEFAULT_NUM_CHARS, truncate_with_hint};
+
+/// Skill entry file name used for directory discovery
+pub const SKILL_FILE: &str = "SKILL.md";
+
+/// Tool for reading skills and the files they reference
+#[derive(Clone)]
+pub struct SkillRead {
+    /// Skill directories keyed by skill name
+    skills: Arc<BTreeMap<String, PathBuf>>,
+}
+
+impl SkillRead {
+    pub const TOOL_NAME: &'static str = "skill_read";
+
+    /// Create a new SkillRead tool for the given skill directories
+    pub fn new(skills: BTreeMap<String, PathBuf>) -> Self {
+        Self {
+            skills: Arc::new(skills),
+        }
+    }
+}
+
+#[tool]
+impl SkillRead {
+    /// Read a skill's instructions, or a file it references by relative path.
+    pub async fn skill_read(
+        self,
+        /// Skill name
+        skill: String,
+        /// Path relative to the skill directory (default: SKILL.md). Directories are listed.
+        path: Option<String>,
+        /// Optional start character index (default: 0)
+        start_char: Option<usize>,
+        /// Optional number of characters to return (default: 5000)
+        num_chars: Option<usize>,
+    ) -> String {
+        match self.read(&skill, path.as_deref().unwrap_or(SKILL_FILE)) {
+            Ok(content) => truncate_with_hint(
+                content,
+                start_char.unwrap_or(0),
+                num_chars.unwrap_or(DEFAULT_NUM_CHARS),
+            ),
+            Err(e) => e,
+        }
+    }
+}
+
+impl SkillRead {
+    fn read(&self, skill: &str, path: &str) -> Result<String, String> {
+        let dir = self.skills.get(skill).ok_or_else(|| {
+            format!(
+                "Unknown skill '{}'. Available skills: {}",
+                skill,
+                self.skills.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })?;
+        let target = resolve_within(dir, path)?;
+        if target.is_dir() {
+            return Ok(list_dir(&target));
+        }
+        std::fs::read_to_string(&target).map_err(|e| format!("Failed to read '{}': {}", path, e))
+    }
+}
+
+/// Resolve a relative path inside `dir`, rejecting paths that escape it
+fn resolve_within(dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let root = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve skill directory: {}", e))?;
+    let target = root
+        .join(path)
+        .canonicalize()
+        .map_err(|_| format!("File not found in skill: {}", path))?;
+    if !target.starts_with(&root) {
+        return Err(format!("Path is outside the skill directory: {}", path));
+    }
+    Ok(target)
+}
+
+/// List directory entries, marking subdirectories with a trailing slash
+fn list_dir(dir: &Path) -> String {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_dir() {
+                format!("{}/", name)
+            } else {
+                name
+            }
+        })
+        .collect();
+    names.sort();
+    names.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_within_skill() {
+        let temp =
+            crate::util::temp::TempDir::new(&format!("firekeeper-skill-{}", std::process::id()));
+        let dir = temp.path().to_path_buf();
+        std::fs::create_dir_all(dir.join("refs")).unwrap();
+        std::fs::write(dir.join(SKILL_FILE), "See refs/guide.md").unwrap();
+        std::fs::write(dir.join("refs/guide.md"), "guide").unwrap();
+        let tool = SkillRead::new(BTreeMap::from([("demo".to_string(), dir.clone())]));
+
+        let skill = tool.read("demo", SKILL_FILE);
+        let guide = tool.read("demo", "refs/guide.md");
+        let listing = tool.read("demo", ".");
+        let escape = tool.read("demo", "../");
+        let unknown = tool.read("other", SKILL_FILE);
+
+        assert_eq!(skill.unwrap(), "See refs/guide.md");
+        assert_eq!(guide.unwrap(), "guide");
+        assert_eq!(listing.unwrap(), "SKILL.md\nrefs/");
+        assert!(escape.unwrap_err().contains("outside"));
+        assert!(unknown.unwrap_err().contains("Available skills: demo"));
+    }
+}
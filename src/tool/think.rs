@@ -1,3 +1,5 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tiny_loop::tool::tool;
 
 /// Maximum lines before overthinking warning (brief reasoning should be 2-4 sentences)
@@ -5,22 +7,44 @@ const MAX_REASONING_LINES: usize = 10;
 /// Maximum characters before overthinking warning (roughly 2-3 paragraphs)
 const MAX_REASONING_CHARS: usize = 1500;
 
-/// Think through whether something is a violation (keep reasoning brief and focused).
-/// MUST be called before reporting any violations to reason about the findings.
+/// Tool for brief reasoning, remembering whether it has been called
+#[derive(Clone, Default)]
+pub struct Think {
+    called: Arc<AtomicBool>,
+}
+
+impl Think {
+    /// Create a new Think tool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the agent has called `think` yet
+    pub fn called(&self) -> bool {
+        self.called.load(Ordering::SeqCst)
+    }
+}
+
 #[tool]
-pub async fn think(
-    /// Brief reasoning (2-4 sentences) about whether the code violates the rule, considering exceptions and context
-    reasoning: String,
-) -> String {
-    let line_count = reasoning.lines().count();
-    let char_count = reasoning.chars().count();
+impl Think {
+    /// Think through whether something is a violation (keep reasoning brief and focused).
+    /// MUST be called before reporting any violations to reason about the findings.
+    pub async fn think(
+        self,
+        /// Brief reasoning (2-4 sentences) about whether the code violates the rule, considering exceptions and context
+        reasoning: String,
+    ) -> String {
+        self.called.store(true, Ordering::SeqCst);
+        let line_count = reasoning.lines().count();
+        let char_count = reasoning.chars().count();
 
-    if line_count > MAX_REASONING_LINES || char_count > MAX_REASONING_CHARS {
-        format!(
-            "OK. Note: Overthinking detected ({} lines, {} chars). Keep reasoning concise and focused.",
-            line_count, char_count
-        )
-    } else {
-        "OK".to_string()
+        if line_count > MAX_REASONING_LINES || char_count > MAX_REASONING_CHARS {
+            format!(
+                "OK. Note: Overthinking detected ({} lines, {} chars). Keep reasoning concise and focused.",
+                line_count, char_count
+            )
+        } else {
+            "OK".to_string()
+        }
     }
 }
@@ -48,6 +48,31 @@ pub fn truncate_with_hint(content: String, start: usize, len: usize) -> String {
     }
 }
 
+/// Default number of lines for line-based pagination
+pub const DEFAULT_NUM_LINES: usize = 500;
+
+/// Return `len` lines starting at 1-indexed `start_line`, with a pagination hint if more remain
+pub fn truncate_lines_with_hint(content: &str, start_line: usize, len: usize) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let total = lines.len();
+    let start = start_line.max(1) - 1;
+    let end = start.saturating_add(len).min(total);
+    if start >= total {
+        return format!("No lines after line {} ({} lines total)", total, total);
+    }
+    let mut result = lines[start..end].join("\n");
+    if end < total {
+        result.push_str(&format!(
+            "\n\n---\ntruncated [lines {}-{}/{}]\nHint: Use start_line={} to read more. Use num_lines to control how many lines to return.",
+            start + 1,
+            end,
+            total,
+            end + 1
+        ));
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,4 +108,20 @@ mod tests {
         assert_eq!(result.content, "");
         assert!(!result.truncated);
     }
+
+    #[test]
+    fn test_truncate_lines_with_hint() {
+        let content = "a\nb\nc\nd";
+        assert_eq!(truncate_lines_with_hint(content, 1, 10), content);
+        let first = truncate_lines_with_hint(content, 1, 2);
+        assert!(first.starts_with("a\nb\n\n---\ntruncated [lines 1-2/4]"));
+        assert!(first.ends_with(
+            "Use start_line=3 to read more. Use num_lines to control how many lines to return."
+        ));
+        assert_eq!(truncate_lines_with_hint(content, 3, 2), "c\nd");
+        assert_eq!(
+            truncate_lines_with_hint(content, 9, 2),
+            "No lines after line 4 (4 lines total)"
+        );
+    }
 }
@@ -12,4 +12,56 @@ pub struct Violation {
     pub start_line: u32,
     /// End line (inclusive)
     pub end_line: u32,
+    /// Stable id of the violated rule, set when results are collected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    pub rule_id: Option<String>,
+    /// Owners of the file from CODEOWNERS or the `[owners]` config table
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(skip)]
+    pub owners: Vec<String>,
+}
+
+impl Violation {
+    /// Stable fingerprint identifying this violation of a rule across runs.
+    ///
+    /// Derived from rule name, file path, and line range (FNV-1a, 64-bit),
+    /// so it does not change with the LLM's wording of the detail.
+    pub fn fingerprint(&self, rule: &str) -> String {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let key = format!(
+            "{}\0{}\0{}\0{}",
+            rule, self.file, self.start_line, self.end_line
+        );
+        let hash = key.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+        format!("{:016x}", hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_detail() {
+        let a = Violation {
+            file: "src/main.rs".into(),
+            detail: "one wording".into(),
+            start_line: 1,
+            end_line: 3,
+            rule_id: None,
+            owners: vec![],
+        };
+        let b = Violation {
+            detail: "another wording".into(),
+            ..a.clone()
+        };
+        assert_eq!(a.fingerprint("Rule"), b.fingerprint("Rule"));
+        assert_ne!(a.fingerprint("Rule"), a.fingerprint("Other Rule"));
+        assert_eq!(a.fingerprint("Rule").len(), 16);
+    }
 }
@@ -0,0 +1,135 @@
+use super::git::{DEFAULT_REMOTE, git_stdout, git_succeeds, is_shallow};
+use tracing::{debug, info, warn};
+
+/// Base reference provided by a CI merge/pull request pipeline
+#[derive(Debug, PartialEq)]
+pub enum CiBase {
+    /// Exact merge-base commit (GitLab `CI_MERGE_REQUEST_DIFF_BASE_SHA`)
+    Commit(String),
+    /// Target branch name, resolved against the remote
+    Branch(String),
+}
+
+/// Read the CI base from environment variables
+///
+/// - GitHub Actions: `GITHUB_BASE_REF` (set on pull_request events)
+/// - GitLab CI: `CI_MERGE_REQUEST_DIFF_BASE_SHA`, then `CI_MERGE_REQUEST_TARGET_BRANCH_NAME`
+pub fn ci_base_from_env(env: impl Fn(&str) -> Option<String>) -> Option<CiBase> {
+    let var = |name: &str| env(name).filter(|v| !v.trim().is_empty());
+
+    if var("GITHUB_ACTIONS").is_some()
+        && let Some(branch) = var("GITHUB_BASE_REF")
+    {
+        return Some(CiBase::Branch(branch));
+    }
+    if var("GITLAB_CI").is_some() {
+        if let Some(sha) = var("CI_MERGE_REQUEST_DIFF_BASE_SHA") {
+            return Some(CiBase::Commit(sha));
+        }
+        if let Some(branch) = var("CI_MERGE_REQUEST_TARGET_BRANCH_NAME") {
+            return Some(CiBase::Branch(branch));
+        }
+    }
+    None
+}
+
+/// Resolve a CI base to a commit, fetching it if missing from a shallow clone
+fn resolve(ci_base: &CiBase) -> Option<String> {
+    match ci_base {
+        CiBase::Commit(sha) => {
+            let commit = format!("{}^{{commit}}", sha);
+            if !git_succeeds(&["cat-file", "-e", &commit]) {
+                debug!("Fetching base commit {}", sha);
+                git_succeeds(&["fetch", "--no-tags", "--depth=1", DEFAULT_REMOTE, sha]);
+            }
+            git_succeeds(&["cat-file", "-e", &commit]).then(|| sha.clone())
+        }
+        CiBase::Branch(branch) => {
+            let remote_ref = format!("{}/{}", DEFAULT_REMOTE, branch);
+            if !git_succeeds(&["rev-parse", "--verify", "--quiet", &remote_ref]) {
+                debug!("Fetching base branch {}", remote_ref);
+                let refspec = format!(
+                    "+refs/heads/{0}:refs/remotes/{1}/{0}",
+                    branch, DEFAULT_REMOTE
+                );
+                git_succeeds(&["fetch", "--no-tags", DEFAULT_REMOTE, &refspec]);
+            }
+            if let Some(merge_base) = git_stdout(&["merge-base", &remote_ref, "HEAD"]) {
+                return Some(merge_base);
+            }
+            // Shallow clones may lack the common ancestor
+            if is_shallow() {
+                debug!("Unshallowing to find merge base with {}", remote_ref);
+                git_succeeds(&["fetch", "--no-tags", "--unshallow", DEFAULT_REMOTE]);
+                if let Some(merge_base) = git_stdout(&["merge-base", &remote_ref, "HEAD"]) {
+                    return Some(merge_base);
+                }
+            }
+            git_succeeds(&["rev-parse", "--verify", "--quiet", &remote_ref]).then_some(remote_ref)
+        }
+    }
+}
+
+/// Detect the review base when running in a CI merge/pull request pipeline
+pub fn detect_ci_base() -> Option<String> {
+    let ci_base = ci_base_from_env(|name| std::env::var(name).ok())?;
+    debug!("Detected CI base: {:?}", ci_base);
+    match resolve(&ci_base) {
+        Some(base) => {
+            info!("Using CI base {}", base);
+            Some(base)
+        }
+        None => {
+            warn!("Failed to resolve CI base {:?}", ci_base);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from(vars: &[(&str, &str)]) -> Option<CiBase> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        ci_base_from_env(|name| vars.get(name).map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn test_github_pull_request() {
+        assert_eq!(
+            from(&[("GITHUB_ACTIONS", "true"), ("GITHUB_BASE_REF", "main")]),
+            Some(CiBase::Branch("main".into()))
+        );
+        // Push events have an empty GITHUB_BASE_REF
+        assert_eq!(
+            from(&[("GITHUB_ACTIONS", "true"), ("GITHUB_BASE_REF", "")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gitlab_merge_request() {
+        assert_eq!(
+            from(&[
+                ("GITLAB_CI", "true"),
+                ("CI_MERGE_REQUEST_DIFF_BASE_SHA", "abc123"),
+                ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", "main"),
+            ]),
+            Some(CiBase::Commit("abc123".into()))
+        );
+        assert_eq!(
+            from(&[
+                ("GITLAB_CI", "true"),
+                ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", "develop"),
+            ]),
+            Some(CiBase::Branch("develop".into()))
+        );
+    }
+
+    #[test]
+    fn test_not_ci() {
+        assert_eq!(from(&[("GITHUB_BASE_REF", "main")]), None);
+    }
+}
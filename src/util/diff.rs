@@ -1,3 +1,33 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use toml_scaffold::TomlScaffold;
+
+/// Context lines kept around changes in compact diffs
+const COMPACT_CONTEXT_LINES: usize = 1;
+
+/// File header lines kept in compact diffs, telling what happened to the file
+const COMPACT_HEADERS: [&str; 5] = [
+    "new file mode",
+    "deleted file mode",
+    "rename from",
+    "rename to",
+    "Binary files",
+];
+
+/// Format of diffs in worker prompts and the `diff` tool
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStyle {
+    /// `git diff` output as is
+    #[default]
+    Unified,
+    /// Hunks without file headers, keeping one line of context around changes
+    Compact,
+}
+
+impl TomlScaffold for DiffStyle {}
+
 /// Check if a file's diff should be included (excludes lock and generated files)
 pub fn should_include_diff(file: &str) -> bool {
     let file_lower = file.to_lowercase();
@@ -26,3 +56,160 @@ pub fn should_include_diff(file: &str) -> bool {
 
     true
 }
+
+/// A line of a hunk: `+`, `-` or ` `, its line number in the old and new file, and its text
+type HunkLine<'a> = (char, usize, usize, &'a str);
+
+/// Start lines in the old and new file of a hunk header, e.g. `@@ -12,7 +12,9 @@ fn main()`
+pub fn parse_hunk_header(line: &str) -> Option<(usize, usize)> {
+    let mut ranges = line.strip_prefix("@@ -")?.split(' ');
+    let start = |range: &str| range.split(',').next()?.parse::<usize>().ok();
+    let old = start(ranges.next()?)?;
+    let new = start(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// Append the changes of a hunk with their surrounding context, starting each run of lines
+/// that is not contiguous with a header
+fn push_hunk(output: &mut String, hunk: &[HunkLine], line_numbers: bool) {
+    let changes: Vec<usize> = (0..hunk.len()).filter(|&i| hunk[i].0 != ' ').collect();
+    let kept = |i: usize| {
+        changes
+            .iter()
+            .any(|&change| change.abs_diff(i) <= COMPACT_CONTEXT_LINES)
+    };
+    let width = hunk.last().map_or(1, |line| line.2.to_string().len());
+    let mut previous_kept = false;
+    for (i, &(kind, old, new, text)) in hunk.iter().enumerate() {
+        if !kept(i) {
+            previous_kept = false;
+            continue;
+        }
+        if !previous_kept {
+            if line_numbers {
+                output.push_str("@@\n");
+            } else {
+                output.push_str(&format!("@@ -{} +{} @@\n", old, new));
+            }
+        }
+        previous_kept = true;
+        if !line_numbers {
+            output.push_str(&format!("{}{}\n", kind, text));
+        } else if kind == '-' {
+            output.push_str(&format!("{:width$} -{}\n", "", text));
+        } else {
+            output.push_str(&format!("{:>width$} {}{}\n", new, kind, text));
+        }
+    }
+}
+
+/// Shorten `git diff` output for prompts: drop file headers and long runs of context, and
+/// optionally number lines with their line in the new file, leaving removed lines unnumbered
+pub fn compact_diff(diff: &str, line_numbers: bool) -> String {
+    let mut output = String::new();
+    let mut hunk: Vec<HunkLine> = Vec::new();
+    let (mut old, mut new) = (0, 0);
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if let Some((old_start, new_start)) = parse_hunk_header(line) {
+            push_hunk(&mut output, &hunk, line_numbers);
+            hunk.clear();
+            (old, new) = (old_start, new_start);
+            in_hunk = true;
+            continue;
+        }
+        let text = line.get(1..).unwrap_or("");
+        match line.chars().next() {
+            Some('+') if in_hunk => {
+                hunk.push(('+', old, new, text));
+                new += 1;
+            }
+            Some('-') if in_hunk => {
+                hunk.push(('-', old, new, text));
+                old += 1;
+            }
+            Some(' ') | None if in_hunk => {
+                hunk.push((' ', old, new, text));
+                old += 1;
+                new += 1;
+            }
+            // `\ No newline at end of file`
+            Some('\\') if in_hunk => {}
+            _ => {
+                // Headers, also of the next file in a multi-file diff
+                push_hunk(&mut output, &hunk, line_numbers);
+                hunk.clear();
+                in_hunk = false;
+                if COMPACT_HEADERS
+                    .iter()
+                    .any(|header| line.starts_with(header))
+                {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+            }
+        }
+    }
+    push_hunk(&mut output, &hunk, line_numbers);
+    output
+}
+
+/// Apply the configured diff style to each file's `git diff` output
+pub fn restyle_diffs(
+    diffs: HashMap<String, String>,
+    style: DiffStyle,
+    line_numbers: bool,
+) -> HashMap<String, String> {
+    match style {
+        DiffStyle::Unified => diffs,
+        DiffStyle::Compact => diffs
+            .into_iter()
+            .map(|(file, diff)| {
+                let compact = compact_diff(&diff, line_numbers);
+                (file, compact)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a9c4f2d 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -8,9 +8,9 @@ fn main() {
+ let a = 1;
+-let b = 2;
++let b = 3;
+ let c = 4;
+ let d = 5;
+ let e = 6;
+ let f = 7;
++let g = 8;
+ let h = 9;
+\\ No newline at end of file
+";
+
+    #[test]
+    fn test_compact_diff() {
+        assert_eq!(
+            compact_diff(DIFF, false),
+            "@@ -8 +8 @@\n let a = 1;\n-let b = 2;\n+let b = 3;\n let c = 4;\n\
+             @@ -13 +13 @@\n let f = 7;\n+let g = 8;\n let h = 9;\n"
+        );
+        assert_eq!(
+            compact_diff(DIFF, true),
+            "@@\n 8  let a = 1;\n   -let b = 2;\n 9 +let b = 3;\n10  let c = 4;\n\
+             @@\n13  let f = 7;\n14 +let g = 8;\n15  let h = 9;\n"
+        );
+        let renamed =
+            "diff --git a/a.rs b/b.rs\nsimilarity index 100%\nrename from a.rs\nrename to b.rs\n";
+        assert_eq!(
+            compact_diff(renamed, false),
+            "rename from a.rs\nrename to b.rs\n"
+        );
+    }
+}
@@ -1,9 +1,13 @@
-use std::collections::HashMap;
-use std::process::Command;
-use tracing::debug;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::{debug, info, warn};
 
 const GIT_EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
 
+/// Remote fetched from when a shallow clone lacks the base
+pub(super) const DEFAULT_REMOTE: &str = "origin";
+
 /// Represents the base reference for git operations
 #[derive(Debug)]
 pub enum Base {
@@ -11,16 +15,27 @@ pub enum Base {
     Root,
     /// Review changes against a specific commit
     Commit(String),
+    /// Review changes staged in the index against HEAD
+    Staged,
+    /// Review an explicit set of files, skipping git change detection
+    Files(Vec<String>),
 }
 
 impl Base {
     /// Parse a base string into a Base enum
     ///
-    /// - Empty string: auto-detect HEAD or ^ based on uncommitted changes
+    /// - Empty string: merge request/pull request base in CI, otherwise
+    ///   auto-detect HEAD or ^ based on uncommitted changes
     /// - "ROOT": all files
     /// - "^" or "~": relative to HEAD
     /// - Otherwise: commit hash or reference
     pub fn parse(diff_base: &str) -> Self {
+        if diff_base.is_empty()
+            && let Some(ci_base) = super::detect_ci_base()
+        {
+            return Self::Commit(ci_base);
+        }
+
         let base = if diff_base.is_empty() {
             debug!("Base is empty, checking for uncommitted changes");
             let has_uncommitted = Command::new("git")
@@ -47,16 +62,286 @@ impl Base {
     /// Get commit reference if available (None for Root)
     fn as_commit_ref(&self) -> Option<&str> {
         match self {
-            Self::Root => None,
+            Self::Root | Self::Staged | Self::Files(_) => None,
             Self::Commit(s) => Some(s),
         }
     }
 
-    /// Get the base reference for git diff operations
-    fn as_diff_base(&self) -> &str {
+    /// Whether files are reviewed in full rather than as changes
+    pub fn is_whole_file(&self) -> bool {
+        matches!(self, Self::Root | Self::Files(_))
+    }
+
+    /// Revision the changes are compared against: the base commit, `HEAD` for staged
+    /// changes, or the empty tree when reviewing whole files
+    pub fn git_ref(&self) -> &str {
         match self {
-            Self::Root => GIT_EMPTY_TREE,
+            Self::Root | Self::Files(_) => GIT_EMPTY_TREE,
             Self::Commit(s) => s,
+            Self::Staged => "HEAD",
+        }
+    }
+
+    /// Commit SHA the changes are compared against, `HEAD` for staged changes
+    pub fn sha(&self) -> Option<String> {
+        match self {
+            Self::Root | Self::Files(_) => None,
+            Self::Commit(s) => rev_parse(s),
+            Self::Staged => rev_parse("HEAD"),
+        }
+    }
+
+    /// Get the base arguments for git diff operations
+    fn as_diff_args(&self) -> Vec<&str> {
+        match self {
+            Self::Root | Self::Files(_) => vec![GIT_EMPTY_TREE],
+            Self::Commit(s) => vec![s],
+            Self::Staged => vec!["--cached"],
+        }
+    }
+}
+
+pub(super) fn git_stdout(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub(super) fn git_succeeds(args: &[&str]) -> bool {
+    Command::new("git")
+        .args(args)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+pub(super) fn is_shallow() -> bool {
+    git_stdout(&["rev-parse", "--is-shallow-repository"]).as_deref() == Some("true")
+}
+
+/// Whether a revision is an abbreviated or full commit SHA, which can be fetched directly
+fn is_commit_sha(rev: &str) -> bool {
+    (7..=40).contains(&rev.len()) && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn shallow_remediation(rev: &str, fetch: bool) -> String {
+    let cause = if fetch {
+        format!(
+            "Base '{}' is missing from this shallow clone and could not be fetched from {}",
+            rev, DEFAULT_REMOTE
+        )
+    } else {
+        format!(
+            "Base '{}' is missing from this shallow clone (review.fetch_missing_base is disabled)",
+            rev
+        )
+    };
+    format!(
+        "{}. Clone with full history instead, e.g. `fetch-depth: 0` for actions/checkout or \
+         `GIT_DEPTH: 0` in GitLab CI, or run `git fetch --unshallow` before reviewing",
+        cause
+    )
+}
+
+/// Make sure the base of a review is present, so a missing base fails the review instead
+/// of producing an empty diff that passes.
+///
+/// With `fetch`, a shallow clone missing the base fetches it from `origin`: commit SHAs
+/// directly, other revisions (e.g. `HEAD^`, tags) by unshallowing. History between the
+/// base and HEAD, listed as commit messages in prompts, is fetched the same way.
+pub fn ensure_base(base: &Base, fetch: bool) -> Result<(), String> {
+    let Base::Commit(rev) = base else {
+        return Ok(());
+    };
+    if rev_parse(rev).is_none() {
+        if !is_shallow() {
+            return Err(format!("Unknown base revision '{}'", rev));
+        }
+        if !fetch {
+            return Err(shallow_remediation(rev, fetch));
+        }
+        info!(
+            "Base {} is missing from the shallow clone, fetching it",
+            rev
+        );
+        if is_commit_sha(rev) {
+            git_succeeds(&["fetch", "--no-tags", "--depth=1", DEFAULT_REMOTE, rev]);
+        }
+        if rev_parse(rev).is_none() {
+            git_succeeds(&["fetch", "--tags", "--unshallow", DEFAULT_REMOTE]);
+        }
+        if rev_parse(rev).is_none() {
+            return Err(shallow_remediation(rev, fetch));
+        }
+    }
+
+    if is_shallow() && git_stdout(&["merge-base", rev, "HEAD"]).is_none() {
+        if fetch {
+            info!("Fetching history between {} and HEAD", rev);
+            git_succeeds(&["fetch", "--no-tags", "--unshallow", DEFAULT_REMOTE]);
+        }
+        if git_stdout(&["merge-base", rev, "HEAD"]).is_none() {
+            warn!(
+                "{} shares no history with HEAD in this shallow clone; the diff is complete, \
+                 but commit messages given to reviewers may not be",
+                rev
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a revision to a commit SHA
+pub fn rev_parse(rev: &str) -> Option<String> {
+    git_stdout(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{}^{{commit}}", rev),
+    ])
+}
+
+/// Email of the configured git user
+pub fn user_email() -> Option<String> {
+    git_stdout(&["config", "user.email"]).filter(|email| !email.is_empty())
+}
+
+/// Content of a file as committed at `rev`, `None` if the revision lacks it
+pub fn show_file(rev: &str, path: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./");
+    let output = Command::new("git")
+        .args(["show", &format!("{}:./{}", rev, path)])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Write the files staged in the index into a temporary directory, removed once dropped
+pub fn checkout_index() -> Result<super::temp::TempDir, String> {
+    let dir = super::temp::TempDir::new(&format!("firekeeper-index-{}", std::process::id()));
+    let prefix = format!("{}/", dir.path().to_string_lossy());
+    let root = git_stdout(&["rev-parse", "--show-toplevel"])
+        .ok_or_else(|| "Not in a git repository".to_string())?;
+    let status = Command::new("git")
+        .args([
+            "-C",
+            &root,
+            "checkout-index",
+            "--all",
+            &format!("--prefix={}", prefix),
+        ])
+        .status()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+    if !status.success() {
+        return Err(format!("Failed to check out the index into {}", prefix));
+    }
+    Ok(dir)
+}
+
+/// Check out a revision of the current repository into a temporary directory, removed
+/// once dropped, sharing its objects instead of copying them
+pub fn checkout_revision(rev: &str) -> Result<super::temp::TempDir, String> {
+    let sha = rev_parse(rev).ok_or_else(|| format!("Unknown revision '{}'", rev))?;
+    let root = git_stdout(&["rev-parse", "--show-toplevel"])
+        .ok_or_else(|| "Not in a git repository".to_string())?;
+    let dir = super::temp::TempDir::new(&format!(
+        "firekeeper-{}-{}",
+        &sha[..12.min(sha.len())],
+        std::process::id()
+    ));
+    let dir_str = dir.path().to_string_lossy();
+    for args in [
+        vec![
+            "clone",
+            "--quiet",
+            "--shared",
+            "--no-checkout",
+            &root,
+            &dir_str,
+        ],
+        vec!["-C", &dir_str, "checkout", "--quiet", "--detach", &sha],
+    ] {
+        let status = Command::new("git")
+            .args(&args)
+            .status()
+            .map_err(|e| format!("Failed to execute git: {}", e))?;
+        if !status.success() {
+            return Err(format!("Failed to check out {} into {}", rev, dir_str));
+        }
+    }
+    Ok(dir)
+}
+
+/// Files tracked by git under a directory
+pub fn list_files(dir: &str) -> Vec<String> {
+    git_stdout(&["ls-files", "--", dir])
+        .map(|stdout| stdout.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Files marked `linguist-vendored` in `.gitattributes`
+pub fn vendored_by_attributes(files: &[String]) -> HashSet<String> {
+    if files.is_empty() {
+        return HashSet::new();
+    }
+    let Ok(mut child) = Command::new("git")
+        .args(["check-attr", "--stdin", "-z", "linguist-vendored"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return HashSet::new();
+    };
+    let input: String = files.iter().map(|f| format!("{}\0", f)).collect();
+    let stdin = child.stdin.take();
+    // Written from a thread so a full stdout pipe cannot block the input
+    let writer = std::thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    let output = child.wait_with_output();
+    let _ = writer.join();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_vendored_attributes(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => {
+            debug!("Failed to read linguist-vendored attributes");
+            HashSet::new()
+        }
+    }
+}
+
+/// Files set vendored in `git check-attr -z` output: NUL-separated path, attribute, value
+fn parse_vendored_attributes(output: &str) -> HashSet<String> {
+    let fields: Vec<&str> = output.split('\0').collect();
+    fields
+        .chunks_exact(3)
+        .filter(|entry| matches!(entry[2], "set" | "true"))
+        .map(|entry| entry[0].to_string())
+        .collect()
+}
+
+/// Installed git version, e.g. `git version 2.43.0`
+pub fn git_version() -> Option<String> {
+    git_stdout(&["--version"])
+}
+
+impl std::fmt::Display for Base {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Root => write!(f, "ROOT"),
+            Self::Commit(s) => write!(f, "{}", s),
+            Self::Staged => write!(f, "staged"),
+            Self::Files(files) => write!(f, "{} explicit files", files.len()),
         }
     }
 }
@@ -71,6 +356,11 @@ pub fn get_changed_files(base: &Base) -> Vec<String> {
             .args(["diff", "--name-only", commit])
             .output()
             .expect("Failed to execute git diff"),
+        Base::Staged => Command::new("git")
+            .args(["diff", "--cached", "--name-only"])
+            .output()
+            .expect("Failed to execute git diff"),
+        Base::Files(files) => return files.clone(),
     };
 
     String::from_utf8_lossy(&output.stdout)
@@ -81,18 +371,28 @@ pub fn get_changed_files(base: &Base) -> Vec<String> {
 
 pub fn get_diffs(base: &Base, files: &[String]) -> HashMap<String, String> {
     let mut diffs = HashMap::new();
-    let diff_base = base.as_diff_base();
+    let diff_args = base.as_diff_args();
 
     for file in files {
+        // An explicit file git does not track has no blob to diff, so it is diffed as new
+        if matches!(base, Base::Files(_))
+            && !git_succeeds(&["ls-files", "--error-unmatch", "--", file])
+        {
+            if let Some(diff) = untracked_diff(file) {
+                diffs.insert(file.clone(), diff);
+            }
+            continue;
+        }
         if let Ok(output) = Command::new("git")
-            .args(["diff", diff_base, "--", file])
+            .arg("diff")
+            .args(&diff_args)
+            .args(["--", file])
             .output()
+            && output.status.success()
         {
-            if output.status.success() {
-                let diff = String::from_utf8_lossy(&output.stdout).to_string();
-                if !diff.is_empty() {
-                    diffs.insert(file.clone(), diff);
-                }
+            let diff = String::from_utf8_lossy(&output.stdout).to_string();
+            if !diff.is_empty() {
+                diffs.insert(file.clone(), diff);
             }
         }
     }
@@ -100,6 +400,17 @@ pub fn get_diffs(base: &Base, files: &[String]) -> HashMap<String, String> {
     diffs
 }
 
+/// Diff of an untracked file against /dev/null, showing all of its lines as added
+fn untracked_diff(file: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["diff", "--no-index", "--", "/dev/null", file])
+        .output()
+        .ok()?;
+    // `--no-index` exits with 1 when the files differ
+    let diff = String::from_utf8_lossy(&output.stdout).to_string();
+    (output.status.code() == Some(1) && !diff.is_empty()).then_some(diff)
+}
+
 pub fn get_commit_messages(base: &Base) -> String {
     let Some(commit) = base.as_commit_ref() else {
         return String::new();
@@ -112,3 +423,65 @@ pub fn get_commit_messages(base: &Base) -> String {
 
     String::from_utf8_lossy(&output.stdout).trim().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shallow_base_remediation() {
+        assert!(is_commit_sha("4b825dc"));
+        assert!(!is_commit_sha("HEAD^"));
+        assert!(!is_commit_sha("v1.2.0"));
+        let message = shallow_remediation("HEAD^", false);
+        assert!(message.starts_with("Base 'HEAD^' is missing from this shallow clone"));
+        assert!(message.contains("fetch-depth: 0"));
+    }
+
+    #[test]
+    fn test_parse_vendored_attributes() {
+        let output = "third_party/a.c\0linguist-vendored\0set\0src/main.rs\0linguist-vendored\0unspecified\0lib/b.js\0linguist-vendored\0true\0docs/c.md\0linguist-vendored\0false\0";
+        let mut vendored: Vec<String> = parse_vendored_attributes(output).into_iter().collect();
+        vendored.sort();
+        assert_eq!(vendored, ["lib/b.js", "third_party/a.c"]);
+    }
+
+    #[test]
+    fn test_untracked_explicit_file_diff() {
+        let dir = crate::util::temp::TempDir::new(&format!(
+            "firekeeper-untracked-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.path()).unwrap();
+        let file = dir.path().join("new.rs").to_string_lossy().to_string();
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let diffs = get_diffs(
+            &Base::Files(vec![file.clone()]),
+            std::slice::from_ref(&file),
+        );
+        assert!(diffs[&file].contains("+fn main() {}"));
+    }
+
+    #[test]
+    fn test_show_file() {
+        let manifest = show_file("HEAD", "./Cargo.toml").unwrap();
+        assert!(manifest.contains("name = \"firekeeper\""));
+        assert_eq!(show_file("HEAD", "missing.toml"), None);
+    }
+
+    #[test]
+    fn test_checkout_revision_removed_on_drop() {
+        let dir = checkout_revision("HEAD").unwrap();
+        let path = dir.path().to_path_buf();
+        assert!(path.join("Cargo.toml").exists());
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_checkout_index() {
+        let dir = checkout_index().unwrap();
+        assert!(dir.path().join("Cargo.toml").exists());
+        assert!(dir.path().join("src/main.rs").exists());
+    }
+}
@@ -0,0 +1,26 @@
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a 64-bit hash, stable across platforms and releases
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Hash NUL-separated parts into a 16-character hex string
+pub fn hash_parts(parts: &[&str]) -> String {
+    format!("{:016x}", fnv1a_64(parts.join("\0").as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_parts() {
+        assert_eq!(hash_parts(&[]), format!("{:016x}", FNV_OFFSET_BASIS));
+        assert_ne!(hash_parts(&["a", "bc"]), hash_parts(&["ab", "c"]));
+        assert_eq!(hash_parts(&["a"]).len(), 16);
+    }
+}
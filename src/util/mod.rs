@@ -1,5 +1,14 @@
+pub mod ci;
 pub mod diff;
 pub mod git;
+pub mod hash;
+pub mod source;
+pub mod temp;
+pub mod template;
 
+pub use ci::*;
 pub use diff::*;
 pub use git::*;
+pub use hash::*;
+pub use source::*;
+pub use template::*;
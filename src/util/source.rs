@@ -0,0 +1,74 @@
+/// Read lines `start..=end` (1-indexed) of a file with surrounding context, prefixed by line numbers
+pub fn read_snippet(path: &str, start: u32, end: u32, context: u32) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    Some(format_snippet(&content, start, end, context))
+}
+
+/// Format lines `start..=end` (1-indexed) of content with surrounding context.
+/// Lines inside the range are marked with `>`.
+pub fn format_snippet(content: &str, start: u32, end: u32, context: u32) -> String {
+    let first = start.saturating_sub(context).max(1) as usize;
+    let last = (end.saturating_add(context) as usize).min(content.lines().count());
+    let width = last.to_string().len();
+
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(n, _)| *n >= first && *n <= last)
+        .map(|(n, line)| {
+            let marker = if n >= start as usize && n <= end as usize {
+                '>'
+            } else {
+                ' '
+            };
+            format!("{} {:>width$} | {}", marker, n, line, width = width)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read a list of file paths, one per line, from a file or stdin (`-`).
+/// Blank lines and `#` comments are skipped.
+pub fn read_file_list(path: &str) -> std::io::Result<Vec<String>> {
+    let content = if path == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+    Ok(parse_file_list(&content))
+}
+
+fn parse_file_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix("./").unwrap_or(line).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_snippet() {
+        let content = "a\nb\nc\nd\ne";
+        assert_eq!(
+            format_snippet(content, 2, 3, 1),
+            "  1 | a\n> 2 | b\n> 3 | c\n  4 | d"
+        );
+    }
+
+    #[test]
+    fn test_format_snippet_clamps_range() {
+        assert_eq!(format_snippet("a\nb", 1, 9, 5), "> 1 | a\n> 2 | b");
+    }
+
+    #[test]
+    fn test_parse_file_list() {
+        let content = "src/main.rs\n\n# comment\n./src/lib.rs  \n";
+        assert_eq!(parse_file_list(content), vec!["src/main.rs", "src/lib.rs"]);
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Temporary directories not yet removed, for exits that skip destructors
+static PENDING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Directory in the system temp dir, removed when dropped or on [`exit`]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Reserve `name` in the system temp dir; the caller creates it, e.g. with `git clone`
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(name);
+        PENDING.lock().unwrap().push(path.clone());
+        Self { path }
+    }
+
+    /// Reserve `name` under `parent` instead, e.g. for tests that need a path relative to
+    /// the repository
+    #[cfg(test)]
+    pub fn new_in(parent: &Path, name: &str) -> Self {
+        let path = parent.join(name);
+        PENDING.lock().unwrap().push(path.clone());
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        PENDING.lock().unwrap().retain(|path| *path != self.path);
+        remove(&self.path);
+    }
+}
+
+fn remove(path: &Path) {
+    // Windows cannot remove the working directory, which a checkout usually is
+    if std::env::current_dir().is_ok_and(|dir| dir.starts_with(path)) {
+        let _ = std::env::set_current_dir(std::env::temp_dir());
+    }
+    match std::fs::remove_dir_all(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            debug!("Failed to remove {}: {}", path.display(), e);
+        }
+        _ => {}
+    }
+}
+
+/// Remove the temporary directories still in use and exit, as `std::process::exit`
+/// skips destructors
+pub fn exit(code: i32) -> ! {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap());
+    for path in pending {
+        remove(&path);
+    }
+    std::process::exit(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_dir_removed_on_drop() {
+        let dir = TempDir::new(&format!("firekeeper-temp-{}", std::process::id()));
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        let path = dir.path().to_path_buf();
+        assert!(PENDING.lock().unwrap().contains(&path));
+        drop(dir);
+        assert!(!path.exists());
+        assert!(!PENDING.lock().unwrap().contains(&path));
+    }
+}
@@ -0,0 +1,44 @@
+/// Expand `{name}` placeholders in a single pass, so expanded values are never re-scanned.
+/// Unknown placeholders are kept as-is.
+pub fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find('}').and_then(|close| {
+            let name = &after[..close];
+            values
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| (close, *value))
+        }) {
+            Some((close, value)) => {
+                output.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        assert_eq!(
+            render_template(
+                "Team context.\n{rule}{diffs}{unknown}",
+                &[("rule", "R {diffs}\n"), ("diffs", "D")]
+            ),
+            "Team context.\nR {diffs}\nD{unknown}"
+        );
+    }
+}